@@ -65,6 +65,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| IgnoredAny::deserialize(&mut Deserializer::from_str(input_str)))
     });
 
+    c.bench_function("tugboat fast check str", |b| {
+        b.iter(|| serde_bibtex::validate::fast_check(input_str))
+    });
+
     c.bench_function("tugboat borrow str", |b| {
         b.iter(|| RawBibliography::deserialize(&mut Deserializer::from_str(input_str)))
     });
@@ -0,0 +1,37 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    use serde::Deserialize;
+    use serde_bibtex::de::{Deserializer, DuplicateFieldPolicy};
+    use serde_bibtex::entry::Entry;
+    use serde_bibtex::MacroDictionary;
+
+    type OwnedBibliography = Vec<Entry>;
+
+    let input_str = std::fs::read_to_string("assets/tugboat.bib").unwrap();
+    let deserializer = || {
+        let mut macros = MacroDictionary::default();
+        macros.set_month_macros();
+        Deserializer::from_str_with_macros(&input_str, macros)
+    };
+
+    c.bench_function("tugboat owned", |b| {
+        b.iter(|| OwnedBibliography::deserialize(&mut deserializer()).unwrap())
+    });
+
+    // the fields of each entry are buffered to resolve duplicate field keys
+    for (name, policy) in [
+        ("tugboat owned use first", DuplicateFieldPolicy::UseFirst),
+        ("tugboat owned use last", DuplicateFieldPolicy::UseLast),
+    ] {
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                OwnedBibliography::deserialize(&mut deserializer().duplicate_fields(policy))
+                    .unwrap()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
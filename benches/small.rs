@@ -3,7 +3,7 @@ use criterion::{criterion_group, criterion_main, Criterion};
 pub fn criterion_benchmark(c: &mut Criterion) {
     use serde::de::IgnoredAny;
     use serde::Deserialize;
-    use serde_bibtex::de::Deserializer;
+    use serde_bibtex::de::{scan_keys, Deserializer};
     use serde_bibtex::entry::{BorrowEntry, Entry};
 
     type OwnedBibliography = Vec<Entry>;
@@ -20,6 +20,8 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         b.iter(|| IgnoredAny::deserialize(&mut Deserializer::from_str(input_str)))
     });
 
+    c.bench_function("biber scan keys str", |b| b.iter(|| scan_keys(input_str)));
+
     c.bench_function("biber owned slice", |b| {
         b.iter(|| OwnedBibliography::deserialize(&mut Deserializer::from_slice(&input_bytes)))
     });
@@ -0,0 +1,91 @@
+//! # A strongly typed entry with field metadata
+//!
+//! This example demonstrates a manual implementation of [`BibEntry`] and [`BibFields`], which
+//! attach BibTeX-specific metadata to a struct which is deserialized and serialized with serde.
+use serde::{Deserialize, Serialize};
+use serde_bibtex::entry::{BibEntry, BibFields, FieldInfo};
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Article {
+    entry_type: String,
+    entry_key: String,
+    fields: ArticleFields,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct ArticleFields {
+    author: String,
+    title: String,
+    journal: String,
+    #[serde(rename = "URL", skip_serializing_if = "String::is_empty")]
+    url: String,
+}
+
+impl BibFields for ArticleFields {
+    const FIELDS: &'static [FieldInfo] = &[
+        FieldInfo::new("author").required(),
+        FieldInfo::new("title").required(),
+        FieldInfo::new("journal").required(),
+        FieldInfo::new("url").rename("URL").verbatim(),
+    ];
+
+    fn has_field(&self, name: &str) -> bool {
+        match name {
+            "author" => !self.author.is_empty(),
+            "title" => !self.title.is_empty(),
+            "journal" => !self.journal.is_empty(),
+            "url" => !self.url.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+impl BibEntry for Article {
+    type Fields = ArticleFields;
+    const ENTRY_TYPES: &'static [&'static str] = &["article"];
+
+    fn entry_type(&self) -> &str {
+        &self.entry_type
+    }
+
+    fn entry_key(&self) -> &str {
+        &self.entry_key
+    }
+
+    fn fields(&self) -> &ArticleFields {
+        &self.fields
+    }
+}
+
+fn main() {
+    let input = r#"
+        @article{complete,
+          author = {Author},
+          title = {Title},
+          journal = {Journal},
+          URL = {https://example.com/~author},
+        }
+        @article{incomplete,
+          title = {Title},
+        }
+    "#;
+
+    let articles: Vec<Article> = serde_bibtex::from_str(input).unwrap();
+    for article in &articles {
+        match article.check() {
+            Ok(()) => println!("{}: ok", article.entry_key()),
+            Err(err) => println!("{}: {err}", article.entry_key()),
+        }
+    }
+
+    let verbatim: Vec<_> = ArticleFields::FIELDS
+        .iter()
+        .filter(|info| info.verbatim)
+        .map(|info| info.key)
+        .collect();
+    println!("verbatim fields: {verbatim:?}");
+
+    let valid: Vec<_> = articles.iter().filter(|a| a.check().is_ok()).collect();
+    print!("{}", serde_bibtex::to_string(&valid).unwrap());
+}
@@ -0,0 +1,181 @@
+//! # Citations in `.aux` and `.tex` files
+//!
+//! BibTeX determines which entries to include in a document from the `\citation{...}` lines which
+//! LaTeX writes to the `.aux` file. This module extracts the cited keys from an `.aux` file, or
+//! approximately from the `\cite{...}` commands of a `.tex` file, so that a bibliography can be
+//! reduced to the cited entries with `Bibliography::subset`, which is available with the `entry`
+//! feature.
+//! ```
+//! # #[cfg(feature = "entry")]
+//! # {
+//! use serde_bibtex::{citations, value::Bibliography};
+//!
+//! let aux_file = r"
+//! \relax
+//! \citation{knuth84}
+//! \citation{lamport94,knuth84}
+//! \bibdata{refs}
+//! ";
+//!
+//! let bibliography: Bibliography = serde_bibtex::from_str(
+//!     "@book{knuth84, title = {The TeXbook}} @book{other, title = {Other}}",
+//! )
+//! .unwrap();
+//!
+//! let keys = citations::citations(aux_file);
+//! assert_eq!(keys, ["knuth84", "lamport94"]);
+//! assert_eq!(bibliography.subset(keys).0.len(), 1);
+//! # }
+//! ```
+use std::collections::HashSet;
+
+/// The command which LaTeX writes to the `.aux` file for each citation.
+const AUX_CITATION: &str = r"\citation";
+
+/// The keys cited by `\citation{...}` commands in the contents of an `.aux` file.
+///
+/// The argument of each command is a comma-separated list of keys. The keys are returned in the
+/// order in which they are first cited, without duplicates, and with surrounding whitespace
+/// removed. A `\nocite{*}` in the document appears as the key `*`.
+/// ```
+/// use serde_bibtex::citations;
+///
+/// let keys = citations::citations("\\citation{a, b}\n\\citation{*}\n\\citation{a}\n");
+/// assert_eq!(keys, ["a", "b", "*"]);
+/// ```
+pub fn citations(input: &str) -> Vec<&str> {
+    let mut keys = CitedKeys::default();
+    let mut rest = input;
+    while let Some(idx) = rest.find(AUX_CITATION) {
+        rest = &rest[idx + AUX_CITATION.len()..];
+        if let Some((list, tail)) = group(rest) {
+            keys.extend(list);
+            rest = tail;
+        }
+    }
+    keys.keys
+}
+
+/// The keys cited by the citation commands in the contents of a `.tex` file.
+///
+/// This is a simple scan rather than a TeX parser: a citation command is any command whose name
+/// contains `cite`, such as `\cite`, `\citep`, `\nocite`, or `\parencite`, optionally followed by
+/// `*` and any number of optional arguments in square brackets, and then a comma-separated list of
+/// keys in braces. Text following an unescaped `%` on a line is ignored. Macros which expand to
+/// citation commands, and commands with more than one list of keys, are not recognized, so the
+/// [`citations`] in the `.aux` file should be preferred when it is available.
+/// ```
+/// use serde_bibtex::citations;
+///
+/// let input = r"
+/// As shown by \citet[p.~3]{knuth84}, and \parencite*[see][]{lamport94, knuth84}.
+/// % \cite{commented}
+/// \nocite{extra}
+/// ";
+/// assert_eq!(citations::tex_citations(input), ["knuth84", "lamport94", "extra"]);
+/// ```
+pub fn tex_citations(input: &str) -> Vec<&str> {
+    let mut keys = CitedKeys::default();
+    let mut rest = input;
+    while let Some(idx) = rest.find(['\\', '%']) {
+        if rest[idx..].starts_with('%') {
+            rest = rest[idx..].find('\n').map_or("", |end| &rest[idx + end..]);
+            continue;
+        }
+
+        rest = &rest[idx + 1..];
+        let name_len = rest
+            .find(|ch: char| !ch.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            // a control symbol such as `\%` or `\\`, which cannot start a citation command
+            rest = rest.chars().next().map_or("", |ch| &rest[ch.len_utf8()..]);
+            continue;
+        }
+
+        let name = &rest[..name_len];
+        rest = &rest[name_len..];
+        if !name.to_ascii_lowercase().contains("cite") {
+            continue;
+        }
+
+        let mut args = rest.strip_prefix('*').unwrap_or(rest).trim_start();
+        while let Some(optional) = args.strip_prefix('[') {
+            match optional.find(']') {
+                Some(end) => args = optional[end + 1..].trim_start(),
+                None => break,
+            }
+        }
+        if let Some((list, tail)) = group(args) {
+            keys.extend(list);
+            rest = tail;
+        }
+    }
+    keys.keys
+}
+
+/// Split a group `{...}` from the start of `input`, returning its contents and the remaining
+/// input. Keys cannot contain braces, so the group ends at the first `}`.
+fn group(input: &str) -> Option<(&str, &str)> {
+    let (contents, rest) = input.strip_prefix('{')?.split_once('}')?;
+    Some((contents, rest))
+}
+
+/// The cited keys, in the order in which they are first cited.
+#[derive(Default)]
+struct CitedKeys<'a> {
+    keys: Vec<&'a str>,
+    seen: HashSet<&'a str>,
+}
+
+impl<'a> CitedKeys<'a> {
+    /// Append the keys in a comma-separated list which have not been seen.
+    fn extend(&mut self, list: &'a str) {
+        for key in list.split(',').map(str::trim) {
+            if !key.is_empty() && self.seen.insert(key) {
+                self.keys.push(key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_citations() {
+        let input = r"\relax
+\providecommand\hyper@newdestlabel[2]{}
+\citation{a,b}
+\citation{ c ,, a}
+\bibstyle{plain}
+\citation{d}\citation{e}
+\citation{unterminated
+";
+        assert_eq!(citations(input), ["a", "b", "c", "d", "e"]);
+        assert!(citations(r"\bibdata{refs}").is_empty());
+        assert!(citations(r"\citation").is_empty());
+    }
+
+    #[test]
+    fn test_tex_citations() {
+        let input = r"
+\documentclass{article}
+\begin{document}
+\cite{a} \citep [p.~1] {b,c} \Textcite[a][b]{d}\footcite*{e}
+100\% \cite{f} 50\%\cite{g} % \cite{commented}
+\\cite{h} \citeauthor{a} \nocite{*}
+\recite \cite[unterminated{i}
+\excitement \cite
+\end{document}
+";
+        assert_eq!(
+            tex_citations(input),
+            ["a", "b", "c", "d", "e", "f", "g", "*"]
+        );
+        assert!(tex_citations("% only a comment \\cite{a}").is_empty());
+        assert!(tex_citations("\\").is_empty());
+        assert_eq!(tex_citations("\\é\\cite{a}"), ["a"]);
+    }
+}
@@ -188,6 +188,13 @@
 //!     }]
 //! );
 //! ```
+//! Note that a `BTreeMap` sorts the fields by key and a `HashMap` stores them in an arbitrary order.
+//! To keep the fields in the order in which they appear in the input, which is useful for programs
+//! which rewrite a bibliography, use a sequence of `(key, value)` tuples such as
+//! `Vec<(String, String)>`, or enable the `indexmap` feature and use an
+//! [`IndexMap`](https://docs.rs/indexmap/latest/indexmap/map/struct.IndexMap.html). The
+//! [`OrderedEntry`](crate::entry::OrderedEntry) type stores its fields in this way.
+//!
 //! It is also possible to explicitly state which field keys you wish to capture. Let's also use an
 //! explicit `Record` variant.
 //! ```
@@ -217,6 +224,97 @@
 //! In the above example, optional fields are set as `None` if not present, and fields which are not present
 //! are automatically skipped.
 //!
+//! The entry type can also be deserialized as an enum with unit variants. Variants are matched
+//! case-insensitively, and a `#[serde(other)]` variant captures the entry types which do not
+//! match any other variant.
+//! ```
+//! # use serde::Deserialize;
+//! # use serde_bibtex::de::Deserializer;
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! #[serde(rename_all = "lowercase")]
+//! enum EntryType {
+//!     Article,
+//!     Book,
+//!     #[serde(other)]
+//!     Other,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct Record {
+//!     entry_type: EntryType,
+//! }
+//!
+//! let input = "@article{a} @BOOK{b} @misc{c}";
+//! let entry_types: Vec<EntryType> = Deserializer::from_str(input)
+//!     .into_iter_regular_entry::<Record>()
+//!     .map(|record| record.unwrap().entry_type)
+//!     .collect();
+//!
+//! assert_eq!(
+//!     entry_types,
+//!     vec![EntryType::Article, EntryType::Book, EntryType::Other]
+//! );
+//! ```
+//!
+//! Fields which are not modelled explicitly can be collected with `#[serde(flatten)]`.
+//! ```
+//! # use serde::Deserialize;
+//! # use serde_bibtex::de::Deserializer;
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct Fields {
+//!     title: String,
+//!     #[serde(flatten)]
+//!     extra: BTreeMap<String, String>,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct Record {
+//!     entry_key: String,
+//!     fields: Fields,
+//! }
+//!
+//! let input = r#"@article{key, title = {Title}, year = 2012}"#;
+//! let mut de_iter = Deserializer::from_str(input).into_iter_regular_entry::<Record>();
+//!
+//! assert_eq!(
+//!     de_iter.next().unwrap().unwrap(),
+//!     Record {
+//!         entry_key: "key".into(),
+//!         fields: Fields {
+//!             title: "Title".into(),
+//!             extra: BTreeMap::from([("year".into(), "2012".into())]),
+//!         },
+//!     }
+//! );
+//! ```
+//! Alternatively, use [`Deserializer::collect_unknown_fields`] to collect the fields which are
+//! not expected by the struct into a designated struct field. Unlike with `#[serde(flatten)]`,
+//! the other struct fields are not buffered.
+//! ```
+//! # use serde::Deserialize;
+//! # use serde_bibtex::de::Deserializer;
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! struct Fields {
+//!     title: String,
+//!     #[serde(rename = "__extra", default)]
+//!     extra: BTreeMap<String, String>,
+//! }
+//!
+//! let input = r#"@article{key, title = {Title}, year = 2012}"#;
+//! let mut de_iter = Deserializer::from_str(input)
+//!     .collect_unknown_fields("__extra")
+//!     .into_iter_regular_entry::<(String, String, Fields)>();
+//!
+//! let (_, _, fields) = de_iter.next().unwrap().unwrap();
+//! assert_eq!(fields.extra, BTreeMap::from([("year".into(), "2012".into())]));
+//! ```
+//! Flattened values are buffered using `deserialize_any`. In order to capture flattened values as
+//! a sequence of tokens, see [`Deserializer::any_as_tokens`].
+//!
 //! The following less strongly typed deserialization scheme would also be valid:
 //! ```
 //! # use serde::Deserialize;
@@ -617,6 +715,10 @@
 //!     Some(Err(_)),
 //! ));
 //! ```
+//! If it is acceptable to lose the distinction between text and macros, use
+//! [`Deserializer::undefined_macros`] to expand undefined macros to their name or to the empty
+//! string instead.
+//!
 //! In order to tolerate errors, it is also possible to deserialize values as a sequence of enums.
 //! The special variants `Variable` and `Text` are required; use `#[serde(rename = "...")]` to use
 //! custom names.
@@ -660,6 +762,48 @@
 //!     assert_eq!(entry.unwrap(), expect);
 //! }
 //! ```
+//! Deserializing into a sequence of tokens, or into an `Option` of one, never fails because of
+//! an undefined macro. To capture a value exactly as it appears in the input, without expanding
+//! any macros, use [`RawValue`](crate::token::RawValue), which borrows the source of the value.
+//!
+//! Self-describing types, such as `serde_json::Value`, are deserialized with
+//! `deserialize_any`, which produces a string by default. To instead capture the macro structure
+//! of each value, use [`Deserializer::any_as_tokens`]. Each token is then represented as a map
+//! with a single key, `Variable` or `Text`.
+//! ```
+//! use std::collections::BTreeMap;
+//!
+//! use serde_bibtex::de::Deserializer;
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! #[serde(untagged)]
+//! enum Value {
+//!     Text(String),
+//!     Tokens(Vec<BTreeMap<String, String>>),
+//! }
+//!
+//! #[derive(Debug, PartialEq, Deserialize)]
+//! enum Entry {
+//!     Macro,
+//!     Comment,
+//!     Preamble(Value),
+//!     Regular,
+//! }
+//!
+//! let input = r#"@preamble{{text} # var}"#;
+//!
+//! let mut de_iter = Deserializer::from_str(input)
+//!     .any_as_tokens(true)
+//!     .into_iter::<Entry>();
+//! assert_eq!(
+//!     de_iter.next().unwrap().unwrap(),
+//!     Entry::Preamble(Value::Tokens(vec![
+//!         BTreeMap::from([("Text".into(), "text".into())]),
+//!         BTreeMap::from([("Variable".into(), "var".into())]),
+//!     ]))
+//! );
+//! ```
 //! Internally, a [`Token`](crate::token::Token) is used to hold `@string` macro definitions. This helps to
 //! automatically tolerate undefined macros when the value of that macro is not required.
 //!
@@ -780,12 +924,90 @@
 //! ));
 //! ```
 mod bibliography;
+mod builder;
+#[cfg(feature = "entry")]
+mod entries;
 mod entry;
+mod index;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod project;
 mod value;
 
-pub use bibliography::{DeserializeIter, DeserializeRegularEntryIter, Deserializer};
+pub use crate::parse::Repair;
+pub use bibliography::{
+    CancellationToken, DeserializeFilteredIter, DeserializeIter, DeserializeKeysIter,
+    DeserializeRegularEntryIter, DeserializeSpannedIter, DeserializeTryParseIter, Deserializer,
+    DuplicateFieldPolicy, InvalidUtf8Policy, MacroRedefinition, MacroRedefinitionPolicy,
+    MacroUsageIter, RawEntry, Spanned, Stats, UndefinedMacroPolicy, UnknownFieldKeyPolicy,
+    VERBATIM_FIELDS,
+};
+pub use builder::DeserializerBuilder;
+pub use index::{scan_keys, scan_keys_slice, IndexedEntry, LazyBibliography};
+#[cfg(feature = "mmap")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+pub use mmap::MappedFile;
+pub use project::{Project, ProjectEntry, ProjectIter};
+pub use value::TokenIter;
+
+/// Deserialize an instance of type `D` from entries of the owned document model of the
+/// [value module](crate::value), as though the entries were read from a `.bib` file.
+///
+/// This is the counterpart of [`to_entries`](crate::ser::to_entries): a bibliography can be read
+/// into the document model, transformed, and then converted into your own types. As usual,
+/// macros defined by the entries are expanded. Since `D` cannot borrow from the entries, it must
+/// own its data.
+///
+/// The entries are deserialized directly, without writing them as BibTeX, but otherwise
+/// deserialization behaves as for a `.bib` file containing the entries with the default
+/// [`Deserializer`] settings: entry types, keys, and tokens which could not be written are an
+/// error, and later macro definitions replace earlier ones. Since the values have no source
+/// text, they cannot be deserialized as a [`RawValue`](crate::token::RawValue).
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use serde::Deserialize;
+/// use serde_bibtex::{de::from_entries, value::{Entry, RegularEntry, Value}};
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// struct Record {
+///     entry_key: String,
+///     fields: BTreeMap<String, String>,
+/// }
+///
+/// let mut entry = RegularEntry::new("article", "key");
+/// entry.set_field("month", Value::variable("m"));
+/// let entries = vec![
+///     Entry::Macro(Some(("m".into(), Value::text("May")))),
+///     Entry::Regular(entry),
+/// ];
+///
+/// #[derive(Debug, PartialEq, Deserialize)]
+/// enum Item {
+///     Regular(Record),
+///     Macro,
+/// }
+///
+/// let items: Vec<Item> = from_entries(&entries).unwrap();
+/// assert_eq!(
+///     items[1],
+///     Item::Regular(Record {
+///         entry_key: "key".into(),
+///         fields: BTreeMap::from([("month".into(), "May".into())]),
+///     })
+/// );
+/// ```
+#[cfg(feature = "entry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "entry")))]
+pub fn from_entries<D>(entries: &[crate::value::Entry]) -> crate::Result<D>
+where
+    D: serde::de::DeserializeOwned,
+{
+    D::deserialize(&mut entries::EntriesDeserializer::new(entries))
+}
 
 #[cfg(test)]
+#[allow(clippy::useless_conversion)]
 mod tests {
 
     use crate::{de::Deserializer, parse::StrReader};
@@ -996,4 +1218,257 @@ mod tests {
             assert_eq!(expected, received.unwrap());
         }
     }
+
+    #[test]
+    fn test_deserialize_take() {
+        // the syntax error is never read
+        let input = r#"
+        @string{k = {12}}
+        @article{key, author = {Author}, title = k}
+        @comment{ignored}
+        @book{key2, author = {Author 2}, title = k # k}
+        @article{key3, author = {Author 3}, title = {Title} # }
+        "#;
+
+        let received: Vec<TestRegularEntry> = Deserializer::from_str(input)
+            .take(2)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[1].entry_key, "key2");
+        assert_eq!(received[1].fields.title, "1212");
+    }
+
+    #[test]
+    fn test_deserialize_iter_keys() {
+        // the syntax error is never read
+        let input = r#"
+        @article{key, author = {Author}, title = {Title}}
+        @string{k = {12}}
+        @book{key2, author = {Author 2}, title = k # {Year}}
+        @preamble{"a value"}
+        @article{key3, author = {Author 3}, title = k # k}
+        @article{key2, author = {Duplicate}, title = {Title}}
+        @article{key4, author = {Author 4}, title = {Title} # }
+        "#;
+
+        let expected = vec![
+            TestRegularEntry {
+                entry_type: "book",
+                entry_key: "key2",
+                fields: TestFields {
+                    author: "Author 2".into(),
+                    title: "12Year".into(),
+                },
+            },
+            TestRegularEntry {
+                entry_type: "article",
+                entry_key: "key3",
+                fields: TestFields {
+                    author: "Author 3".into(),
+                    title: "1212".into(),
+                },
+            },
+        ];
+
+        let received: Vec<TestRegularEntry> = Deserializer::from_str(input)
+            .into_iter_keys(["KEY3", "key2"])
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(expected, received);
+
+        // a missing key scans the entire input
+        let mut iter =
+            Deserializer::from_str(input).into_iter_keys::<TestRegularEntry, _, _>(["missing"]);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        use crate::error::Category;
+
+        let input = b"\xEF\xBB\xBF@article{key, author = {Author}, title = {Title}}";
+        let received: Vec<TestRegularEntry> = Deserializer::from_slice(input)
+            .into_iter_regular_entry()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(received[0].entry_key, "key");
+        assert_eq!(crate::de::scan_keys_slice(input).unwrap().len(), 1);
+
+        let input: Vec<u8> = "@article{key, title = {Title}}"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let input = [&[0xFF, 0xFE], input.as_slice()].concat();
+        let err = Deserializer::from_slice(&input)
+            .into_iter_regular_entry::<TestRegularEntry>()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.classify(), Category::Data);
+        assert_eq!(
+            err.to_string(),
+            "unsupported encoding UTF-16LE: input must be UTF-8"
+        );
+    }
+
+    #[test]
+    fn test_tex_comments() {
+        use crate::TexComments;
+        use serde::de::IgnoredAny;
+
+        fn keys(input: &str, tex_comments: TexComments) -> Result<Vec<String>, crate::Error> {
+            Deserializer::from_str(input)
+                .tex_comments(tex_comments)
+                .into_iter_regular_entry::<(String, String, IgnoredAny)>()
+                .map(|res| res.map(|(_, key, _)| key))
+                .collect()
+        }
+
+        let input = "50% off @misc{a, title = {100%}}\n@misc{b, title = {B}}";
+        assert_eq!(keys(input, TexComments::Everywhere).unwrap(), vec!["b"]);
+        assert_eq!(keys(input, TexComments::BetweenEntries).unwrap(), vec!["b"]);
+        assert_eq!(keys(input, TexComments::Never).unwrap(), vec!["a", "b"]);
+
+        let input = "@misc{a, % a comment\n title = {A}}";
+        assert_eq!(keys(input, TexComments::Everywhere).unwrap(), vec!["a"]);
+        assert!(keys(input, TexComments::BetweenEntries).is_err());
+        assert!(keys(input, TexComments::Never).is_err());
+    }
+
+    #[test]
+    fn test_deny_junk() {
+        use crate::{de::DeserializerBuilder, error::ErrorKind, TexComments};
+        use serde::de::IgnoredAny;
+
+        fn junk(input: &str, tex_comments: TexComments) -> Option<&str> {
+            let mut de = DeserializerBuilder::new()
+                .tex_comments(tex_comments)
+                .deny_junk(true)
+                .build_from_str(input);
+            match IgnoredAny::deserialize(&mut de) {
+                Ok(_) => None,
+                Err(err) => match err.kind() {
+                    ErrorKind::Junk { span } => Some(&input[span]),
+                    _ => panic!("unexpected error {err}"),
+                },
+            }
+        }
+
+        let input = " @a{k}\n\t% comment @b{k}\n@comment{text}";
+        assert_eq!(junk(input, TexComments::Everywhere), None);
+        assert_eq!(junk(input, TexComments::Never), Some("% comment"));
+        assert_eq!(junk("@a{k} }\n", TexComments::Everywhere), Some("}"));
+        assert_eq!(
+            junk("@a{k}\n\ntrailing ", TexComments::Everywhere),
+            Some("trailing")
+        );
+    }
+
+    #[test]
+    fn test_error_kind() {
+        use crate::error::ErrorKind;
+
+        let err = Deserializer::from_str("@article{key, author = {A}, title = }")
+            .into_iter_regular_entry::<TestRegularEntry>()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Syntax {
+                found: Some('}'),
+                span: Some(std::ops::Range { start: 36, end: 37 }),
+                ..
+            }
+        ));
+        assert_eq!(err.position(), Some(36));
+        assert!(err.to_string().ends_with(" at byte 36"));
+
+        // the span covers the whole character which was found
+        let err = Deserializer::from_str("@article{key, title é}")
+            .into_iter_regular_entry::<TestRegularEntry>()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::Syntax {
+                found: Some('é'),
+                span: Some(std::ops::Range { start: 20, end: 22 }),
+                ..
+            }
+        ));
+
+        let err = Deserializer::from_str("@article{key, author = {A}, title = k}")
+            .into_iter_regular_entry::<TestRegularEntry>()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::UnresolvedMacro { name: "k" }
+        ));
+        assert_eq!(err.position(), None);
+
+        let err = crate::from_str::<Vec<TestEntry>>("@article{key, author = {A}").unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Custom { .. }));
+        assert_eq!(err.position(), None);
+
+        let err = crate::from_str::<Vec<TestEntry>>("@article{key, title = ").unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::UnexpectedEof { .. }));
+        assert_eq!(err.position(), Some(22));
+    }
+
+    #[cfg(feature = "entry")]
+    #[test]
+    fn test_from_entries() {
+        use super::from_entries;
+        use crate::value::{Bibliography, Entry, RegularEntry, Value};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Owned {
+            Regular(String, String, Vec<(String, String)>),
+            Macro,
+            Comment(String),
+            Preamble(String),
+        }
+
+        let input = "@string{a = {A}} @preamble{a} @comment{c} @misc{k, t = a # { b}}";
+        let Bibliography(entries) = crate::from_str(input).unwrap();
+        assert_eq!(
+            from_entries::<Vec<Owned>>(&entries).unwrap(),
+            vec![
+                Owned::Macro,
+                Owned::Preamble("A".into()),
+                Owned::Comment("c".into()),
+                Owned::Regular("misc".into(), "k".into(), vec![("t".into(), "A b".into())]),
+            ]
+        );
+        assert_eq!(from_entries::<Bibliography>(&entries).unwrap().0, entries);
+        assert_eq!(
+            from_entries::<Bibliography>(&crate::ser::to_entries(&entries).unwrap())
+                .unwrap()
+                .0,
+            entries
+        );
+
+        // entries which cannot be written are an error
+        let mut entry = RegularEntry::new("misc", "k");
+        entry.set_field("t", Value::text("{"));
+        assert!(from_entries::<Bibliography>(&[Entry::Regular(entry)]).is_err());
+
+        // a macro captured by the visitor is not defined
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Captured {
+            Regular(String, String, Vec<(String, String)>),
+            Macro(Option<(String, String)>),
+        }
+
+        let err = from_entries::<Vec<Captured>>(&entries[3..]).unwrap_err();
+        assert_eq!(err.entry_key(), Some("k"));
+        assert_eq!(err.field_key(), Some("t"));
+        assert!(from_entries::<Vec<Captured>>(&[entries[0].clone(), entries[3].clone()]).is_err());
+    }
 }
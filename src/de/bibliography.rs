@@ -1,16 +1,24 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use serde::de::{self, DeserializeSeed, SeqAccess};
 use serde::forward_to_deserialize_any;
+use unicase::UniCase;
 
 use crate::{
-    error::{Error, Result},
-    parse::{BibtexParse, MacroDictionary},
-    token::{EntryType, Token},
-    SliceReader, StrReader,
+    error::{Error, ErrorCode, Result},
+    parse::{BibtexParse, MacroDictionary, Repair},
+    token::{EntryType, FieldKey, Text, Token, Variable},
+    LineEnding, SliceReader, StrReader, TexComments,
 };
 
+use super::builder::DeserializerBuilder;
 use super::entry::{EntryDeserializer, RegularEntryDeserializer};
+use super::value::{TokenIter, ValueFilter, ValueOptions};
 
 /// The core `.bib` deserializer.
 ///
@@ -19,16 +27,251 @@ use super::entry::{EntryDeserializer, RegularEntryDeserializer};
 /// - [`Deserializer::from_str_with_macros`]
 /// - [`Deserializer::from_slice`]
 /// - [`Deserializer::from_slice_with_macros`]
+/// - [`Deserializer::builder`], to configure the deserializer before providing the input
 ///
 /// The type parameter `R` is the input type from which you are deserializing. If you construct a
 /// [`Deserializer`] using one of the above methods, the type will be inferred automatically.
 pub struct Deserializer<'r, R> {
     pub(crate) parser: R,
-    pub(crate) macros: MacroDictionary<&'r str, &'r [u8]>,
+    /// The macros, which are only copied if they are shared and a new macro is defined.
+    pub(crate) macros: Arc<MacroDictionary<&'r str, &'r [u8]>>,
     pub(crate) scratch: Vec<Token<&'r str, &'r [u8]>>,
+    /// Temporary storage used when resolving macros.
+    pub(crate) resolve_scratch: Vec<Token<&'r str, &'r [u8]>>,
+    /// Empty token buffers whose allocations are reused when the fields of an entry are buffered.
+    pub(crate) token_buffers: Vec<Vec<Token<&'r str, &'r [u8]>>>,
+    pub(crate) value_options: ValueOptions,
+    pub(crate) value_filter: Option<Box<ValueFilter<'r>>>,
+    pub(crate) unknown_fields: Option<&'static str>,
+    pub(crate) duplicate_fields: Option<DuplicateFieldPolicy>,
+    pub(crate) entry_types: Vec<(UniCase<&'r str>, &'r str)>,
+    pub(crate) verbatim_fields: Vec<UniCase<&'r str>>,
+    /// The macros expanded since usage tracking was last reset, if tracking is enabled.
+    pub(crate) macro_usage: Option<Vec<Variable<&'r str>>>,
+    pub(crate) macro_redefinition: MacroRedefinitionPolicy,
+    /// The span of the `@string` entry with the current definition of each captured macro, if
+    /// redefinitions are checked.
+    pub(crate) macro_spans: HashMap<Variable<&'r str>, Range<usize>>,
+    pub(crate) unknown_field_keys: UnknownFieldKeyPolicy,
+    pub(crate) progress: Option<Box<Progress<'r>>>,
+    pub(crate) repair_warning: Option<Box<RepairWarning<'r>>>,
+    pub(crate) cancellation: Option<CancellationToken>,
+    pub(crate) stats: Option<Stats>,
+    /// The position of the `@` which starts the current entry.
+    pub(crate) entry_start: usize,
 }
 
+/// A function called with the number of bytes consumed and the total length of the input.
+pub(crate) type Progress<'r> = dyn FnMut(usize, usize) + Send + 'r;
+
+/// A function called with each repair to a text token with unbalanced brackets.
+pub(crate) type RepairWarning<'r> = dyn FnMut(&Repair) + Send + 'r;
+
+/// How to handle a field key which appears more than once within an entry.
+///
+/// Field keys are compared case-insensitively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateFieldPolicy {
+    /// Keep the value of the first occurrence of the field.
+    UseFirst,
+    /// Keep the value of the last occurrence of the field.
+    UseLast,
+    /// Return an error when a duplicate field is encountered.
+    Error,
+}
+
+/// How to expand a macro which is not defined when a value is deserialized as text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UndefinedMacroPolicy {
+    /// Return an error.
+    #[default]
+    Error,
+    /// Use the name of the macro as the text, so that `feb` expands to `"feb"`.
+    KeepName,
+    /// Expand the macro to the empty string.
+    Empty,
+}
+
+/// How text which is not valid UTF-8 is handled when it is deserialized as a string.
+///
+/// Text can only be invalid UTF-8 when the input is a byte slice, such as with
+/// [`Deserializer::from_slice`]. Text deserialized as bytes is unaffected.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum InvalidUtf8Policy {
+    /// Return an error.
+    #[default]
+    Error,
+    /// Replace each invalid sequence by `U+FFFD REPLACEMENT CHARACTER`, as in
+    /// [`String::from_utf8_lossy`].
+    Replace,
+    /// Convert the text with the function, for instance to decode text in a legacy encoding.
+    /// Text which is valid UTF-8 is not passed to the function.
+    Transcode(fn(&[u8]) -> String),
+}
+
+impl InvalidUtf8Policy {
+    /// Convert the text into a string according to the policy.
+    pub(crate) fn decode<'r>(self, text: Text<&'r str, &'r [u8]>) -> Result<Cow<'r, str>> {
+        let bytes = match text {
+            Text::Str(s) => return Ok(Cow::Borrowed(s)),
+            Text::Bytes(bytes) => bytes,
+        };
+        match (std::str::from_utf8(bytes), self) {
+            (Ok(s), _) => Ok(Cow::Borrowed(s)),
+            (Err(err), Self::Error) => Err(err.into()),
+            (Err(_), Self::Replace) => Ok(String::from_utf8_lossy(bytes)),
+            (Err(_), Self::Transcode(transcode)) => Ok(Cow::Owned(transcode(bytes))),
+        }
+    }
+}
+
+/// A macro which is redefined with a different value, as passed to
+/// [`MacroRedefinitionPolicy::Warn`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroRedefinition<'a> {
+    /// The name of the macro, as written in the new definition.
+    pub name: &'a str,
+    /// The byte range of the `@string` entry with the previous definition, or None if the macro
+    /// was predefined with [`Deserializer::from_str_with_macros`] or a similar method.
+    pub previous: Option<Range<usize>>,
+    /// The byte range of the `@string` entry with the new definition.
+    pub span: Range<usize>,
+}
+
+/// How to handle an `@string` entry which redefines a macro with a different value, as set with
+/// [`Deserializer::macro_redefinition`].
+///
+/// Macro names are compared case-insensitively, and a definition with the same value as the
+/// current definition is never considered to be a redefinition.
+#[derive(Default)]
+pub enum MacroRedefinitionPolicy {
+    /// Replace the value, as BibTeX does.
+    #[default]
+    Overwrite,
+    /// Keep the first value, ignoring the new definition.
+    KeepFirst,
+    /// Return an error.
+    Error,
+    /// Call the provided function with both definitions, and replace the value.
+    Warn(Box<dyn FnMut(&MacroRedefinition) + Send>),
+}
+
+impl std::fmt::Debug for MacroRedefinitionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Overwrite => f.write_str("Overwrite"),
+            Self::KeepFirst => f.write_str("KeepFirst"),
+            Self::Error => f.write_str("Error"),
+            Self::Warn(_) => f.write_str("Warn(..)"),
+        }
+    }
+}
+
+/// How to handle a field key which does not match any variant when the field keys are
+/// deserialized as an enum, as set with [`Deserializer::unknown_field_keys`].
+#[derive(Default)]
+pub enum UnknownFieldKeyPolicy {
+    /// Pass the field key to the enum, so that an error is returned unless the enum has a
+    /// `#[serde(other)]` variant.
+    #[default]
+    Error,
+    /// Skip the field, along with its value.
+    Skip,
+    /// Call the provided function with the field key, and skip the field along with its value.
+    Warn(Box<dyn FnMut(&str) + Send>),
+}
+
+impl std::fmt::Debug for UnknownFieldKeyPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => f.write_str("Error"),
+            Self::Skip => f.write_str("Skip"),
+            Self::Warn(_) => f.write_str("Warn(..)"),
+        }
+    }
+}
+
+/// A flag which cancels deserialization, as set with [`Deserializer::with_cancellation`].
+///
+/// Clones of a token share the same flag, so a token can be cancelled from another thread or from
+/// a user interface callback while deserialization is in progress.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(pub(crate) Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Construct a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel deserialization. The deserializer returns an error the next time that it checks the
+    /// token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Statistics about the input read by a [`Deserializer`], as returned by
+/// [`Deserializer::finish_with_stats`].
+///
+/// Only the input which was read is counted: if deserialization stops early, the remaining
+/// entries are not included. Entries which are skipped, for instance by
+/// [`Deserializer::into_iter_filtered`], are still read and counted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of entries of every kind.
+    pub entries: usize,
+    /// The number of regular entries with each entry type, converted to lowercase. Entry type
+    /// aliases are not applied.
+    pub entry_types: BTreeMap<String, usize>,
+    /// The number of `@string` entries, which define macros.
+    pub macros: usize,
+    /// The number of `@comment` entries.
+    pub comments: usize,
+    /// The number of `@preamble` entries.
+    pub preambles: usize,
+    /// The number of fields in the regular entries.
+    pub fields: usize,
+    /// The number of bytes outside of entries which are ignored as junk, not counting whitespace.
+    pub junk_bytes: usize,
+}
+
+impl Stats {
+    fn record_entry(&mut self, entry_type: &EntryType<&str>) {
+        self.entries += 1;
+        match entry_type {
+            EntryType::Regular(entry_type) => {
+                *self
+                    .entry_types
+                    .entry(entry_type.as_ref().to_lowercase())
+                    .or_default() += 1;
+            }
+            EntryType::Macro => self.macros += 1,
+            EntryType::Comment => self.comments += 1,
+            EntryType::Preamble => self.preambles += 1,
+        }
+    }
+}
+
+/// The biblatex fields whose values are verbatim, such as URLs and file paths.
+///
+/// This is the default for [`Deserializer::verbatim_fields`].
+pub const VERBATIM_FIELDS: [&str; 8] = [
+    "doi", "eprint", "file", "pdf", "url", "verba", "verbb", "verbc",
+];
+
 impl<'r> Deserializer<'r, StrReader<'r>> {
+    /// Returns a builder to configure a deserializer before providing the input. The builder can
+    /// construct a deserializer for either a `&str` or a `&[u8]`.
+    pub fn builder() -> DeserializerBuilder<'r> {
+        DeserializerBuilder::new()
+    }
+
     /// Construct a deserialier from a `&str`.
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &'r str) -> Self {
@@ -59,23 +302,729 @@ where
 {
     /// Construct a new [`Deserializer`] from any [`BibtexParse`] implementation.
     pub(crate) fn new(parser: R) -> Self {
-        Self {
-            parser,
-            macros: MacroDictionary::default(),
-            scratch: Vec::new(),
-        }
+        DeserializerBuilder::new().build(parser)
     }
 
     /// Construct a new [`Deserializer`] from any [`BibtexParse`] implementation and pre-defined
     /// macros in [`MacroDictionary`].
     pub(crate) fn new_with_macros(parser: R, macros: MacroDictionary<&'r str, &'r [u8]>) -> Self {
-        Self {
-            parser,
-            macros,
-            scratch: Vec::new(),
+        DeserializerBuilder::new().macros(macros).build(parser)
+    }
+
+    /// Set whether values requested through `deserialize_any` are deserialized as a sequence of
+    /// tokens rather than as a string.
+    ///
+    /// This is useful when deserializing into self-describing types such as
+    /// `serde_json::Value`: undefined macros are preserved instead of resulting in an error.
+    /// Each token is represented as a map with a single key, either `Variable` or `Text`. The
+    /// default is `false`.
+    pub fn any_as_tokens(mut self, enable: bool) -> Self {
+        self.value_options.any_as_tokens = enable;
+        self
+    }
+
+    /// Set how to expand macros which are not defined when a value is deserialized as text,
+    /// such as into a `String` or `Cow<str>`. The default is [`UndefinedMacroPolicy::Error`].
+    ///
+    /// Values deserialized as a sequence of tokens are unaffected, since undefined macros are
+    /// preserved as variable tokens.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::{Deserializer, UndefinedMacroPolicy};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Fields {
+    ///     month: String,
+    ///     note: String,
+    /// }
+    ///
+    /// let input = "@article{key, month = feb, note = {See } # ref # {.}}";
+    ///
+    /// let mut iter = Deserializer::from_str(input).into_iter_regular_entry::<Record>();
+    /// assert!(iter.next().unwrap().is_err());
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .undefined_macros(UndefinedMacroPolicy::KeepName)
+    ///     .into_iter_regular_entry::<Record>();
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.fields.month, "feb");
+    /// assert_eq!(record.fields.note, "See ref.");
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .undefined_macros(UndefinedMacroPolicy::Empty)
+    ///     .into_iter_regular_entry::<Record>();
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.fields.month, "");
+    /// assert_eq!(record.fields.note, "See .");
+    /// ```
+    pub fn undefined_macros(mut self, policy: UndefinedMacroPolicy) -> Self {
+        self.value_options.undefined_macros = policy;
+        self
+    }
+
+    /// Set whether to normalize whitespace when a value is deserialized as text, such as into a
+    /// `String` or `Cow<str>`. The default is `false`.
+    ///
+    /// Values often contain line breaks and indentation from manual line wrapping. When enabled,
+    /// each run of whitespace is collapsed into a single space, and leading and trailing
+    /// whitespace is removed. Normalization is applied after macros are expanded and before any
+    /// [value filter](Deserializer::with_value_filter). Values deserialized as bytes or as a
+    /// sequence of tokens, and values of [verbatim fields](Deserializer::verbatim_fields), are
+    /// unaffected.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Fields {
+    ///     title: String,
+    /// }
+    ///
+    /// let input = "@article{key,
+    ///   title = {A Long Title
+    ///            Wrapped Over Lines },
+    /// }";
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .normalize_whitespace(true)
+    ///     .into_iter_regular_entry::<Record>();
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.fields.title, "A Long Title Wrapped Over Lines");
+    /// ```
+    pub fn normalize_whitespace(mut self, enable: bool) -> Self {
+        self.value_options.normalize_whitespace = enable;
+        self
+    }
+
+    /// Set how text which is not valid UTF-8 is handled when it is deserialized as a string, such
+    /// as into a `String` or `Cow<str>`. The default is [`InvalidUtf8Policy::Error`].
+    ///
+    /// This applies to field values, macro values, preambles, and comments, and to text tokens
+    /// deserialized as strings. Text which is converted by the policy can no longer be borrowed
+    /// from the input, so it cannot be deserialized into a `&str`.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::{Deserializer, InvalidUtf8Policy};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Fields {
+    ///     author: String,
+    /// }
+    ///
+    /// // `é` encoded as Latin-1
+    /// let input = b"@book{key, author = {Andr\xe9}}";
+    ///
+    /// let mut iter = Deserializer::from_slice(input).into_iter_regular_entry::<Record>();
+    /// assert!(iter.next().unwrap().is_err());
+    ///
+    /// let mut iter = Deserializer::from_slice(input)
+    ///     .invalid_utf8(InvalidUtf8Policy::Replace)
+    ///     .into_iter_regular_entry::<Record>();
+    /// assert_eq!(iter.next().unwrap().unwrap().fields.author, "Andr\u{fffd}");
+    ///
+    /// let latin1 = |bytes: &[u8]| bytes.iter().map(|b| char::from(*b)).collect();
+    /// let mut iter = Deserializer::from_slice(input)
+    ///     .invalid_utf8(InvalidUtf8Policy::Transcode(latin1))
+    ///     .into_iter_regular_entry::<Record>();
+    /// assert_eq!(iter.next().unwrap().unwrap().fields.author, "André");
+    /// ```
+    pub fn invalid_utf8(mut self, policy: InvalidUtf8Policy) -> Self {
+        self.value_options.invalid_utf8 = policy;
+        self
+    }
+
+    /// Collect fields which are not expected by a fields struct into the struct field `name`.
+    ///
+    /// The unrecognized fields are deserialized as a map from field keys to values. Fields
+    /// structs which do not have a field called `name` are unaffected. An input field which is
+    /// itself called `name` is collected along with the other unrecognized fields. By default,
+    /// unrecognized fields are skipped.
+    pub fn collect_unknown_fields(mut self, name: &'static str) -> Self {
+        self.unknown_fields = Some(name);
+        self
+    }
+
+    /// Set where a `%` begins a comment. The default is [`TexComments::Everywhere`].
+    ///
+    /// Use [`TexComments::BetweenEntries`] or [`TexComments::Never`] to match the behaviour of
+    /// classic BibTeX, for instance when field values contain an unbracketed `%`.
+    pub fn tex_comments(mut self, tex_comments: TexComments) -> Self {
+        self.parser.set_tex_comments(tex_comments);
+        self
+    }
+
+    /// Return an error if there is text between entries other than whitespace and comments,
+    /// which catches mistakes such as an entry with a missing `@`.
+    ///
+    /// By default, as in BibTeX, any text outside of entries is skipped. The error has kind
+    /// [`ErrorKind::Junk`](crate::error::ErrorKind::Junk), which records the span of the junk.
+    /// Note that when `%` does not begin a comment, as with [`TexComments::Never`], a line
+    /// beginning with `%` is also junk.
+    /// ```
+    /// use serde::de::{Deserialize, IgnoredAny};
+    /// use serde_bibtex::de::Deserializer;
+    /// use serde_bibtex::error::ErrorKind;
+    ///
+    /// let input = "% a comment\n@misc{a,}\narticle{b, title = {B}}\n";
+    ///
+    /// let mut de = Deserializer::from_str(input);
+    /// assert!(IgnoredAny::deserialize(&mut de).is_ok());
+    ///
+    /// let mut de = Deserializer::from_str(input).deny_junk(true);
+    /// let err = IgnoredAny::deserialize(&mut de).unwrap_err();
+    /// let ErrorKind::Junk { span } = err.kind() else {
+    ///     panic!("expected junk");
+    /// };
+    /// assert_eq!(&input[span], "article{b, title = {B}}");
+    /// assert_eq!(err.to_string(), "unexpected text between entries at byte 22");
+    /// ```
+    pub fn deny_junk(mut self, enable: bool) -> Self {
+        self.parser.set_deny_junk(enable);
+        self
+    }
+
+    /// Enforce a [`DuplicateFieldPolicy`] on the fields of each regular entry.
+    ///
+    /// When a policy is set, the fields of an entry are read in advance of deserialization. By
+    /// default, duplicate fields are passed through to the target type unchanged.
+    pub fn duplicate_fields(mut self, policy: DuplicateFieldPolicy) -> Self {
+        self.duplicate_fields = Some(policy);
+        self
+    }
+
+    /// Apply `filter` to the text of every field value, after macros are expanded and before the
+    /// value is passed to the target type.
+    ///
+    /// The filter is called with the field key and the text of the value. It is only applied to
+    /// values which are deserialized as text, such as into a `String` or `Cow<str>`, and not to
+    /// values deserialized as bytes or as a sequence of tokens. Values of fields which are skipped
+    /// by the target type and values of [verbatim fields](Deserializer::verbatim_fields) are not
+    /// filtered.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    /// use std::borrow::Cow;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Fields {
+    ///     title: String,
+    ///     pages: String,
+    /// }
+    ///
+    /// let input = "@article{key, title = {Title--Subtitle}, pages = {1--10}}";
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .with_value_filter(|key, value| {
+    ///         if key.eq_ignore_ascii_case("pages") && value.contains("--") {
+    ///             Cow::Owned(value.replace("--", "–"))
+    ///         } else {
+    ///             value
+    ///         }
+    ///     })
+    ///     .into_iter_regular_entry::<Record>();
+    ///
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.fields.title, "Title--Subtitle");
+    /// assert_eq!(record.fields.pages, "1–10");
+    /// ```
+    pub fn with_value_filter<F>(mut self, filter: F) -> Self
+    where
+        F: for<'c> Fn(&str, Cow<'c, str>) -> Cow<'c, str> + Send + 'r,
+    {
+        self.value_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Set the fields whose values are verbatim, replacing the default [`VERBATIM_FIELDS`].
+    ///
+    /// The text of a verbatim field is passed to the target type exactly as it appears, even if
+    /// [whitespace normalization](Deserializer::normalize_whitespace) or a
+    /// [value filter](Deserializer::with_value_filter) is enabled: for instance, a filter which
+    /// decodes TeX would otherwise corrupt a URL containing `%` or `~`. Field keys are compared
+    /// case-insensitively. Pass an empty iterator to treat every field as text.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    /// use std::borrow::Cow;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Fields {
+    ///     title: String,
+    ///     url: String,
+    /// }
+    ///
+    /// let input = r"@misc{key, title = {A~Title}, url = {https://example.org/~user}}";
+    /// fn tilde_to_space<'c>(_key: &str, value: Cow<'c, str>) -> Cow<'c, str> {
+    ///     Cow::Owned(value.replace('~', " "))
+    /// }
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .with_value_filter(tilde_to_space)
+    ///     .into_iter_regular_entry::<Record>();
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.fields.title, "A Title");
+    /// assert_eq!(record.fields.url, "https://example.org/~user");
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .with_value_filter(tilde_to_space)
+    ///     .verbatim_fields([])
+    ///     .into_iter_regular_entry::<Record>();
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.fields.url, "https://example.org/ user");
+    /// ```
+    pub fn verbatim_fields<I>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = &'r str>,
+    {
+        self.verbatim_fields = fields.into_iter().map(UniCase::new).collect();
+        self
+    }
+
+    /// The options used to deserialize the value of the field `key`.
+    pub(crate) fn field_options(&self, key: &str) -> ValueOptions {
+        self.value_options.for_field(&self.verbatim_fields, key)
+    }
+
+    /// Replace regular entry types using the provided `(from, to)` pairs before they are
+    /// deserialized.
+    ///
+    /// Entry types are matched case-insensitively, and later aliases take precedence over earlier
+    /// ones. For example, [`BIBTEX_TO_BIBLATEX`](crate::ser::BIBTEX_TO_BIBLATEX) maps
+    /// `@conference` to `@inproceedings`.
+    pub fn with_entry_type_aliases<I>(mut self, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = (&'r str, &'r str)>,
+    {
+        self.entry_types.extend(
+            aliases
+                .into_iter()
+                .map(|(from, to)| (UniCase::new(from), to)),
+        );
+        self
+    }
+
+    /// Set how to handle an `@string` entry which redefines a macro with a different value. The
+    /// default is [`MacroRedefinitionPolicy::Overwrite`].
+    ///
+    /// The policy applies to macros which are captured automatically, such as when deserializing
+    /// with [`Deserializer::into_iter_regular_entry`] or into a unit `Macro` variant.
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use serde::de::IgnoredAny;
+    /// use serde_bibtex::de::{Deserializer, MacroRedefinitionPolicy};
+    ///
+    /// let input = "@string{pub = {AMS}} @string{PUB = {SIAM}} @misc{key}";
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .macro_redefinition(MacroRedefinitionPolicy::Error)
+    ///     .into_iter_regular_entry::<IgnoredAny>();
+    /// assert!(iter.next().unwrap().is_err());
+    ///
+    /// let warnings = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&warnings);
+    /// let iter = Deserializer::from_str(input)
+    ///     .macro_redefinition(MacroRedefinitionPolicy::Warn(Box::new(move |redefinition| {
+    ///         sink.lock()
+    ///             .unwrap()
+    ///             .push((redefinition.previous.clone(), redefinition.span.clone()))
+    ///     })))
+    ///     .into_iter_regular_entry::<IgnoredAny>();
+    /// assert_eq!(iter.count(), 1);
+    /// assert_eq!(*warnings.lock().unwrap(), vec![(Some(0..20), 21..42)]);
+    /// ```
+    pub fn macro_redefinition(mut self, policy: MacroRedefinitionPolicy) -> Self {
+        self.macro_redefinition = policy;
+        self
+    }
+
+    /// Set how to handle a field key which does not match any variant when the field keys are
+    /// deserialized as an enum, for instance into a `HashMap<FieldKey, String>`. The default is
+    /// [`UnknownFieldKeyPolicy::Error`].
+    ///
+    /// Field keys are matched with the variants case-insensitively.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::{Deserializer, UnknownFieldKeyPolicy};
+    ///
+    /// #[derive(Debug, PartialEq, Eq, Hash, Deserialize)]
+    /// #[serde(rename_all = "lowercase")]
+    /// enum FieldKey {
+    ///     Author,
+    ///     Title,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     fields: HashMap<FieldKey, String>,
+    /// }
+    ///
+    /// let input = "@book{key, Title = {T}, year = 2024, author = {A}}";
+    ///
+    /// let mut iter = Deserializer::from_str(input).into_iter_regular_entry::<Record>();
+    /// assert!(iter.next().unwrap().is_err());
+    ///
+    /// let skipped = Arc::new(Mutex::new(Vec::new()));
+    /// let sink = Arc::clone(&skipped);
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .unknown_field_keys(UnknownFieldKeyPolicy::Warn(Box::new(move |key| {
+    ///         sink.lock().unwrap().push(key.to_owned())
+    ///     })))
+    ///     .into_iter_regular_entry::<Record>();
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(
+    ///     record.fields,
+    ///     HashMap::from([(FieldKey::Author, "A".into()), (FieldKey::Title, "T".into())])
+    /// );
+    /// assert_eq!(*skipped.lock().unwrap(), vec!["year"]);
+    /// ```
+    pub fn unknown_field_keys(mut self, policy: UnknownFieldKeyPolicy) -> Self {
+        self.unknown_field_keys = policy;
+        self
+    }
+
+    /// Call `progress` with the number of bytes consumed and the total length of the input
+    /// before each entry is read, and once the end of the input is reached.
+    ///
+    /// This is useful to display the progress of a long-running import, since the input is
+    /// read incrementally. Entries which are skipped count as consumed.
+    /// ```
+    /// use std::sync::Mutex;
+    ///
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     entry_key: String,
+    /// }
+    ///
+    /// let input = "@misc{a,}\n@misc{b,}\n";
+    /// let reports = Mutex::new(Vec::new());
+    ///
+    /// let keys: Vec<String> = Deserializer::from_str(input)
+    ///     .with_progress(|consumed, total| reports.lock().unwrap().push((consumed, total)))
+    ///     .into_iter_regular_entry::<Record>()
+    ///     .map(|record| record.map(|record| record.entry_key))
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(keys, ["a", "b"]);
+    /// assert_eq!(
+    ///     reports.into_inner().unwrap(),
+    ///     [(0, 20), (9, 20), (19, 20), (20, 20)]
+    /// );
+    /// ```
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(usize, usize) + Send + 'r,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Repair text tokens with unbalanced brackets instead of returning an error, calling `warn`
+    /// with each [`Repair`] before the next entry is read and once the end of the input is
+    /// reached.
+    ///
+    /// This matches the behaviour of resilient importers with messy exported files. The following
+    /// heuristics are applied to bracketed and quoted field values:
+    /// - A closing bracket `}` without a matching opening bracket is kept as text, unless it is
+    ///   followed by the end of the value.
+    /// - A token which is still open at a line which begins a new field, such as `year =`,
+    ///   following a line ending with `,`, or at a line which ends the entry, is closed at the end
+    ///   of the preceding line.
+    ///
+    /// The repaired text may itself contain unbalanced brackets. Note that a value which spans
+    /// several lines, one of which looks like the start of a new field, can be cut short by the
+    /// second heuristic, so repairs are disabled by default.
+    /// ```
+    /// use std::sync::Mutex;
+    ///
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::{Deserializer, Repair};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Fields {
+    ///     title: String,
+    ///     year: String,
+    /// }
+    ///
+    /// let input = "@article{key,\n  title = {The {TeX}book,\n  year = {1984},\n}\n";
+    ///
+    /// let mut iter = Deserializer::from_str(input).into_iter_regular_entry::<Record>();
+    /// assert!(iter.next().unwrap().is_err());
+    ///
+    /// let repairs = Mutex::new(Vec::new());
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .repair_brackets(|repair| repairs.lock().unwrap().push(repair.clone()))
+    ///     .into_iter_regular_entry::<Record>();
+    /// let record = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.fields.title, "The {TeX}book");
+    /// assert_eq!(record.fields.year, "1984");
+    /// assert!(iter.next().is_none());
+    /// drop(iter);
+    /// assert_eq!(
+    ///     repairs.into_inner().unwrap(),
+    ///     [Repair::Unclosed { span: 25..38 }]
+    /// );
+    /// ```
+    pub fn repair_brackets<F>(mut self, warn: F) -> Self
+    where
+        F: FnMut(&Repair) + Send + 'r,
+    {
+        self.parser.set_repair_brackets(true);
+        self.repair_warning = Some(Box::new(warn));
+        self
+    }
+
+    /// Check `token` before each entry and field and between the tokens of each value, returning
+    /// an error of kind [`ErrorKind::Cancelled`](crate::error::ErrorKind::Cancelled) once it is
+    /// cancelled.
+    ///
+    /// Cancellation is cooperative: a text token or macro which is being read when cancellation is
+    /// requested is read to completion first.
+    /// ```
+    /// use serde::de::IgnoredAny;
+    /// use serde_bibtex::de::{CancellationToken, Deserializer};
+    /// use serde_bibtex::error::ErrorKind;
+    ///
+    /// let token = CancellationToken::new();
+    /// let mut iter = Deserializer::from_str("@misc{a,} @misc{b,}")
+    ///     .with_cancellation(token.clone())
+    ///     .into_iter_regular_entry::<IgnoredAny>();
+    ///
+    /// assert!(iter.next().unwrap().is_ok());
+    /// token.cancel();
+    /// let err = iter.next().unwrap().unwrap_err();
+    /// assert!(matches!(err.kind(), ErrorKind::Cancelled));
+    /// ```
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.parser.set_cancellation(Arc::clone(&token.0));
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Set whether [`Stats`] about the input are collected, to be returned by
+    /// [`Deserializer::finish_with_stats`]. The default is `false`.
+    /// ```
+    /// use serde::{de::IgnoredAny, Deserialize};
+    /// use serde_bibtex::de::Deserializer;
+    ///
+    /// let input = r#"
+    ///     @string{a = {A}}
+    ///     junk
+    ///     @article{x, title = a, year = 2024}
+    ///     @Article{y, title = {B}}
+    ///     @book{z,}
+    /// "#;
+    ///
+    /// let mut de = Deserializer::from_str(input).collect_stats(true);
+    /// Vec::<IgnoredAny>::deserialize(&mut de).unwrap();
+    ///
+    /// let (_, stats) = de.finish_with_stats();
+    /// assert_eq!(stats.entries, 4);
+    /// assert_eq!(stats.entry_types["article"], 2);
+    /// assert_eq!(stats.entry_types["book"], 1);
+    /// assert_eq!(stats.macros, 1);
+    /// assert_eq!(stats.fields, 3);
+    /// assert_eq!(stats.junk_bytes, 4);
+    /// ```
+    pub fn collect_stats(mut self, enable: bool) -> Self {
+        self.stats = enable.then(Stats::default);
+        self
+    }
+
+    /// Return an error if deserialization was cancelled.
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(Error::cancelled()),
+            _ => Ok(()),
         }
     }
 
+    /// Read the type of the next entry along with the position of the `@` which starts the
+    /// entry, reporting the progress before the entry and at the end of the input.
+    pub(crate) fn next_entry_type_with_start(
+        &mut self,
+    ) -> Result<Option<(usize, EntryType<&'r str>)>> {
+        self.check_cancelled()?;
+        self.report_repairs();
+        self.parser.byte_order_mark()?;
+        let start = self.parser.position();
+        self.report_progress();
+        let entry = self.parser.entry_type_with_start()?;
+        if entry.is_none() && self.parser.position() != start {
+            self.report_progress();
+        }
+        if let Some((at, _)) = &entry {
+            self.entry_start = *at;
+        }
+        if let Some(stats) = &mut self.stats {
+            let end = entry.as_ref().map_or(self.parser.position(), |(at, _)| *at);
+            stats.junk_bytes += self
+                .parser
+                .source(start..end)
+                .iter()
+                .filter(|b| !b.is_ascii_whitespace())
+                .count();
+            if let Some((_, entry_type)) = &entry {
+                stats.record_entry(entry_type);
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Read the next field key, or `None` if there are no more fields.
+    pub(crate) fn next_field_key(&mut self) -> Result<Option<FieldKey<&'r str>>> {
+        let field_key = self.parser.field_or_terminal()?;
+        if let (Some(stats), Some(_)) = (&mut self.stats, &field_key) {
+            stats.fields += 1;
+        }
+        Ok(field_key)
+    }
+
+    /// Ignore the remaining fields in a regular entry.
+    pub(crate) fn ignore_fields(&mut self) -> Result<()> {
+        while self.next_field_key()?.is_some() {
+            self.parser.field_sep()?;
+            self.parser.ignore_value()?;
+        }
+        Ok(())
+    }
+
+    /// Ignore the contents of a regular entry.
+    pub(crate) fn ignore_regular_entry(&mut self) -> Result<()> {
+        let closing_bracket = self.parser.initial()?;
+        self.parser.entry_key()?;
+        self.ignore_fields()?;
+        self.parser.comma_opt();
+        self.parser.terminal(closing_bracket)
+    }
+
+    /// Ignore the contents of an entry.
+    pub(crate) fn ignore_entry(&mut self, entry_type: EntryType<&'r str>) -> Result<()> {
+        match entry_type {
+            EntryType::Regular(_) => self.ignore_regular_entry(),
+            entry_type => self.parser.ignore_entry(entry_type),
+        }
+    }
+
+    /// Read the type of the next entry, reporting the progress.
+    pub(crate) fn next_entry_type(&mut self) -> Result<Option<EntryType<&'r str>>> {
+        Ok(self
+            .next_entry_type_with_start()?
+            .map(|(_, entry_type)| entry_type))
+    }
+
+    /// Call the repair warning with the repairs made since the repairs were last reported.
+    pub(crate) fn report_repairs(&mut self) {
+        if let Some(warn) = &mut self.repair_warning {
+            for repair in self.parser.take_repairs() {
+                warn(&repair);
+            }
+        }
+    }
+
+    fn report_progress(&mut self) {
+        if let Some(progress) = &mut self.progress {
+            progress(self.parser.position(), self.parser.input_len());
+        }
+    }
+
+    /// Capture the macro defined by an `@string` entry, applying the
+    /// [redefinition policy](Deserializer::macro_redefinition).
+    pub(crate) fn capture_macro(&mut self) -> Result<()> {
+        if matches!(self.macro_redefinition, MacroRedefinitionPolicy::Overwrite) {
+            return self
+                .parser
+                .ignore_macro_captured(Arc::make_mut(&mut self.macros));
+        }
+
+        let closing_bracket = self.parser.initial()?;
+        let Some(name) = self.parser.macro_variable_opt()? else {
+            return self.parser.terminal(closing_bracket);
+        };
+        let mut tokens = Vec::new();
+        self.parser.field_sep()?;
+        self.parser.value_into(&mut tokens)?;
+        self.macros
+            .resolve_with(&mut tokens, &mut self.resolve_scratch);
+        self.parser.comma_opt();
+        self.parser.terminal(closing_bracket)?;
+        let span = self.entry_start..self.parser.position();
+        self.define_macro(name, tokens, span)
+    }
+
+    fn define_macro(
+        &mut self,
+        name: Variable<&'r str>,
+        tokens: Vec<Token<&'r str, &'r [u8]>>,
+        span: Range<usize>,
+    ) -> Result<()> {
+        match self.macros.get(&name) {
+            Some(existing) if existing == tokens.as_slice() => return Ok(()),
+            Some(_) => match &mut self.macro_redefinition {
+                MacroRedefinitionPolicy::Overwrite => {}
+                MacroRedefinitionPolicy::KeepFirst => return Ok(()),
+                MacroRedefinitionPolicy::Error => {
+                    return Err(Error::duplicate_macro(name.into_inner().to_owned()))
+                }
+                MacroRedefinitionPolicy::Warn(warn) => warn(&MacroRedefinition {
+                    name: name.as_ref(),
+                    previous: self.macro_spans.get(&name).cloned(),
+                    span: span.clone(),
+                }),
+            },
+            None => {}
+        }
+        self.macro_spans.insert(name.clone(), span);
+        Arc::make_mut(&mut self.macros).insert_raw_tokens(name, tokens);
+        Ok(())
+    }
+
+    /// Apply the entry type aliases to `entry_type`.
+    pub(crate) fn resolve_entry_type(&self, entry_type: &'r str) -> &'r str {
+        self.entry_types
+            .iter()
+            .rev()
+            .find(|(from, _)| *from == UniCase::new(entry_type))
+            .map_or(entry_type, |(_, to)| to)
+    }
+
     /// Returns an iterator over the entries in the underlying BibTeX data.
     ///
     /// Note that a [`Deserializer`] does not implement [`IntoIterator`] because of lifetime
@@ -89,6 +1038,49 @@ where
         }
     }
 
+    /// Returns an iterator over the entries in the underlying BibTeX data, along with the byte
+    /// range of each entry in the input.
+    ///
+    /// The range extends from the `@` up to and including the closing bracket, so it can be used
+    /// to edit the input directly, such as to replace or delete a single entry without
+    /// serializing the entire bibliography again.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// enum Entry<'a> {
+    ///     Regular {
+    ///         entry_key: &'a str,
+    ///     },
+    ///     Macro,
+    ///     Comment,
+    ///     Preamble,
+    /// }
+    ///
+    /// let input = "@string{ams = {AMS}}\n@book{old, title = {Old}}\n@book{new, title = {New}}\n";
+    ///
+    /// let range = Deserializer::from_str(input)
+    ///     .into_iter_spanned::<Entry>()
+    ///     .map(Result::unwrap)
+    ///     .find(|entry| matches!(entry.value, Entry::Regular { entry_key: "old" }))
+    ///     .unwrap()
+    ///     .range;
+    ///
+    /// let mut output = input.to_owned();
+    /// output.replace_range(range, "@book{old, title = {Replaced}}");
+    /// assert_eq!(
+    ///     output,
+    ///     "@string{ams = {AMS}}\n@book{old, title = {Replaced}}\n@book{new, title = {New}}\n"
+    /// );
+    /// ```
+    pub fn into_iter_spanned<D: de::Deserialize<'r>>(self) -> DeserializeSpannedIter<'r, R, D> {
+        DeserializeSpannedIter {
+            de: self,
+            _output: PhantomData,
+        }
+    }
+
     /// Returns an iterator over the regular entries of the underlying BibTeX data, ignoring
     /// entries which are not regular entries but automatically capturing and expanding macros.
     pub fn into_iter_regular_entry<D: de::Deserialize<'r>>(
@@ -100,159 +1092,881 @@ where
         }
     }
 
+    /// Returns an iterator over the regular entries whose entry type is accepted by `filter`,
+    /// ignoring every other entry but automatically capturing and expanding macros.
+    ///
+    /// The filter is called with the entry type, after applying any
+    /// [aliases](Deserializer::with_entry_type_aliases). Entries which are not accepted are
+    /// skipped without being deserialized, which is much faster than deserializing every entry
+    /// and discarding the unwanted ones.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record<'a> {
+    ///     entry_key: &'a str,
+    /// }
+    ///
+    /// let input = r#"
+    ///     @article{a, title = {A}}
+    ///     @book{b, title = {B}}
+    ///     @InProceedings{c, title = {C}}
+    /// "#;
+    ///
+    /// let keys = Deserializer::from_str(input)
+    ///     .into_iter_filtered::<Record, _>(|entry_type| {
+    ///         ["article", "inproceedings"]
+    ///             .iter()
+    ///             .any(|accepted| entry_type.eq_ignore_ascii_case(accepted))
+    ///     })
+    ///     .map(|record| record.map(|record| record.entry_key))
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(keys, ["a", "c"]);
+    /// ```
+    pub fn into_iter_filtered<D, F>(self, filter: F) -> DeserializeFilteredIter<'r, R, D, F>
+    where
+        D: de::Deserialize<'r>,
+        F: FnMut(&str) -> bool,
+    {
+        DeserializeFilteredIter {
+            de: self,
+            filter,
+            _output: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the regular entries of the underlying BibTeX data, along with the
+    /// [`RawEntry`] from which each entry was read, capturing and expanding macros as with
+    /// [`Deserializer::into_iter_regular_entry`].
+    ///
+    /// The raw entry is returned even when the entry cannot be deserialized, so that an
+    /// application can fall back to showing or storing the text of the entry rather than losing
+    /// it. After an error, reading resumes after the end of the failed entry. If the entry is not
+    /// even syntactically valid, the raw entry extends up to the next line which begins with `@`.
+    /// An error which does not belong to an entry, such as
+    /// [cancellation](Deserializer::with_cancellation), is returned with an empty raw entry and
+    /// ends the iteration.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     entry_key: String,
+    ///     fields: Fields,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Fields {
+    ///     year: String,
+    /// }
+    ///
+    /// let input = r#"
+    /// @string{y = 2024}
+    /// @misc{a, year = y}
+    /// @misc{b, title = {Undated}}
+    /// @misc{c, year = }
+    /// @misc{d, year = 1999}
+    /// "#;
+    ///
+    /// let mut iter = Deserializer::from_str(input).into_iter_try_parse::<Record>();
+    ///
+    /// let (record, raw) = iter.next().unwrap();
+    /// assert_eq!(record.unwrap().fields.year, "2024");
+    /// assert_eq!(raw.as_str(), Some("@misc{a, year = y}"));
+    ///
+    /// let (record, raw) = iter.next().unwrap();
+    /// assert!(record.is_err());
+    /// assert_eq!(raw.as_str(), Some("@misc{b, title = {Undated}}"));
+    ///
+    /// let (record, raw) = iter.next().unwrap();
+    /// assert!(record.is_err());
+    /// assert_eq!(raw.as_str(), Some("@misc{c, year = }"));
+    ///
+    /// let (record, _) = iter.next().unwrap();
+    /// assert_eq!(record.unwrap().entry_key, "d");
+    /// assert!(iter.next().is_none());
+    /// ```
+    pub fn into_iter_try_parse<D: de::Deserialize<'r>>(self) -> DeserializeTryParseIter<'r, R, D> {
+        DeserializeTryParseIter {
+            de: self,
+            done: false,
+            _output: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over at most the first `n` regular entries of the underlying BibTeX
+    /// data. No input is read after the `n`th regular entry.
+    pub fn take<D: de::Deserialize<'r>>(
+        self,
+        n: usize,
+    ) -> std::iter::Take<DeserializeRegularEntryIter<'r, R, D>> {
+        self.into_iter_regular_entry().take(n)
+    }
+
+    /// Deserialize the underlying BibTeX data, which must contain exactly one entry.
+    ///
+    /// Junk characters before the entry are ignored as usual, but only whitespace and comments
+    /// may follow it.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Record {
+    ///     entry_type: String,
+    ///     entry_key: String,
+    /// }
+    ///
+    /// let record: Record = Deserializer::from_str("@article{key, title = {T}}\n")
+    ///     .single_entry()
+    ///     .unwrap();
+    /// assert_eq!(record.entry_key, "key");
+    ///
+    /// let result: Result<Record, _> = Deserializer::from_str("@article{a,} @article{b,}")
+    ///     .single_entry();
+    /// assert!(result.is_err());
+    /// ```
+    pub fn single_entry<D: de::Deserialize<'r>>(mut self) -> Result<D> {
+        let result = self.single_entry_inner();
+        self.locate(result)
+    }
+
+    fn single_entry_inner<D: de::Deserialize<'r>>(&mut self) -> Result<D> {
+        let entry = self
+            .next_entry_type()?
+            .ok_or_else(|| Error::syntax(ErrorCode::ExpectedEntry))?;
+        let value = D::deserialize(EntryDeserializer::new(self, entry))?;
+        self.report_repairs();
+        self.parser.comment();
+        match self.parser.peek() {
+            Some(_) => Err(Error::syntax(ErrorCode::TrailingCharacters)),
+            None => Ok(value),
+        }
+    }
+
+    /// Returns an iterator over the regular entries with the given entry keys, in the order in
+    /// which they appear in the underlying BibTeX data. Entry keys are compared
+    /// case-insensitively.
+    ///
+    /// Entries with other keys are skipped without being deserialized, and no input is read after
+    /// every requested key is found. Only the first entry with each key is returned. Macros are
+    /// captured and expanded as with [`Deserializer::into_iter_regular_entry`].
+    pub fn into_iter_keys<D, I, K>(self, keys: I) -> DeserializeKeysIter<'r, R, D>
+    where
+        D: de::Deserialize<'r>,
+        I: IntoIterator<Item = K>,
+        K: Into<String>,
+    {
+        DeserializeKeysIter {
+            de: self,
+            keys: keys
+                .into_iter()
+                .map(|key| UniCase::new(key.into()))
+                .collect(),
+            _output: PhantomData,
+        }
+    }
+
+    /// Call `f` with the entry key, field key, and tokens of each field of each regular entry,
+    /// without deserializing the entries.
+    ///
+    /// The tokens are read directly from the input as the [`TokenIter`] is advanced, so that
+    /// no value is copied or allocated: this is the fastest way to inspect the field values of a
+    /// large bibliography. Macros are captured but not expanded, so a macro in a value is
+    /// provided as a [`Token::Variable`]. Tokens which are not consumed by `f` are skipped.
+    /// ```
+    /// use serde_bibtex::{de::Deserializer, token::{Text, Token}};
+    ///
+    /// let input = r#"
+    ///     @article{a, title = {A}, doi = {10.1000/xyz}}
+    ///     @book{b, title = {B}, doi = "10.2000/" # {abc}}
+    /// "#;
+    ///
+    /// let mut matches = Vec::new();
+    /// Deserializer::from_str(input)
+    ///     .for_each_value(|entry_key, field_key, tokens| {
+    ///         if field_key.eq_ignore_ascii_case("doi")
+    ///             && tokens.any(|token| matches!(token, Token::Text(Text::Str(s)) if s.contains("xyz")))
+    ///         {
+    ///             matches.push(entry_key);
+    ///         }
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(matches, ["a"]);
+    /// ```
+    pub fn for_each_value<F>(&mut self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&'r str, &'r str, &mut TokenIter<'_, 'r, R>),
+    {
+        let result = self.for_each_value_inner(&mut f);
+        self.locate(result)
+    }
+
+    fn for_each_value_inner<F>(&mut self, f: &mut F) -> Result<()>
+    where
+        F: FnMut(&'r str, &'r str, &mut TokenIter<'_, 'r, R>),
+    {
+        while let Some(entry) = self.next_entry_type()? {
+            match entry {
+                EntryType::Macro => self.capture_macro()?,
+                EntryType::Regular(_) => {
+                    let closing_bracket = self.parser.initial()?;
+                    let entry_key = self.parser.entry_key()?.into_inner();
+                    self.for_each_field(entry_key, f)
+                        .map_err(|err| err.in_entry(entry_key))?;
+                    self.parser.comma_opt();
+                    self.parser
+                        .terminal(closing_bracket)
+                        .map_err(|err| err.in_entry(entry_key))?;
+                }
+                chunk => self.ignore_entry(chunk)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn for_each_field<F>(&mut self, entry_key: &'r str, f: &mut F) -> Result<()>
+    where
+        F: FnMut(&'r str, &'r str, &mut TokenIter<'_, 'r, R>),
+    {
+        while let Some(field_key) = self.next_field_key()? {
+            self.check_cancelled()?;
+            let field_key = field_key.into_inner();
+            self.parser.field_sep()?;
+            let mut tokens = TokenIter::new(&mut self.parser);
+            f(entry_key, field_key, &mut tokens);
+            tokens.finish().map_err(|err| err.in_field(field_key))?;
+        }
+        Ok(())
+    }
+
+    /// Ignore the remaining entries, while still checking validity.
+    fn ignore_bibliography(&mut self) -> Result<()> {
+        while let Some(entry_type) = self.next_entry_type()? {
+            self.ignore_entry(entry_type)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve the macros in `tokens`, recording each expanded macro if usage is tracked.
+    pub(crate) fn resolve(&mut self, tokens: &mut Vec<Token<&'r str, &'r [u8]>>) {
+        if let Some(usage) = &mut self.macro_usage {
+            for token in tokens.iter() {
+                if let Token::Variable(var) = token {
+                    if self.macros.get(var).is_some() && !usage.contains(var) {
+                        usage.push(var.clone());
+                    }
+                }
+            }
+        }
+        self.macros.resolve_with(tokens, &mut self.resolve_scratch);
+    }
+
+    /// Take an empty token buffer, reusing the allocation of a recycled buffer if possible.
+    pub(crate) fn take_token_buffer(&mut self) -> Vec<Token<&'r str, &'r [u8]>> {
+        self.token_buffers.pop().unwrap_or_default()
+    }
+
+    /// Return a token buffer which is no longer needed, so that its allocation can be reused.
+    pub(crate) fn recycle_token_buffer(&mut self, mut tokens: Vec<Token<&'r str, &'r [u8]>>) {
+        tokens.clear();
+        self.token_buffers.push(tokens);
+    }
+
+    /// Resolve the macros in the scratch buffer.
+    pub(crate) fn resolve_scratch(&mut self) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        self.resolve(&mut scratch);
+        self.scratch = scratch;
+    }
+
+    /// Record the current position in the input on a syntax error.
+    pub(crate) fn locate<T>(&self, result: Result<T>) -> Result<T> {
+        result.map_err(|err| self.parser.locate(err))
+    }
+
+    /// Deserialize the next regular entry whose entry type is accepted by `accept`, capturing
+    /// macros and ignoring every other entry.
+    pub(crate) fn next_regular_entry<D, F>(&mut self, accept: F) -> Option<Result<D>>
+    where
+        D: de::Deserialize<'r>,
+        F: FnMut(&str) -> bool,
+    {
+        self.next_regular_entry_spanned(accept)
+            .map(|res| res.map(|(_, entry)| entry))
+    }
+
+    /// As [`Deserializer::next_regular_entry`], also returning the byte range of the entry in the
+    /// input, from the `@` up to and including the closing bracket.
+    pub(crate) fn next_regular_entry_spanned<D, F>(
+        &mut self,
+        mut accept: F,
+    ) -> Option<Result<(Range<usize>, D)>>
+    where
+        D: de::Deserialize<'r>,
+        F: FnMut(&str) -> bool,
+    {
+        loop {
+            let result = match self.next_entry_type_with_start() {
+                Ok(Some((_, EntryType::Macro))) => self.capture_macro(),
+                Ok(Some((_, EntryType::Comment))) => self.parser.ignore_comment(),
+                Ok(Some((_, EntryType::Preamble))) => self.parser.ignore_preamble(),
+                Ok(Some((start, EntryType::Regular(entry_type)))) => {
+                    let entry_type = entry_type.into_inner();
+                    if accept(self.resolve_entry_type(entry_type)) {
+                        let entry = D::deserialize(RegularEntryDeserializer::new(self, entry_type));
+                        return Some(entry.map(|entry| (start..self.parser.position(), entry)));
+                    }
+                    self.ignore_regular_entry()
+                }
+                Ok(None) => return None,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = result {
+                return Some(Err(err));
+            }
+        }
+    }
+
+    /// The line ending used by the input, as determined by its first line break.
+    pub fn line_ending(&self) -> LineEnding {
+        self.parser.line_ending()
+    }
+
     /// Drop the deserializer, returning the underlying [`MacroDictionary`].
     pub fn finish(self) -> MacroDictionary<&'r str, &'r [u8]> {
         let Self { macros, .. } = self;
-        macros
+        Arc::unwrap_or_clone(macros)
+    }
+
+    /// Drop the deserializer, returning the underlying [`MacroDictionary`] and the [`Stats`]
+    /// about the input which was read.
+    ///
+    /// The statistics are empty unless they were enabled with [`Deserializer::collect_stats`].
+    pub fn finish_with_stats(self) -> (MacroDictionary<&'r str, &'r [u8]>, Stats) {
+        let Self { macros, stats, .. } = self;
+        (Arc::unwrap_or_clone(macros), stats.unwrap_or_default())
+    }
+
+    /// Drop the deserializer, returning the underlying [`MacroDictionary`] and the line ending
+    /// used by the input.
+    ///
+    /// The line ending can be passed to [`Serializer::line_ending`](crate::ser::Serializer::line_ending)
+    /// so that the output uses the same convention as the input.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::{de::Deserializer, LineEnding};
+    ///
+    /// #[derive(Deserialize)]
+    /// enum Entry {
+    ///     Macro,
+    /// }
+    ///
+    /// let mut de = Deserializer::from_str("@string{a = {A}}\r\n@string{b = {B}}\r\n");
+    /// let entries = Vec::<Entry>::deserialize(&mut de).unwrap();
+    /// assert_eq!(entries.len(), 2);
+    ///
+    /// let (macros, line_ending) = de.finish_with_line_ending();
+    /// assert_eq!(macros.into_inner().len(), 2);
+    /// assert_eq!(line_ending, LineEnding::CrLf);
+    /// ```
+    pub fn finish_with_line_ending(self) -> (MacroDictionary<&'r str, &'r [u8]>, LineEnding) {
+        let line_ending = self.line_ending();
+        (self.finish(), line_ending)
+    }
+}
+
+impl<'a, 'de: 'a, R> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
+where
+    R: BibtexParse<'de>,
+{
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_seq(self)
+    }
+
+    #[inline]
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let result = self.ignore_bibliography();
+        self.locate(result)?;
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option newtype_struct seq tuple
+        tuple_struct map struct enum identifier
+    }
+}
+
+impl<'a, 'de: 'a, R> SeqAccess<'de> for &'a mut Deserializer<'de, R>
+where
+    R: BibtexParse<'de>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let result = match self.next_entry_type() {
+            Ok(Some(entry)) => seed
+                .deserialize(EntryDeserializer::new(&mut **self, entry))
+                .map(Some),
+            Ok(None) => Ok(None),
+            Err(err) => Err(err),
+        };
+        self.locate(result)
+    }
+}
+
+/// A lazy iterator over BibTeX entries.
+///
+/// The recommended way to construct this struct is to use the [`Deserializer::into_iter`] method.
+/// To only iterate over regular entries, see [`DeserializeRegularEntryIter`].
+/// To deserialize into an arbitrary wrapper type, see [`Deserializer`].
+pub struct DeserializeIter<'r, R, D>
+where
+    R: BibtexParse<'r>,
+    D: de::Deserialize<'r>,
+{
+    de: Deserializer<'r, R>,
+    _output: PhantomData<D>,
+}
+
+impl<'de, R, D> Iterator for DeserializeIter<'de, R, D>
+where
+    R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
+{
+    type Item = Result<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match self.de.next_entry_type() {
+            Ok(Some(entry)) => Some(D::deserialize(EntryDeserializer::new(&mut self.de, entry))),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        };
+        result.map(|res| self.de.locate(res))
+    }
+}
+
+/// A value along with the byte range in the input from which it was read, as returned by
+/// [`Deserializer::into_iter_spanned`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<D> {
+    /// The deserialized value.
+    pub value: D,
+    /// The byte range of the entry in the input, from the `@` up to and including the closing
+    /// bracket.
+    pub range: Range<usize>,
+}
+
+/// A lazy iterator over BibTeX entries, along with the byte range of each entry in the input.
+///
+/// The recommended way to construct this struct is to use the
+/// [`Deserializer::into_iter_spanned`] method.
+pub struct DeserializeSpannedIter<'r, R, D>
+where
+    R: BibtexParse<'r>,
+    D: de::Deserialize<'r>,
+{
+    de: Deserializer<'r, R>,
+    _output: PhantomData<D>,
+}
+
+impl<'de, R, D> Iterator for DeserializeSpannedIter<'de, R, D>
+where
+    R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
+{
+    type Item = Result<Spanned<D>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match self.de.next_entry_type_with_start() {
+            Ok(Some((start, entry))) => Some(
+                D::deserialize(EntryDeserializer::new(&mut self.de, entry)).map(|value| {
+                    let end = self.de.parser.position();
+                    let source = self.de.parser.source(start..end).trim_ascii_end();
+                    Spanned {
+                        value,
+                        range: start..start + source.len(),
+                    }
+                }),
+            ),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        };
+        result.map(|res| self.de.locate(res))
+    }
+}
+
+/// A lazy iterator over BibTeX regular entries.
+///
+/// Note that macros are automatically captured and expanded, when possible.
+///
+/// The recommended way to construct this struct is to use the
+/// [`Deserializer::into_iter_regular_entry`] method.
+/// To also iterate over preamble, comment, or macro entries, see [`DeserializeIter`].
+/// To deserialize into an arbitrary wrapper type, see [`Deserializer`].
+pub struct DeserializeRegularEntryIter<'r, R, D>
+where
+    R: BibtexParse<'r>,
+    D: de::Deserialize<'r>,
+{
+    de: Deserializer<'r, R>,
+    _output: PhantomData<D>,
+}
+
+impl<'de, R, D> Iterator for DeserializeRegularEntryIter<'de, R, D>
+where
+    R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
+{
+    type Item = Result<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.de.next_regular_entry(|_| true);
+        result.map(|res| self.de.locate(res))
     }
 }
 
-impl<'a, 'de: 'a, R> de::Deserializer<'de> for &'a mut Deserializer<'de, R>
+impl<'de, R, D> DeserializeRegularEntryIter<'de, R, D>
 where
     R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
 {
-    type Error = Error;
-
-    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        visitor.visit_seq(self)
+    /// Also return the macros which were expanded while deserializing each entry.
+    ///
+    /// The macros are listed in the order in which they first appear in the entry, without
+    /// repetition. Only macros referenced directly by the entry are listed: a macro used in the
+    /// definition of another macro is not. Undefined macros and macros in fields which are skipped
+    /// by the target type are not expanded, and hence are not listed.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::de::Deserializer;
+    /// use std::collections::BTreeMap;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Record {
+    ///     entry_key: String,
+    ///     fields: BTreeMap<String, String>,
+    /// }
+    ///
+    /// let input = r#"
+    ///     @string{ams = {AMS}}
+    ///     @string{pr = {Providence}}
+    ///     @book{key1, publisher = ams, address = pr # {, RI}}
+    ///     @book{key2, publisher = ams}
+    /// "#;
+    ///
+    /// let mut iter = Deserializer::from_str(input)
+    ///     .into_iter_regular_entry::<Record>()
+    ///     .with_macro_usage();
+    ///
+    /// let (record, macros) = iter.next().unwrap().unwrap();
+    /// assert_eq!(record.entry_key, "key1");
+    /// assert_eq!(macros.iter().map(|var| var.as_ref()).collect::<Vec<_>>(), ["ams", "pr"]);
+    ///
+    /// let (_, macros) = iter.next().unwrap().unwrap();
+    /// assert_eq!(macros.iter().map(|var| var.as_ref()).collect::<Vec<_>>(), ["ams"]);
+    /// ```
+    pub fn with_macro_usage(mut self) -> MacroUsageIter<'de, R, D> {
+        self.de.macro_usage = Some(Vec::new());
+        MacroUsageIter { inner: self }
     }
+}
 
-    #[inline]
-    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_ignored_any(visitor)
-    }
+/// The source of an entry in the input, as returned by [`Deserializer::into_iter_try_parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEntry<'r> {
+    /// The byte range of the entry in the input, from the `@` up to and including the closing
+    /// bracket.
+    pub span: Range<usize>,
+    /// The bytes of the entry.
+    pub source: &'r [u8],
+}
 
-    #[inline]
-    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.deserialize_ignored_any(visitor)
+impl<'r> RawEntry<'r> {
+    /// The source of the entry as a `&str`, or `None` if it is not valid UTF-8.
+    pub fn as_str(&self) -> Option<&'r str> {
+        std::str::from_utf8(self.source).ok()
     }
+}
 
-    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
-    where
-        V: de::Visitor<'de>,
-    {
-        self.parser.ignore_bibliography()?;
-        visitor.visit_unit()
+/// A lazy iterator over BibTeX regular entries, along with the source of each entry, which
+/// continues after entries which cannot be deserialized.
+///
+/// The recommended way to construct this struct is to use the
+/// [`Deserializer::into_iter_try_parse`] method.
+pub struct DeserializeTryParseIter<'r, R, D>
+where
+    R: BibtexParse<'r>,
+    D: de::Deserialize<'r>,
+{
+    de: Deserializer<'r, R>,
+    done: bool,
+    _output: PhantomData<D>,
+}
+
+impl<'de, R, D> DeserializeTryParseIter<'de, R, D>
+where
+    R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
+{
+    fn raw(&self, span: Range<usize>) -> RawEntry<'de> {
+        let source = self.de.parser.source(span.clone()).trim_ascii_end();
+        RawEntry {
+            span: span.start..span.start + source.len(),
+            source,
+        }
     }
 
-    forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf option newtype_struct seq tuple
-        tuple_struct map struct enum identifier
+    /// After an error in the entry whose contents begin at `body`, move to the end of the entry
+    /// and return its position.
+    fn recover(&mut self, body: usize, entry_type: EntryType<&'de str>) -> usize {
+        // the repairs were already made when the entry was first read
+        self.de.report_repairs();
+        self.de.parser.seek(body);
+        let result = self.de.parser.ignore_entry(entry_type);
+        let _ = self.de.parser.take_repairs();
+        if result.is_ok() {
+            return self.de.parser.position();
+        }
+
+        // the entry is invalid, so skip to the next line which begins an entry
+        let len = self.de.parser.input_len();
+        let rest = self.de.parser.source(body..len);
+        let mut offset = 0;
+        let end = loop {
+            match memchr::memchr(b'\n', &rest[offset..]) {
+                Some(newline) => {
+                    offset += newline + 1;
+                    if rest[offset..].trim_ascii_start().starts_with(b"@") {
+                        break body + offset;
+                    }
+                }
+                None => break len,
+            }
+        };
+        self.de.parser.seek(end);
+        end
     }
 }
 
-impl<'a, 'de: 'a, R> SeqAccess<'de> for &'a mut Deserializer<'de, R>
+impl<'de, R, D> Iterator for DeserializeTryParseIter<'de, R, D>
 where
     R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
 {
-    type Error = Error;
+    type Item = (Result<D>, RawEntry<'de>);
 
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
-    where
-        T: DeserializeSeed<'de>,
-    {
-        match self.parser.entry_type()? {
-            Some(entry) => seed
-                .deserialize(EntryDeserializer::new(*self, entry))
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let (start, entry_type) = match self.de.next_entry_type_with_start() {
+                Ok(Some(entry)) => entry,
+                Ok(None) => return None,
+                Err(err) => {
+                    self.done = true;
+                    let position = self.de.parser.position();
+                    return Some((self.de.locate(Err(err)), self.raw(position..position)));
+                }
+            };
+            let body = self.de.parser.position();
+            let result = match entry_type.clone() {
+                EntryType::Macro => self.de.capture_macro().map(|()| None),
+                EntryType::Comment => self.de.parser.ignore_comment().map(|()| None),
+                EntryType::Preamble => self.de.parser.ignore_preamble().map(|()| None),
+                EntryType::Regular(entry_type) => D::deserialize(RegularEntryDeserializer::new(
+                    &mut self.de,
+                    entry_type.into_inner(),
+                ))
                 .map(Some),
-            None => Ok(None),
+            };
+            match self.de.locate(result) {
+                Ok(None) => {}
+                Ok(Some(entry)) => {
+                    let raw = self.raw(start..self.de.parser.position());
+                    return Some((Ok(entry), raw));
+                }
+                Err(err) => {
+                    let end = self.recover(body, entry_type);
+                    return Some((Err(err), self.raw(start..end)));
+                }
+            }
         }
     }
 }
 
-/// A lazy iterator over BibTeX entries.
+/// A lazy iterator over the BibTeX regular entries with specific entry types.
 ///
-/// The recommended way to construct this struct is to use the [`Deserializer::into_iter`] method.
-/// To only iterate over regular entries, see [`DeserializeRegularEntryIter`].
-/// To deserialize into an arbitrary wrapper type, see [`Deserializer`].
-pub struct DeserializeIter<'r, R, D>
+/// The recommended way to construct this struct is to use the
+/// [`Deserializer::into_iter_filtered`] method.
+pub struct DeserializeFilteredIter<'r, R, D, F>
 where
     R: BibtexParse<'r>,
     D: de::Deserialize<'r>,
+    F: FnMut(&str) -> bool,
 {
     de: Deserializer<'r, R>,
+    filter: F,
     _output: PhantomData<D>,
 }
 
-impl<'de, R, D> Iterator for DeserializeIter<'de, R, D>
+impl<'de, R, D, F> Iterator for DeserializeFilteredIter<'de, R, D, F>
 where
     R: BibtexParse<'de>,
     D: de::Deserialize<'de>,
+    F: FnMut(&str) -> bool,
 {
     type Item = Result<D>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.de.parser.entry_type() {
-            Ok(Some(entry)) => Some(D::deserialize(EntryDeserializer::new(&mut self.de, entry))),
-            Ok(None) => None,
-            Err(err) => Some(Err(err)),
-        }
+        let result = self.de.next_regular_entry(&mut self.filter);
+        result.map(|res| self.de.locate(res))
     }
 }
 
-/// A lazy iterator over BibTeX regular entries.
-///
-/// Note that macros are automatically captured and expanded, when possible.
+/// A lazy iterator over BibTeX regular entries, along with the macros expanded in each entry.
 ///
 /// The recommended way to construct this struct is to use the
-/// [`Deserializer::into_iter_regular_entry`] method.
-/// To also iterate over preamble, comment, or macro entries, see [`DeserializeIter`].
-/// To deserialize into an arbitrary wrapper type, see [`Deserializer`].
-pub struct DeserializeRegularEntryIter<'r, R, D>
+/// [`DeserializeRegularEntryIter::with_macro_usage`] method.
+pub struct MacroUsageIter<'r, R, D>
 where
     R: BibtexParse<'r>,
     D: de::Deserialize<'r>,
 {
-    de: Deserializer<'r, R>,
-    _output: PhantomData<D>,
+    inner: DeserializeRegularEntryIter<'r, R, D>,
 }
 
-impl<'de, R, D> Iterator for DeserializeRegularEntryIter<'de, R, D>
+impl<'de, R, D> Iterator for MacroUsageIter<'de, R, D>
 where
     R: BibtexParse<'de>,
     D: de::Deserialize<'de>,
 {
-    type Item = Result<D>;
+    type Item = Result<(D, Vec<Variable<&'de str>>)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.de.parser.entry_type() {
-                Ok(Some(entry)) => match entry {
-                    EntryType::Macro => {
-                        match self.de.parser.ignore_macro_captured(&mut self.de.macros) {
-                            Ok(()) => {}
-                            Err(err) => return Some(Err(err)),
+        let result = self.inner.next()?;
+        let usage = self
+            .inner
+            .de
+            .macro_usage
+            .replace(Vec::new())
+            .unwrap_or_default();
+        Some(result.map(|entry| (entry, usage)))
+    }
+}
+
+/// A lazy iterator over the BibTeX regular entries with specific entry keys.
+///
+/// The recommended way to construct this struct is to use the [`Deserializer::into_iter_keys`]
+/// method.
+pub struct DeserializeKeysIter<'r, R, D>
+where
+    R: BibtexParse<'r>,
+    D: de::Deserialize<'r>,
+{
+    de: Deserializer<'r, R>,
+    keys: Vec<UniCase<String>>,
+    _output: PhantomData<D>,
+}
+
+impl<'de, R, D> DeserializeKeysIter<'de, R, D>
+where
+    R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
+{
+    /// Skip entries until the next requested regular entry, returning the entry type, closing
+    /// bracket, and entry key.
+    fn next_requested(&mut self) -> Result<Option<(&'de str, u8, &'de str)>> {
+        while !self.keys.is_empty() {
+            let Some(entry) = self.de.next_entry_type()? else {
+                return Ok(None);
+            };
+            match entry {
+                EntryType::Macro => self.de.capture_macro()?,
+                EntryType::Regular(entry_type) => {
+                    let closing_bracket = self.de.parser.initial()?;
+                    let entry_key = self.de.parser.entry_key()?.into_inner();
+                    let unicase_key = UniCase::new(entry_key);
+                    match self
+                        .keys
+                        .iter()
+                        .position(|key| UniCase::new(key.as_str()) == unicase_key)
+                    {
+                        Some(idx) => {
+                            self.keys.swap_remove(idx);
+                            return Ok(Some((entry_type.into_inner(), closing_bracket, entry_key)));
+                        }
+                        None => {
+                            self.de.ignore_fields()?;
+                            self.de.parser.comma_opt();
+                            self.de.parser.terminal(closing_bracket)?;
                         }
                     }
-                    EntryType::Comment => match self.de.parser.ignore_comment() {
-                        Ok(()) => {}
-                        Err(err) => return Some(Err(err)),
-                    },
-                    EntryType::Preamble => match self.de.parser.ignore_preamble() {
-                        Ok(()) => {}
-                        Err(err) => return Some(Err(err)),
-                    },
-                    EntryType::Regular(entry_type) => {
-                        return Some(D::deserialize(RegularEntryDeserializer::new(
-                            &mut self.de,
-                            entry_type.into_inner(),
-                        )))
-                    }
-                },
-                Ok(None) => return None,
-                Err(err) => return Some(Err(err)),
+                }
+                chunk => self.de.ignore_entry(chunk)?,
             }
         }
+        Ok(None)
+    }
+}
+
+impl<'de, R, D> Iterator for DeserializeKeysIter<'de, R, D>
+where
+    R: BibtexParse<'de>,
+    D: de::Deserialize<'de>,
+{
+    type Item = Result<D>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = match self.next_requested() {
+            Ok(Some((entry_type, closing_bracket, entry_key))) => {
+                Some(D::deserialize(RegularEntryDeserializer::with_entry_key(
+                    &mut self.de,
+                    entry_type,
+                    closing_bracket,
+                    entry_key,
+                )))
+            }
+            Ok(None) => None,
+            Err(err) => {
+                self.keys.clear();
+                Some(Err(err))
+            }
+        };
+        result.map(|res| self.de.locate(res))
     }
 }
 
@@ -407,6 +2121,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_macro_redefinition() {
+        use std::sync::Mutex;
+
+        let input = "@string{a = {1}}@string{b = a}@string{B = {1}}@string(A = b # {2})";
+        let a = Variable::new_unchecked("a");
+        let value = |policy| {
+            let mut bib_de = Deserializer::new(StrReader::new(input)).macro_redefinition(policy);
+            TestBibCaptureMacro::deserialize(&mut bib_de)
+                .map(|_| bib_de.macros.get(&a).unwrap().to_vec())
+        };
+
+        assert_eq!(
+            value(MacroRedefinitionPolicy::Overwrite).unwrap(),
+            vec![Token::str_unchecked("1"), Token::str_unchecked("2")]
+        );
+        assert_eq!(
+            value(MacroRedefinitionPolicy::KeepFirst).unwrap(),
+            vec![Token::str_unchecked("1")]
+        );
+        let err = value(MacroRedefinitionPolicy::Error).unwrap_err();
+        assert_eq!(err.to_string(), "conflicting definitions of macro 'A'");
+
+        // redefining `b` with the same value is not reported
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&warnings);
+        let policy = MacroRedefinitionPolicy::Warn(Box::new(move |redefinition| {
+            sink.lock().unwrap().push(redefinition.name.to_owned());
+            assert_eq!(redefinition.previous, Some(0..16));
+            assert_eq!(redefinition.span, 46..66);
+        }));
+        assert_eq!(value(policy).unwrap().len(), 2);
+        assert_eq!(*warnings.lock().unwrap(), vec!["A".to_owned()]);
+
+        // predefined macros have no span
+        let mut macros = MacroDictionary::default();
+        macros.insert(a.clone(), vec![Token::str_unchecked("0")]);
+        let mut bib_de = Deserializer::from_str_with_macros("@string{a = {1}}", macros)
+            .macro_redefinition(MacroRedefinitionPolicy::Warn(Box::new(|redefinition| {
+                assert!(redefinition.previous.is_none());
+            })));
+        TestBibCaptureMacro::deserialize(&mut bib_de).unwrap();
+        assert_eq!(
+            bib_de.macros.get(&a).unwrap(),
+            [Token::str_unchecked("1")].as_slice()
+        );
+    }
+
     #[test]
     fn test_string_capturing_ignore() {
         type TypeOnlyBib = Vec<BareEntry>;
@@ -454,6 +2216,172 @@ mod tests {
         assert_eq!(data.unwrap(), expected);
     }
 
+    #[test]
+    fn test_macro_usage() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record<'a> {
+            #[serde(borrow)]
+            entry_key: &'a str,
+            fields: Fields,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Fields {
+            title: String,
+        }
+
+        let input = r#"
+            @string{a = {A}}
+            @string{b = a # {B}}
+            @article{k1, title = b # a # B # {C}, month = a}
+            @comment{unused # a}
+            @article{k2, title = {T}, note = undefined, year = b}
+            @article{k3, title = {T}}
+        "#;
+
+        let mut bib_de = Deserializer::from_str(input)
+            .into_iter_regular_entry::<Record>()
+            .with_macro_usage();
+
+        let usage: Vec<(&str, Vec<&str>)> = bib_de
+            .by_ref()
+            .map(|res| {
+                let (record, usage) = res.unwrap();
+                (
+                    record.entry_key,
+                    usage.into_iter().map(Variable::into_inner).collect(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            usage,
+            vec![("k1", vec!["b", "a"]), ("k2", vec![]), ("k3", vec![])]
+        );
+
+        // errors are returned without usage information
+        let mut bib_de = Deserializer::from_str("@string{a = {A}}@article{k, title = a}@article{")
+            .into_iter_regular_entry::<Record>()
+            .with_macro_usage();
+        assert_eq!(
+            bib_de.next().unwrap().unwrap().1,
+            vec![Variable::new_unchecked("a")]
+        );
+        assert!(bib_de.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_into_iter_filtered() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record<'a> {
+            entry_type: &'a str,
+            entry_key: &'a str,
+            fields: Fields,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Fields {
+            title: String,
+        }
+
+        // skipped entries may contain undefined macros or fields which do not match the record
+        let input = r#"
+            @string{a = {A}}
+            @book{k1, title = undefined, pages = 1}
+            @article{k2, title = a # {B}}
+            @conference{k3, title = {C}}
+            @misc{k4}
+        "#;
+
+        let accepted: Vec<(&str, &str, String)> = Deserializer::from_str(input)
+            .with_entry_type_aliases([("conference", "inproceedings")])
+            .into_iter_filtered::<Record, _>(|entry_type| {
+                entry_type == "article" || entry_type == "inproceedings"
+            })
+            .map(|res| {
+                let record = res.unwrap();
+                (record.entry_type, record.entry_key, record.fields.title)
+            })
+            .collect();
+
+        assert_eq!(
+            accepted,
+            vec![
+                ("article", "k2", "AB".to_owned()),
+                ("inproceedings", "k3", "C".to_owned())
+            ]
+        );
+
+        // syntax errors in skipped entries are still reported
+        let mut iter = Deserializer::from_str("@book{k1, title = }@article{k2, title = {T}}")
+            .into_iter_filtered::<Record, _>(|entry_type| entry_type == "article");
+        assert!(iter.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_skipped_fields_undefined_macros() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record<'a> {
+            entry_key: &'a str,
+            fields: Fields<'a>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Fields<'a> {
+            title: &'a str,
+        }
+
+        let input = r#"
+            @string{m = {May}}
+            @article{k1, month = feb, title = {One}, note = m # undefined}
+            @article{k2, title = {Two}, month = feb,}
+        "#;
+        let expected = vec![
+            Record {
+                entry_key: "k1",
+                fields: Fields { title: "One" },
+            },
+            Record {
+                entry_key: "k2",
+                fields: Fields { title: "Two" },
+            },
+        ];
+
+        let records: Result<Vec<Record>> = Deserializer::from_str(input)
+            .into_iter_regular_entry()
+            .collect();
+        assert_eq!(records.unwrap(), expected);
+
+        let records: Result<Vec<Record>> = Deserializer::from_str(input)
+            .duplicate_fields(DuplicateFieldPolicy::UseLast)
+            .into_iter_regular_entry()
+            .collect();
+        assert_eq!(records.unwrap(), expected);
+
+        let records: Result<Vec<Record>> = Deserializer::from_str(input)
+            .into_iter_keys(["k2"])
+            .collect();
+        assert_eq!(records.unwrap(), expected[1..]);
+
+        // skipped fields do not count as macro usage
+        for res in Deserializer::from_str(input)
+            .into_iter_regular_entry::<Record>()
+            .with_macro_usage()
+        {
+            assert!(res.unwrap().1.is_empty());
+        }
+
+        // captured fields are still resolved
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Month<'a> {
+            month: &'a str,
+        }
+
+        let mut iter = Deserializer::from_str(input)
+            .into_iter_regular_entry::<(IgnoredAny, IgnoredAny, Month)>();
+        assert!(iter.next().unwrap().is_err());
+    }
+
     macro_rules! syntax {
         ($input:expr, $expect:ident) => {
             let reader = StrReader::new($input);
@@ -511,6 +2439,128 @@ mod tests {
         syntax!(r"@comment(})", is_err);
     }
 
+    #[test]
+    fn test_raw_value() {
+        use crate::token::RawValue;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Fields<'a> {
+            #[serde(borrow)]
+            title: RawValue<'a>,
+            #[serde(borrow)]
+            author: Option<Vec<Tok<'a>>>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record<'a> {
+            #[serde(borrow)]
+            fields: Fields<'a>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Entry<'a> {
+            #[serde(borrow)]
+            Regular(Record<'a>),
+            #[serde(borrow)]
+            Preamble(RawValue<'a>),
+        }
+
+        let input = r#"
+            @preamble{ "a" # b }
+            @article{key,
+              title = {A } # var # "B" % comment
+              ,
+              author = a # {b},
+            }
+        "#;
+
+        // undefined macros are preserved, whether or not the fields are buffered
+        for policy in [None, Some(DuplicateFieldPolicy::UseLast)] {
+            let mut de = Deserializer::from_str(input);
+            de.duplicate_fields = policy;
+            let entries: Vec<Entry> = Vec::deserialize(&mut de).unwrap();
+            assert_eq!(
+                entries[0],
+                Entry::Preamble(RawValue::new(r#""a" # b"#).unwrap())
+            );
+            let Entry::Regular(record) = &entries[1] else {
+                panic!("expected a regular entry");
+            };
+            assert_eq!(record.fields.title.as_str(), r#"{A } # var # "B""#);
+            assert_eq!(record.fields.author, Some(vec![Tok::V("a"), Tok::T("b")]));
+        }
+
+        // the raw value is not expanded, even if the macro is defined
+        let fields: Vec<(&str, RawValue)> =
+            Deserializer::from_slice(b"@string{var = {V}} @a{k, t = var, u = 1}")
+                .into_iter_regular_entry::<(&str, &str, Vec<(&str, RawValue)>)>()
+                .next()
+                .unwrap()
+                .unwrap()
+                .2;
+        assert_eq!(
+            fields,
+            vec![
+                ("t", RawValue::new("var").unwrap()),
+                ("u", RawValue::new("1").unwrap())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_entry() {
+        let entry: TestEntryMap = Deserializer::from_str("junk @a{k, t = v} % comment\n\n")
+            .single_entry()
+            .unwrap();
+        assert_eq!(entry.entry_key, "k");
+        assert_eq!(entry.fields["t"], vec![Tok::V("v")]);
+
+        let entry: TestEntryMap = Deserializer::from_slice(b"@a(k)").single_entry().unwrap();
+        assert!(entry.fields.is_empty());
+
+        for (input, code, position) in [
+            ("", "ExpectedEntry", 0),
+            ("  % comment", "ExpectedEntry", 11),
+            ("@a{k,} @b{k,}", "TrailingCharacters", 7),
+            ("@a{k,} junk", "TrailingCharacters", 7),
+        ] {
+            let err = Deserializer::from_str(input)
+                .single_entry::<TestEntryMap>()
+                .unwrap_err();
+            assert_eq!(format!("{:?}", err.code), code);
+            assert_eq!(err.position(), Some(position));
+        }
+
+        // errors in the entry are still reported
+        assert!(Deserializer::from_str("@a{k, t = }")
+            .single_entry::<TestEntryMap>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_line_ending() {
+        for (input, expected) in [
+            ("", LineEnding::Lf),
+            ("@a{k}", LineEnding::Lf),
+            ("@a{k}\n", LineEnding::Lf),
+            ("\r\n@a{k}\n", LineEnding::CrLf),
+            ("@a{k,\r\n t = {A\r\n}}\r\n", LineEnding::CrLf),
+            ("@a{k, t = {\r}}\n", LineEnding::Lf),
+        ] {
+            assert_eq!(Deserializer::from_str(input).line_ending(), expected);
+            assert_eq!(
+                Deserializer::from_slice(input.as_bytes()).line_ending(),
+                expected
+            );
+        }
+
+        let mut de = Deserializer::from_str("@string{a = {A}}\r\n@preamble{a}\r\n");
+        TestBibCaptureMacro::deserialize(&mut de).unwrap();
+        let (macros, line_ending) = de.finish_with_line_ending();
+        assert!(macros.get(&Variable::new("a").unwrap()).is_some());
+        assert_eq!(line_ending, LineEnding::CrLf);
+    }
+
     #[test]
     fn test_comment_syntax() {
         syntax!(r"@comment{{}}", is_ok);
@@ -593,4 +2643,286 @@ mod tests {
         syntax!("@a{k}", is_ok);
         syntax!("@a(k)", is_ok);
     }
+
+    #[test]
+    fn test_progress() {
+        use std::sync::Mutex;
+
+        let input = "@string{u = {v}}\n@comment{c}\n@a{k, a = u}";
+        let reports = Mutex::new(Vec::new());
+        let take = || std::mem::take(&mut *reports.lock().unwrap());
+
+        let mut de = Deserializer::from_str(input)
+            .with_progress(|consumed, total| reports.lock().unwrap().push((consumed, total)));
+        let bib = TypeOnlyBib::deserialize(&mut de).unwrap();
+        drop(de);
+        assert_eq!(bib.len(), 3);
+        assert_eq!(take(), [(0, 41), (16, 41), (28, 41), (41, 41)]);
+
+        // skipped entries are consumed, and an error stops the reports
+        let builder = DeserializerBuilder::new()
+            .with_progress(|consumed, total| reports.lock().unwrap().push((consumed, total)));
+        let mut iter = builder
+            .build_from_str(input)
+            .into_iter_regular_entry::<IgnoredAny>();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().is_none());
+        drop(iter);
+        assert_eq!(take(), [(0, 41), (16, 41), (28, 41), (41, 41)]);
+
+        let mut iter = Deserializer::from_str("@a{k,} @a{")
+            .with_progress(|consumed, total| reports.lock().unwrap().push((consumed, total)))
+            .into_iter::<IgnoredAny>();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        drop(iter);
+        assert_eq!(take(), [(0, 10), (6, 10)]);
+    }
+
+    #[test]
+    fn test_try_parse() {
+        #[derive(Debug, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Fields {
+            #[allow(dead_code)]
+            title: String,
+        }
+
+        let input = "@a{k, note = {x}, title = {a @b{c}}}\n@a{l, title = {t}}\n@a{m, title = {\n  }\n@a{n, title = {u}}";
+        let spans: Vec<_> = Deserializer::from_str(input)
+            .into_iter_try_parse::<(String, String, Fields)>()
+            .map(|(res, raw)| (res.is_ok(), raw.as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (false, "@a{k, note = {x}, title = {a @b{c}}}"),
+                (true, "@a{l, title = {t}}"),
+                (false, "@a{m, title = {\n  }"),
+                (true, "@a{n, title = {u}}"),
+            ]
+        );
+
+        // an error outside of an entry ends the iteration
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut iter = Deserializer::from_str(input)
+            .with_cancellation(token)
+            .into_iter_try_parse::<IgnoredAny>();
+        let (res, raw) = iter.next().unwrap();
+        assert!(res.is_err());
+        assert_eq!(raw.span, 0..0);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_into_iter_spanned() {
+        let input = b"% a\n@string{s = {x}}  @comment{c}\n\n@a{k, title = {a @b{c}}, note = s}\r\n@preamble{\"p\"}";
+        let entries: Vec<_> = Deserializer::from_slice(input)
+            .into_iter_spanned::<IgnoredAny>()
+            .map(|entry| &input[entry.unwrap().range])
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                &b"@string{s = {x}}"[..],
+                b"@comment{c}",
+                b"@a{k, title = {a @b{c}}, note = s}",
+                b"@preamble{\"p\"}",
+            ]
+        );
+
+        let mut iter = Deserializer::from_str("@a{k, t = {x}} @b{l, t = u}").into_iter_spanned::<(
+            String,
+            String,
+            BTreeMap<String, String>,
+        )>();
+        let entry = iter.next().unwrap().unwrap();
+        assert_eq!(entry.value.1, "k");
+        assert_eq!(entry.range, 0..14);
+        assert!(iter.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_repair_brackets() {
+        use std::sync::Mutex;
+
+        let input = b"@a{k, title = \"A}\", note = {B} C}}\n@b{l, t = {unclosed\n}\n";
+        let repairs = Mutex::new(Vec::new());
+
+        let mut de = DeserializerBuilder::new()
+            .repair_brackets(|repair| repairs.lock().unwrap().push(repair.clone()))
+            .build_from_slice(input);
+        let bib: Vec<(String, String, BTreeMap<String, String>)> =
+            Deserialize::deserialize(&mut de).unwrap();
+        drop(de);
+        assert_eq!(bib[0].2["title"], "A}");
+        assert_eq!(bib[0].2["note"], "B} C");
+        assert_eq!(bib[1].2["t"], "unclosed");
+        assert_eq!(
+            repairs.into_inner().unwrap(),
+            [
+                Repair::StrayClosingBracket { position: 16 },
+                Repair::StrayClosingBracket { position: 29 },
+                Repair::Unclosed { span: 46..54 },
+            ]
+        );
+
+        // without repairs, the input is invalid
+        let mut de = Deserializer::from_slice(input);
+        assert!(IgnoredAny::deserialize(&mut de).is_err());
+    }
+
+    #[test]
+    fn test_cancellation() {
+        use crate::error::{Category, ErrorKind};
+
+        #[derive(Debug, Deserialize)]
+        struct Record {
+            #[allow(dead_code)]
+            fields: HashMap<String, String>,
+        }
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        let input = "@a{k1, a = {1}}\n@a{k2, a = {2}, b = {3}}";
+
+        // the token is checked between fields
+        let mut iter = Deserializer::from_str(input)
+            .with_cancellation(token.clone())
+            .with_value_filter(move |_, value| {
+                if value == "2" {
+                    canceller.cancel();
+                }
+                value
+            })
+            .into_iter_regular_entry::<Record>();
+        assert!(iter.next().unwrap().is_ok());
+        let err = iter.next().unwrap().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Cancelled));
+        assert_eq!(err.classify(), Category::Cancelled);
+        assert_eq!(err.entry_key(), Some("k2"));
+        assert!(token.is_cancelled());
+
+        // the token is also checked between the tokens of a value
+        let mut de = Deserializer::from_str("{a} # b # {c}}").with_cancellation(token.clone());
+        let err = de.parser.ignore_value().unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Cancelled));
+        assert!(de.parser.value_into(&mut Vec::new()).is_err());
+
+        // a cancelled token stops deserialization before the first entry
+        let mut de = DeserializerBuilder::new()
+            .with_cancellation(token)
+            .build_from_str(input);
+        assert!(TypeOnlyBib::deserialize(&mut de).is_err());
+    }
+
+    #[test]
+    fn test_for_each_value() {
+        let input = r#"
+            @string{m = {M}}
+            @comment{x = {y}}
+            @a{k1, a = {1} # m, b = "2"}
+            @preamble{{p}}
+            @b(k2, c = 3,)
+        "#;
+        let mut values = Vec::new();
+        let mut de = Deserializer::from_str(input);
+        de.for_each_value(|entry_key, field_key, tokens| {
+            values.push((entry_key, field_key, tokens.collect::<Vec<_>>()));
+        })
+        .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                (
+                    "k1",
+                    "a",
+                    vec![
+                        Token::str_unchecked("1"),
+                        Token::Variable(Variable::new_unchecked("m"))
+                    ]
+                ),
+                ("k1", "b", vec![Token::str_unchecked("2")]),
+                ("k2", "c", vec![Token::str_unchecked("3")]),
+            ]
+        );
+        // macros are still captured
+        assert_eq!(de.finish().into_inner().len(), 1);
+
+        // unconsumed tokens are skipped
+        let mut keys = Vec::new();
+        Deserializer::from_str(input)
+            .for_each_value(|_, field_key, _| keys.push(field_key))
+            .unwrap();
+        assert_eq!(keys, ["a", "b", "c"]);
+
+        // syntax errors in a value are reported in the field
+        let err = Deserializer::from_str("@a{k, a = {1} # {2} {3}}")
+            .for_each_value(|_, _, tokens| {
+                tokens.next();
+            })
+            .unwrap_err();
+        assert_eq!(err.entry_key(), Some("k"));
+        assert_eq!(err.field_key(), Some("a"));
+    }
+
+    #[test]
+    fn test_stats() {
+        #[derive(Deserialize)]
+        enum Entry {
+            Regular {
+                #[allow(dead_code)]
+                fields: HashMap<String, String>,
+            },
+            Macro,
+            Comment,
+            Preamble,
+        }
+
+        let input = "\u{feff}% header\n@string{a = {A}}\n@comment{c}\n@preamble{a}\nx\n\
+            @Misc{k1, a = 1, b = 2} @misc{k2,} yy @article(k3, c = a) z";
+        let expected = Stats {
+            entries: 6,
+            entry_types: BTreeMap::from([("article".into(), 1), ("misc".into(), 2)]),
+            macros: 1,
+            comments: 1,
+            preambles: 1,
+            fields: 3,
+            junk_bytes: 11,
+        };
+
+        // the entries are counted whether they are deserialized or ignored
+        let mut de = Deserializer::from_str(input).collect_stats(true);
+        assert_eq!(Vec::<Entry>::deserialize(&mut de).unwrap().len(), 6);
+        assert_eq!(de.finish_with_stats().1, expected);
+
+        let mut de = Deserializer::from_str(input).collect_stats(true);
+        assert_eq!(Vec::<IgnoredAny>::deserialize(&mut de).unwrap().len(), 6);
+        assert_eq!(de.finish_with_stats().1, expected);
+
+        let mut de = DeserializerBuilder::new()
+            .collect_stats(true)
+            .build_from_str(input);
+        IgnoredAny::deserialize(&mut de).unwrap();
+        assert_eq!(de.finish_with_stats().1, expected);
+
+        // statistics are only collected if enabled
+        let mut de = Deserializer::from_str(input);
+        IgnoredAny::deserialize(&mut de).unwrap();
+        assert_eq!(de.finish_with_stats().1, Stats::default());
+    }
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_send() {
+        // callbacks must not prevent moving a deserializer to another thread
+        assert_send::<Box<ValueFilter<'static>>>();
+        assert_send::<MacroRedefinitionPolicy>();
+        assert_send::<UnknownFieldKeyPolicy>();
+        assert_send::<Box<Progress<'static>>>();
+        assert_send::<Box<RepairWarning<'static>>>();
+        assert_send::<Deserializer<'static, StrReader<'static>>>();
+    }
 }
@@ -0,0 +1,354 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use unicase::UniCase;
+
+use crate::{
+    parse::{BibtexParse, MacroDictionary, Repair},
+    SliceReader, StrReader, TexComments,
+};
+
+use super::bibliography::{
+    CancellationToken, Deserializer, DuplicateFieldPolicy, InvalidUtf8Policy,
+    MacroRedefinitionPolicy, Progress, RepairWarning, Stats, UndefinedMacroPolicy,
+    UnknownFieldKeyPolicy, VERBATIM_FIELDS,
+};
+use super::value::{ValueFilter, ValueOptions};
+
+/// A builder for a [`Deserializer`], which collects the configuration before the input is
+/// provided.
+///
+/// Each method corresponds to the [`Deserializer`] method of the same name, with the same
+/// defaults. Construct a builder using [`Deserializer::builder`], and then construct the
+/// deserializer using [`DeserializerBuilder::build_from_str`] or
+/// [`DeserializerBuilder::build_from_slice`].
+/// ```
+/// use serde::Deserialize;
+/// use serde_bibtex::de::{Deserializer, UndefinedMacroPolicy};
+/// use serde_bibtex::token::{Token, Variable};
+/// use serde_bibtex::MacroDictionary;
+///
+/// #[derive(Deserialize)]
+/// struct Record {
+///     fields: Fields,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Fields {
+///     journal: String,
+///     month: String,
+/// }
+///
+/// let mut macros = MacroDictionary::default();
+/// macros.insert(Variable::new("jams").unwrap(), vec![Token::str("J. Amer. Math. Soc.").unwrap()]);
+///
+/// let builder = Deserializer::builder()
+///     .macros(macros)
+///     .undefined_macros(UndefinedMacroPolicy::KeepName);
+///
+/// let input = "@article{key, journal = jams, month = feb}";
+/// let record = builder
+///     .build_from_str(input)
+///     .into_iter_regular_entry::<Record>()
+///     .next()
+///     .unwrap()
+///     .unwrap();
+///
+/// assert_eq!(record.fields.journal, "J. Amer. Math. Soc.");
+/// assert_eq!(record.fields.month, "feb");
+/// ```
+pub struct DeserializerBuilder<'r> {
+    macros: Arc<MacroDictionary<&'r str, &'r [u8]>>,
+    value_options: ValueOptions,
+    value_filter: Option<Box<ValueFilter<'r>>>,
+    unknown_fields: Option<&'static str>,
+    duplicate_fields: Option<DuplicateFieldPolicy>,
+    entry_types: Vec<(UniCase<&'r str>, &'r str)>,
+    verbatim_fields: Vec<UniCase<&'r str>>,
+    tex_comments: Option<TexComments>,
+    deny_junk: bool,
+    macro_redefinition: MacroRedefinitionPolicy,
+    unknown_field_keys: UnknownFieldKeyPolicy,
+    progress: Option<Box<Progress<'r>>>,
+    repair_warning: Option<Box<RepairWarning<'r>>>,
+    cancellation: Option<CancellationToken>,
+    collect_stats: bool,
+}
+
+impl Default for DeserializerBuilder<'_> {
+    fn default() -> Self {
+        Self {
+            macros: Arc::default(),
+            value_options: ValueOptions::default(),
+            value_filter: None,
+            unknown_fields: None,
+            duplicate_fields: None,
+            entry_types: Vec::new(),
+            verbatim_fields: VERBATIM_FIELDS.into_iter().map(UniCase::new).collect(),
+            tex_comments: None,
+            deny_junk: false,
+            macro_redefinition: MacroRedefinitionPolicy::Overwrite,
+            unknown_field_keys: UnknownFieldKeyPolicy::Error,
+            progress: None,
+            repair_warning: None,
+            cancellation: None,
+            collect_stats: false,
+        }
+    }
+}
+
+impl<'r> DeserializerBuilder<'r> {
+    /// Construct a builder with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the macros which are defined before the input is read.
+    pub fn macros(mut self, macros: MacroDictionary<&'r str, &'r [u8]>) -> Self {
+        self.macros = Arc::new(macros);
+        self
+    }
+
+    /// Set the macros which are defined before the input is read, without copying them unless
+    /// the input defines a macro.
+    pub(crate) fn shared_macros(mut self, macros: Arc<MacroDictionary<&'r str, &'r [u8]>>) -> Self {
+        self.macros = macros;
+        self
+    }
+
+    /// See [`Deserializer::any_as_tokens`].
+    pub fn any_as_tokens(mut self, enable: bool) -> Self {
+        self.value_options.any_as_tokens = enable;
+        self
+    }
+
+    /// See [`Deserializer::undefined_macros`].
+    pub fn undefined_macros(mut self, policy: UndefinedMacroPolicy) -> Self {
+        self.value_options.undefined_macros = policy;
+        self
+    }
+
+    /// See [`Deserializer::normalize_whitespace`].
+    pub fn normalize_whitespace(mut self, enable: bool) -> Self {
+        self.value_options.normalize_whitespace = enable;
+        self
+    }
+
+    /// See [`Deserializer::invalid_utf8`].
+    pub fn invalid_utf8(mut self, policy: InvalidUtf8Policy) -> Self {
+        self.value_options.invalid_utf8 = policy;
+        self
+    }
+
+    /// See [`Deserializer::collect_unknown_fields`].
+    pub fn collect_unknown_fields(mut self, name: &'static str) -> Self {
+        self.unknown_fields = Some(name);
+        self
+    }
+
+    /// See [`Deserializer::tex_comments`].
+    pub fn tex_comments(mut self, tex_comments: TexComments) -> Self {
+        self.tex_comments = Some(tex_comments);
+        self
+    }
+
+    /// See [`Deserializer::deny_junk`].
+    pub fn deny_junk(mut self, enable: bool) -> Self {
+        self.deny_junk = enable;
+        self
+    }
+
+    /// See [`Deserializer::duplicate_fields`].
+    pub fn duplicate_fields(mut self, policy: DuplicateFieldPolicy) -> Self {
+        self.duplicate_fields = Some(policy);
+        self
+    }
+
+    /// See [`Deserializer::with_value_filter`].
+    pub fn with_value_filter<F>(mut self, filter: F) -> Self
+    where
+        F: for<'c> Fn(&str, Cow<'c, str>) -> Cow<'c, str> + Send + 'r,
+    {
+        self.value_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// See [`Deserializer::verbatim_fields`].
+    pub fn verbatim_fields<I>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = &'r str>,
+    {
+        self.verbatim_fields = fields.into_iter().map(UniCase::new).collect();
+        self
+    }
+
+    /// See [`Deserializer::with_entry_type_aliases`].
+    pub fn with_entry_type_aliases<I>(mut self, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = (&'r str, &'r str)>,
+    {
+        self.entry_types.extend(
+            aliases
+                .into_iter()
+                .map(|(from, to)| (UniCase::new(from), to)),
+        );
+        self
+    }
+
+    /// See [`Deserializer::macro_redefinition`].
+    pub fn macro_redefinition(mut self, policy: MacroRedefinitionPolicy) -> Self {
+        self.macro_redefinition = policy;
+        self
+    }
+
+    /// See [`Deserializer::unknown_field_keys`].
+    pub fn unknown_field_keys(mut self, policy: UnknownFieldKeyPolicy) -> Self {
+        self.unknown_field_keys = policy;
+        self
+    }
+
+    /// See [`Deserializer::with_progress`].
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(usize, usize) + Send + 'r,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// See [`Deserializer::repair_brackets`].
+    pub fn repair_brackets<F>(mut self, warn: F) -> Self
+    where
+        F: FnMut(&Repair) + Send + 'r,
+    {
+        self.repair_warning = Some(Box::new(warn));
+        self
+    }
+
+    /// See [`Deserializer::with_cancellation`].
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// See [`Deserializer::collect_stats`].
+    pub fn collect_stats(mut self, enable: bool) -> Self {
+        self.collect_stats = enable;
+        self
+    }
+
+    /// Construct a deserializer which reads from a `&str`.
+    pub fn build_from_str(self, s: &'r str) -> Deserializer<'r, StrReader<'r>> {
+        self.build(StrReader::new(s))
+    }
+
+    /// Construct a deserializer which reads from a `&[u8]`.
+    pub fn build_from_slice(self, s: &'r [u8]) -> Deserializer<'r, SliceReader<'r>> {
+        self.build(SliceReader::new(s))
+    }
+
+    /// Construct a deserializer from any [`BibtexParse`] implementation.
+    pub(crate) fn build<R: BibtexParse<'r>>(self, mut parser: R) -> Deserializer<'r, R> {
+        if let Some(tex_comments) = self.tex_comments {
+            parser.set_tex_comments(tex_comments);
+        }
+        parser.set_deny_junk(self.deny_junk);
+        parser.set_repair_brackets(self.repair_warning.is_some());
+        if let Some(token) = &self.cancellation {
+            parser.set_cancellation(Arc::clone(&token.0));
+        }
+        Deserializer {
+            parser,
+            macros: self.macros,
+            scratch: Vec::new(),
+            resolve_scratch: Vec::new(),
+            token_buffers: Vec::new(),
+            value_options: self.value_options,
+            value_filter: self.value_filter,
+            unknown_fields: self.unknown_fields,
+            duplicate_fields: self.duplicate_fields,
+            entry_types: self.entry_types,
+            verbatim_fields: self.verbatim_fields,
+            macro_usage: None,
+            macro_redefinition: self.macro_redefinition,
+            macro_spans: HashMap::new(),
+            unknown_field_keys: self.unknown_field_keys,
+            progress: self.progress,
+            repair_warning: self.repair_warning,
+            cancellation: self.cancellation,
+            stats: self.collect_stats.then(Stats::default),
+            entry_start: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record {
+        entry_type: String,
+        entry_key: String,
+        fields: BTreeMap<String, String>,
+    }
+
+    #[test]
+    fn test_builder() {
+        let input = "% @misc{hidden}\n@online{key, title = {A  Title}, url = {a  b}, note = {N}, Note = {M}}";
+
+        let builder = || {
+            Deserializer::builder()
+                .normalize_whitespace(true)
+                .tex_comments(TexComments::Never)
+                .with_entry_type_aliases([("online", "misc")])
+                .duplicate_fields(DuplicateFieldPolicy::UseFirst)
+                .with_value_filter(|_, text| Cow::Owned(text.to_uppercase()))
+        };
+
+        // with `TexComments::Never`, the first line is not a comment
+        let expected = vec![
+            Record {
+                entry_type: "misc".into(),
+                entry_key: "hidden".into(),
+                fields: BTreeMap::new(),
+            },
+            Record {
+                entry_type: "misc".into(),
+                entry_key: "key".into(),
+                fields: BTreeMap::from([
+                    ("title".into(), "A TITLE".into()),
+                    ("url".into(), "a  b".into()),
+                    ("note".into(), "N".into()),
+                ]),
+            },
+        ];
+
+        let records: Vec<Record> = builder()
+            .build_from_str(input)
+            .into_iter_regular_entry()
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        assert_eq!(records, expected);
+
+        let records: Vec<Record> = builder()
+            .build_from_slice(input.as_bytes())
+            .into_iter_regular_entry()
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        assert_eq!(records, expected);
+
+        // the default configuration matches the default deserializer
+        let records: Vec<Record> = DeserializerBuilder::new()
+            .build_from_str(input)
+            .into_iter_regular_entry()
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].entry_type, "online");
+        assert_eq!(records[0].fields["title"], "A  Title");
+    }
+}
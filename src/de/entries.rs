@@ -0,0 +1,808 @@
+//! Deserialization from the owned document model of the [value module](crate::value).
+//!
+//! The entries are checked and their macros are resolved as they are visited, after which the
+//! values are deserialized exactly as values read from a `.bib` file.
+use serde::de::{
+    self, value::BorrowedStrDeserializer, DeserializeSeed, EnumAccess, MapAccess, SeqAccess,
+    Unexpected, VariantAccess,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::{
+    error::{Error, Result},
+    naming::{
+        COMMENT_ENTRY_VARIANT_NAME, ENTRY_KEY_NAME, ENTRY_TYPE_NAME, FIELDS_NAME,
+        MACRO_ENTRY_VARIANT_NAME, PREAMBLE_ENTRY_VARIANT_NAME, REGULAR_ENTRY_VARIANT_NAME,
+    },
+    parse::MacroDictionary,
+    token::{
+        validate::{
+            check_balanced, check_entry_key, check_field_key, check_regular_entry_type,
+            check_variable,
+        },
+        Text, Token, TokenErrorKind, Variable,
+    },
+    value::{self, Entry},
+};
+
+use super::{
+    entry::EntryPosition,
+    value::{
+        match_variant, EntryTypeDeserializer, KeyValueDeserializer, ResolvedField,
+        TextDeserializer, ValueDeserializer, WrappedBorrowStrDeserializer,
+    },
+};
+
+/// Check a name with `check`, returning an error which names the kind of token.
+fn checked<'r>(
+    kind: &str,
+    name: &'r str,
+    check: fn(&str) -> std::result::Result<(), TokenErrorKind>,
+) -> Result<&'r str> {
+    match check(name) {
+        Ok(()) => Ok(name),
+        Err(err) => Err(de::Error::custom(format!("invalid {kind} '{name}': {err}"))),
+    }
+}
+
+/// A deserializer for a bibliography which is a slice of entries.
+pub(crate) struct EntriesDeserializer<'r> {
+    entries: std::slice::Iter<'r, Entry>,
+    macros: MacroDictionary<&'r str, &'r [u8]>,
+}
+
+impl<'r> EntriesDeserializer<'r> {
+    pub(crate) fn new(entries: &'r [Entry]) -> Self {
+        Self {
+            entries: entries.iter(),
+            macros: MacroDictionary::default(),
+        }
+    }
+
+    /// Check the tokens of `value` and resolve the macros defined by the preceding entries.
+    fn resolve(&mut self, value: &'r value::Value) -> Result<Vec<Token<&'r str, &'r [u8]>>> {
+        let mut tokens = value
+            .0
+            .iter()
+            .map(|token| match token {
+                value::Token::Variable(name) => Ok(Token::Variable(Variable::new_unchecked(
+                    checked("variable", name, check_variable)?,
+                ))),
+                value::Token::Text(text) => Ok(Token::Text(Text::Str(checked(
+                    "text token",
+                    text,
+                    |text| check_balanced(text.as_bytes()),
+                )?))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.macros.resolve(&mut tokens);
+        Ok(tokens)
+    }
+
+    /// Check the next entry and resolve its macros.
+    fn next_entry(&mut self) -> Result<Option<ResolvedEntry<'r>>> {
+        let Some(entry) = self.entries.next() else {
+            return Ok(None);
+        };
+        let resolved = match entry {
+            Entry::Regular(entry) => {
+                let entry_key = checked("entry key", &entry.entry_key, check_entry_key)?;
+                let fields = entry
+                    .fields
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok((
+                            checked("field key", key, check_field_key)?,
+                            self.resolve(value)?,
+                        ))
+                    })
+                    .collect::<Result<_>>()
+                    .map_err(|err: Error| err.in_entry(entry_key))?;
+                ResolvedEntry::Regular(ResolvedRegularEntry {
+                    entry_type: checked("entry type", &entry.entry_type, check_regular_entry_type)?,
+                    entry_key,
+                    fields,
+                })
+            }
+            Entry::Macro(Some((name, value))) => {
+                let name = checked("macro name", name, check_variable)?;
+                ResolvedEntry::Macro(Some((name, self.resolve(value)?)))
+            }
+            Entry::Macro(None) => ResolvedEntry::Macro(None),
+            Entry::Comment(text) => ResolvedEntry::Comment(checked("comment", text, |text| {
+                check_balanced(text.as_bytes())
+            })?),
+            Entry::Preamble(value) => ResolvedEntry::Preamble(self.resolve(value)?),
+        };
+        Ok(Some(resolved))
+    }
+}
+
+impl<'a, 'de: 'a> de::Deserializer<'de> for &'a mut EntriesDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_seq(self)
+    }
+
+    #[inline]
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    /// The remaining entries are still checked.
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        while self.next_entry()?.is_some() {}
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option newtype_struct seq tuple
+        tuple_struct map struct enum identifier
+    }
+}
+
+impl<'a, 'de: 'a> SeqAccess<'de> for &'a mut EntriesDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.next_entry()? {
+            Some(entry) => seed
+                .deserialize(EntryDeserializer {
+                    entry,
+                    macros: &mut self.macros,
+                })
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// An entry whose tokens were checked and whose macros were resolved.
+enum ResolvedEntry<'r> {
+    Regular(ResolvedRegularEntry<'r>),
+    Macro(Option<ResolvedField<'r>>),
+    Comment(&'r str),
+    Preamble(Vec<Token<&'r str, &'r [u8]>>),
+}
+
+struct ResolvedRegularEntry<'r> {
+    entry_type: &'r str,
+    entry_key: &'r str,
+    fields: Vec<ResolvedField<'r>>,
+}
+
+/// A single entry, which is deserialized as an enum with a variant for each kind of entry. Struct,
+/// map, and tuple deserialization is assumed to be of a regular entry.
+struct EntryDeserializer<'a, 'r> {
+    entry: ResolvedEntry<'r>,
+    /// The macros, which are defined by a macro entry deserialized as a unit variant.
+    macros: &'a mut MacroDictionary<&'r str, &'r [u8]>,
+}
+
+impl<'a, 'r> EntryDeserializer<'a, 'r> {
+    fn regular(
+        self,
+        unexpected: Unexpected,
+        expected: &str,
+    ) -> Result<RegularEntryDeserializer<'r>> {
+        match self.entry {
+            ResolvedEntry::Regular(entry) => Ok(RegularEntryDeserializer { entry }),
+            _ => Err(de::Error::invalid_type(unexpected, &expected)),
+        }
+    }
+}
+
+impl<'a, 'de: 'a> de::Deserializer<'de> for EntryDeserializer<'a, 'de> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.regular(
+            Unexpected::StructVariant,
+            "non-regular entry as struct variant",
+        )?
+        .deserialize_map(visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.regular(
+            Unexpected::StructVariant,
+            "non-regular entry as struct variant",
+        )?
+        .deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.regular(
+            Unexpected::TupleVariant,
+            "non-regular entry as tuple variant",
+        )?
+        .deserialize_tuple(len, visitor)
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq enum identifier ignored_any
+    }
+}
+
+impl<'a, 'de: 'a> EnumAccess<'de> for EntryDeserializer<'a, 'de> {
+    type Error = Error;
+
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = match self.entry {
+            ResolvedEntry::Regular(_) => REGULAR_ENTRY_VARIANT_NAME,
+            ResolvedEntry::Macro(_) => MACRO_ENTRY_VARIANT_NAME,
+            ResolvedEntry::Comment(_) => COMMENT_ENTRY_VARIANT_NAME,
+            ResolvedEntry::Preamble(_) => PREAMBLE_ENTRY_VARIANT_NAME,
+        };
+        Ok((
+            seed.deserialize(BorrowedStrDeserializer::<Error>::new(variant))?,
+            self,
+        ))
+    }
+}
+
+impl<'a, 'de: 'a> VariantAccess<'de> for EntryDeserializer<'a, 'de> {
+    type Error = Error;
+
+    /// A macro entry which is not captured by the visitor defines its macro, replacing any
+    /// previous definition.
+    fn unit_variant(self) -> Result<()> {
+        if let ResolvedEntry::Macro(Some((name, tokens))) = self.entry {
+            self.macros
+                .insert_raw_tokens(Variable::new_unchecked(name), tokens);
+        }
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.entry {
+            ResolvedEntry::Regular(entry) => seed.deserialize(RegularEntryDeserializer { entry }),
+            ResolvedEntry::Macro(rule) => seed.deserialize(MacroRuleDeserializer { rule }),
+            ResolvedEntry::Comment(text) => {
+                seed.deserialize(TextDeserializer::new(Text::Str(text)))
+            }
+            ResolvedEntry::Preamble(mut tokens) => {
+                seed.deserialize(ValueDeserializer::new(&mut tokens))
+            }
+        }
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
+    }
+
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match (self.entry, len) {
+            (ResolvedEntry::Regular(entry), _) => de::Deserializer::deserialize_tuple(
+                RegularEntryDeserializer { entry },
+                len,
+                visitor,
+            ),
+            (ResolvedEntry::Macro(rule), 2) => {
+                de::Deserializer::deserialize_any(MacroRuleDeserializer { rule }, visitor)
+            }
+            (ResolvedEntry::Macro(_), _) => Err(de::Error::invalid_type(
+                Unexpected::TupleVariant,
+                &"macro as tuple of length not 2",
+            )),
+            (ResolvedEntry::Preamble(_), _) => Err(de::Error::invalid_type(
+                Unexpected::TupleVariant,
+                &"preamble as tuple variant",
+            )),
+            (ResolvedEntry::Comment(_), _) => Err(de::Error::invalid_type(
+                Unexpected::TupleVariant,
+                &"comment as tuple variant",
+            )),
+        }
+    }
+}
+
+/// The contents of a macro entry, which is empty if `rule` is `None`.
+struct MacroRuleDeserializer<'r> {
+    rule: Option<ResolvedField<'r>>,
+}
+
+impl<'de> de::Deserializer<'de> for MacroRuleDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.rule {
+            Some((name, mut tokens)) => {
+                visitor.visit_seq(KeyValueDeserializer::new(name, &mut tokens))
+            }
+            None => Err(de::Error::invalid_type(
+                Unexpected::Option,
+                &"non-empty macro entry",
+            )),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.rule {
+            Some((name, mut tokens)) => {
+                visitor.visit_some(KeyValueDeserializer::new(name, &mut tokens))
+            }
+            None => visitor.visit_none(),
+        }
+    }
+
+    #[inline]
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier
+    }
+}
+
+/// The contents of a regular entry.
+struct RegularEntryDeserializer<'r> {
+    entry: ResolvedRegularEntry<'r>,
+}
+
+impl<'de> de::Deserializer<'de> for RegularEntryDeserializer<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_map(EntryAccess::new(self.entry))
+    }
+
+    /// Struct deserialization records the expected field names, so that the entry type, entry
+    /// key, and fields are only provided if the struct has a corresponding field.
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut access = EntryAccess::new(self.entry);
+        access.expected = Some(fields);
+        visitor.visit_map(access)
+    }
+
+    fn deserialize_seq<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            Unexpected::Seq,
+            &"entry can only be deserialized as a tuple of length 3",
+        ))
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        if len == 3 {
+            visitor.visit_seq(EntryAccess::new(self.entry))
+        } else {
+            self.deserialize_seq(visitor)
+        }
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    #[inline]
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    #[inline]
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    forward_to_deserialize_any!(
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str
+        string bytes byte_buf option newtype_struct
+        map enum identifier);
+}
+
+/// The parts of a regular entry, as a map or as a tuple `(entry_type, entry_key, fields)`.
+struct EntryAccess<'r> {
+    entry_type: &'r str,
+    entry_key: &'r str,
+    fields: Option<Vec<ResolvedField<'r>>>,
+    pos: EntryPosition,
+    /// The names expected by the visitor, if known.
+    expected: Option<&'static [&'static str]>,
+}
+
+impl<'r> EntryAccess<'r> {
+    fn new(entry: ResolvedRegularEntry<'r>) -> Self {
+        Self {
+            entry_type: entry.entry_type,
+            entry_key: entry.entry_key,
+            fields: Some(entry.fields),
+            pos: EntryPosition::EndOfEntry,
+            expected: None,
+        }
+    }
+
+    fn value<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'r>,
+    {
+        match self.pos {
+            EntryPosition::EntryType => {
+                seed.deserialize(EntryTypeDeserializer::new(self.entry_type))
+            }
+            EntryPosition::CitationKey => {
+                seed.deserialize(WrappedBorrowStrDeserializer::new(self.entry_key))
+            }
+            EntryPosition::Fields => seed
+                .deserialize(FieldDeserializer::new(
+                    self.fields.take().unwrap_or_default(),
+                ))
+                .map_err(|err| err.in_entry(self.entry_key)),
+            // SAFETY: the access ends when the end of the entry is reached
+            EntryPosition::EndOfEntry => unreachable!(),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for EntryAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        loop {
+            self.pos = self.pos.next();
+            let name = match self.pos {
+                EntryPosition::EntryType => ENTRY_TYPE_NAME,
+                EntryPosition::CitationKey => ENTRY_KEY_NAME,
+                EntryPosition::Fields => FIELDS_NAME,
+                EntryPosition::EndOfEntry => return Ok(None),
+            };
+            // the visitor does not expect this part of the entry, so that a struct with
+            // `#[serde(deny_unknown_fields)]` need not have every part
+            if self
+                .expected
+                .is_none_or(|expected| expected.contains(&name))
+            {
+                return seed
+                    .deserialize(BorrowedStrDeserializer::new(name))
+                    .map(Some);
+            }
+        }
+    }
+
+    #[inline]
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        self.value(seed)
+    }
+}
+
+impl<'de> SeqAccess<'de> for EntryAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.pos = self.pos.next();
+        match self.pos {
+            EntryPosition::EndOfEntry => Ok(None),
+            _ => self.value(seed).map(Some),
+        }
+    }
+}
+
+/// The fields of a regular entry, as a map or as a sequence of `(key, value)` pairs.
+struct FieldDeserializer<'r> {
+    fields: std::vec::IntoIter<ResolvedField<'r>>,
+    /// The most recently read field.
+    current: Option<ResolvedField<'r>>,
+}
+
+impl<'r> FieldDeserializer<'r> {
+    fn new(fields: Vec<ResolvedField<'r>>) -> Self {
+        Self {
+            fields: fields.into_iter(),
+            current: None,
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for FieldDeserializer<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_map(self)
+    }
+
+    #[inline]
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_seq(self)
+    }
+
+    #[inline]
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    #[inline]
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    #[inline]
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    #[inline]
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    #[inline]
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.deserialize_ignored_any(visitor)
+    }
+
+    forward_to_deserialize_any!(
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf identifier option newtype_struct enum map struct);
+}
+
+impl<'de> MapAccess<'de> for FieldDeserializer<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.fields.next() {
+            Some(field) => {
+                let key = field.0;
+                self.current = Some(field);
+                seed.deserialize(FieldKeyDeserializer { key })
+                    .map(Some)
+                    .map_err(|err| err.in_field(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        // SAFETY: a value is only requested after its key
+        let (key, mut tokens) = self.current.take().unwrap();
+        seed.deserialize(ValueDeserializer::new(&mut tokens))
+            .map_err(|err| err.in_field(key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+impl<'de> SeqAccess<'de> for FieldDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.fields.next() {
+            Some((key, mut tokens)) => seed
+                .deserialize(KeyValueDeserializer::new(key, &mut tokens))
+                .map(Some)
+                .map_err(|err| err.in_field(key)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.fields.len())
+    }
+}
+
+/// A field key. When deserialized as an enum, the key is matched case-insensitively against the
+/// variants.
+struct FieldKeyDeserializer<'r> {
+    key: &'r str,
+}
+
+impl<'de> de::Deserializer<'de> for FieldKeyDeserializer<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.key)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let variant = match_variant(variants, self.key).unwrap_or(self.key);
+        visitor.visit_enum(BorrowedStrDeserializer::new(variant))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
@@ -1,24 +1,31 @@
 use serde::de::{
-    self, value::BorrowedStrDeserializer, DeserializeSeed, EnumAccess, MapAccess, SeqAccess,
-    Unexpected, VariantAccess,
+    self, value::BorrowedStrDeserializer, DeserializeSeed, EnumAccess, IgnoredAny, MapAccess,
+    SeqAccess, Unexpected, VariantAccess,
 };
 use serde::forward_to_deserialize_any;
 
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use unicase::UniCase;
+
 use crate::{
-    error::{Error, Result},
+    error::{Error, ErrorCode, Result},
     naming::{
         COMMENT_ENTRY_VARIANT_NAME, ENTRY_KEY_NAME, ENTRY_TYPE_NAME, FIELDS_NAME,
         MACRO_ENTRY_VARIANT_NAME, PREAMBLE_ENTRY_VARIANT_NAME, REGULAR_ENTRY_VARIANT_NAME,
     },
     parse::BibtexParse,
-    token::EntryType,
+    token::{EntryType, FieldKey, Token},
 };
 
 use super::{
     value::{
-        KeyValueDeserializer, TextDeserializer, ValueDeserializer, WrappedBorrowStrDeserializer,
+        match_variant, EntryTypeDeserializer, KeyValueDeserializer, ResolvedField,
+        SkippedValueDeserializer, TextDeserializer, UnknownFieldsDeserializer, ValueDeserializer,
+        WrappedBorrowStrDeserializer,
     },
-    Deserializer,
+    Deserializer, DuplicateFieldPolicy, UnknownFieldKeyPolicy,
 };
 
 pub struct EntryDeserializer<'a, 'r, R>
@@ -73,16 +80,28 @@ where
         }
     }
 
+    /// Struct deserialization is assumed to be of a regular entry.
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
+        name: &'static str,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        match self.entry_type {
+            EntryType::Regular(entry_type) => de::Deserializer::deserialize_struct(
+                RegularEntryDeserializer::new(&mut *self.de, entry_type.into_inner()),
+                name,
+                fields,
+                visitor,
+            ),
+            _ => Err(de::Error::invalid_type(
+                Unexpected::StructVariant,
+                &"non-regular entry as struct variant",
+            )),
+        }
     }
 
     /// Tuple deserialization is assumed to be a regular entry, and must be of length 3.
@@ -132,9 +151,10 @@ where
     type Error = Error;
 
     fn unit_variant(self) -> Result<()> {
-        self.de
-            .parser
-            .ignore_entry_captured(self.entry_type, &mut self.de.macros)
+        match self.entry_type {
+            EntryType::Macro => self.de.capture_macro(),
+            entry_type => self.de.ignore_entry(entry_type),
+        }
     }
 
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
@@ -148,7 +168,11 @@ where
             )),
             EntryType::Macro => seed.deserialize(MacroRuleDeserializer::new(&mut *self.de)),
             EntryType::Comment => {
-                seed.deserialize(TextDeserializer::new(self.de.parser.comment_contents()?))
+                let contents = self.de.parser.comment_contents()?;
+                seed.deserialize(
+                    TextDeserializer::new(contents)
+                        .invalid_utf8(self.de.value_options.invalid_utf8),
+                )
             }
             EntryType::Preamble => {
                 let closing_bracket = self.de.parser.initial()?;
@@ -159,11 +183,11 @@ where
         }
     }
 
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
-        de::Deserializer::deserialize_map(self, visitor)
+        de::Deserializer::deserialize_struct(self, "", fields, visitor)
     }
 
     fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
@@ -326,6 +350,8 @@ where
 {
     de: &'a mut Deserializer<'r, R>,
     name: &'r str,
+    /// The closing bracket and entry key, if already parsed.
+    entry_key: Option<(u8, &'r str)>,
 }
 
 impl<'a, 'r, R> RegularEntryDeserializer<'a, 'r, R>
@@ -333,7 +359,25 @@ where
     R: BibtexParse<'r>,
 {
     pub fn new(de: &'a mut Deserializer<'r, R>, name: &'r str) -> Self {
-        Self { de, name }
+        let name = de.resolve_entry_type(name);
+        Self {
+            de,
+            name,
+            entry_key: None,
+        }
+    }
+
+    /// Construct a deserializer for an entry whose opening bracket and entry key were already
+    /// parsed.
+    pub fn with_entry_key(
+        de: &'a mut Deserializer<'r, R>,
+        name: &'r str,
+        closing_bracket: u8,
+        entry_key: &'r str,
+    ) -> Self {
+        let mut deserializer = Self::new(de, name);
+        deserializer.entry_key = Some((closing_bracket, entry_key));
+        deserializer
     }
 }
 
@@ -348,7 +392,23 @@ where
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_map(EntryAccess::new(&mut *self.de, self.name))
+        visitor.visit_map(EntryAccess::new(&mut *self.de, self.name, self.entry_key))
+    }
+
+    /// Struct deserialization records the expected field names, so that the entry type, entry
+    /// key, and fields are only provided if the struct has a corresponding field.
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let mut access = EntryAccess::new(&mut *self.de, self.name, self.entry_key);
+        access.fields = Some(fields);
+        visitor.visit_map(access)
     }
 
     fn deserialize_seq<V>(self, _visitor: V) -> Result<V::Value>
@@ -367,7 +427,7 @@ where
         V: de::Visitor<'de>,
     {
         if len == 3 {
-            visitor.visit_seq(EntryAccess::new(&mut *self.de, self.name))
+            visitor.visit_seq(EntryAccess::new(&mut *self.de, self.name, self.entry_key))
         } else {
             Err(de::Error::invalid_type(
                 Unexpected::Seq,
@@ -393,7 +453,14 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.de.parser.ignore_regular_entry()?;
+        match self.entry_key {
+            Some((closing_bracket, _)) => {
+                self.de.ignore_fields()?;
+                self.de.parser.comma_opt();
+                self.de.parser.terminal(closing_bracket)?;
+            }
+            None => self.de.ignore_regular_entry()?,
+        }
         visitor.visit_unit()
     }
 
@@ -416,17 +483,29 @@ where
     forward_to_deserialize_any!(
         bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str
         string bytes byte_buf option newtype_struct
-        map struct enum identifier);
+        map enum identifier);
 }
 
 #[derive(Debug, Copy, Clone)]
-enum EntryPosition {
+pub(crate) enum EntryPosition {
     EntryType,
     CitationKey,
     Fields,
     EndOfEntry,
 }
 
+impl EntryPosition {
+    /// The next part of the entry, wrapping around to the entry type after the end of the entry.
+    pub(crate) fn next(self) -> Self {
+        match self {
+            Self::EntryType => Self::CitationKey,
+            Self::CitationKey => Self::Fields,
+            Self::Fields => Self::EndOfEntry,
+            Self::EndOfEntry => Self::EntryType,
+        }
+    }
+}
+
 /// Deserialize an Entry.
 ///
 /// This deserializes the contents from `>` to `<`
@@ -450,28 +529,65 @@ where
     pos: EntryPosition,
     /// What closing bracket to expect.
     closing_bracket: u8,
+    /// The entry key, if already parsed.
+    entry_key: Option<&'r str>,
+    /// The names expected by the visitor, if known.
+    fields: Option<&'static [&'static str]>,
 }
 
 impl<'a, 'r, R> EntryAccess<'a, 'r, R>
 where
     R: BibtexParse<'r>,
 {
-    fn new(de: &'a mut Deserializer<'r, R>, name: &'r str) -> Self {
+    fn new(
+        de: &'a mut Deserializer<'r, R>,
+        name: &'r str,
+        entry_key: Option<(u8, &'r str)>,
+    ) -> Self {
         Self {
             de,
             name,
             pos: EntryPosition::EndOfEntry,
-            closing_bracket: b'}',
+            closing_bracket: entry_key.map_or(b'}', |(closing_bracket, _)| closing_bracket),
+            entry_key: entry_key.map(|(_, key)| key),
+            fields: None,
+        }
+    }
+
+    /// Parse the opening bracket and entry key, unless they were already parsed.
+    fn entry_key(&mut self) -> Result<&'r str> {
+        match self.entry_key {
+            Some(key) => Ok(key),
+            None => {
+                self.closing_bracket = self.de.parser.initial()?;
+                let key = self.de.parser.entry_key()?.into_inner();
+                self.entry_key = Some(key);
+                Ok(key)
+            }
+        }
+    }
+
+    /// Parse the fields and the end of the entry.
+    fn fields<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        'r: 'a,
+        T: DeserializeSeed<'r>,
+    {
+        let val = seed
+            .deserialize(FieldDeserializer::new(&mut *self.de))
+            .and_then(|val| {
+                self.de.parser.comma_opt();
+                self.de.parser.terminal(self.closing_bracket)?;
+                Ok(val)
+            });
+        match self.entry_key {
+            Some(entry_key) => val.map_err(|err| err.in_entry(entry_key)),
+            None => val,
         }
     }
 
     fn step_position(&mut self) {
-        self.pos = match self.pos {
-            EntryPosition::EntryType => EntryPosition::CitationKey,
-            EntryPosition::CitationKey => EntryPosition::Fields,
-            EntryPosition::Fields => EntryPosition::EndOfEntry,
-            EntryPosition::EndOfEntry => EntryPosition::EntryType,
-        };
+        self.pos = self.pos.next();
     }
 }
 
@@ -485,18 +601,31 @@ where
     where
         K: DeserializeSeed<'de>,
     {
-        self.step_position();
-        match self.pos {
-            EntryPosition::EntryType => seed
-                .deserialize(BorrowedStrDeserializer::new(ENTRY_TYPE_NAME))
-                .map(Some),
-            EntryPosition::CitationKey => seed
-                .deserialize(BorrowedStrDeserializer::new(ENTRY_KEY_NAME))
-                .map(Some),
-            EntryPosition::Fields => seed
-                .deserialize(BorrowedStrDeserializer::new(FIELDS_NAME))
-                .map(Some),
-            EntryPosition::EndOfEntry => Ok(None),
+        loop {
+            self.step_position();
+            let name = match self.pos {
+                EntryPosition::EntryType => ENTRY_TYPE_NAME,
+                EntryPosition::CitationKey => ENTRY_KEY_NAME,
+                EntryPosition::Fields => FIELDS_NAME,
+                EntryPosition::EndOfEntry => return Ok(None),
+            };
+            if self.fields.is_none_or(|fields| fields.contains(&name)) {
+                return seed
+                    .deserialize(BorrowedStrDeserializer::new(name))
+                    .map(Some);
+            }
+
+            // the visitor does not expect this part of the entry, so that a struct with
+            // `#[serde(deny_unknown_fields)]` need not have every part
+            match self.pos {
+                EntryPosition::CitationKey => {
+                    self.entry_key()?;
+                }
+                EntryPosition::Fields => {
+                    self.fields(PhantomData::<IgnoredAny>)?;
+                }
+                _ => {}
+            }
         }
     }
 
@@ -505,21 +634,11 @@ where
         V: DeserializeSeed<'de>,
     {
         match self.pos {
-            EntryPosition::EntryType => {
-                seed.deserialize(WrappedBorrowStrDeserializer::new(self.name))
-            }
+            EntryPosition::EntryType => seed.deserialize(EntryTypeDeserializer::new(self.name)),
             EntryPosition::CitationKey => {
-                self.closing_bracket = self.de.parser.initial()?;
-                seed.deserialize(WrappedBorrowStrDeserializer::new(
-                    self.de.parser.entry_key()?.into_inner(),
-                ))
-            }
-            EntryPosition::Fields => {
-                let val = seed.deserialize(FieldDeserializer::new(&mut *self.de))?;
-                self.de.parser.comma_opt();
-                self.de.parser.terminal(self.closing_bracket)?;
-                Ok(val)
+                seed.deserialize(WrappedBorrowStrDeserializer::new(self.entry_key()?))
             }
+            EntryPosition::Fields => self.fields(seed),
             // SAFETY: MapAccess ends when Parsed::EndOfEntry is reached in `self.next_key_seed`
             EntryPosition::EndOfEntry => unreachable!(),
         }
@@ -539,23 +658,12 @@ where
         self.step_position();
         match self.pos {
             EntryPosition::EntryType => seed
-                .deserialize(WrappedBorrowStrDeserializer::new(self.name))
+                .deserialize(EntryTypeDeserializer::new(self.name))
                 .map(Some),
-            EntryPosition::CitationKey => {
-                self.closing_bracket = self.de.parser.initial()?;
-                seed.deserialize(WrappedBorrowStrDeserializer::new(
-                    self.de.parser.entry_key()?.into_inner(),
-                ))
-                .map(Some)
-            }
-            EntryPosition::Fields => {
-                let val = seed
-                    .deserialize(FieldDeserializer::new(&mut *self.de))
-                    .map(Some)?;
-                self.de.parser.comma_opt();
-                self.de.parser.terminal(self.closing_bracket)?;
-                Ok(val)
-            }
+            EntryPosition::CitationKey => seed
+                .deserialize(WrappedBorrowStrDeserializer::new(self.entry_key()?))
+                .map(Some),
+            EntryPosition::Fields => self.fields(seed).map(Some),
             // SAFETY: We only permit deserialization into a tuple of length 3
             EntryPosition::EndOfEntry => unreachable!(),
         }
@@ -568,14 +676,145 @@ where
     R: BibtexParse<'r>,
 {
     de: &'a mut Deserializer<'r, R>,
+    /// The field names expected by the visitor, if known.
+    fields: Option<&'static [&'static str]>,
+    /// The most recently read field key.
+    key: &'r str,
+    /// Whether the value of the most recently read field key is not expected by the visitor.
+    skip_value: bool,
+    /// The name of the struct field which collects the unknown fields, if requested.
+    unknown_fields_name: Option<&'static str>,
+    /// The unknown fields, with resolved values.
+    unknown_fields: Vec<ResolvedField<'r>>,
+    /// Whether the next value is the collection of unknown fields.
+    emit_unknown_fields: bool,
+    /// The fields, if read in advance to enforce a [`DuplicateFieldPolicy`].
+    buffered: Option<std::vec::IntoIter<BufferedField<'r>>>,
+    /// The value of the most recently read buffered field key.
+    buffered_value: Option<BufferedValue<'r>>,
+    /// Whether the fields ended while skipping field keys which do not match an enum variant.
+    exhausted: bool,
 }
 
+/// The tokens of a value before macros are resolved, and the source of the value.
+type BufferedValue<'r> = (Vec<Token<&'r str, &'r [u8]>>, &'r [u8]);
+
+/// A field key and its value, before macros are resolved.
+type BufferedField<'r> = (&'r str, Vec<Token<&'r str, &'r [u8]>>, &'r [u8]);
+
 impl<'a, 'r, R> FieldDeserializer<'a, 'r, R>
 where
     R: BibtexParse<'r>,
 {
     pub fn new(de: &'a mut Deserializer<'r, R>) -> Self {
-        Self { de }
+        Self {
+            de,
+            fields: None,
+            key: "",
+            skip_value: false,
+            unknown_fields_name: None,
+            unknown_fields: Vec::new(),
+            emit_unknown_fields: false,
+            buffered: None,
+            buffered_value: None,
+            exhausted: false,
+        }
+    }
+
+    /// Whether the value of the field `key` is neither expected by the visitor nor collected as an
+    /// unknown field.
+    fn is_ignored(&self, key: &str) -> bool {
+        self.unknown_fields_name.is_none()
+            && self.fields.is_some_and(|fields| !fields.contains(&key))
+    }
+
+    /// Read all of the remaining fields, resolving duplicate field keys according to `policy`.
+    ///
+    /// Macros are not resolved, and the values of ignored fields are skipped without tokenizing
+    /// them.
+    fn read_fields(&mut self, policy: DuplicateFieldPolicy) -> Result<Vec<BufferedField<'r>>> {
+        let mut fields: Vec<BufferedField<'r>> = Vec::new();
+        let mut seen: HashMap<UniCase<&'r str>, usize> = HashMap::new();
+
+        while let Some(field_key) = self.de.next_field_key()? {
+            self.de.check_cancelled()?;
+            let end = self.de.parser.position();
+            let key = field_key.into_inner();
+            self.de.parser.field_sep()?;
+            let (tokens, raw) = if self.is_ignored(key) {
+                self.de.parser.ignore_value()?;
+                (Vec::new(), &[][..])
+            } else {
+                let mut tokens = self.de.take_token_buffer();
+                let raw = self.de.parser.value_into(&mut tokens)?;
+                (tokens, raw)
+            };
+
+            match (seen.get(&UniCase::new(key)), policy) {
+                (None, _) => {
+                    seen.insert(UniCase::new(key), fields.len());
+                    fields.push((key, tokens, raw));
+                }
+                (Some(_), DuplicateFieldPolicy::UseFirst) => self.de.recycle_token_buffer(tokens),
+                (Some(idx), DuplicateFieldPolicy::UseLast) => {
+                    let previous = std::mem::replace(&mut fields[*idx].1, tokens);
+                    fields[*idx].2 = raw;
+                    self.de.recycle_token_buffer(previous);
+                }
+                (Some(_), DuplicateFieldPolicy::Error) => {
+                    return Err(Error::syntax(ErrorCode::DuplicateField(
+                        key.to_owned(),
+                        end - key.len()..end,
+                    )));
+                }
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Read the next field key, or `None` if there are no more fields.
+    fn next_field_key(&mut self) -> Result<Option<&'r str>> {
+        self.de.check_cancelled()?;
+        if self.buffered.is_none() {
+            if let Some(policy) = self.de.duplicate_fields {
+                self.buffered = Some(self.read_fields(policy)?.into_iter());
+            }
+        }
+
+        match &mut self.buffered {
+            Some(iter) => Ok(iter.next().map(|(key, tokens, raw)| {
+                self.buffered_value = Some((tokens, raw));
+                key
+            })),
+            None => Ok(self.de.next_field_key()?.map(FieldKey::into_inner)),
+        }
+    }
+
+    /// Skip the value corresponding to the most recently read field key.
+    fn skip_field_value(&mut self) -> Result<()> {
+        match self.buffered_value.take() {
+            Some((tokens, _)) => self.de.recycle_token_buffer(tokens),
+            None => {
+                self.de.parser.field_sep()?;
+                self.de.parser.ignore_value()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the resolved value corresponding to the most recently read field key.
+    fn next_field_tokens(&mut self) -> Result<Vec<Token<&'r str, &'r [u8]>>> {
+        let mut tokens = match self.buffered_value.take() {
+            Some((tokens, _)) => tokens,
+            None => {
+                self.de.parser.field_sep()?;
+                let mut tokens = Vec::new();
+                self.de.parser.value_into(&mut tokens)?;
+                tokens
+            }
+        };
+        self.de.resolve(&mut tokens);
+        Ok(tokens)
     }
 }
 
@@ -624,7 +863,7 @@ where
     where
         V: de::Visitor<'de>,
     {
-        self.de.parser.ignore_fields()?;
+        self.de.ignore_fields()?;
         visitor.visit_unit()
     }
 
@@ -644,9 +883,25 @@ where
         self.deserialize_ignored_any(visitor)
     }
 
+    /// Struct deserialization records the expected field names, so that values of other fields
+    /// can be skipped by the parser without tokenizing them.
+    fn deserialize_struct<V>(
+        mut self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.fields = Some(fields);
+        self.unknown_fields_name = self.de.unknown_fields.filter(|name| fields.contains(name));
+        visitor.visit_map(self)
+    }
+
     forward_to_deserialize_any!(
         bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
-        bytes byte_buf identifier option newtype_struct enum map struct);
+        bytes byte_buf identifier option newtype_struct enum map);
 }
 
 impl<'a, 'de: 'a, R> MapAccess<'de> for FieldDeserializer<'a, 'de, R>
@@ -659,10 +914,29 @@ where
     where
         K: DeserializeSeed<'de>,
     {
-        match self.de.parser.field_or_terminal()? {
-            Some(var) => seed
-                .deserialize(WrappedBorrowStrDeserializer::new(var.into_inner()))
-                .map(Some),
+        while let Some(key) = self.next_field_key()? {
+            self.key = key;
+            // a field which is literally called `name` is collected like any other unknown field,
+            // rather than being emitted alongside the collection of unknown fields
+            self.skip_value = self.fields.is_some_and(|fields| !fields.contains(&key))
+                || self.unknown_fields_name == Some(key);
+            if self.skip_value && self.unknown_fields_name.is_some() {
+                let tokens = self.next_field_tokens()?;
+                self.unknown_fields.push((key, tokens));
+            } else {
+                match seed.deserialize(FieldKeyDeserializer { fields: self }) {
+                    Err(_) if self.exhausted => return Ok(None),
+                    res => return res.map(Some),
+                }
+            }
+        }
+
+        match self.unknown_fields_name.take() {
+            Some(name) => {
+                self.emit_unknown_fields = true;
+                seed.deserialize(BorrowedStrDeserializer::new(name))
+                    .map(Some)
+            }
             None => Ok(None),
         }
     }
@@ -671,8 +945,131 @@ where
     where
         V: DeserializeSeed<'de>,
     {
+        if self.emit_unknown_fields {
+            self.emit_unknown_fields = false;
+            return seed.deserialize(UnknownFieldsDeserializer::new(
+                std::mem::take(&mut self.unknown_fields),
+                self.de.value_options,
+                self.de.value_filter.as_deref(),
+                &self.de.verbatim_fields,
+            ));
+        }
+
+        let key = self.key;
+        self.field_value(seed).map_err(|err| err.in_field(key))
+    }
+}
+
+impl<'a, 'de: 'a, R> FieldDeserializer<'a, 'de, R>
+where
+    R: BibtexParse<'de>,
+{
+    /// Deserialize the value corresponding to the most recently read field key.
+    fn field_value<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        if let Some((mut tokens, raw)) = self.buffered_value.take() {
+            // the value of an ignored field was never tokenized
+            if !self.skip_value {
+                self.de.resolve(&mut tokens);
+            }
+            let value = seed.deserialize(
+                ValueDeserializer::new(&mut tokens)
+                    .options(self.de.field_options(self.key))
+                    .filter(
+                        self.de
+                            .value_filter
+                            .as_deref()
+                            .map(|filter| (self.key, filter)),
+                    )
+                    .raw(Some(raw)),
+            );
+            self.de.recycle_token_buffer(tokens);
+            return value;
+        }
+
         self.de.parser.field_sep()?;
-        seed.deserialize(ValueDeserializer::try_from_de_resolved(&mut *self.de)?)
+        if self.skip_value {
+            seed.deserialize(SkippedValueDeserializer::new(&mut *self.de))
+        } else {
+            seed.deserialize(ValueDeserializer::try_from_de_field(
+                &mut *self.de,
+                self.key,
+            )?)
+        }
+    }
+}
+
+/// A deserializer for the most recently read field key. When deserialized as an enum, field keys
+/// which do not match any variant are handled according to the [`UnknownFieldKeyPolicy`]: if the
+/// field is skipped, the next field key is read in its place.
+struct FieldKeyDeserializer<'b, 'a, 'r, R>
+where
+    R: BibtexParse<'r>,
+{
+    fields: &'b mut FieldDeserializer<'a, 'r, R>,
+}
+
+impl<'b, 'a, 'de: 'a, R> de::Deserializer<'de> for FieldKeyDeserializer<'b, 'a, 'de, R>
+where
+    R: BibtexParse<'de>,
+{
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.fields.key)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        loop {
+            let key = self.fields.key;
+            if let Some(variant) = match_variant(variants, key) {
+                return visitor.visit_enum(BorrowedStrDeserializer::new(variant));
+            }
+            match &mut self.fields.de.unknown_field_keys {
+                UnknownFieldKeyPolicy::Error => {
+                    return visitor.visit_enum(BorrowedStrDeserializer::new(key))
+                }
+                UnknownFieldKeyPolicy::Skip => {}
+                UnknownFieldKeyPolicy::Warn(warn) => warn(key),
+            }
+            self.fields.skip_field_value()?;
+            match self.fields.next_field_key()? {
+                Some(key) => self.fields.key = key,
+                None => {
+                    // caught by `FieldDeserializer::next_key_seed`
+                    self.fields.exhausted = true;
+                    return Err(de::Error::custom("no remaining field keys"));
+                }
+            }
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
     }
 }
 
@@ -686,16 +1083,47 @@ where
     where
         T: DeserializeSeed<'de>,
     {
-        let field_key = match self.de.parser.field_or_terminal()? {
-            Some(field_key) => field_key,
-            None => return Ok(None),
-        };
+        match self.next_field_key()? {
+            Some(field_key) => self
+                .field_element(field_key, seed)
+                .map(Some)
+                .map_err(|err| err.in_field(field_key)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a, 'de: 'a, R> FieldDeserializer<'a, 'de, R>
+where
+    R: BibtexParse<'de>,
+{
+    /// Deserialize the field `field_key` and its value as a single element.
+    fn field_element<T>(&mut self, field_key: &'de str, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if let Some((mut tokens, raw)) = self.buffered_value.take() {
+            self.de.resolve(&mut tokens);
+            let value = seed.deserialize(
+                KeyValueDeserializer::new(field_key, &mut tokens)
+                    .options(self.de.field_options(field_key))
+                    .filter(
+                        self.de
+                            .value_filter
+                            .as_deref()
+                            .map(|filter| (field_key, filter)),
+                    )
+                    .raw(Some(raw)),
+            );
+            self.de.recycle_token_buffer(tokens);
+            return value;
+        }
+
         self.de.parser.field_sep()?;
-        seed.deserialize(KeyValueDeserializer::new_from_de(
-            field_key.into_inner(),
+        seed.deserialize(KeyValueDeserializer::field_from_de(
+            field_key,
             &mut *self.de,
         )?)
-        .map(Some)
     }
 }
 
@@ -1049,6 +1477,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_entry_type_enum() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Type {
+            Article,
+            #[serde(rename = "inProceedings")]
+            InProceedings,
+            #[serde(other)]
+            Other,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Record(Type, String, HashMap<String, String>);
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum Strict {
+            Article,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct StrictRecord {
+            entry_type: Strict,
+        }
+
+        for (entry_type, expected) in [
+            ("article", Type::Article),
+            ("ARTICLE", Type::Article),
+            ("inproceedings", Type::InProceedings),
+            ("InProceedings", Type::InProceedings),
+            ("misc", Type::Other),
+            ("other", Type::Other),
+        ] {
+            let mut bib_de = Deserializer::new(StrReader::new("{k}"));
+            let deserializer =
+                EntryDeserializer::new(&mut bib_de, EntryType::Regular(entry_type.into()));
+            let data = Record::deserialize(deserializer).unwrap();
+            assert_eq!(data, Record(expected, "k".into(), HashMap::new()));
+        }
+
+        // without a fallback variant, unknown entry types are an error
+        let mut bib_de = Deserializer::new(StrReader::new("{k}"));
+        let deserializer = EntryDeserializer::new(&mut bib_de, EntryType::Regular("book".into()));
+        assert!(StrictRecord::deserialize(deserializer).is_err());
+        let mut bib_de = Deserializer::new(StrReader::new("{k}"));
+        let deserializer =
+            EntryDeserializer::new(&mut bib_de, EntryType::Regular("Article".into()));
+        assert_eq!(
+            StrictRecord::deserialize(deserializer).unwrap(),
+            StrictRecord {
+                entry_type: Strict::Article
+            }
+        );
+    }
+
     #[test]
     fn test_fields_as_map_enum() {
         let reader = StrReader::new(", year = 2012, month = 11, day = 5,}");
@@ -1072,6 +1556,67 @@ mod tests {
         assert_eq!(data, expected_data);
     }
 
+    #[test]
+    fn test_fields_as_map_enum_unknown() {
+        #[derive(Deserialize, Debug, Hash, PartialEq, Eq)]
+        #[serde(rename_all = "lowercase")]
+        enum Date {
+            Year,
+            Month,
+        }
+
+        let input = ", Year = 2012, day = 5, month = 11, note = {N},}";
+        let expected = HashMap::from([(Date::Year, "2012".into()), (Date::Month, "11".into())]);
+
+        let mut bib_de = Deserializer::new(StrReader::new(input));
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+        assert!(HashMap::<Date, String>::deserialize(deserializer).is_err());
+
+        let mut bib_de = Deserializer::new(StrReader::new(input))
+            .unknown_field_keys(UnknownFieldKeyPolicy::Skip);
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+        let data: HashMap<Date, String> = HashMap::deserialize(deserializer).unwrap();
+        assert_eq!(data, expected);
+
+        // the skipped keys are reported, also when the fields are read in advance
+        let skipped = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = skipped.clone();
+        let mut bib_de = Deserializer::new(StrReader::new(input))
+            .duplicate_fields(DuplicateFieldPolicy::Error)
+            .unknown_field_keys(UnknownFieldKeyPolicy::Warn(Box::new(move |key| {
+                sink.lock().unwrap().push(key.to_owned())
+            })));
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+        let data: HashMap<Date, String> = HashMap::deserialize(deserializer).unwrap();
+        assert_eq!(data, expected);
+        assert_eq!(*skipped.lock().unwrap(), vec!["day", "note"]);
+
+        // a fallback variant receives the unknown keys
+        #[derive(Deserialize, Debug, Hash, PartialEq, Eq)]
+        #[serde(rename_all = "lowercase")]
+        enum Field {
+            Year,
+            #[serde(other)]
+            Other,
+        }
+
+        let mut bib_de = Deserializer::new(StrReader::new(", year = 2012, day = 5}"));
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+        let data: Vec<(Field, String)> = HashMap::<Field, String>::deserialize(deserializer)
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(data.len(), 2);
+        assert!(data.contains(&(Field::Other, "5".into())));
+
+        // only unknown fields
+        let mut bib_de = Deserializer::new(StrReader::new(", day = 5}"))
+            .unknown_field_keys(UnknownFieldKeyPolicy::Skip);
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+        let data: HashMap<Date, String> = HashMap::deserialize(deserializer).unwrap();
+        assert!(data.is_empty());
+    }
+
     #[test]
     fn test_fields_as_struct() {
         #[derive(Deserialize, Debug, PartialEq)]
@@ -1096,6 +1641,477 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_entry_type_aliases() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "lowercase")]
+        enum AliasedEntryType {
+            InProceedings,
+            Online,
+        }
+
+        let types = |input: &'static str| {
+            Deserializer::new(StrReader::new(input))
+                .with_entry_type_aliases(crate::ser::BIBTEX_TO_BIBLATEX)
+                .with_entry_type_aliases([("ONLINE", "online"), ("web", "online")])
+                .into_iter_regular_entry::<(AliasedEntryType, &str, ())>()
+                .map(|entry| entry.unwrap().0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            types("@Conference{a,} @inproceedings{b,} @WWW{c,} @web{d,} @online{e,}"),
+            vec![
+                AliasedEntryType::InProceedings,
+                AliasedEntryType::InProceedings,
+                AliasedEntryType::Online,
+                AliasedEntryType::Online,
+                AliasedEntryType::Online,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fields_flatten() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Common {
+            title: String,
+            year: Option<String>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Flattened<'a> {
+            #[serde(flatten)]
+            common: Common,
+            #[serde(flatten, borrow)]
+            extra: HashMap<&'a str, Cow<'a, str>>,
+        }
+
+        let reader = StrReader::new(", title = {T}, note = {N} # {M}, year = 2023, series = {S}}");
+        let mut bib_de = Deserializer::new(reader);
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+
+        assert_eq!(
+            Flattened::deserialize(deserializer).unwrap(),
+            Flattened {
+                common: Common {
+                    title: "T".into(),
+                    year: Some("2023".into()),
+                },
+                extra: HashMap::from([("note", "NM".into()), ("series", "S".into())]),
+            }
+        );
+
+        // flattened token sequences are captured when values are self-describing
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Tok<'a> {
+            Variable(&'a str),
+            Text(&'a str),
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct FlattenedTokens<'a> {
+            title: String,
+            #[serde(flatten, borrow)]
+            extra: HashMap<&'a str, Vec<Tok<'a>>>,
+        }
+
+        let reader = StrReader::new(", title = {T}, note = {N} # a}");
+        let mut bib_de = Deserializer::new(reader).any_as_tokens(true);
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+
+        assert_eq!(
+            FlattenedTokens::deserialize(deserializer).unwrap(),
+            FlattenedTokens {
+                title: "T".into(),
+                extra: HashMap::from([("note", vec![Tok::Text("N"), Tok::Variable("a")])]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_fields_unknown_capture() {
+        use std::collections::BTreeMap;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Known<'a> {
+            title: &'a str,
+            #[serde(rename = "__extra", default)]
+            extra: BTreeMap<&'a str, String>,
+        }
+
+        let input = ", title = {T}, note = {N} # m, year = 2023}";
+
+        // unknown fields are skipped by default
+        let mut bib_de = Deserializer::new(StrReader::new(input));
+        assert_eq!(
+            Known::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            Known {
+                title: "T",
+                extra: BTreeMap::new(),
+            }
+        );
+
+        // unknown fields are collected, with macros resolved
+        let mut bib_de = Deserializer::new(StrReader::new(input)).collect_unknown_fields("__extra");
+        std::sync::Arc::make_mut(&mut bib_de.macros).insert(
+            crate::token::Variable::new_unchecked("m"),
+            vec![crate::token::Token::str_unchecked("M")],
+        );
+        assert_eq!(
+            Known::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            Known {
+                title: "T",
+                extra: BTreeMap::from([("note", "NM".into()), ("year", "2023".into())]),
+            }
+        );
+        assert_eq!(bib_de.parser.pos, bib_de.parser.input.len() - 1);
+
+        // structs without the designated field are unaffected
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TitleOnly<'a> {
+            title: &'a str,
+        }
+
+        let mut bib_de = Deserializer::new(StrReader::new(input)).collect_unknown_fields("__extra");
+        assert_eq!(
+            TitleOnly::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            TitleOnly { title: "T" }
+        );
+
+        // a field with the reserved name is collected rather than emitted twice
+        let input = ", title = {T}, __extra = {E}}";
+        let mut bib_de = Deserializer::new(StrReader::new(input)).collect_unknown_fields("__extra");
+        assert_eq!(
+            Known::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            Known {
+                title: "T",
+                extra: BTreeMap::from([("__extra", "E".into())]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_deny_unknown_fields() {
+        use crate::error::ErrorKind;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(deny_unknown_fields)]
+        struct Strict<'a> {
+            title: &'a str,
+            year: Option<&'a str>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(deny_unknown_fields)]
+        struct Record<'a> {
+            entry_key: &'a str,
+            #[serde(borrow)]
+            fields: Strict<'a>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(deny_unknown_fields)]
+        struct KeyOnly<'a> {
+            entry_key: &'a str,
+        }
+
+        // the parts of the entry which are not struct fields are skipped
+        let input = "@article{a, title = {T}}\n@book{b, year = 2024, title = {U}}";
+        assert_eq!(
+            crate::from_str::<Vec<Record>>(input).unwrap(),
+            vec![
+                Record {
+                    entry_key: "a",
+                    fields: Strict {
+                        title: "T",
+                        year: None
+                    }
+                },
+                Record {
+                    entry_key: "b",
+                    fields: Strict {
+                        title: "U",
+                        year: Some("2024")
+                    }
+                }
+            ]
+        );
+        assert_eq!(
+            crate::from_str::<Vec<KeyOnly>>(input).unwrap(),
+            vec![KeyOnly { entry_key: "a" }, KeyOnly { entry_key: "b" }]
+        );
+
+        // unknown field keys are reported with the entry key
+        let input = "@article{a, title = {T}}\n@book{b, title = {U}, note = {N}}";
+        let err = crate::from_str::<Vec<Record>>(input).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::UnknownField {
+                key: "note",
+                expected: ["title", "year"]
+            }
+        ));
+        assert_eq!(err.entry_key(), Some("b"));
+        assert_eq!(
+            err.to_string(),
+            "unknown field `note`, expected one of `title`, `year` in entry `b`"
+        );
+
+        // the entry key is unknown when deserializing fields directly
+        let mut bib_de = Deserializer::new(StrReader::new(", note = {N}}"));
+        let err = Strict::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap_err();
+        assert_eq!(err.entry_key(), None);
+        assert_eq!(
+            err.to_string(),
+            "unknown field `note`, expected one of `title`, `year`"
+        );
+    }
+
+    #[test]
+    fn test_error_context() {
+        #[derive(Deserialize, Debug)]
+        struct Year {
+            #[allow(dead_code)]
+            year: String,
+        }
+
+        let input = "@misc{ok, year = 2024}\n@book{Knuth1984, title = {T}, year = undefined}";
+
+        let err = crate::from_str::<Vec<(String, String, Year)>>(input).unwrap_err();
+        assert_eq!(err.entry_key(), Some("Knuth1984"));
+        assert_eq!(err.field_key(), Some("year"));
+        assert_eq!(
+            err.to_string(),
+            "expected text, got unresolved macro undefined in field `year` of entry `Knuth1984`"
+        );
+
+        // fields as a sequence of pairs
+        let err =
+            crate::from_str::<Vec<(String, String, Vec<(String, String)>)>>(input).unwrap_err();
+        assert_eq!(err.entry_key(), Some("Knuth1984"));
+        assert_eq!(err.field_key(), Some("year"));
+
+        // syntax errors in a value are in the field, and other syntax errors after the entry key
+        // are only in the entry
+        let err =
+            crate::from_str::<Vec<(String, String, Year)>>("@misc{k, year = 1 2}").unwrap_err();
+        assert_eq!(err.entry_key(), Some("k"));
+        assert_eq!(err.field_key(), Some("year"));
+        let err =
+            crate::from_str::<Vec<(String, String, Year)>>("@misc{k, year = 1, = 2}").unwrap_err();
+        assert_eq!(err.entry_key(), Some("k"));
+        assert_eq!(err.field_key(), None);
+
+        // errors before the entry key have no context
+        let err = crate::from_str::<Vec<(String, String, Year)>>("@misc[k, year = 1]").unwrap_err();
+        assert_eq!(err.entry_key(), None);
+    }
+
+    #[test]
+    fn test_fields_duplicate_policy() {
+        let input = ", title = {A}, year = 2023, TITLE = {B}}";
+
+        let fields = |policy| {
+            let mut bib_de = Deserializer::new(StrReader::new(input)).duplicate_fields(policy);
+            Vec::<(&str, String)>::deserialize(FieldDeserializer::new(&mut bib_de))
+        };
+
+        assert_eq!(
+            fields(DuplicateFieldPolicy::UseFirst).unwrap(),
+            vec![("title", "A".into()), ("year", "2023".into())]
+        );
+        assert_eq!(
+            fields(DuplicateFieldPolicy::UseLast).unwrap(),
+            vec![("title", "B".into()), ("year", "2023".into())]
+        );
+
+        let err = fields(DuplicateFieldPolicy::Error).unwrap_err();
+        assert_eq!(err.classify(), crate::error::Category::Data);
+        assert_eq!(err.to_string(), "duplicate field 'TITLE' at bytes 28..33");
+
+        // structs would otherwise reject the duplicate field
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Title {
+            title: String,
+        }
+
+        let input = ", title = {A}, title = {B}}";
+        let mut bib_de = Deserializer::new(StrReader::new(input));
+        assert!(Title::deserialize(FieldDeserializer::new(&mut bib_de)).is_err());
+
+        let mut bib_de = Deserializer::new(StrReader::new(input))
+            .duplicate_fields(DuplicateFieldPolicy::UseLast);
+        assert_eq!(
+            Title::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            Title { title: "B".into() }
+        );
+        assert_eq!(bib_de.parser.pos, bib_de.parser.input.len() - 1);
+
+        // the token buffers of both values are recycled
+        assert_eq!(bib_de.token_buffers.len(), 2);
+        assert!(bib_de.token_buffers.iter().all(|tokens| tokens.is_empty()));
+    }
+
+    #[test]
+    fn test_fields_as_struct_skip() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TitleOnly<'a> {
+            title: &'a str,
+        }
+
+        // skipped values are not tokenized, so undefined macros are never resolved
+        let reader = StrReader::new(
+            ", month = feb, title = {A nice title}, note = {A} # undef # {B}, year = 2023}",
+        );
+        let mut bib_de = Deserializer::new(reader);
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+
+        assert_eq!(
+            TitleOnly {
+                title: "A nice title"
+            },
+            TitleOnly::deserialize(deserializer).unwrap()
+        );
+        assert_eq!(bib_de.parser.pos, bib_de.parser.input.len() - 1);
+
+        // skipped values must still be syntactically valid
+        let reader = StrReader::new(", note = {A} # , title = {T}}");
+        let mut bib_de = Deserializer::new(reader);
+        let deserializer = FieldDeserializer::new(&mut bib_de);
+        assert!(TitleOnly::deserialize(deserializer).is_err());
+
+        // skipped values are also not resolved when fields are read in advance
+        for policy in [
+            DuplicateFieldPolicy::UseFirst,
+            DuplicateFieldPolicy::UseLast,
+            DuplicateFieldPolicy::Error,
+        ] {
+            let reader = StrReader::new(", month = feb, title = {T}, MONTH = feb # {1}}");
+            let mut bib_de = Deserializer::new(reader).duplicate_fields(policy);
+            bib_de.macro_usage = Some(Vec::new());
+            let result = TitleOnly::deserialize(FieldDeserializer::new(&mut bib_de));
+            match policy {
+                DuplicateFieldPolicy::Error => assert!(result.is_err()),
+                _ => assert_eq!(result.unwrap(), TitleOnly { title: "T" }),
+            }
+            assert_eq!(bib_de.macro_usage, Some(Vec::new()));
+
+            let reader = StrReader::new(", note = {A} # , title = {T}}");
+            let mut bib_de = Deserializer::new(reader).duplicate_fields(policy);
+            assert!(TitleOnly::deserialize(FieldDeserializer::new(&mut bib_de)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_verbatim_fields() {
+        use std::collections::BTreeMap;
+
+        let input = ", title = {a  b}, URL = {c  d}, doi = {e} # {  f}}";
+        let new = || {
+            Deserializer::new(StrReader::new(input))
+                .normalize_whitespace(true)
+                .with_value_filter(|_, value| Cow::Owned(value.to_uppercase()))
+        };
+        let expected = vec![
+            ("title", "A B".to_owned()),
+            ("URL", "c  d".to_owned()),
+            ("doi", "e  f".to_owned()),
+        ];
+
+        let mut bib_de = new();
+        assert_eq!(
+            Vec::<(&str, String)>::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            expected
+        );
+
+        let mut bib_de = new().duplicate_fields(DuplicateFieldPolicy::UseFirst);
+        assert_eq!(
+            BTreeMap::<&str, String>::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            BTreeMap::from_iter(expected.clone())
+        );
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Known<'a> {
+            title: String,
+            #[serde(rename = "__extra", borrow)]
+            extra: BTreeMap<&'a str, String>,
+        }
+
+        let mut bib_de = new().collect_unknown_fields("__extra");
+        let known = Known::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap();
+        assert_eq!(known.title, "A B");
+        assert_eq!(known.extra, BTreeMap::from_iter(expected[1..].to_vec()));
+
+        // with a custom table
+        let mut bib_de = new().verbatim_fields(["Title"]);
+        assert_eq!(
+            Vec::<(&str, String)>::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            vec![
+                ("title", "a  b".to_owned()),
+                ("URL", "C D".to_owned()),
+                ("doi", "E F".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_filter() {
+        use std::collections::BTreeMap;
+
+        let input = ", title = {a  b}, note = {c} # {  d}, year = 2023}";
+        let new = || {
+            Deserializer::new(StrReader::new(input)).with_value_filter(|key, value| {
+                if key == "year" {
+                    value
+                } else {
+                    Cow::Owned(value.split_whitespace().collect::<Vec<_>>().join(" "))
+                }
+            })
+        };
+        let expected = vec![
+            ("title", "a b".to_owned()),
+            ("note", "c d".to_owned()),
+            ("year", "2023".to_owned()),
+        ];
+
+        // as a sequence of key-value pairs
+        let mut bib_de = new();
+        assert_eq!(
+            Vec::<(&str, String)>::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            expected
+        );
+
+        // as a map, with fields read in advance
+        let mut bib_de = new().duplicate_fields(DuplicateFieldPolicy::UseFirst);
+        assert_eq!(
+            BTreeMap::<&str, String>::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap(),
+            BTreeMap::from_iter(expected.clone())
+        );
+
+        // as a struct, with unknown fields
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Known<'a> {
+            #[serde(borrow)]
+            year: Cow<'a, str>,
+            #[serde(rename = "__extra")]
+            extra: BTreeMap<&'a str, String>,
+        }
+
+        let mut bib_de = new().collect_unknown_fields("__extra");
+        let known = Known::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap();
+        assert!(matches!(known.year, Cow::Borrowed("2023")));
+        assert_eq!(known.extra, BTreeMap::from_iter(expected[..2].to_vec()));
+
+        // values deserialized as tokens are not filtered
+        let mut bib_de = new();
+        assert_eq!(
+            HashMap::<&str, Vec<Tok>>::deserialize(FieldDeserializer::new(&mut bib_de)).unwrap()
+                ["title"],
+            vec![Tok::T("a  b")]
+        );
+    }
+
     #[test]
     fn test_optional_struct_field() {
         // test optional fields
@@ -1132,4 +2148,109 @@ mod tests {
             OptionFields::deserialize(deserializer).unwrap()
         );
     }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_fields_indexmap() {
+        use indexmap::IndexMap;
+
+        // unlike a BTreeMap or a HashMap, the fields are kept in the order of the input
+        let input = ", year = 2024, Title = {T}, author = {A}}";
+        let mut bib_de = Deserializer::new(StrReader::new(input));
+        let fields = IndexMap::<&str, String>::deserialize(FieldDeserializer::new(&mut bib_de));
+        assert_eq!(
+            fields.unwrap().into_iter().collect::<Vec<_>>(),
+            vec![
+                ("year", "2024".into()),
+                ("Title", "T".into()),
+                ("author", "A".into())
+            ]
+        );
+
+        let input = ", title = {A}, year = 2023, title = {B}}";
+        let mut bib_de = Deserializer::new(StrReader::new(input))
+            .duplicate_fields(DuplicateFieldPolicy::UseLast);
+        let fields = IndexMap::<String, String>::deserialize(FieldDeserializer::new(&mut bib_de));
+        assert_eq!(
+            fields.unwrap().into_iter().collect::<Vec<_>>(),
+            vec![("title".into(), "B".into()), ("year".into(), "2023".into())]
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        use crate::de::{DeserializerBuilder, InvalidUtf8Policy};
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Token {
+            Variable(String),
+            Text(String),
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Entry {
+            Regular { fields: Vec<(String, String)> },
+            Comment(String),
+            Macro(Option<(String, Vec<Token>)>),
+            Preamble(String),
+        }
+
+        let input = b"@string{m = {\xe9} # m0} @comment{\xff} @preamble{{a\xe9}}\
+                      @misc{k, a = {\xe9} # {b\xe9}, b = {ok}}";
+        let read = |policy| {
+            DeserializerBuilder::default()
+                .invalid_utf8(policy)
+                .build_from_slice(input)
+                .into_iter::<Entry>()
+                .collect::<Result<Vec<_>>>()
+        };
+
+        assert!(read(InvalidUtf8Policy::Error).is_err());
+        assert_eq!(
+            read(InvalidUtf8Policy::Replace).unwrap(),
+            vec![
+                Entry::Macro(Some((
+                    "m".to_owned(),
+                    vec![
+                        Token::Text("\u{fffd}".to_owned()),
+                        Token::Variable("m0".to_owned())
+                    ]
+                ))),
+                Entry::Comment("\u{fffd}".to_owned()),
+                Entry::Preamble("a\u{fffd}".to_owned()),
+                Entry::Regular {
+                    fields: vec![
+                        ("a".to_owned(), "\u{fffd}b\u{fffd}".to_owned()),
+                        ("b".to_owned(), "ok".to_owned())
+                    ]
+                },
+            ]
+        );
+
+        // only text which is not valid UTF-8 is transcoded
+        let latin1 = |bytes: &[u8]| bytes.iter().map(|b| char::from(*b)).collect();
+        let entries = read(InvalidUtf8Policy::Transcode(latin1)).unwrap();
+        assert_eq!(entries[1], Entry::Comment("ÿ".to_owned()));
+        assert_eq!(
+            entries[3],
+            Entry::Regular {
+                fields: vec![
+                    ("a".to_owned(), "ébé".to_owned()),
+                    ("b".to_owned(), "ok".to_owned())
+                ]
+            }
+        );
+
+        // text deserialized as bytes is unaffected
+        #[derive(Deserialize)]
+        struct Record<'a> {
+            #[serde(borrow)]
+            fields: Vec<(&'a str, &'a [u8])>,
+        }
+        let record: Record = Deserializer::from_slice(b"@misc{k, a = {\xe9}}")
+            .invalid_utf8(InvalidUtf8Policy::Replace)
+            .single_entry()
+            .unwrap();
+        assert_eq!(record.fields, vec![("a", &b"\xe9"[..])]);
+    }
 }
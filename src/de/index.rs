@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use serde::de;
+use unicase::UniCase;
+
+use crate::{
+    error::Result,
+    parse::{BibtexParse, MacroDictionary},
+    token::{EntryKey, EntryType},
+    SliceReader, StrReader,
+};
+
+use super::Deserializer;
+
+/// The location of a regular entry, as found by [`scan_keys`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEntry<'r> {
+    /// The entry type, such as `article`.
+    pub entry_type: &'r str,
+    /// The entry key.
+    pub entry_key: EntryKey<&'r str>,
+    /// The byte range of the entry in the input, from the `@` up to and including the closing
+    /// bracket.
+    pub span: Range<usize>,
+}
+
+/// Scan a `&str` for the regular entries it contains, without deserializing any field values.
+///
+/// The entries are returned in the order in which they appear in the input. The input is still
+/// checked for syntax errors, but field values are never copied or expanded, which is
+/// significantly faster than deserializing the entries.
+/// ```
+/// use serde_bibtex::de::scan_keys;
+///
+/// let input = "@string{k = {v}} @article{Knuth1984, title = k}";
+/// let index = scan_keys(input).unwrap();
+/// assert_eq!(index.len(), 1);
+/// assert_eq!(index[0].entry_key.as_ref(), "Knuth1984");
+/// assert_eq!(&input[index[0].span.clone()], "@article{Knuth1984, title = k}");
+/// ```
+pub fn scan_keys(input: &str) -> Result<Vec<IndexedEntry<'_>>> {
+    scan(StrReader::new(input), None)
+}
+
+/// Scan a `&[u8]` for the regular entries it contains, without deserializing any field values.
+///
+/// See [`scan_keys`] for more detail.
+pub fn scan_keys_slice(input: &[u8]) -> Result<Vec<IndexedEntry<'_>>> {
+    scan(SliceReader::new(input), None)
+}
+
+/// Scan the input for regular entries, also capturing macros if `macros` is provided.
+pub(crate) fn scan<'r, R: BibtexParse<'r>>(
+    mut parser: R,
+    macros: Option<&mut MacroDictionary<&'r str, &'r [u8]>>,
+) -> Result<Vec<IndexedEntry<'r>>> {
+    let result = scan_entries(&mut parser, macros);
+    result.map_err(|err| parser.locate(err))
+}
+
+fn scan_entries<'r, R: BibtexParse<'r>>(
+    parser: &mut R,
+    mut macros: Option<&mut MacroDictionary<&'r str, &'r [u8]>>,
+) -> Result<Vec<IndexedEntry<'r>>> {
+    let mut index = Vec::new();
+
+    parser.byte_order_mark()?;
+    while parser.next_entry_or_eof()? {
+        // `next_entry_or_eof` consumes the `@`
+        let start = parser.position() - 1;
+        parser.comment();
+        match EntryType::from(parser.identifier()?) {
+            EntryType::Regular(entry_type) => {
+                let closing_bracket = parser.initial()?;
+                let entry_key = parser.entry_key()?;
+                parser.ignore_fields()?;
+                parser.comma_opt();
+                parser.terminal(closing_bracket)?;
+                index.push(IndexedEntry {
+                    entry_type: entry_type.into_inner(),
+                    entry_key,
+                    span: start..parser.position(),
+                });
+            }
+            EntryType::Macro => match macros.as_deref_mut() {
+                Some(macros) => parser.ignore_macro_captured(macros)?,
+                None => parser.ignore_macro()?,
+            },
+            chunk => parser.ignore_entry(chunk)?,
+        }
+    }
+
+    Ok(index)
+}
+
+/// A bibliography which deserializes individual regular entries on demand.
+///
+/// Constructing a [`LazyBibliography`] performs a single scan of the input, recording the
+/// location of every regular entry as well as the macros which are defined. Afterwards, each
+/// call to [`LazyBibliography::get`] only reads the requested entry.
+/// ```
+/// use serde::Deserialize;
+/// use serde_bibtex::de::LazyBibliography;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Record {
+///     entry_key: String,
+///     fields: Fields,
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Fields {
+///     title: String,
+/// }
+///
+/// let input = r#"
+///     @string{t = {Title}}
+///     @article{first, title = t # { One}}
+///     @book{second, title = t # { Two}}
+/// "#;
+///
+/// let bib = LazyBibliography::from_str(input).unwrap();
+/// assert_eq!(bib.len(), 2);
+///
+/// let record: Record = bib.get("second").unwrap().unwrap();
+/// assert_eq!(record.fields.title, "Title Two");
+/// assert!(bib.get::<Record>("third").is_none());
+/// ```
+///
+/// Macros are expanded using their final definition in the input. Entry keys are compared
+/// case-insensitively, and if an entry key appears more than once, [`LazyBibliography::get`]
+/// returns the first entry with that key.
+#[derive(Debug, Clone)]
+pub struct LazyBibliography<'r> {
+    input: &'r [u8],
+    index: Vec<IndexedEntry<'r>>,
+    keys: HashMap<UniCase<&'r str>, usize>,
+    macros: Arc<MacroDictionary<&'r str, &'r [u8]>>,
+}
+
+impl<'r> LazyBibliography<'r> {
+    /// Construct a lazy bibliography from a `&str`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'r str) -> Result<Self> {
+        Self::from_slice(input.as_bytes())
+    }
+
+    /// Construct a lazy bibliography from a `&[u8]`.
+    pub fn from_slice(input: &'r [u8]) -> Result<Self> {
+        let mut macros = MacroDictionary::default();
+        let index = scan(SliceReader::new(input), Some(&mut macros))?;
+        let mut keys = HashMap::with_capacity(index.len());
+        for (idx, entry) in index.iter().enumerate() {
+            keys.entry(UniCase::new(entry.entry_key.clone().into_inner()))
+                .or_insert(idx);
+        }
+        Ok(Self {
+            input,
+            index,
+            keys,
+            macros: Arc::new(macros),
+        })
+    }
+
+    /// Deserialize the regular entry with the given entry key, or return `None` if there is no
+    /// such entry.
+    pub fn get<D: de::Deserialize<'r>>(&self, key: &str) -> Option<Result<D>> {
+        let entry = &self.index[*self.keys.get(&UniCase::new(key))?];
+        Deserializer::builder()
+            .shared_macros(Arc::clone(&self.macros))
+            .build_from_slice(&self.input[entry.span.clone()])
+            .into_iter_regular_entry()
+            .next()
+    }
+
+    /// Whether there is a regular entry with the given entry key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys.contains_key(&UniCase::new(key))
+    }
+
+    /// The locations of the regular entries, in the order in which they appear in the input.
+    pub fn index(&self) -> &[IndexedEntry<'r>] {
+        &self.index
+    }
+
+    /// The number of regular entries.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether there are no regular entries.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// The macros defined in the input.
+    pub fn macros(&self) -> &MacroDictionary<&'r str, &'r [u8]> {
+        &self.macros
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_keys() {
+        let input = r#"junk
+        @preamble{"@a{b}"}
+        @Article{key, title = {@book{fake,}}, year = 2024,}
+        @comment{@misc{fake2}}
+        @string(k = {v})
+        @book(key2)
+        "#;
+        let index = scan_keys(input).unwrap();
+        assert_eq!(
+            index
+                .iter()
+                .map(|entry| (entry.entry_type, entry.entry_key.as_ref()))
+                .collect::<Vec<_>>(),
+            vec![("Article", "key"), ("book", "key2")]
+        );
+        assert_eq!(
+            &input[index[0].span.clone()],
+            "@Article{key, title = {@book{fake,}}, year = 2024,}"
+        );
+        assert_eq!(&input[index[1].span.clone()], "@book(key2)");
+
+        assert_eq!(scan_keys_slice(input.as_bytes()).unwrap(), index);
+    }
+
+    #[test]
+    fn test_scan_keys_err() {
+        assert!(scan_keys("@article{key, title = }").is_err());
+        assert!(scan_keys("@article{key, title = {a}").is_err());
+        assert_eq!(scan_keys("").unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_lazy_bibliography() {
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Record<'a> {
+            entry_type: &'a str,
+            fields: Fields,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Fields {
+            title: String,
+        }
+
+        let input = r#"
+        @article{a, title = k}
+        @string{k = {Title}}
+        @book{b, title = k # { Two}}
+        @misc{a, title = {Duplicate}}
+        @misc{c, title = 12}
+        "#;
+        let bib = LazyBibliography::from_str(input).unwrap();
+        assert_eq!(bib.len(), 4);
+        assert!(bib.contains_key("c"));
+        // entry keys are compared case-insensitively
+        assert!(bib.contains_key("C"));
+
+        let record: Record = bib.get("a").unwrap().unwrap();
+        assert_eq!(record.entry_type, "article");
+        assert_eq!(record.fields.title, "Title");
+        let record: Record = bib.get("b").unwrap().unwrap();
+        assert_eq!(record.fields.title, "Title Two");
+        assert!(bib.get::<Record>("d").is_none());
+        let record: Record = bib.get("A").unwrap().unwrap();
+        assert_eq!(record.entry_type, "article");
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Author {
+            fields: AuthorFields,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct AuthorFields {
+            author: String,
+        }
+        assert!(bib.get::<Author>("c").unwrap().is_err());
+
+        assert!(LazyBibliography::from_str("@article{a, title = }").is_err());
+    }
+}
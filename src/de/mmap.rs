@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{error::Result, parse::MacroDictionary, SliceReader};
+
+use super::Deserializer;
+
+/// A memory-mapped `.bib` file.
+///
+/// The contents of the file are not copied into memory; instead, a [`Deserializer`] constructed
+/// using [`Deserializer::from_mapped`] borrows directly from the mapped file.
+/// ```no_run
+/// use serde_bibtex::de::{Deserializer, MappedFile};
+/// use serde_bibtex::entry::BorrowEntry;
+///
+/// // SAFETY: the file is not modified while it is mapped
+/// let file = unsafe { MappedFile::open("references.bib") }.unwrap();
+/// for entry in Deserializer::from_mapped(&file).into_iter::<BorrowEntry>() {
+///     println!("{:?}", entry.unwrap());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MappedFile {
+    mmap: Mmap,
+}
+
+impl MappedFile {
+    /// Memory-map the file at `path`.
+    ///
+    /// # Safety
+    /// The behaviour is undefined if the file is modified, by this or any other process, while it
+    /// is mapped. See [`Mmap::map`] for more detail.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file(&File::open(path)?)
+    }
+
+    /// Memory-map an open file.
+    ///
+    /// # Safety
+    /// The behaviour is undefined if the file is modified, by this or any other process, while it
+    /// is mapped. See [`Mmap::map`] for more detail.
+    pub unsafe fn from_file(file: &File) -> Result<Self> {
+        Ok(Self {
+            mmap: Mmap::map(file)?,
+        })
+    }
+
+    /// The contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+}
+
+impl<'r> Deserializer<'r, SliceReader<'r>> {
+    /// Construct a deserializer from a memory-mapped file.
+    #[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+    pub fn from_mapped(file: &'r MappedFile) -> Self {
+        Self::from_slice(file.as_bytes())
+    }
+
+    /// Construct a deserializer from a memory-mapped file, with a prescribed macro dictionary.
+    #[cfg_attr(docsrs, doc(cfg(feature = "mmap")))]
+    pub fn from_mapped_with_macros(
+        file: &'r MappedFile,
+        macros: MacroDictionary<&'r str, &'r [u8]>,
+    ) -> Self {
+        Self::from_slice_with_macros(file.as_bytes(), macros)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapped_file() {
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Record<'a> {
+            entry_key: &'a str,
+        }
+
+        // SAFETY: the asset is not modified
+        let file = unsafe { MappedFile::open("assets/biber_test.bib") }.unwrap();
+        assert_eq!(
+            file.as_bytes(),
+            std::fs::read("assets/biber_test.bib").unwrap()
+        );
+
+        let records = Deserializer::from_mapped(&file)
+            .into_iter_regular_entry::<Record>()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(!records.is_empty());
+
+        assert!(unsafe { MappedFile::open("assets/does_not_exist.bib") }.is_err());
+    }
+}
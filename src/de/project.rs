@@ -0,0 +1,234 @@
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::de;
+
+use crate::{error::Result, parse::MacroDictionary, StrReader};
+
+use super::Deserializer;
+
+/// A collection of `.bib` files which are read in order, sharing a single macro scope.
+///
+/// As when TeX is given several bibliography resources, a macro defined by an `@string` entry in
+/// one file can be used by the entries of every later file. The regular entries of every file
+/// are returned by [`Project::entries`], along with the file and the location in the file of each
+/// entry.
+/// ```
+/// use serde::Deserialize;
+/// use serde_bibtex::de::Project;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Record {
+///     entry_key: String,
+///     fields: Fields,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Fields {
+///     journal: String,
+/// }
+///
+/// let project = Project::new()
+///     .with_source("macros.bib", "@string{jams = {J. Amer. Math. Soc.}}")
+///     .with_source("refs.bib", "@article{key, journal = jams}");
+///
+/// let entries = project
+///     .entries::<Record>()
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(entries.len(), 1);
+/// assert_eq!(entries[0].source.to_str(), Some("refs.bib"));
+/// assert_eq!(entries[0].span, 0..29);
+/// assert_eq!(entries[0].entry.fields.journal, "J. Amer. Math. Soc.");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Project {
+    sources: Vec<(PathBuf, String)>,
+}
+
+impl Project {
+    /// Construct a project without any files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a project from the files at the given paths, in order.
+    pub fn open<I, P>(paths: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        paths
+            .into_iter()
+            .try_fold(Self::new(), |project, path| project.with_file(path))
+    }
+
+    /// Append the file at `path` to the project.
+    pub fn with_file<P: AsRef<Path>>(self, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = String::from_utf8(std::fs::read(path)?).map_err(|err| err.utf8_error())?;
+        Ok(self.with_source(path, contents))
+    }
+
+    /// Append a file with the given name and contents to the project, without reading from the
+    /// file system.
+    pub fn with_source<P, S>(mut self, name: P, contents: S) -> Self
+    where
+        P: Into<PathBuf>,
+        S: Into<String>,
+    {
+        self.sources.push((name.into(), contents.into()));
+        self
+    }
+
+    /// The names of the files in the project, in order.
+    pub fn sources(&self) -> impl Iterator<Item = &Path> {
+        self.sources.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Returns an iterator over the regular entries of every file, in order, automatically
+    /// capturing and expanding macros.
+    ///
+    /// Errors are returned as for
+    /// [`Deserializer::into_iter_regular_entry`](super::Deserializer::into_iter_regular_entry);
+    /// use [`ProjectIter::source`] to find the file in which an error occurred.
+    pub fn entries<'p, D: de::Deserialize<'p>>(&'p self) -> ProjectIter<'p, D> {
+        ProjectIter {
+            sources: self.sources.iter(),
+            current: None,
+            macros: MacroDictionary::default(),
+            _output: PhantomData,
+        }
+    }
+}
+
+/// A regular entry of a [`Project`], along with its location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectEntry<'p, D> {
+    /// The name of the file which contains the entry.
+    pub source: &'p Path,
+    /// The byte range of the entry in the file, from the `@` up to and including the closing
+    /// bracket.
+    pub span: Range<usize>,
+    /// The deserialized entry.
+    pub entry: D,
+}
+
+/// A lazy iterator over the regular entries of a [`Project`].
+///
+/// This struct is created by [`Project::entries`].
+pub struct ProjectIter<'p, D> {
+    sources: std::slice::Iter<'p, (PathBuf, String)>,
+    current: Option<(&'p Path, Deserializer<'p, StrReader<'p>>)>,
+    /// The macros captured by earlier files, while no file is being read.
+    macros: MacroDictionary<&'p str, &'p [u8]>,
+    _output: PhantomData<D>,
+}
+
+impl<'p, D> ProjectIter<'p, D> {
+    /// The name of the file which is currently being read, or None if every file has been read.
+    pub fn source(&self) -> Option<&'p Path> {
+        self.current.as_ref().map(|(path, _)| *path)
+    }
+
+    /// Return the macros defined by the files which have been read so far.
+    pub fn finish(self) -> MacroDictionary<&'p str, &'p [u8]> {
+        match self.current {
+            Some((_, de)) => de.finish(),
+            None => self.macros,
+        }
+    }
+}
+
+impl<'p, D: de::Deserialize<'p>> Iterator for ProjectIter<'p, D> {
+    type Item = Result<ProjectEntry<'p, D>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((source, de)) = self.current.as_mut() {
+                if let Some(res) = de.next_regular_entry_spanned(|_| true) {
+                    let source = *source;
+                    return Some(de.locate(res).map(|(span, entry)| ProjectEntry {
+                        source,
+                        span,
+                        entry,
+                    }));
+                }
+                if let Some((_, de)) = self.current.take() {
+                    self.macros = de.finish();
+                }
+            }
+
+            let (path, contents) = self.sources.next()?;
+            let macros = std::mem::take(&mut self.macros);
+            self.current = Some((
+                path.as_path(),
+                Deserializer::from_str_with_macros(contents, macros),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Record<'a> {
+        entry_key: &'a str,
+        fields: Fields,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Fields {
+        title: String,
+    }
+
+    #[test]
+    fn test_project() {
+        let first = "@string{a = {A}}\n@book{one, title = a}\n";
+        let second = "@string{a = {B}}\n\n@book{two, title = a # c}";
+        let third = "% c\n@string{c = {C}} @book{three, title = a # c}";
+        let project = Project::new()
+            .with_source("first.bib", first)
+            .with_source("empty.bib", "")
+            .with_source("second.bib", second)
+            .with_source("third.bib", third);
+        assert_eq!(project.sources().count(), 4);
+
+        let mut iter = project.entries::<Record>();
+        let entry = iter.next().unwrap().unwrap();
+        assert_eq!(entry.source, Path::new("first.bib"));
+        assert_eq!(&first[entry.span], "@book{one, title = a}");
+        assert_eq!(entry.entry.fields.title, "A");
+
+        // `c` is not yet defined
+        assert!(iter.next().unwrap().is_err());
+        assert_eq!(iter.source(), Some(Path::new("second.bib")));
+
+        let entry = iter.next().unwrap().unwrap();
+        assert_eq!(entry.source, Path::new("third.bib"));
+        assert_eq!(&third[entry.span], "@book{three, title = a # c}");
+        assert_eq!(entry.entry.fields.title, "BC");
+
+        assert!(iter.next().is_none());
+        assert_eq!(iter.source(), None);
+        assert_eq!(iter.finish().len(), 2);
+    }
+
+    #[test]
+    fn test_project_open() {
+        let project = Project::open(["assets/biber_test.bib"]).unwrap();
+        let count = project
+            .entries::<de::IgnoredAny>()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .len();
+        assert!(count > 0);
+
+        assert!(Project::open(["assets/biber_test.bib", "assets/does_not_exist.bib"]).is_err());
+    }
+}
@@ -1,24 +1,72 @@
 use std::borrow::Cow;
 
 use serde::de::{
-    self, value::BorrowedStrDeserializer, value::StringDeserializer, DeserializeSeed, EnumAccess,
-    SeqAccess, Unexpected, VariantAccess, Visitor,
+    self, value::BorrowedStrDeserializer, DeserializeSeed, EnumAccess, MapAccess, SeqAccess,
+    Unexpected, VariantAccess, Visitor,
 };
 use serde::forward_to_deserialize_any;
+use unicase::UniCase;
 
 use crate::{
     error::{Error, Result},
-    naming::{MACRO_TOKEN_VARIANT_NAME, TEXT_TOKEN_VARIANT_NAME},
+    naming::{MACRO_TOKEN_VARIANT_NAME, RAW_VALUE_NAME, TEXT_TOKEN_VARIANT_NAME},
     parse::BibtexParse,
     token::{Text, Token},
 };
 
-use super::Deserializer;
+use super::{Deserializer, InvalidUtf8Policy, UndefinedMacroPolicy};
+
+/// Options which control how values are deserialized.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ValueOptions {
+    /// Whether `deserialize_any` produces a sequence of tokens rather than a string.
+    pub any_as_tokens: bool,
+    /// How undefined macros are expanded when a value is deserialized as text.
+    pub undefined_macros: UndefinedMacroPolicy,
+    /// Whether whitespace is normalized when a value is deserialized as text.
+    pub normalize_whitespace: bool,
+    /// Whether the value is verbatim, so that its text is neither normalized nor filtered.
+    pub verbatim: bool,
+    /// How text which is not valid UTF-8 is converted when a value is deserialized as text.
+    pub invalid_utf8: InvalidUtf8Policy,
+}
+
+impl ValueOptions {
+    /// The options for the value of the field `key`, given the keys of the verbatim fields.
+    pub(crate) fn for_field(mut self, verbatim_fields: &[UniCase<&str>], key: &str) -> Self {
+        self.verbatim = verbatim_fields.contains(&UniCase::new(key));
+        self
+    }
+}
+
+/// Collapse each run of whitespace into a single space, and remove leading and trailing
+/// whitespace. The text is only copied if it is not already normalized.
+fn normalize_whitespace(text: Cow<'_, str>) -> Cow<'_, str> {
+    let trimmed = text.trim();
+    if trimmed.contains(|ch: char| ch.is_whitespace() && ch != ' ') || trimmed.contains("  ") {
+        return Cow::Owned(trimmed.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+
+    match text {
+        Cow::Borrowed(s) => Cow::Borrowed(s.trim()),
+        Cow::Owned(s) if s.len() == s.trim().len() => Cow::Owned(s),
+        Cow::Owned(s) => Cow::Owned(s.trim().to_owned()),
+    }
+}
+
+/// A function applied to the text of a field value, given the field key.
+pub(crate) type ValueFilter<'f> = dyn for<'c> Fn(&str, Cow<'c, str>) -> Cow<'c, str> + Send + 'f;
+
+/// A field key and the filter to apply to the text of its value.
+pub(crate) type FieldFilter<'a, 'r> = (&'r str, &'a ValueFilter<'r>);
 
 pub struct KeyValueDeserializer<'a, 'r> {
     key: Option<&'r str>,
     tokens: &'a mut Vec<Token<&'r str, &'r [u8]>>,
     complete: bool,
+    options: ValueOptions,
+    filter: Option<FieldFilter<'a, 'r>>,
+    raw: Option<&'r [u8]>,
 }
 
 impl<'a, 'r> KeyValueDeserializer<'a, 'r> {
@@ -27,6 +75,9 @@ impl<'a, 'r> KeyValueDeserializer<'a, 'r> {
             key: Some(s),
             tokens,
             complete: false,
+            options: ValueOptions::default(),
+            filter: None,
+            raw: None,
         }
     }
 
@@ -35,9 +86,45 @@ impl<'a, 'r> KeyValueDeserializer<'a, 'r> {
         de: &'a mut Deserializer<'r, R>,
     ) -> Result<Self> {
         de.scratch.clear();
-        de.parser.value_into(&mut de.scratch)?;
-        de.macros.resolve(&mut de.scratch);
-        Ok(Self::new(s, &mut de.scratch))
+        let raw = de.parser.value_into(&mut de.scratch)?;
+        de.resolve_scratch();
+        Ok(Self::new(s, &mut de.scratch)
+            .options(de.value_options)
+            .raw(Some(raw)))
+    }
+
+    /// Read the value of the field `s` after resolving macros, applying the value filter of the
+    /// deserializer.
+    pub fn field_from_de<R: BibtexParse<'r>>(
+        s: &'r str,
+        de: &'a mut Deserializer<'r, R>,
+    ) -> Result<Self> {
+        de.scratch.clear();
+        let raw = de.parser.value_into(&mut de.scratch)?;
+        de.resolve_scratch();
+        let options = de.field_options(s);
+        Ok(Self::new(s, &mut de.scratch)
+            .options(options)
+            .filter(de.value_filter.as_deref().map(|filter| (s, filter)))
+            .raw(Some(raw)))
+    }
+
+    /// Set the options used to deserialize the value.
+    pub(crate) fn options(mut self, options: ValueOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the filter applied to the text of the value.
+    pub(crate) fn filter(mut self, filter: Option<FieldFilter<'a, 'r>>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Set the source of the value, for deserializing a [`RawValue`](crate::token::RawValue).
+    pub(crate) fn raw(mut self, raw: Option<&'r [u8]>) -> Self {
+        self.raw = raw;
+        self
     }
 }
 
@@ -71,8 +158,13 @@ impl<'a, 'de: 'a> SeqAccess<'de> for KeyValueDeserializer<'a, 'de> {
                 .map(Some),
             (None, false) => {
                 self.complete = true;
-                seed.deserialize(ValueDeserializer::new(self.tokens))
-                    .map(Some)
+                seed.deserialize(
+                    ValueDeserializer::new(self.tokens)
+                        .options(self.options)
+                        .filter(self.filter)
+                        .raw(self.raw),
+                )
+                .map(Some)
             }
             _ => Ok(None),
         }
@@ -169,14 +261,100 @@ impl<'de> de::Deserializer<'de> for WrappedBorrowStrDeserializer<'de> {
     }
 }
 
+/// The variant which matches `name`, preferring an exact match to a case-insensitive match.
+pub(crate) fn match_variant(variants: &'static [&'static str], name: &str) -> Option<&'static str> {
+    if let Some(variant) = variants.iter().find(|variant| **variant == name) {
+        return Some(variant);
+    }
+    let name = UniCase::new(name);
+    variants
+        .iter()
+        .find(|variant| UniCase::new(**variant) == name)
+        .copied()
+}
+
+/// A deserializer for an entry type. This is the same as a [`WrappedBorrowStrDeserializer`],
+/// except that when deserialized as an enum, the entry type matches a unit variant
+/// case-insensitively, as in BibTeX. An entry type which does not match any variant is passed
+/// unchanged, so that a `#[serde(other)]` variant can be used as a fallback.
+#[derive(Debug, Clone)]
+pub struct EntryTypeDeserializer<'r> {
+    entry_type: &'r str,
+}
+
+impl<'r> EntryTypeDeserializer<'r> {
+    pub fn new(entry_type: &'r str) -> Self {
+        Self { entry_type }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for EntryTypeDeserializer<'de> {
+    type Error = Error;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_str(self.entry_type)
+    }
+
+    #[inline]
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let variant = match_variant(variants, self.entry_type).unwrap_or(self.entry_type);
+        visitor.visit_enum(BorrowedStrDeserializer::new(variant))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
 /// A deserializer for a [`Token`]. This only supports deserialization as an Enum.
 pub struct TokenDeserializer<'r> {
     value: Token<&'r str, &'r [u8]>,
+    invalid_utf8: InvalidUtf8Policy,
+    /// Whether `deserialize_any` should produce a map with a single entry from the variant name
+    /// to the token contents, rather than an enum.
+    any_as_map: bool,
 }
 
 impl<'r> TokenDeserializer<'r> {
     pub fn new(value: Token<&'r str, &'r [u8]>) -> Self {
-        Self { value }
+        Self {
+            value,
+            invalid_utf8: InvalidUtf8Policy::default(),
+            any_as_map: false,
+        }
+    }
+
+    /// Set how text which is not valid UTF-8 is converted when deserialized as a string.
+    pub(crate) fn invalid_utf8(mut self, policy: InvalidUtf8Policy) -> Self {
+        self.invalid_utf8 = policy;
+        self
+    }
+
+    /// Set whether `deserialize_any` produces a single-entry map rather than an enum.
+    pub(crate) fn any_as_map(mut self, enable: bool) -> Self {
+        self.any_as_map = enable;
+        self
     }
 }
 
@@ -185,6 +363,26 @@ impl<'de> de::Deserializer<'de> for TokenDeserializer<'de> {
 
     #[inline]
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.any_as_map {
+            visitor.visit_map(TokenMapAccess {
+                value: Some(self.value),
+                invalid_utf8: self.invalid_utf8,
+            })
+        } else {
+            visitor.visit_enum(self)
+        }
+    }
+
+    #[inline]
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
@@ -194,7 +392,46 @@ impl<'de> de::Deserializer<'de> for TokenDeserializer<'de> {
     forward_to_deserialize_any! {
         bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
         bytes byte_buf option unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct identifier ignored_any enum
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Map access for a [`Token`], represented as a single entry from the variant name to the token
+/// contents.
+struct TokenMapAccess<'r> {
+    value: Option<Token<&'r str, &'r [u8]>>,
+    invalid_utf8: InvalidUtf8Policy,
+}
+
+impl<'de> MapAccess<'de> for TokenMapAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let name = match self.value {
+            Some(Token::Variable(_)) => MACRO_TOKEN_VARIANT_NAME,
+            Some(Token::Text(_)) => TEXT_TOKEN_VARIANT_NAME,
+            None => return Ok(None),
+        };
+        seed.deserialize(BorrowedStrDeserializer::new(name))
+            .map(Some)
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(Token::Variable(var)) => {
+                seed.deserialize(WrappedBorrowStrDeserializer::new(var.into_inner()))
+            }
+            Some(Token::Text(text)) => {
+                seed.deserialize(TextDeserializer::new(text).invalid_utf8(self.invalid_utf8))
+            }
+            None => Err(de::Error::custom("value is missing")),
+        }
     }
 }
 
@@ -213,7 +450,9 @@ impl<'de> VariantAccess<'de> for TokenDeserializer<'de> {
             Token::Variable(var) => {
                 seed.deserialize(WrappedBorrowStrDeserializer::new(var.into_inner()))
             }
-            Token::Text(text) => seed.deserialize(TextDeserializer::new(text)),
+            Token::Text(text) => {
+                seed.deserialize(TextDeserializer::new(text).invalid_utf8(self.invalid_utf8))
+            }
         }
     }
 
@@ -255,12 +494,26 @@ impl<'de> de::EnumAccess<'de> for TokenDeserializer<'de> {
 }
 
 macro_rules! as_cow_impl {
-    ($fname:ident, $target:ty, $push:ident, $null:expr) => {
+    ($fname:ident, $target:ty, $push:ident, $null:expr, $name:expr, $text:expr) => {
         fn $fname(&mut self) -> Result<Cow<'r, $target>> {
+            let policy = self.options.undefined_macros;
+            let invalid_utf8 = self.options.invalid_utf8;
+            let convert = |token: Token<&'r str, &'r [u8]>| -> Result<Cow<'r, $target>> {
+                let text: &'r $target = match (token, policy) {
+                    (Token::Variable(var), UndefinedMacroPolicy::KeepName) => {
+                        $name(var.into_inner())
+                    }
+                    (Token::Variable(_), UndefinedMacroPolicy::Empty) => $null,
+                    (Token::Text(text), _) => return $text(invalid_utf8, text),
+                    (token, _) => token.try_into()?,
+                };
+                Ok(Cow::Borrowed(text))
+            };
+
             let mut init = loop {
                 match self.iter.next() {
                     Some(token) => {
-                        let cow: Cow<'r, $target> = Cow::Borrowed(token.try_into()?);
+                        let cow = convert(token)?;
                         if cow.len() > 0 {
                             break cow;
                         }
@@ -270,7 +523,7 @@ macro_rules! as_cow_impl {
             };
 
             for token in self.iter.by_ref() {
-                let cow: Cow<'r, $target> = Cow::Borrowed(token.try_into()?);
+                let cow = convert(token)?;
                 if cow.len() > 0 {
                     init.to_mut().$push(&cow)
                 }
@@ -280,15 +533,21 @@ macro_rules! as_cow_impl {
     };
 }
 
-#[derive(Debug)]
 pub struct ValueDeserializer<'a, 'r> {
     iter: std::vec::Drain<'a, Token<&'r str, &'r [u8]>>,
+    options: ValueOptions,
+    filter: Option<FieldFilter<'a, 'r>>,
+    /// The source of the value, if known.
+    raw: Option<&'r [u8]>,
 }
 
 impl<'a, 'r> ValueDeserializer<'a, 'r> {
     pub fn new(scratch: &'a mut Vec<Token<&'r str, &'r [u8]>>) -> Self {
         Self {
             iter: scratch.drain(..),
+            options: ValueOptions::default(),
+            filter: None,
+            raw: None,
         }
     }
 
@@ -297,39 +556,142 @@ impl<'a, 'r> ValueDeserializer<'a, 'r> {
     where
         R: BibtexParse<'r>,
     {
-        de.parser.value_into(&mut de.scratch)?;
-        de.macros.resolve(&mut de.scratch);
+        let raw = de.parser.value_into(&mut de.scratch)?;
+        de.resolve_scratch();
+        Ok(Self {
+            iter: de.scratch.drain(..),
+            options: de.value_options,
+            filter: None,
+            raw: Some(raw),
+        })
+    }
+
+    /// Create a new value for the field `key` from the tokens after resolving macros, applying the
+    /// value filter of the deserializer.
+    pub(crate) fn try_from_de_field<R>(
+        de: &'a mut Deserializer<'r, R>,
+        key: &'r str,
+    ) -> Result<Self>
+    where
+        R: BibtexParse<'r>,
+    {
+        let raw = de.parser.value_into(&mut de.scratch)?;
+        de.resolve_scratch();
+        let options = de.field_options(key);
         Ok(Self {
             iter: de.scratch.drain(..),
+            options,
+            filter: de.value_filter.as_deref().map(|filter| (key, filter)),
+            raw: Some(raw),
         })
     }
 
-    as_cow_impl!(as_cow_str, str, push_str, "");
+    /// Set the options used to deserialize the value.
+    pub(crate) fn options(mut self, options: ValueOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the filter applied to the text of the value.
+    pub(crate) fn filter(mut self, filter: Option<FieldFilter<'a, 'r>>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Set the source of the value, for deserializing a [`RawValue`](crate::token::RawValue).
+    pub(crate) fn raw(mut self, raw: Option<&'r [u8]>) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// The text of the value, after normalizing whitespace and applying the filter.
+    fn text(&mut self) -> Result<Cow<'r, str>> {
+        let mut text = self.as_cow_str()?;
+        if self.options.verbatim {
+            return Ok(text);
+        }
+        if self.options.normalize_whitespace {
+            text = normalize_whitespace(text);
+        }
+        Ok(match self.filter {
+            Some((key, filter)) => filter(key, text),
+            None => text,
+        })
+    }
 
-    as_cow_impl!(as_cow_bytes, [u8], extend_from_slice, b"");
+    as_cow_impl!(
+        as_cow_str,
+        str,
+        push_str,
+        "",
+        |name| name,
+        InvalidUtf8Policy::decode
+    );
+
+    as_cow_impl!(
+        as_cow_bytes,
+        [u8],
+        extend_from_slice,
+        b"",
+        str::as_bytes,
+        |_, text: Text<&'r str, &'r [u8]>| Ok(Cow::Borrowed(text.into_bytes()))
+    );
 }
 
 impl<'a, 'de: 'a> de::Deserializer<'de> for ValueDeserializer<'a, 'de> {
     type Error = Error;
 
-    fn deserialize_any<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.as_cow_str()? {
+        if self.options.any_as_tokens {
+            self.deserialize_seq(visitor)
+        } else {
+            self.deserialize_str(visitor)
+        }
+    }
+
+    fn deserialize_str<V>(mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.text()? {
             Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
             Cow::Owned(s) => visitor.visit_string(s),
         }
     }
 
-    fn deserialize_option<V>(mut self, visitor: V) -> Result<V::Value>
+    #[inline]
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.as_cow_str()? {
-            Cow::Borrowed(s) => visitor.visit_some(BorrowedStrDeserializer::new(s)),
-            Cow::Owned(s) => visitor.visit_some(StringDeserializer::new(s)),
-        }
+        self.deserialize_str(visitor)
+    }
+
+    #[inline]
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    #[inline]
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    #[inline]
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
     }
 
     fn deserialize_bytes<V>(mut self, visitor: V) -> Result<V::Value>
@@ -350,12 +712,20 @@ impl<'a, 'de: 'a> de::Deserializer<'de> for ValueDeserializer<'a, 'de> {
         self.deserialize_bytes(visitor)
     }
 
-    #[inline]
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        if name == RAW_VALUE_NAME {
+            match self.raw {
+                Some(raw) => visitor.visit_borrowed_str(std::str::from_utf8(raw)?),
+                None => Err(de::Error::custom(
+                    "the source of the value is not available",
+                )),
+            }
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
     }
 
     #[inline]
@@ -425,8 +795,8 @@ impl<'a, 'de: 'a> de::Deserializer<'de> for ValueDeserializer<'a, 'de> {
     }
 
     forward_to_deserialize_any!(
-        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char
-        map struct str string identifier);
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64
+        map struct);
 }
 
 impl<'a, 'de: 'a> SeqAccess<'de> for ValueDeserializer<'a, 'de> {
@@ -437,7 +807,13 @@ impl<'a, 'de: 'a> SeqAccess<'de> for ValueDeserializer<'a, 'de> {
         T: DeserializeSeed<'de>,
     {
         match self.iter.next() {
-            Some(token) => seed.deserialize(TokenDeserializer::new(token)).map(Some),
+            Some(token) => seed
+                .deserialize(
+                    TokenDeserializer::new(token)
+                        .invalid_utf8(self.options.invalid_utf8)
+                        .any_as_map(self.options.any_as_tokens),
+                )
+                .map(Some),
             None => Ok(None),
         }
     }
@@ -455,13 +831,182 @@ impl<'a, 'de: 'a> EnumAccess<'de> for ValueDeserializer<'a, 'de> {
     }
 }
 
+/// A deserializer for a value which the visitor is not expected to capture.
+///
+/// If the value is ignored, it is skipped by the parser without tokenizing or resolving macros.
+/// Otherwise, deserialization falls back to a [`ValueDeserializer`].
+pub struct SkippedValueDeserializer<'a, 'r, R>
+where
+    R: BibtexParse<'r>,
+{
+    de: &'a mut Deserializer<'r, R>,
+}
+
+impl<'a, 'r, R> SkippedValueDeserializer<'a, 'r, R>
+where
+    R: BibtexParse<'r>,
+{
+    pub fn new(de: &'a mut Deserializer<'r, R>) -> Self {
+        Self { de }
+    }
+}
+
+macro_rules! forward_to_value_deserializer {
+    ($($fname:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            #[inline]
+            fn $fname<V>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                ValueDeserializer::try_from_de_resolved(self.de)?.$fname($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'a, 'de: 'a, R> de::Deserializer<'de> for SkippedValueDeserializer<'a, 'de, R>
+where
+    R: BibtexParse<'de>,
+{
+    type Error = Error;
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.de.parser.ignore_value()?;
+        visitor.visit_unit()
+    }
+
+    forward_to_value_deserializer! {
+        deserialize_any()
+        deserialize_bool()
+        deserialize_i8()
+        deserialize_i16()
+        deserialize_i32()
+        deserialize_i64()
+        deserialize_u8()
+        deserialize_u16()
+        deserialize_u32()
+        deserialize_u64()
+        deserialize_f32()
+        deserialize_f64()
+        deserialize_char()
+        deserialize_str()
+        deserialize_string()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_option()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+    }
+}
+
+/// A field key and its resolved value.
+pub type ResolvedField<'r> = (&'r str, Vec<Token<&'r str, &'r [u8]>>);
+
+/// A deserializer for the fields which are not expected by a fields struct, as a map from field
+/// keys to values.
+pub struct UnknownFieldsDeserializer<'a, 'r> {
+    iter: std::vec::IntoIter<ResolvedField<'r>>,
+    value: Option<ResolvedField<'r>>,
+    options: ValueOptions,
+    filter: Option<&'a ValueFilter<'r>>,
+    verbatim_fields: &'a [UniCase<&'r str>],
+}
+
+impl<'a, 'r> UnknownFieldsDeserializer<'a, 'r> {
+    pub(crate) fn new(
+        fields: Vec<ResolvedField<'r>>,
+        options: ValueOptions,
+        filter: Option<&'a ValueFilter<'r>>,
+        verbatim_fields: &'a [UniCase<&'r str>],
+    ) -> Self {
+        Self {
+            iter: fields.into_iter(),
+            value: None,
+            options,
+            filter,
+            verbatim_fields,
+        }
+    }
+}
+
+impl<'a, 'de: 'a> de::Deserializer<'de> for UnknownFieldsDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'a, 'de: 'a> MapAccess<'de> for UnknownFieldsDeserializer<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, tokens)) => {
+                self.value = Some((key, tokens));
+                seed.deserialize(WrappedBorrowStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let Some((key, mut tokens)) = self.value.take() else {
+            return Err(de::Error::custom("value is missing"));
+        };
+        seed.deserialize(
+            ValueDeserializer::new(&mut tokens)
+                .options(self.options.for_field(self.verbatim_fields, key))
+                .filter(self.filter.map(|filter| (key, filter))),
+        )
+    }
+}
+
 pub struct TextDeserializer<'r> {
     text: Text<&'r str, &'r [u8]>,
+    invalid_utf8: InvalidUtf8Policy,
 }
 
 impl<'r> TextDeserializer<'r> {
     pub fn new(text: Text<&'r str, &'r [u8]>) -> Self {
-        Self { text }
+        Self {
+            text,
+            invalid_utf8: InvalidUtf8Policy::default(),
+        }
+    }
+
+    /// Set how text which is not valid UTF-8 is converted when deserialized as a string.
+    pub(crate) fn invalid_utf8(mut self, policy: InvalidUtf8Policy) -> Self {
+        self.invalid_utf8 = policy;
+        self
     }
 }
 
@@ -472,7 +1017,10 @@ impl<'de> de::Deserializer<'de> for TextDeserializer<'de> {
     where
         V: de::Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.text.into_str()?)
+        match self.invalid_utf8.decode(self.text)? {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
@@ -496,6 +1044,65 @@ impl<'de> de::Deserializer<'de> for TextDeserializer<'de> {
     }
 }
 
+/// An iterator over the tokens of a field value, read directly from the input, as provided by
+/// [`Deserializer::for_each_value`].
+///
+/// The tokens borrow from the input and macros are not expanded, so that iterating does not
+/// allocate. If the input is not valid BibTeX, the iterator ends early and the error is returned
+/// by [`Deserializer::for_each_value`]. Tokens which are not consumed are skipped.
+pub struct TokenIter<'a, 'r, R>
+where
+    R: BibtexParse<'r>,
+{
+    parser: &'a mut R,
+    is_first_token: bool,
+    error: Option<Error>,
+    _input: std::marker::PhantomData<&'r ()>,
+}
+
+impl<'a, 'r, R> TokenIter<'a, 'r, R>
+where
+    R: BibtexParse<'r>,
+{
+    pub(crate) fn new(parser: &'a mut R) -> Self {
+        Self {
+            parser,
+            is_first_token: true,
+            error: None,
+            _input: std::marker::PhantomData,
+        }
+    }
+
+    /// Skip the remaining tokens, returning the first error.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        for _ in self.by_ref() {}
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, 'r, R> Iterator for TokenIter<'a, 'r, R>
+where
+    R: BibtexParse<'r>,
+{
+    type Item = Token<&'r str, &'r [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.parser.token(&mut self.is_first_token) {
+            Ok(token) => token,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -504,6 +1111,7 @@ mod tests {
     use crate::parse::StrReader;
     use crate::token::Variable;
     use serde::Deserialize;
+    use std::collections::BTreeMap;
 
     #[derive(Debug, Deserialize, PartialEq)]
     enum Tok<'a> {
@@ -551,6 +1159,50 @@ mod tests {
         assert_de!(" {1} # a", (Tok::T("1"), Tok::V("a")), DoubleToken);
     }
 
+    #[test]
+    fn test_value_any_as_tokens() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
+        enum AnyValue {
+            Str(String),
+            Tokens(Vec<BTreeMap<String, String>>),
+        }
+
+        let token = |name: &str, val: &str| BTreeMap::from([(name.to_owned(), val.to_owned())]);
+
+        // by default, `deserialize_any` produces a string and fails on undefined macros
+        let mut bib_de = Deserializer::new(StrReader::new(" {1} # {2}"));
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(
+            AnyValue::deserialize(deserializer).unwrap(),
+            AnyValue::Str("12".into())
+        );
+
+        let mut bib_de = Deserializer::new(StrReader::new(" {1} # a"));
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert!(AnyValue::deserialize(deserializer).is_err());
+
+        // with the option set, the macro structure is captured
+        let mut bib_de = Deserializer::new(StrReader::new(" {1} # a")).any_as_tokens(true);
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(
+            AnyValue::deserialize(deserializer).unwrap(),
+            AnyValue::Tokens(vec![token("Text", "1"), token("Variable", "a")])
+        );
+
+        // explicit types are unaffected by the option
+        let mut bib_de = Deserializer::new(StrReader::new(" {1} # {2}")).any_as_tokens(true);
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(String::deserialize(deserializer).unwrap(), "12");
+
+        let mut bib_de = Deserializer::new(StrReader::new(" {1} # a")).any_as_tokens(true);
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(
+            Vec::<Tok>::deserialize(deserializer).unwrap(),
+            vec![Tok::T("1"), Tok::V("a")]
+        );
+    }
+
     #[test]
     fn test_value_cow() {
         assert_de!("{a} # { b}", Cow::Borrowed("a b"), Cow::<str>);
@@ -666,6 +1318,15 @@ mod tests {
         let data = ReToken::deserialize(de).unwrap();
         assert_eq!(data, ReToken::T(Cow::Borrowed("key")));
         assert!(matches!(data, ReToken::T(Cow::Borrowed(_))));
+
+        // self-describing types only receive a map when explicitly requested
+        let de = TokenDeserializer::new(Token::variable_unchecked("key"));
+        assert!(BTreeMap::<String, String>::deserialize(de).is_err());
+        let de = TokenDeserializer::new(Token::variable_unchecked("key")).any_as_map(true);
+        assert_eq!(
+            BTreeMap::<String, String>::deserialize(de).unwrap(),
+            BTreeMap::from([("Variable".into(), "key".into())])
+        );
     }
 
     #[test]
@@ -826,4 +1487,110 @@ mod tests {
         assert_value_matching!(" c # c", "11", Cow::Owned(_));
         assert_value_matching!(" {} # c # {} # a # b", "1", Cow::Borrowed(_));
     }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        for (input, expected) in [
+            ("abc", "abc"),
+            ("a b c", "a b c"),
+            ("  a b ", "a b"),
+            ("a  b", "a b"),
+            ("a\n\t b", "a b"),
+            ("a\nb", "a b"),
+            (" \n ", ""),
+            ("", ""),
+        ] {
+            assert_eq!(normalize_whitespace(Cow::Borrowed(input)), expected);
+            assert_eq!(normalize_whitespace(Cow::Owned(input.to_owned())), expected);
+        }
+
+        // normalized text is borrowed, if possible
+        assert!(matches!(
+            normalize_whitespace(Cow::Borrowed(" a b\n")),
+            Cow::Borrowed("a b")
+        ));
+
+        let mut abbrevs = MacroDictionary::<&str, &[u8]>::default();
+        abbrevs.insert(
+            Variable::new_unchecked("a"),
+            vec![Token::str_unchecked(" A ")],
+        );
+
+        #[derive(Deserialize, Debug, PartialEq, Eq)]
+        struct Val<'r>(#[serde(borrow)] Cow<'r, str>);
+
+        let reader = StrReader::new(" {x\n  } # a # { y}");
+        let mut bib_de = Deserializer::new_with_macros(reader, abbrevs).normalize_whitespace(true);
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(Val::deserialize(deserializer).unwrap(), Val("x A y".into()));
+    }
+
+    #[test]
+    fn test_undefined_macro_policy() {
+        #[derive(Deserialize, Debug, PartialEq, Eq)]
+        struct Val<'r>(#[serde(borrow)] Cow<'r, str>);
+
+        #[derive(Deserialize, Debug, PartialEq, Eq)]
+        struct Bytes<'r>(#[serde(borrow)] Cow<'r, [u8]>);
+
+        let mut abbrevs = MacroDictionary::<&str, &[u8]>::default();
+        abbrevs.insert(
+            Variable::new_unchecked("a"),
+            vec![Token::str_unchecked("A")],
+        );
+
+        let value = |input, policy| {
+            let reader = StrReader::new(input);
+            let mut bib_de =
+                Deserializer::new_with_macros(reader, abbrevs.clone()).undefined_macros(policy);
+            let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+            Val::deserialize(deserializer)
+        };
+
+        assert!(value(" a # u", UndefinedMacroPolicy::Error).is_err());
+        assert_eq!(
+            value(" a # u", UndefinedMacroPolicy::KeepName).unwrap(),
+            Val("Au".into())
+        );
+        assert_eq!(
+            value(" a # u", UndefinedMacroPolicy::Empty).unwrap(),
+            Val("A".into())
+        );
+
+        // the name of the macro can be borrowed
+        assert!(matches!(
+            value(" {} # u", UndefinedMacroPolicy::KeepName),
+            Ok(Val(Cow::Borrowed("u")))
+        ));
+        assert!(matches!(
+            value(" u # a # v", UndefinedMacroPolicy::Empty),
+            Ok(Val(Cow::Borrowed("A")))
+        ));
+
+        // the policy also applies to bytes and options
+        let reader = StrReader::new(" {x} # u");
+        let mut bib_de = Deserializer::new(reader).undefined_macros(UndefinedMacroPolicy::KeepName);
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(
+            Bytes::deserialize(deserializer).unwrap(),
+            Bytes(b"xu".as_slice().into())
+        );
+
+        let reader = StrReader::new(" u");
+        let mut bib_de = Deserializer::new(reader).undefined_macros(UndefinedMacroPolicy::Empty);
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(
+            Option::<String>::deserialize(deserializer).unwrap(),
+            Some(String::new())
+        );
+
+        // tokens are unaffected
+        let reader = StrReader::new(" u");
+        let mut bib_de = Deserializer::new(reader).undefined_macros(UndefinedMacroPolicy::Empty);
+        let deserializer = ValueDeserializer::try_from_de_resolved(&mut bib_de).unwrap();
+        assert_eq!(
+            Vec::<Tok>::deserialize(deserializer).unwrap(),
+            vec![Tok::V("u")]
+        );
+    }
 }
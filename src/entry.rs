@@ -1,9 +1,30 @@
 //! # Built-in types
+mod bibliography;
 mod borrow;
+mod field_map;
+mod fingerprint;
+mod identifier;
+mod list;
+mod merge;
+mod month;
+mod normalize;
 mod owned;
+mod preamble;
+mod schema;
 
+pub use bibliography::Bibliography;
 pub use borrow::{BorrowEntry, Token};
-pub use owned::Entry;
+pub(crate) use field_map::CaseKey;
+pub use field_map::FieldMap;
+pub use fingerprint::{content_hash, fingerprint};
+pub use identifier::{Doi, IdentifierError, Isbn, Issn};
+pub use list::{Keywords, UrlList};
+pub use merge::{merge, MergePolicy};
+pub use month::Month;
+pub use normalize::{normalized_eq, NormalizedEntry};
+pub use owned::{Entry, OrderedEntry};
+pub use preamble::Preamble;
+pub use schema::{BibEntry, BibFields, EntryCheckError, FieldInfo};
 
 /// A bibliography of owned entries.
 pub type OwnedBibliography = Vec<Entry>;
@@ -0,0 +1,322 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use unicase::UniCase;
+
+use super::field_map::CaseKey;
+use super::Entry;
+use crate::error::Result;
+use crate::transform::REFERENCE_FIELDS;
+
+/// A bibliography of owned entries, indexed by entry key.
+///
+/// Entry keys and entry types are compared case-insensitively. If an entry key appears more than
+/// once, [`Bibliography::get`] returns the first entry with that key.
+///
+/// This type is for querying a bibliography. Unlike [`value::Bibliography`](crate::value::Bibliography),
+/// which keeps the unexpanded tokens of every entry so that a bibliography can be edited and
+/// written back, the macros in an [`Entry`] are expanded, and the regular entries are indexed so
+/// that looking up an entry key does not scan the bibliography.
+/// ```
+/// use serde_bibtex::entry::Bibliography;
+///
+/// let mut bib: Bibliography = serde_bibtex::from_str(
+///     "@article{a, title = {A}} @book{b, title = {B}} @Article{c, title = {C}}",
+/// )
+/// .unwrap();
+/// bib.extend_from_str("@misc{d, title = {D}}").unwrap();
+///
+/// assert_eq!(bib.len(), 4);
+/// assert!(bib.get("B").is_some());
+/// assert_eq!(bib.iter_by_type("article").count(), 2);
+/// assert_eq!(bib.cited_by(["d", "a", "missing"]).len(), 2);
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Bibliography {
+    entries: Vec<Entry>,
+    keys: HashMap<UniCase<String>, usize>,
+}
+
+impl Bibliography {
+    /// Construct an empty bibliography.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The regular entry with the given entry key, or `None` if there is no such entry.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.keys
+            .get(&UniCase::new(key) as &dyn CaseKey)
+            .map(|idx| &self.entries[*idx])
+    }
+
+    /// Whether there is a regular entry with the given entry key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys.contains_key(&UniCase::new(key) as &dyn CaseKey)
+    }
+
+    /// Returns an iterator over the entries, in order.
+    pub fn iter(&self) -> std::slice::Iter<'_, Entry> {
+        self.entries.iter()
+    }
+
+    /// Returns an iterator over the regular entries with the given entry type, in order.
+    pub fn iter_by_type<'a>(&'a self, entry_type: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(move |entry| match entry {
+            Entry::Regular {
+                entry_type: other, ..
+            } => other.eq_ignore_ascii_case(entry_type),
+            _ => false,
+        })
+    }
+
+    /// The regular entries with any of the given entry keys, such as the keys cited by a
+    /// document, in the order in which they appear in the bibliography. Each entry is returned
+    /// once, and keys without an entry are ignored.
+    pub fn cited_by<I, K>(&self, keys: I) -> Vec<&Entry>
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<str>,
+    {
+        let indices: BTreeSet<usize> = keys
+            .into_iter()
+            .filter_map(|key| self.keys.get(&UniCase::new(key.as_ref()) as &dyn CaseKey))
+            .copied()
+            .collect();
+        indices.into_iter().map(|idx| &self.entries[idx]).collect()
+    }
+
+    /// The bibliography reduced to the regular entries with the given entry keys, along with the
+    /// entries which they require, as with
+    /// [`value::Bibliography::subset`](crate::value::Bibliography::subset).
+    ///
+    /// The key `*` selects every regular entry, and the entries referred to by the [reference
+    /// fields](crate::transform::REFERENCE_FIELDS) of a selected entry are selected recursively.
+    /// Since macros are already expanded, only the selected entries and the preambles are kept, in
+    /// their original order.
+    /// ```
+    /// use serde_bibtex::entry::Bibliography;
+    ///
+    /// let bib: Bibliography = serde_bibtex::from_str(
+    ///     "@proceedings{conf,} @inproceedings{paper, crossref = {conf}} @misc{other,}",
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(bib.subset(["Paper"]).len(), 2);
+    /// ```
+    pub fn subset<I, K>(&self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<str>,
+    {
+        let mut selected = BTreeSet::new();
+        for key in keys {
+            match key.as_ref() {
+                "*" => selected.extend(self.keys.values().copied()),
+                key => selected.extend(self.keys.get(&UniCase::new(key) as &dyn CaseKey)),
+            }
+        }
+
+        let mut pending: Vec<&str> = selected
+            .iter()
+            .flat_map(|idx| references(&self.entries[*idx]))
+            .collect();
+        while let Some(key) = pending.pop() {
+            if let Some(idx) = self.keys.get(&UniCase::new(key) as &dyn CaseKey) {
+                if selected.insert(*idx) {
+                    pending.extend(references(&self.entries[*idx]));
+                }
+            }
+        }
+
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| selected.contains(idx) || matches!(entry, Entry::Preamble))
+            .map(|(_, entry)| entry.clone())
+            .collect()
+    }
+
+    /// Append an entry, indexing it if it is a regular entry.
+    pub fn push(&mut self, entry: Entry) {
+        if let Entry::Regular { entry_key, .. } = &entry {
+            self.keys
+                .entry(entry_key.clone())
+                .or_insert(self.entries.len());
+        }
+        self.entries.push(entry);
+    }
+
+    /// Deserialize the entries in `input` and append them to the bibliography.
+    ///
+    /// Macros defined in `input` are expanded, but the macros defined by earlier inputs are not
+    /// available. If `input` is not valid, the bibliography is unchanged.
+    pub fn extend_from_str(&mut self, input: &str) -> Result<()> {
+        let entries: Vec<Entry> = crate::from_str(input)?;
+        self.extend(entries);
+        Ok(())
+    }
+
+    /// The entries, in order.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Drop the index, returning the entries.
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.entries
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl Extend<Entry> for Bibliography {
+    fn extend<I: IntoIterator<Item = Entry>>(&mut self, iter: I) {
+        for entry in iter {
+            self.push(entry);
+        }
+    }
+}
+
+impl FromIterator<Entry> for Bibliography {
+    fn from_iter<I: IntoIterator<Item = Entry>>(iter: I) -> Self {
+        let mut bibliography = Self::default();
+        bibliography.extend(iter);
+        bibliography
+    }
+}
+
+impl From<Vec<Entry>> for Bibliography {
+    fn from(entries: Vec<Entry>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a Bibliography {
+    type Item = &'a Entry;
+    type IntoIter = std::slice::Iter<'a, Entry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Bibliography {
+    type Item = Entry;
+    type IntoIter = std::vec::IntoIter<Entry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'de> Deserialize<'de> for Bibliography {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<Entry>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl Serialize for Bibliography {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.entries.serialize(serializer)
+    }
+}
+
+/// The entry keys in the reference fields of an entry.
+fn references(entry: &Entry) -> impl Iterator<Item = &str> {
+    let fields = match entry {
+        Entry::Regular { fields, .. } => Some(&fields.0),
+        _ => None,
+    };
+    fields
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| {
+            REFERENCE_FIELDS
+                .iter()
+                .any(|field| key.eq_ignore_ascii_case(field))
+        })
+        .flat_map(|(_, value)| value.split(','))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Vec<&'a str> {
+        entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Entry::Regular { entry_key, .. } => Some(entry_key.as_ref()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bibliography() {
+        let input = r#"
+            @string{t = {T}}
+            @article{a, title = t}
+            @book{B, title = {B}}
+            @comment{c}
+            @Article{c, title = {C}}
+            @misc{a, title = {Duplicate}}
+        "#;
+        let mut bib: Bibliography = crate::from_str(input).unwrap();
+        assert_eq!(bib.len(), 6);
+        assert_eq!(keys(&bib), ["a", "B", "c", "a"]);
+
+        // the first entry with a key is returned
+        let Some(Entry::Regular { fields, .. }) = bib.get("A") else {
+            panic!("expected a regular entry");
+        };
+        assert_eq!(fields.0[&UniCase::new("title".to_owned())], "T");
+        assert!(bib.contains_key("b"));
+        assert!(bib.get("d").is_none());
+
+        assert_eq!(keys(bib.iter_by_type("ARTICLE")), ["a", "c"]);
+        assert_eq!(keys(bib.iter_by_type("comment")), Vec::<&str>::new());
+        assert_eq!(keys(bib.cited_by(["c", "a", "d", "C"])), ["a", "c"]);
+
+        // invalid input leaves the bibliography unchanged
+        assert!(bib.extend_from_str("@misc{d, title = t}").is_err());
+        assert_eq!(bib.len(), 6);
+        bib.extend_from_str("@misc{d, title = {D}}").unwrap();
+        assert!(bib.contains_key("D"));
+
+        // the subset contains the cited entries and the entries to which they refer
+        let refs: Bibliography = crate::from_str(
+            "@preamble{{p}} @book{parent,} @misc{child, xref = {Parent}} @misc{other,}",
+        )
+        .unwrap();
+        assert_eq!(
+            keys(&refs.subset(["CHILD", "missing"])),
+            ["parent", "child"]
+        );
+        assert_eq!(refs.subset(["child"]).len(), 3);
+        assert_eq!(keys(&refs.subset(["*"])), ["parent", "child", "other"]);
+        assert!(refs.subset(["other"]).contains_key("OTHER"));
+
+        let output = crate::to_string(&bib).unwrap();
+        let parsed: Bibliography = crate::from_str(&output).unwrap();
+        assert_eq!(keys(&parsed), ["a", "B", "c", "a", "d"]);
+        assert_eq!(parsed.into_entries().len(), 5);
+    }
+}
@@ -10,6 +10,9 @@ pub enum Token<'a> {
 }
 
 /// An entry which borrows as much as possible from the underlying record.
+///
+/// Since the tokens of each value are kept, a sequence of [`BorrowEntry`] is written back with
+/// the same entries, values, and macro variables as the input, apart from empty macro entries.
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub enum BorrowEntry<'a> {
     /// A regular entry
@@ -21,7 +24,8 @@ pub enum BorrowEntry<'a> {
         /// The unordered list of fields
         fields: Vec<(&'a str, Vec<Token<'a>>)>,
     },
-    /// A macro entry
+    /// A macro entry, which is `None` for an empty `@string{}` entry and then skipped when
+    /// serialized
     Macro(Option<(&'a str, Vec<Token<'a>>)>),
     /// A comment entry
     Comment(&'a str),
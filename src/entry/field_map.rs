@@ -0,0 +1,258 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::{btree_map, BTreeMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use unicase::UniCase;
+
+/// A map from field keys to values, in which keys are compared case-insensitively as in BibTeX.
+///
+/// The case of each key is retained, and is used when the map is serialized. If a key is inserted
+/// more than once, for instance with a different case, the later value replaces the earlier
+/// value but the original key is kept. The map is ordered case-insensitively by key.
+/// ```
+/// use serde::Deserialize;
+/// use serde_bibtex::{entry::FieldMap, from_entry_str};
+///
+/// #[derive(Deserialize)]
+/// struct Record {
+///     fields: FieldMap<String>,
+/// }
+///
+/// let record: Record = from_entry_str("@book{key, AUTHOR = {Last, First}}").unwrap();
+///
+/// assert_eq!(record.fields.get("Author").map(String::as_str), Some("Last, First"));
+/// assert_eq!(record.fields.keys().collect::<Vec<_>>(), vec!["AUTHOR"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMap<V>(BTreeMap<UniCase<String>, V>);
+
+/// A key which is compared case-insensitively. A map keyed by `UniCase<String>` can be queried
+/// with a `&dyn CaseKey`, so that lookups by `&str` do not allocate.
+pub(crate) trait CaseKey {
+    fn key(&self) -> UniCase<&str>;
+}
+
+impl CaseKey for UniCase<String> {
+    fn key(&self) -> UniCase<&str> {
+        UniCase::new(self.as_str())
+    }
+}
+
+impl CaseKey for UniCase<&str> {
+    fn key(&self) -> UniCase<&str> {
+        *self
+    }
+}
+
+impl<'a> Borrow<dyn CaseKey + 'a> for UniCase<String> {
+    fn borrow(&self) -> &(dyn CaseKey + 'a) {
+        self
+    }
+}
+
+impl PartialEq for dyn CaseKey + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for dyn CaseKey + '_ {}
+
+impl PartialOrd for dyn CaseKey + '_ {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for dyn CaseKey + '_ {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for dyn CaseKey + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl<V> Default for FieldMap<V> {
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<V> FieldMap<V> {
+    /// Construct an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of fields in the map.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether the map contains no fields.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The value of a field.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.0.get(&UniCase::new(key) as &dyn CaseKey)
+    }
+
+    /// A mutable reference to the value of a field.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.0.get_mut(&UniCase::new(key) as &dyn CaseKey)
+    }
+
+    /// Whether the map contains a field.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.0.contains_key(&UniCase::new(key) as &dyn CaseKey)
+    }
+
+    /// Set the value of a field, returning the previous value if any. If the field already
+    /// exists, its key is unchanged.
+    pub fn insert<K: Into<String>>(&mut self, key: K, value: V) -> Option<V> {
+        self.0.insert(UniCase::new(key.into()), value)
+    }
+
+    /// Remove a field, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        self.0.remove(&UniCase::new(key) as &dyn CaseKey)
+    }
+
+    /// An iterator over the keys of the map, in their original case.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|key| key.as_str())
+    }
+
+    /// An iterator over the fields of the map.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.0.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// An iterator over the fields of the map, with mutable references to the values.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut V)> {
+        self.0.iter_mut().map(|(key, value)| (key.as_str(), value))
+    }
+}
+
+impl<V> IntoIterator for FieldMap<V> {
+    type Item = (String, V);
+    type IntoIter = std::iter::Map<
+        btree_map::IntoIter<UniCase<String>, V>,
+        fn((UniCase<String>, V)) -> (String, V),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0
+            .into_iter()
+            .map(|(key, value)| (key.into_inner(), value))
+    }
+}
+
+impl<K: Into<String>, V> FromIterator<(K, V)> for FieldMap<V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Into<String>, V> Extend<(K, V)> for FieldMap<V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+struct FieldMapVisitor<V>(PhantomData<V>);
+
+impl<'de, V: Deserialize<'de>> Visitor<'de> for FieldMapVisitor<V> {
+    type Value = FieldMap<V>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("fields map")
+    }
+
+    fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        let mut map = FieldMap::new();
+        while let Some((key, value)) = access.next_entry::<String, V>()? {
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for FieldMap<V> {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(FieldMapVisitor(PhantomData))
+    }
+}
+
+impl<V: Serialize> Serialize for FieldMap<V> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_entry_str, to_string_entry};
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Record {
+        entry_type: String,
+        entry_key: String,
+        fields: FieldMap<String>,
+    }
+
+    #[test]
+    fn test_field_map() {
+        let input = "@book{key,\n  author = {A},\n  Title = {T},\n}\n";
+        let mut record: Record = from_entry_str(input).unwrap();
+        assert_eq!(record.fields.len(), 2);
+        assert_eq!(record.fields.get("TITLE").map(String::as_str), Some("T"));
+        assert!(record.fields.contains_key("Author"));
+        assert_eq!(to_string_entry(&record).unwrap(), input);
+
+        // the original key is retained when a value is replaced
+        assert_eq!(record.fields.insert("TITLE", "U".into()), Some("T".into()));
+        *record.fields.get_mut("AUTHOR").unwrap() = "B".into();
+        assert_eq!(
+            record.fields.iter().collect::<Vec<_>>(),
+            vec![("author", &"B".to_owned()), ("Title", &"U".to_owned())]
+        );
+        assert_eq!(record.fields.remove("title"), Some("U".into()));
+        assert_eq!(record.fields.remove("title"), None);
+        record.fields.insert("Übersetzer", "C".into());
+        assert!(record.fields.contains_key("übersetzer"));
+        assert!(record.fields.contains_key("author"));
+
+        let map: FieldMap<u32> = [("a", 1), ("A", 2), ("b", 3)].into_iter().collect();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            vec![("a".to_owned(), 2), ("b".to_owned(), 3)]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use super::Entry;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A byte which never occurs in UTF-8, used to terminate each component of an entry.
+const TERMINATOR: u8 = 0xff;
+
+/// A 64-bit FNV-1a hash.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Write a string followed by the terminator, so that adjacent strings are distinguished.
+    fn write_str(&mut self, s: &str) {
+        self.write(s.as_bytes());
+        self.write(&[TERMINATOR]);
+    }
+}
+
+/// A fingerprint of the contents of an entry, for use as a cache key or to detect changes.
+///
+/// The entry is normalized similarly to [`normalized_eq`](super::normalized_eq): ASCII letters in
+/// the entry type, entry key, and field keys are converted to lowercase, each run of whitespace in
+/// a field value is replaced by a single space, with leading and trailing whitespace removed, and
+/// the fields are sorted by key and then by value. Unlike `normalized_eq`, other letters are not
+/// case-folded, so that the normalization does not depend on the version of Unicode. Entries other
+/// than regular entries only contribute their variant.
+///
+/// The fingerprint is the 64-bit FNV-1a hash of the normalized entry, and does not depend on the
+/// platform or the Rust version. It is stable across versions of this crate, so fingerprints can
+/// be stored and compared later: a change to the fingerprint of an entry is considered a breaking
+/// change.
+/// ```
+/// use serde_bibtex::{entry::{fingerprint, Entry}, from_entry_str};
+///
+/// let a: Entry = from_entry_str("@article{Key, title = {A Title}, year = 2024}").unwrap();
+/// let b: Entry = from_entry_str("@Article{key, YEAR = {2024}, title = { A\n  Title }}").unwrap();
+/// let c: Entry = from_entry_str("@article{key, title = {A title}, year = 2024}").unwrap();
+///
+/// assert_eq!(fingerprint(&a), fingerprint(&b));
+/// assert_ne!(fingerprint(&a), fingerprint(&c));
+/// ```
+pub fn fingerprint(entry: &Entry) -> u64 {
+    let mut hasher = Fnv1a(FNV_OFFSET_BASIS);
+    match entry {
+        Entry::Regular {
+            entry_type,
+            entry_key,
+            fields,
+        } => {
+            hasher.write_str("regular");
+            hasher.write_str(&entry_type.to_ascii_lowercase());
+            hasher.write_str(&entry_key.to_ascii_lowercase());
+
+            let mut normalized: Vec<(String, String)> = fields
+                .0
+                .iter()
+                .map(|(key, value)| {
+                    let words: Vec<&str> = value.split_whitespace().collect();
+                    (key.to_ascii_lowercase(), words.join(" "))
+                })
+                .collect();
+            normalized.sort_unstable();
+            for (key, value) in normalized {
+                hasher.write_str(&key);
+                hasher.write_str(&value);
+            }
+        }
+        Entry::Macro => hasher.write_str("macro"),
+        Entry::Comment => hasher.write_str("comment"),
+        Entry::Preamble => hasher.write_str("preamble"),
+    }
+    hasher.0
+}
+
+/// The [`fingerprint`] of an entry as a string of 16 lowercase hexadecimal digits.
+/// ```
+/// use serde_bibtex::{entry::{content_hash, Entry}, from_entry_str};
+///
+/// let entry: Entry = from_entry_str("@book{knuth, title = {The TeXbook}}").unwrap();
+/// let hash = content_hash(&entry);
+/// assert_eq!(hash.len(), 16);
+/// assert!(hash.chars().all(|ch| ch.is_ascii_hexdigit()));
+/// ```
+pub fn content_hash(entry: &Entry) -> String {
+    format!("{:016x}", fingerprint(entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_entry_str, from_str};
+
+    #[test]
+    fn test_fingerprint() {
+        // the fingerprint is stable, so these values must never change
+        let entry: Entry = from_entry_str("@book{knuth, title = {The TeXbook}}").unwrap();
+        assert_eq!(fingerprint(&entry), 0x1984_492b_c1f5_017c);
+        assert_eq!(content_hash(&entry), "1984492bc1f5017c");
+        assert_eq!(fingerprint(&Entry::Comment), 0x2ce6_2bab_e8cf_8bf3);
+
+        let entry = |s| from_str::<Vec<Entry>>(s).unwrap().pop().unwrap();
+        let a = entry("@article{Key, author = {First Last}, title = {Title}}");
+        for equal in [
+            "@Article{key, Author = {First Last}, title = {Title}}",
+            "@article{KEY, title = {  Title\n}, author = {First\t  Last}}",
+            "@string{first = {First}} @article{key, author = first # { Last}, title = {Title}}",
+        ] {
+            assert_eq!(fingerprint(&a), fingerprint(&entry(equal)), "{equal}");
+        }
+
+        for different in [
+            "@book{key, author = {First Last}, title = {Title}}",
+            "@article{key2, author = {First Last}, title = {Title}}",
+            "@article{key, author = {FirstLast}, title = {Title}}",
+            "@article{key, author = {First Last}, title = {title}}",
+            "@article{key, author = {First Last}}",
+            "@article{key, author = {First Last}, title = {Title}, year = {2024}}",
+            "@article{key, author = {First}, title = {Last Title}}",
+        ] {
+            assert_ne!(
+                fingerprint(&a),
+                fingerprint(&entry(different)),
+                "{different}"
+            );
+        }
+
+        assert_ne!(fingerprint(&Entry::Comment), fingerprint(&Entry::Preamble));
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// An error which occurs when parsing a [`Doi`], [`Isbn`], or [`Issn`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentifierError {
+    /// The kind of identifier which was expected.
+    pub kind: &'static str,
+    /// The input which could not be parsed.
+    pub input: String,
+}
+
+impl fmt::Display for IdentifierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {}: '{}'", self.kind, self.input)
+    }
+}
+
+impl std::error::Error for IdentifierError {}
+
+macro_rules! identifier_impl {
+    ($name:ident, $kind:literal, $normalize:ident) => {
+        impl $name {
+            /// Validate and normalize the input.
+            pub fn new(input: &str) -> Result<Self, IdentifierError> {
+                $normalize(input.trim())
+                    .map(Self)
+                    .ok_or_else(|| IdentifierError {
+                        kind: $kind,
+                        input: input.to_owned(),
+                    })
+            }
+
+            /// The canonical form of the identifier.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Return the canonical form of the identifier.
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = IdentifierError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::new(s)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                struct IdentifierVisitor;
+
+                impl<'de> Visitor<'de> for IdentifierVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str(concat!("a valid ", $kind))
+                    }
+
+                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                    where
+                        E: de::Error,
+                    {
+                        $name::new(v).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+                    }
+                }
+
+                deserializer.deserialize_str(IdentifierVisitor)
+            }
+        }
+    };
+}
+
+/// A Digital Object Identifier, such as `10.1000/182`.
+///
+/// Resolver prefixes such as `https://doi.org/` and `doi:` are removed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Doi(String);
+
+/// An International Standard Book Number, in either the 10 or 13 digit format.
+///
+/// The check digit is validated, and the canonical form has no separators, such as
+/// `9780306406157`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Isbn(String);
+
+/// An International Standard Serial Number.
+///
+/// The check digit is validated, and the canonical form is hyphenated, such as `0317-8471`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Issn(String);
+
+identifier_impl!(Doi, "DOI", normalize_doi);
+identifier_impl!(Isbn, "ISBN", normalize_isbn);
+identifier_impl!(Issn, "ISSN", normalize_issn);
+
+/// Remove the ASCII case-insensitive `prefix` from `s`, if present.
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    match s.get(..prefix.len()) {
+        Some(head) if head.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+fn normalize_doi(input: &str) -> Option<String> {
+    const PREFIXES: [&str; 5] = [
+        "https://doi.org/",
+        "http://doi.org/",
+        "https://dx.doi.org/",
+        "http://dx.doi.org/",
+        "doi:",
+    ];
+
+    let doi = PREFIXES
+        .iter()
+        .find_map(|prefix| strip_prefix_ignore_case(input, prefix))
+        .unwrap_or(input)
+        .trim_start();
+
+    let (registrant, suffix) = doi.strip_prefix("10.")?.split_once('/')?;
+    if registrant.is_empty()
+        || suffix.is_empty()
+        || !registrant.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+        || doi.chars().any(char::is_whitespace)
+    {
+        return None;
+    }
+    Some(doi.to_owned())
+}
+
+/// The digits of `input`, ignoring hyphens and spaces. An `X` or `x` is permitted only as the
+/// final character and is represented as 10.
+fn digits(input: &str) -> Option<Vec<u32>> {
+    let mut digits = Vec::with_capacity(13);
+    let mut chars = input.chars().filter(|c| *c != '-' && *c != ' ').peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '0'..='9' => digits.push(c as u32 - '0' as u32),
+            'X' | 'x' if chars.peek().is_none() => digits.push(10),
+            _ => return None,
+        }
+    }
+    Some(digits)
+}
+
+/// Render digits as a string, with 10 represented by `X`.
+fn render(digits: &[u32]) -> String {
+    digits
+        .iter()
+        .map(|d| char::from_digit(*d, 10).unwrap_or('X'))
+        .collect()
+}
+
+fn normalize_isbn(input: &str) -> Option<String> {
+    let input = ["isbn-13:", "isbn-10:", "isbn:", "isbn"]
+        .iter()
+        .find_map(|prefix| strip_prefix_ignore_case(input, prefix))
+        .unwrap_or(input);
+    let digits = digits(input.trim_start())?;
+
+    let valid = match digits.len() {
+        10 => {
+            digits[..9].iter().all(|d| *d < 10)
+                && digits
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, d)| (10 - idx as u32) * d)
+                    .sum::<u32>()
+                    % 11
+                    == 0
+        }
+        13 => {
+            digits.iter().all(|d| *d < 10)
+                && digits
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, d)| if idx % 2 == 0 { *d } else { 3 * d })
+                    .sum::<u32>()
+                    % 10
+                    == 0
+        }
+        _ => false,
+    };
+    valid.then(|| render(&digits))
+}
+
+fn normalize_issn(input: &str) -> Option<String> {
+    let input = ["issn:", "issn"]
+        .iter()
+        .find_map(|prefix| strip_prefix_ignore_case(input, prefix))
+        .unwrap_or(input);
+    let digits = digits(input.trim_start())?;
+
+    let valid = digits.len() == 8
+        && digits[..7].iter().all(|d| *d < 10)
+        && digits
+            .iter()
+            .enumerate()
+            .map(|(idx, d)| (8 - idx as u32) * d)
+            .sum::<u32>()
+            % 11
+            == 0;
+    valid.then(|| {
+        let rendered = render(&digits);
+        format!("{}-{}", &rendered[..4], &rendered[4..])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::Deserializer;
+
+    #[test]
+    fn test_doi() {
+        for input in [
+            "10.1000/182",
+            "https://doi.org/10.1000/182",
+            "HTTP://DX.DOI.ORG/10.1000/182",
+            "doi:10.1000/182",
+            " doi: 10.1000/182 ",
+        ] {
+            assert_eq!(Doi::new(input).unwrap().as_str(), "10.1000/182");
+        }
+        assert_eq!(
+            Doi::new("10.1002/(SICI)1097-4571").unwrap().as_str(),
+            "10.1002/(SICI)1097-4571"
+        );
+
+        for input in [
+            "",
+            "10.1000",
+            "10./182",
+            "11.1000/182",
+            "10.1000/",
+            "10.1000/1 82",
+        ] {
+            assert!(Doi::new(input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_isbn() {
+        for input in [
+            "978-0-306-40615-7",
+            "9780306406157",
+            "ISBN 978 0 306 40615 7",
+            "ISBN-13: 978-0-306-40615-7",
+        ] {
+            assert_eq!(Isbn::new(input).unwrap().as_str(), "9780306406157");
+        }
+        assert_eq!(Isbn::new("0-8044-2957-x").unwrap().as_str(), "080442957X");
+        assert_eq!(
+            Isbn::new("ISBN-10: 0306406152").unwrap().as_str(),
+            "0306406152"
+        );
+        assert_eq!(Isbn::new("ISBN0306406152").unwrap().as_str(), "0306406152");
+
+        for input in [
+            "978-0-306-40615-8",
+            "0306406153",
+            "030640615",
+            "X306406152",
+            "",
+        ] {
+            assert!(Isbn::new(input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_issn() {
+        assert_eq!(Issn::new("0317-8471").unwrap().as_str(), "0317-8471");
+        assert_eq!(Issn::new("03178471").unwrap().as_str(), "0317-8471");
+        assert_eq!(Issn::new("ISSN: 1050-124x").unwrap().as_str(), "1050-124X");
+
+        for input in ["0317-8472", "0317-847", "X317-8471", ""] {
+            assert!(Issn::new(input).is_err());
+        }
+    }
+
+    #[test]
+    fn test_identifier_serde() {
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Fields {
+            doi: Doi,
+            isbn: Isbn,
+            #[serde(skip_serializing)]
+            issn: Option<Issn>,
+        }
+
+        let input = "@book{key, doi = {https://doi.org/10.1000/182}, isbn = {0-306-40615-2}}";
+        let (entry_type, entry_key, fields) = Deserializer::from_str(input)
+            .into_iter_regular_entry::<(String, String, Fields)>()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            fields,
+            Fields {
+                doi: Doi::new("10.1000/182").unwrap(),
+                isbn: Isbn::new("0306406152").unwrap(),
+                issn: None,
+            }
+        );
+
+        let input = "@book{key, doi = {10.1000/182}, isbn = {0-306-40615-3}}";
+        assert!(Deserializer::from_str(input)
+            .into_iter_regular_entry::<(String, String, Fields)>()
+            .next()
+            .unwrap()
+            .is_err());
+
+        let bib = vec![(entry_type, entry_key, fields)];
+        assert_eq!(
+            crate::to_string_compact(&bib).unwrap(),
+            "@book{key,doi={10.1000/182},isbn={0306406152}}"
+        );
+    }
+}
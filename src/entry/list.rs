@@ -0,0 +1,216 @@
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// A list of URLs stored in a single field, such as `url = {https://a.org https://b.org}`.
+///
+/// When deserializing, the URLs may be separated by any combination of whitespace and
+/// semicolons, and any surrounding `<` `>` are removed. The URLs are serialized separated by a
+/// single space.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct UrlList(pub Vec<String>);
+
+impl UrlList {
+    /// Split the input into URLs.
+    pub fn parse(input: &str) -> Self {
+        Self(
+            input
+                .split(|c: char| c.is_whitespace() || c == ';')
+                .map(|url| url.trim_start_matches('<').trim_end_matches('>'))
+                .filter(|url| !url.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for UrlList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, url) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(url)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for UrlList {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct UrlListVisitor;
+
+impl<'de> Visitor<'de> for UrlListVisitor {
+    type Value = UrlList;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of URLs")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(UrlList::parse(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for UrlList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(UrlListVisitor)
+    }
+}
+
+/// A list of keywords stored in a single field, such as `keywords = {a, b; c}`.
+///
+/// When deserializing, the keywords may be separated by commas or semicolons, and surrounding
+/// whitespace is removed. The keywords are serialized separated by `SEP` followed by a space;
+/// for example, use `Keywords<';'>` to serialize as `a; b; c`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Keywords<const SEP: char = ','>(pub Vec<String>);
+
+impl<const SEP: char> Keywords<SEP> {
+    /// Split the input into keywords.
+    pub fn parse(input: &str) -> Self {
+        Self(
+            input
+                .split([',', ';'])
+                .map(str::trim)
+                .filter(|keyword| !keyword.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        )
+    }
+}
+
+impl<const SEP: char> fmt::Display for Keywords<SEP> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, keyword) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "{SEP} ")?;
+            }
+            f.write_str(keyword)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const SEP: char> Serialize for Keywords<SEP> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+struct KeywordsVisitor<const SEP: char>;
+
+impl<'de, const SEP: char> Visitor<'de> for KeywordsVisitor<SEP> {
+    type Value = Keywords<SEP>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of keywords")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Keywords::parse(v))
+    }
+}
+
+impl<'de, const SEP: char> Deserialize<'de> for Keywords<SEP> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(KeywordsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::Deserializer;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Fields {
+        url: UrlList,
+    }
+
+    #[test]
+    fn test_url_list_parse() {
+        assert_eq!(
+            UrlList::parse(" https://a.org;https://b.org/x?y=1,2 ;\n <https://c.org> "),
+            UrlList(vec![
+                "https://a.org".into(),
+                "https://b.org/x?y=1,2".into(),
+                "https://c.org".into(),
+            ])
+        );
+        assert_eq!(UrlList::parse(" ; "), UrlList::default());
+    }
+
+    #[test]
+    fn test_url_list_serde() {
+        let input = "@misc{key, url = {https://a.org; https://b.org}}";
+        let bib: Vec<(String, String, Fields)> = Deserializer::from_str(input)
+            .into_iter_regular_entry()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            bib[0].2.url,
+            UrlList(vec!["https://a.org".into(), "https://b.org".into()])
+        );
+
+        assert_eq!(
+            crate::to_string_compact(&bib).unwrap(),
+            "@misc{key,url={https://a.org https://b.org}}"
+        );
+    }
+
+    #[test]
+    fn test_keywords_parse() {
+        assert_eq!(
+            Keywords::<','>::parse(" a, b c;c ;; ,d ").0,
+            vec!["a", "b c", "c", "d"]
+        );
+        assert_eq!(Keywords::<','>::parse(" "), Keywords::default());
+    }
+
+    #[test]
+    fn test_keywords_serde() {
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct KeywordFields {
+            keywords: Keywords,
+            #[serde(rename = "tags")]
+            semicolon: Keywords<';'>,
+        }
+
+        let input = "@misc{key, keywords = {a; b, c}, tags = {x, y}}";
+        let bib: Vec<(String, String, KeywordFields)> = Deserializer::from_str(input)
+            .into_iter_regular_entry()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(bib[0].2.keywords.0, vec!["a", "b", "c"]);
+        assert_eq!(bib[0].2.semicolon.0, vec!["x", "y"]);
+
+        assert_eq!(
+            crate::to_string_compact(&bib).unwrap(),
+            "@misc{key,keywords={a, b, c},tags={x; y}}"
+        );
+    }
+}
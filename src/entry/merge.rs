@@ -0,0 +1,413 @@
+use std::collections::{HashMap, HashSet};
+
+use unicase::UniCase;
+
+use crate::error::{Error, Result};
+use crate::transform::{RewriteKeys, Transform};
+use crate::value::{Bibliography, Entry, Token, Value};
+
+/// How [`merge`] resolves an entry key or macro name which is defined by more than one
+/// bibliography.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Keep both definitions, renaming the later one by appending `-2`, `-3`, and so on until the
+    /// name is unique.
+    Rename,
+    /// Keep the later definition, in the position of the earlier one.
+    PreferNewest,
+    /// Return an error.
+    Error,
+}
+
+/// Merge several bibliographies into one, in the given order.
+///
+/// The merged bibliography starts with the `@string` entries of every bibliography, followed by a
+/// single `@preamble` entry which concatenates every preamble, followed by the regular entries and
+/// `@comment` entries in the order in which they appear. Empty `@string` entries are removed.
+///
+/// Entry keys and macro names are compared case-insensitively. Macros are only considered to
+/// conflict if their values differ, and a macro which is defined more than once in a single
+/// bibliography is handled as a conflict. When an entry or a macro is renamed by
+/// [`MergePolicy::Rename`], the references to it from the same bibliography are rewritten: entry
+/// keys in the [reference fields](crate::transform::REFERENCE_FIELDS), and macro uses in the
+/// values which follow the renamed definition, since earlier uses refer to the previous
+/// definition.
+/// ```
+/// use serde_bibtex::entry::{merge, MergePolicy};
+/// use serde_bibtex::value::Bibliography;
+///
+/// let first: Bibliography = serde_bibtex::from_str(
+///     "@string{pub = {AMS}} @preamble{{A}} @book{key, publisher = pub}"
+/// ).unwrap();
+/// let second: Bibliography = serde_bibtex::from_str(
+///     "@string{pub = {SIAM}} @preamble{{B}} @article{key, publisher = pub}"
+/// ).unwrap();
+///
+/// assert!(merge(vec![first.clone(), second.clone()], MergePolicy::Error).is_err());
+///
+/// let merged = merge(vec![first, second], MergePolicy::Rename).unwrap();
+/// assert_eq!(
+///     serde_bibtex::to_string(&merged).unwrap(),
+///     r#"@string{pub = {AMS}}
+///
+/// @string{pub-2 = {SIAM}}
+///
+/// @preamble{{A} # {B}}
+///
+/// @book{key,
+///   publisher = pub,
+/// }
+///
+/// @article{key-2,
+///   publisher = pub-2,
+/// }
+/// "#
+/// );
+/// ```
+pub fn merge(bibliographies: Vec<Bibliography>, policy: MergePolicy) -> Result<Bibliography> {
+    let mut merger = Merger {
+        policy,
+        macros: Vec::new(),
+        macro_index: HashMap::new(),
+        preamble: None,
+        entries: Vec::new(),
+        key_index: HashMap::new(),
+    };
+    for bibliography in bibliographies {
+        merger.add(bibliography)?;
+    }
+    Ok(merger.finish())
+}
+
+/// The first name of the form `{name}-{n}` with `n >= 2` which is not taken.
+fn fresh_name<F: Fn(&str) -> bool>(name: &str, taken: F) -> String {
+    (2..)
+        .map(|n| format!("{name}-{n}"))
+        .find(|candidate| !taken(candidate))
+        .unwrap()
+}
+
+/// Replace the macros in a value using a map from old to new macro names.
+fn rename_macros(value: &mut Value, renamed: &HashMap<UniCase<String>, String>) {
+    for token in value.0.iter_mut() {
+        if let Token::Variable(name) = token {
+            if let Some(new_name) = renamed.get(&UniCase::new(name.clone())) {
+                name.clone_from(new_name);
+            }
+        }
+    }
+}
+
+struct Merger {
+    policy: MergePolicy,
+    macros: Vec<(String, Value)>,
+    macro_index: HashMap<UniCase<String>, usize>,
+    preamble: Option<Value>,
+    /// The regular entries and comments.
+    entries: Vec<Entry>,
+    key_index: HashMap<UniCase<String>, usize>,
+}
+
+impl Merger {
+    fn add(&mut self, bibliography: Bibliography) -> Result<()> {
+        let mut entries = bibliography.0;
+
+        // a renamed macro only replaces the uses which follow its definition
+        let mut renamed = HashMap::new();
+        for entry in entries.iter_mut() {
+            match entry {
+                Entry::Macro(Some((name, value))) => {
+                    rename_macros(value, &renamed);
+                    self.add_macro(name, value, &mut renamed)?;
+                }
+                Entry::Regular(regular) if !renamed.is_empty() => {
+                    for (_, value) in regular.fields.iter_mut() {
+                        rename_macros(value, &renamed);
+                    }
+                }
+                Entry::Preamble(value) => rename_macros(value, &renamed),
+                _ => {}
+            }
+        }
+
+        if self.policy == MergePolicy::Rename {
+            self.rename_keys(&mut entries);
+        }
+
+        for entry in entries {
+            match entry {
+                Entry::Regular(mut regular) => match self
+                    .key_index
+                    .get(&UniCase::new(regular.entry_key.clone()))
+                {
+                    None => {
+                        self.key_index
+                            .insert(UniCase::new(regular.entry_key.clone()), self.entries.len());
+                        self.entries.push(Entry::Regular(regular));
+                    }
+                    Some(&idx) => match self.policy {
+                        MergePolicy::Error => return Err(Error::duplicate_key(regular.entry_key)),
+                        MergePolicy::PreferNewest => self.entries[idx] = Entry::Regular(regular),
+                        MergePolicy::Rename => {
+                            // a duplicate within a single bibliography, so references to it are
+                            // ambiguous and are left unchanged
+                            regular.entry_key = fresh_name(&regular.entry_key, |key| {
+                                self.key_index.contains_key(&UniCase::new(key.to_owned()))
+                            });
+                            self.key_index.insert(
+                                UniCase::new(regular.entry_key.clone()),
+                                self.entries.len(),
+                            );
+                            self.entries.push(Entry::Regular(regular));
+                        }
+                    },
+                },
+                Entry::Comment(comment) => self.entries.push(Entry::Comment(comment)),
+                Entry::Preamble(value) => self
+                    .preamble
+                    .get_or_insert_with(Value::default)
+                    .0
+                    .extend(value.0),
+                Entry::Macro(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn add_macro(
+        &mut self,
+        name: &str,
+        value: &Value,
+        renamed: &mut HashMap<UniCase<String>, String>,
+    ) -> Result<()> {
+        let key = UniCase::new(name.to_owned());
+        // the definition which is currently in effect, if the macro was already renamed
+        let current = renamed
+            .get(&key)
+            .and_then(|name| self.macro_index.get(&UniCase::new(name.clone())));
+        if current.is_some_and(|&idx| self.macros[idx].1 == *value) {
+            return Ok(());
+        }
+        match self.macro_index.get(&key) {
+            None => {
+                self.macro_index.insert(key, self.macros.len());
+                self.macros.push((name.to_owned(), value.clone()));
+            }
+            Some(&idx) if self.macros[idx].1 == *value => {
+                // later uses refer to the original definition again
+                renamed.remove(&key);
+            }
+            Some(&idx) => match self.policy {
+                MergePolicy::Error => return Err(Error::duplicate_macro(name.to_owned())),
+                MergePolicy::PreferNewest => self.macros[idx].1 = value.clone(),
+                MergePolicy::Rename => {
+                    let new_name = fresh_name(name, |candidate| {
+                        self.macro_index
+                            .contains_key(&UniCase::new(candidate.to_owned()))
+                    });
+                    self.macro_index
+                        .insert(UniCase::new(new_name.clone()), self.macros.len());
+                    self.macros.push((new_name.clone(), value.clone()));
+                    renamed.insert(key, new_name);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Rename the entries whose keys are used by earlier bibliographies, rewriting the references
+    /// to them.
+    fn rename_keys(&self, entries: &mut [Entry]) {
+        let keys: HashSet<UniCase<String>> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Regular(regular) => Some(UniCase::new(regular.entry_key.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let renamed: HashMap<UniCase<String>, String> = keys
+            .iter()
+            .filter(|key| self.key_index.contains_key(*key))
+            .map(|key| {
+                let new_key = fresh_name(key, |candidate| {
+                    let candidate = UniCase::new(candidate.to_owned());
+                    self.key_index.contains_key(&candidate) || keys.contains(&candidate)
+                });
+                (key.clone(), new_key)
+            })
+            .collect();
+
+        if renamed.is_empty() {
+            return;
+        }
+
+        let mut rewrite = RewriteKeys::new(move |key| {
+            renamed
+                .get(&UniCase::new(key.to_owned()))
+                .cloned()
+                .unwrap_or_else(|| key.to_owned())
+        });
+        for entry in entries.iter_mut() {
+            rewrite.apply(entry);
+        }
+    }
+
+    fn finish(self) -> Bibliography {
+        let mut merged: Vec<Entry> = self
+            .macros
+            .into_iter()
+            .map(|definition| Entry::Macro(Some(definition)))
+            .collect();
+        merged.extend(self.preamble.map(Entry::Preamble));
+        merged.extend(self.entries);
+        Bibliography(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn bib(input: &str) -> Bibliography {
+        crate::from_str(input).unwrap()
+    }
+
+    fn keys(bibliography: &Bibliography) -> Vec<&str> {
+        bibliography
+            .regular_entries()
+            .map(|entry| entry.entry_key.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_keys() {
+        let first = bib("@book{a, title = {A1}} @comment{x} @book{b, title = {B}}");
+        let second = bib("@book{c, crossref = {a}} @book{a, title = {A2}} @book{a-2}");
+
+        let merged = merge(vec![first.clone(), second.clone()], MergePolicy::Rename).unwrap();
+        assert_eq!(keys(&merged), vec!["a", "b", "c", "a-3", "a-2"]);
+        assert_eq!(merged.0[1], Entry::Comment("x".into()));
+        assert_eq!(
+            merged.get("c").unwrap().field("crossref"),
+            Some(&Value::text("a-3"))
+        );
+
+        let merged = merge(
+            vec![first.clone(), second.clone()],
+            MergePolicy::PreferNewest,
+        )
+        .unwrap();
+        assert_eq!(keys(&merged), vec!["a", "b", "c", "a-2"]);
+        assert_eq!(
+            merged.get("a").unwrap().field("title"),
+            Some(&Value::text("A2"))
+        );
+        assert_eq!(
+            merged.get("c").unwrap().field("crossref"),
+            Some(&Value::text("a"))
+        );
+
+        let err = merge(vec![first.clone(), second], MergePolicy::Error).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::DuplicateKey { key: "a" }));
+
+        // duplicates within a single bibliography
+        let merged = merge(vec![bib("@book{a} @book{a}")], MergePolicy::Rename).unwrap();
+        assert_eq!(keys(&merged), vec!["a", "a-2"]);
+
+        // keys which differ only in case collide
+        let merged = merge(
+            vec![bib("@book{Key}"), bib("@book{key} @book{c, xref = {KEY}}")],
+            MergePolicy::Rename,
+        )
+        .unwrap();
+        assert_eq!(keys(&merged), vec!["Key", "key-2", "c"]);
+        assert_eq!(
+            merged.get("c").unwrap().field("xref"),
+            Some(&Value::text("key-2"))
+        );
+        let err = merge(
+            vec![bib("@book{Key}"), bib("@book{key}")],
+            MergePolicy::Error,
+        )
+        .unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::DuplicateKey { key: "key" }));
+
+        assert_eq!(
+            merge(vec![], MergePolicy::Error).unwrap(),
+            Bibliography::default()
+        );
+    }
+
+    #[test]
+    fn test_merge_macros() {
+        let first = bib("@string{a = {A}} @string{b = {B}} @preamble{a}");
+        let second = bib(
+            "@preamble{{x}} @string{B = {B}} @string{A = {C}} @string{d = a} \
+             @string{} @book{k, title = a # b # d}",
+        );
+
+        let merged = merge(vec![first.clone(), second.clone()], MergePolicy::Rename).unwrap();
+        assert_eq!(
+            merged.0[..5],
+            [
+                Entry::Macro(Some(("a".into(), Value::text("A")))),
+                Entry::Macro(Some(("b".into(), Value::text("B")))),
+                Entry::Macro(Some(("A-2".into(), Value::text("C")))),
+                Entry::Macro(Some(("d".into(), Value::variable("A-2")))),
+                Entry::Preamble(Value(vec![
+                    Token::Variable("a".into()),
+                    Token::Text("x".into())
+                ])),
+            ]
+        );
+        assert_eq!(
+            merged.get("k").unwrap().field("title"),
+            Some(&Value(vec![
+                Token::Variable("A-2".into()),
+                Token::Variable("b".into()),
+                Token::Variable("d".into()),
+            ]))
+        );
+        assert_eq!(merged.0.len(), 6);
+
+        let merged = merge(
+            vec![first.clone(), second.clone()],
+            MergePolicy::PreferNewest,
+        )
+        .unwrap();
+        assert_eq!(
+            merged.0[0],
+            Entry::Macro(Some(("a".into(), Value::text("C"))))
+        );
+
+        let err = merge(vec![first, second], MergePolicy::Error).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ErrorKind::DuplicateMacro { name: "A" }
+        ));
+
+        // only the uses which follow the redefinition are renamed
+        let merged = merge(
+            vec![bib(
+                "@string{a = {A}} @book{x, t = a} @string{a = {B}} @book{y, t = a} \
+                 @string{a = {A}} @book{z, t = a}",
+            )],
+            MergePolicy::Rename,
+        )
+        .unwrap();
+        assert_eq!(
+            merged.get("x").unwrap().field("t"),
+            Some(&Value::variable("a"))
+        );
+        assert_eq!(
+            merged.get("y").unwrap().field("t"),
+            Some(&Value::variable("a-2"))
+        );
+        assert_eq!(
+            merged.get("z").unwrap().field("t"),
+            Some(&Value::variable("a"))
+        );
+    }
+}
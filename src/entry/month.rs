@@ -0,0 +1,260 @@
+use std::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use unicase::UniCase;
+
+use super::Token;
+
+/// A month of the year, as used in the `month` field.
+///
+/// A [`Month`] is deserialized from any of:
+/// - a month macro such as `apr`, even if the macro is not defined,
+/// - a month number such as `{4}` or `{04}`,
+/// - an English month name or abbreviation such as `{April}` or `{Apr}`.
+///
+/// A [`Month`] is serialized as the corresponding month macro, such as `apr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Month {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+const MONTHS: [Month; 12] = [
+    Month::January,
+    Month::February,
+    Month::March,
+    Month::April,
+    Month::May,
+    Month::June,
+    Month::July,
+    Month::August,
+    Month::September,
+    Month::October,
+    Month::November,
+    Month::December,
+];
+
+impl Month {
+    /// The month number, from 1 to 12.
+    pub fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The month corresponding to the month number, from 1 to 12.
+    pub fn from_number(number: u8) -> Option<Self> {
+        MONTHS.get(usize::from(number).checked_sub(1)?).copied()
+    }
+
+    /// The name of the month macro, such as `apr`.
+    pub fn macro_name(self) -> &'static str {
+        match self {
+            Self::January => "jan",
+            Self::February => "feb",
+            Self::March => "mar",
+            Self::April => "apr",
+            Self::May => "may",
+            Self::June => "jun",
+            Self::July => "jul",
+            Self::August => "aug",
+            Self::September => "sep",
+            Self::October => "oct",
+            Self::November => "nov",
+            Self::December => "dec",
+        }
+    }
+
+    /// The English name of the month, such as `April`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::January => "January",
+            Self::February => "February",
+            Self::March => "March",
+            Self::April => "April",
+            Self::May => "May",
+            Self::June => "June",
+            Self::July => "July",
+            Self::August => "August",
+            Self::September => "September",
+            Self::October => "October",
+            Self::November => "November",
+            Self::December => "December",
+        }
+    }
+
+    /// The month corresponding to a month macro name, such as `apr`.
+    fn from_macro_name(s: &str) -> Option<Self> {
+        let s = UniCase::new(s);
+        MONTHS
+            .into_iter()
+            .find(|month| s == UniCase::new(month.macro_name()))
+    }
+
+    /// The month corresponding to a month number, name, or abbreviation.
+    fn from_text(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Ok(number) = s.parse::<u8>() {
+            return Self::from_number(number);
+        }
+        Self::from_macro_name(s).or_else(|| {
+            let s = UniCase::new(s);
+            MONTHS
+                .into_iter()
+                .find(|month| s == UniCase::new(month.name()))
+        })
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+struct MonthVisitor;
+
+impl<'de> Visitor<'de> for MonthVisitor {
+    type Value = Month;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a month macro, number, or name")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Month::from_text(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut text = String::new();
+        let mut variable = None;
+        let mut count = 0;
+
+        while let Some(token) = seq.next_element::<Token>()? {
+            count += 1;
+            match token {
+                Token::Variable(var) => variable = Some(var),
+                Token::Text(s) => text.push_str(s),
+            }
+        }
+
+        match (variable, count) {
+            (Some(var), 1) => Month::from_macro_name(var).ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Other("undefined macro"), &self)
+            }),
+            (Some(_), _) => Err(de::Error::invalid_value(
+                de::Unexpected::Other("value containing undefined macros"),
+                &self,
+            )),
+            (None, _) => self.visit_str(&text),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Month {
+    /// Deserialize from the tokens of the value, so that month macros are accepted even when they
+    /// are not defined.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(MonthVisitor)
+    }
+}
+
+impl Serialize for Month {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        [Token::Variable(self.macro_name())].serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::Deserializer;
+    use crate::MacroDictionary;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct Fields {
+        month: Month,
+    }
+
+    fn month(input: &str) -> crate::Result<Month> {
+        Deserializer::from_str(input)
+            .into_iter_regular_entry::<(String, String, Fields)>()
+            .next()
+            .unwrap()
+            .map(|(_, _, fields)| fields.month)
+    }
+
+    #[test]
+    fn test_month_de() {
+        assert_eq!(month("@a{k, month = apr}").unwrap(), Month::April);
+        assert_eq!(month("@a{k, month = DEC}").unwrap(), Month::December);
+        assert_eq!(month("@a{k, month = {4}}").unwrap(), Month::April);
+        assert_eq!(month("@a{k, month = 04}").unwrap(), Month::April);
+        assert_eq!(month("@a{k, month = \"April\"}").unwrap(), Month::April);
+        assert_eq!(month("@a{k, month = {Ju} # {ne}}").unwrap(), Month::June);
+        assert_eq!(month("@a{k, month = {sep}}").unwrap(), Month::September);
+
+        assert!(month("@a{k, month = {13}}").is_err());
+        assert!(month("@a{k, month = {0}}").is_err());
+        assert!(month("@a{k, month = april}").is_err());
+        assert!(month("@a{k, month = apr # {~1}}").is_err());
+    }
+
+    #[test]
+    fn test_month_de_expanded() {
+        let mut macros = MacroDictionary::default();
+        macros.set_month_macros();
+        let (_, _, fields) = Deserializer::from_str_with_macros("@a{k, month = nov}", macros)
+            .into_iter_regular_entry::<(String, String, Fields)>()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(fields.month, Month::November);
+    }
+
+    #[test]
+    fn test_month_ser() {
+        let bib = vec![(
+            "article",
+            "key",
+            Fields {
+                month: Month::April,
+            },
+        )];
+        assert_eq!(
+            crate::to_string_compact(&bib).unwrap(),
+            "@article{key,month=apr}"
+        );
+    }
+
+    #[test]
+    fn test_month_number() {
+        for month in MONTHS {
+            assert_eq!(Month::from_number(month.number()), Some(month));
+            assert_eq!(Month::from_text(month.name()), Some(month));
+            assert_eq!(Month::from_text(month.macro_name()), Some(month));
+        }
+        assert_eq!(Month::from_number(0), None);
+    }
+}
@@ -0,0 +1,150 @@
+use std::hash::{Hash, Hasher};
+
+use unicase::UniCase;
+
+use super::Entry;
+
+/// Whether two entries are equal after normalization.
+///
+/// Entry types, entry keys, and field keys are compared case-insensitively, and field values are
+/// compared after collapsing each run of whitespace into a single space and removing leading and
+/// trailing whitespace. Since the values of an [`Entry`] are deserialized as text, macros are
+/// already expanded. Entries other than regular entries are equal if they have the same variant.
+/// ```
+/// use serde_bibtex::{entry::{normalized_eq, Entry}, from_entry_str};
+///
+/// let a: Entry = from_entry_str("@article{Key, title = {A Title}}").unwrap();
+/// let b: Entry = from_entry_str("@ARTICLE{key, Title = { A\n  Title }}").unwrap();
+/// let c: Entry = from_entry_str("@article{key, title = {A title}}").unwrap();
+///
+/// assert!(normalized_eq(&a, &b));
+/// assert!(!normalized_eq(&a, &c));
+/// ```
+pub fn normalized_eq(a: &Entry, b: &Entry) -> bool {
+    match (a, b) {
+        (
+            Entry::Regular {
+                entry_type: type_a,
+                entry_key: key_a,
+                fields: fields_a,
+            },
+            Entry::Regular {
+                entry_type: type_b,
+                entry_key: key_b,
+                fields: fields_b,
+            },
+        ) => {
+            UniCase::new(type_a) == UniCase::new(type_b)
+                && key_a == key_b
+                && fields_a.0.len() == fields_b.0.len()
+                && fields_a.0.iter().zip(fields_b.0.iter()).all(
+                    |((field_a, value_a), (field_b, value_b))| {
+                        field_a == field_b
+                            && value_a.split_whitespace().eq(value_b.split_whitespace())
+                    },
+                )
+        }
+        (Entry::Macro, Entry::Macro)
+        | (Entry::Comment, Entry::Comment)
+        | (Entry::Preamble, Entry::Preamble) => true,
+        _ => false,
+    }
+}
+
+/// A wrapper around an [`Entry`] which implements [`Eq`] and [`Hash`] using [`normalized_eq`].
+///
+/// This is useful to find duplicate entries, for instance by collecting the entries of a
+/// bibliography into a [`HashSet`](std::collections::HashSet).
+/// ```
+/// use std::collections::HashSet;
+///
+/// use serde_bibtex::{entry::{Entry, NormalizedEntry}, from_str};
+///
+/// let entries: Vec<Entry> = from_str(
+///     "@book{key, title = {Title}} @Book{KEY, title = {Title }} @book{key, title = {Other}}"
+/// ).unwrap();
+///
+/// let distinct: HashSet<_> = entries.iter().map(NormalizedEntry).collect();
+/// assert_eq!(distinct.len(), 2);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizedEntry<'a>(pub &'a Entry);
+
+impl PartialEq for NormalizedEntry<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        normalized_eq(self.0, other.0)
+    }
+}
+
+impl Eq for NormalizedEntry<'_> {}
+
+impl Hash for NormalizedEntry<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self.0).hash(state);
+        if let Entry::Regular {
+            entry_type,
+            entry_key,
+            fields,
+        } = self.0
+        {
+            UniCase::new(entry_type).hash(state);
+            entry_key.hash(state);
+            state.write_usize(fields.0.len());
+            for (field, value) in fields.0.iter() {
+                field.hash(state);
+                for word in value.split_whitespace() {
+                    word.hash(state);
+                }
+                // terminate the value, so that the words of adjacent fields are distinguished
+                state.write_u8(0xff);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+    use crate::from_str;
+
+    fn hash(entry: &Entry) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        NormalizedEntry(entry).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_normalized_eq() {
+        // the last entry, so that macros can be defined first
+        let entry = |s| from_str::<Vec<Entry>>(s).unwrap().pop().unwrap();
+
+        let a = entry("@article{Key, author = {First Last}, title = {Title}}");
+        for equal in [
+            "@Article{key, Author = {First Last}, title = {Title}}",
+            "@article{KEY, title = {  Title\n}, author = {First\t  Last}}",
+            "@string{first = {First}} @article{key, author = first # { Last}, title = {Title}}",
+        ] {
+            let b = entry(equal);
+            assert!(normalized_eq(&a, &b), "{equal}");
+            assert_eq!(NormalizedEntry(&a), NormalizedEntry(&b));
+            assert_eq!(hash(&a), hash(&b));
+        }
+
+        for different in [
+            "@book{key, author = {First Last}, title = {Title}}",
+            "@article{key2, author = {First Last}, title = {Title}}",
+            "@article{key, author = {FirstLast}, title = {Title}}",
+            "@article{key, author = {First Last}, title = {title}}",
+            "@article{key, author = {First Last}}",
+            "@article{key, author = {First Last}, title = {Title}, year = {2024}}",
+        ] {
+            assert!(!normalized_eq(&a, &entry(different)), "{different}");
+        }
+
+        assert!(normalized_eq(&Entry::Comment, &Entry::Comment));
+        assert!(!normalized_eq(&Entry::Comment, &Entry::Preamble));
+        assert!(!normalized_eq(&a, &Entry::Macro));
+    }
+}
@@ -1,19 +1,30 @@
 use serde::de::{Deserializer, MapAccess, Visitor};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::fmt;
 use unicase::UniCase;
 
 use std::collections::BTreeMap;
 
 /// An owned entry, which only captures regular entries.
-#[derive(Deserialize, Debug, PartialEq)]
+///
+/// An [`Entry`] is displayed as BibTeX, as written by the pretty formatter.
+/// ```
+/// use serde_bibtex::{entry::Entry, from_entry_str};
+///
+/// let entry: Entry = from_entry_str("@article{Key, title = {A   Title}}").unwrap();
+/// assert_eq!(entry.to_string(), "@article{Key,\n  title = {A   Title},\n}");
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum Entry {
     /// A regular entry.
     Regular {
         /// The entry type
         entry_type: String,
         /// The entry key
-        #[serde(deserialize_with = "deserialize_unicase")]
+        #[serde(
+            deserialize_with = "deserialize_unicase",
+            serialize_with = "serialize_unicase"
+        )]
         entry_key: UniCase<String>,
         /// The fields
         fields: Fields,
@@ -26,7 +37,81 @@ pub enum Entry {
     Preamble,
 }
 
-#[derive(Debug, PartialEq)]
+impl Entry {
+    /// Serialize the entry as BibTeX, checking that the output is valid.
+    ///
+    /// Unlike the [`Display`](fmt::Display) implementation, the output ends with a newline, as
+    /// in [`to_string_entry`](crate::to_string_entry). Entries other than regular entries are
+    /// skipped, so their output is empty.
+    pub fn to_bib_string(&self) -> crate::Result<String> {
+        crate::to_string_entry(self)
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ser = crate::ser::Serializer::unchecked(Vec::new());
+        ser.serialize_entry(self).map_err(|_| fmt::Error)?;
+        let output = String::from_utf8(ser.into_inner()).map_err(|_| fmt::Error)?;
+        f.write_str(output.trim_end_matches('\n'))
+    }
+}
+
+/// An owned entry, which only captures regular entries and keeps the fields in the order in
+/// which they appear.
+///
+/// Unlike [`Entry`], whose fields are sorted by key, an [`OrderedEntry`] is written back with its
+/// fields in their original order. Duplicate fields are handled according to the
+/// [duplicate field policy](crate::de::Deserializer::duplicate_fields) of the deserializer.
+/// ```
+/// use serde_bibtex::{entry::OrderedEntry, from_entry_str};
+///
+/// let input = "@article{Key,\n  title = {Title},\n  author = {Last, First},\n}\n";
+/// let entry: OrderedEntry = from_entry_str(input).unwrap();
+///
+/// assert_eq!(entry.field("Author"), Some("Last, First"));
+/// assert_eq!(serde_bibtex::to_string_entry(&entry).unwrap(), input);
+/// ```
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub enum OrderedEntry {
+    /// A regular entry.
+    Regular {
+        /// The entry type
+        entry_type: String,
+        /// The entry key
+        #[serde(
+            deserialize_with = "deserialize_unicase",
+            serialize_with = "serialize_unicase"
+        )]
+        entry_key: UniCase<String>,
+        /// The fields, in the order in which they appear
+        fields: Vec<(String, String)>,
+    },
+    /// A macro entry, which is skipped.
+    Macro,
+    /// A comment entry, which is skipped.
+    Comment,
+    /// A preamble entry, which is skipped.
+    Preamble,
+}
+
+impl OrderedEntry {
+    /// The value of a field of a regular entry. Field keys are compared case-insensitively.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        match self {
+            Self::Regular { fields, .. } => {
+                let key = UniCase::new(key);
+                fields
+                    .iter()
+                    .find(|(field_key, _)| UniCase::new(field_key.as_str()) == key)
+                    .map(|(_, value)| value.as_str())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Fields(pub BTreeMap<UniCase<String>, String>);
 
 struct FieldsVisitor;
@@ -62,6 +147,23 @@ impl<'de> Deserialize<'de> for Fields {
     }
 }
 
+impl Serialize for Fields {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key.as_ref(), value)))
+    }
+}
+
+#[inline]
+fn serialize_unicase<S>(key: &UniCase<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(key.as_ref())
+}
+
 #[inline]
 fn deserialize_unicase<'de, D>(deserializer: D) -> Result<UniCase<String>, D::Error>
 where
@@ -69,3 +171,90 @@ where
 {
     Ok(UniCase::new(String::deserialize(deserializer)?))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_entry_str, to_string_entry};
+
+    #[test]
+    fn test_display() {
+        let input = "@book{Key,\n  Author = {Last, First},\n  title = {A {Title}},\n}\n";
+        let entry: Entry = from_entry_str(input).unwrap();
+        assert_eq!(entry.to_bib_string().unwrap(), input);
+        assert_eq!(entry.to_string(), input.trim_end());
+        assert_eq!(from_entry_str::<Entry>(&entry.to_string()).unwrap(), entry);
+
+        assert_eq!(format!("{}", Entry::Comment), "");
+        assert_eq!(Entry::Preamble.to_bib_string().unwrap(), "");
+
+        // unbalanced brackets are only detected when the output is checked
+        let mut fields = BTreeMap::new();
+        fields.insert(UniCase::new("title".to_owned()), "}".to_owned());
+        let entry = Entry::Regular {
+            entry_type: "misc".to_owned(),
+            entry_key: UniCase::new("key".to_owned()),
+            fields: Fields(fields),
+        };
+        assert_eq!(entry.to_string(), "@misc{key,\n  title = {}},\n}");
+        assert!(entry.to_bib_string().is_err());
+        assert!(to_string_entry(&entry).is_err());
+    }
+
+    #[test]
+    fn test_ordered_entry() {
+        let input = "@book{Key,\n  year = {2024},\n  Title = {T},\n  author = {A},\n}\n";
+        let entry: OrderedEntry = from_entry_str(input).unwrap();
+        assert_eq!(
+            entry,
+            OrderedEntry::Regular {
+                entry_type: "book".into(),
+                entry_key: UniCase::new("key".into()),
+                fields: vec![
+                    ("year".into(), "2024".into()),
+                    ("Title".into(), "T".into()),
+                    ("author".into(), "A".into()),
+                ],
+            }
+        );
+        assert_eq!(entry.field("title"), Some("T"));
+        assert_eq!(entry.field("note"), None);
+        assert_eq!(to_string_entry(&entry).unwrap(), input);
+
+        let entries: Vec<OrderedEntry> = crate::from_str("@comment{x} @preamble{{p}}").unwrap();
+        assert_eq!(entries, vec![OrderedEntry::Comment, OrderedEntry::Preamble]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        use crate::entry::{BorrowEntry, Token};
+        use crate::{from_str, to_string};
+
+        let input = "@comment{c} @string{v = {x}} @book{k, a = {1} # v # 12, b = v, c = {}} \
+                     @preamble{{p} # v} @comment{d}";
+
+        let entries: Vec<Entry> = from_str(input).unwrap();
+        let output = to_string(&entries).unwrap();
+        assert_eq!(
+            output,
+            "@book{k,\n  a = {1x12},\n  b = {x},\n  c = {},\n}\n"
+        );
+        let regular: Vec<_> = entries
+            .into_iter()
+            .filter(|entry| matches!(entry, Entry::Regular { .. }))
+            .collect();
+        assert_eq!(from_str::<Vec<Entry>>(&output).unwrap(), regular);
+
+        let entries: Vec<BorrowEntry> = from_str(input).unwrap();
+        let output = to_string(&entries).unwrap();
+        assert_eq!(
+            output,
+            "@comment{c}\n\n@string{v = {x}}\n\n@book{k,\n  a = {1} # v # {12},\n  b = v,\n  c = {},\n}\n\n@preamble{{p} # v}\n\n@comment{d}\n"
+        );
+        assert_eq!(from_str::<Vec<BorrowEntry>>(&output).unwrap(), entries);
+        assert!(matches!(
+            entries[2],
+            BorrowEntry::Regular { ref fields, .. } if fields[1].1 == [Token::Variable("v")]
+        ));
+    }
+}
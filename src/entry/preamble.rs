@@ -0,0 +1,139 @@
+use std::fmt;
+
+use serde::de::{Deserializer, EnumAccess, SeqAccess, VariantAccess, Visitor};
+use serde::Deserialize;
+
+/// The contents of the `@preamble` entries of a bibliography, concatenated in order.
+///
+/// As in BibTeX, the preambles are joined without a separator. Macros are captured from the
+/// `@string` entries and expanded, and every other entry is skipped. A [`Preamble`] can be
+/// deserialized from an entire bibliography, or from each entry returned by
+/// [`Deserializer::into_iter`](crate::de::Deserializer::into_iter), in which case the entries can be
+/// collected into a single [`Preamble`].
+/// ```
+/// use serde_bibtex::de::Deserializer;
+/// use serde_bibtex::entry::Preamble;
+///
+/// let input = r#"
+///     @string{cmd = "\newcommand"}
+///     @preamble{cmd # {\noopsort}}
+///     @article{key, title = {Title}}
+///     @preamble{"[1]{}"}
+/// "#;
+///
+/// let preamble: Preamble = serde_bibtex::from_str(input).unwrap();
+/// assert_eq!(preamble.as_tex(), r"\newcommand\noopsort[1]{}");
+///
+/// let preamble: Preamble = Deserializer::from_str(input)
+///     .into_iter::<Preamble>()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(preamble.as_tex(), r"\newcommand\noopsort[1]{}");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Preamble(pub String);
+
+impl Preamble {
+    /// The concatenated preambles, as TeX source.
+    pub fn as_tex(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Extend<Preamble> for Preamble {
+    fn extend<I: IntoIterator<Item = Preamble>>(&mut self, iter: I) {
+        for preamble in iter {
+            self.0.push_str(&preamble.0);
+        }
+    }
+}
+
+impl FromIterator<Preamble> for Preamble {
+    fn from_iter<I: IntoIterator<Item = Preamble>>(iter: I) -> Self {
+        let mut preamble = Self::default();
+        preamble.extend(iter);
+        preamble
+    }
+}
+
+/// The kinds of entry, as provided by the entry deserializer.
+#[derive(Deserialize)]
+enum EntryKind {
+    Regular,
+    Macro,
+    Comment,
+    Preamble,
+}
+
+struct PreambleVisitor;
+
+impl<'de> Visitor<'de> for PreambleVisitor {
+    type Value = Preamble;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bibliography or entry")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut preamble = Preamble::default();
+        while let Some(entry) = seq.next_element::<Preamble>()? {
+            preamble.0.push_str(&entry.0);
+        }
+        Ok(preamble)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        match data.variant()? {
+            (EntryKind::Preamble, variant) => variant.newtype_variant().map(Preamble),
+            (EntryKind::Regular | EntryKind::Macro | EntryKind::Comment, variant) => {
+                variant.unit_variant()?;
+                Ok(Preamble::default())
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Preamble {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // a bibliography is provided as a sequence, and an entry is provided as an enum
+        deserializer.deserialize_any(PreambleVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::Deserializer;
+
+    #[test]
+    fn test_preamble() {
+        let input = "@preamble{{A}} @comment{x} @string{b = {B}} @misc{k}\n@preamble(b # {C})";
+
+        let preamble: Preamble = crate::from_str(input).unwrap();
+        assert_eq!(preamble, Preamble("ABC".into()));
+
+        let preambles: Vec<Preamble> = Deserializer::from_str(input)
+            .into_iter()
+            .collect::<crate::Result<_>>()
+            .unwrap();
+        assert_eq!(preambles.len(), 5);
+        assert_eq!(preambles[0].as_tex(), "A");
+        assert_eq!(preambles[3].as_tex(), "");
+        assert_eq!(preambles.into_iter().collect::<Preamble>().as_tex(), "ABC");
+
+        let preamble: Preamble = crate::from_str("@misc{k}").unwrap();
+        assert_eq!(preamble.as_tex(), "");
+
+        // undefined macros are an error
+        assert!(crate::from_str::<Preamble>("@preamble{b}").is_err());
+    }
+}
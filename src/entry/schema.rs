@@ -0,0 +1,300 @@
+use std::fmt;
+
+use unicase::UniCase;
+
+/// BibTeX-specific metadata about a field of a strongly typed entry.
+///
+/// The metadata records properties of a field which cannot be expressed with serde attributes,
+/// such as whether the field is required by the entry type. It is constructed in a `const`
+/// context, so that it can be stored in [`BibFields::FIELDS`].
+/// ```
+/// use serde_bibtex::entry::FieldInfo;
+///
+/// const URL: FieldInfo = FieldInfo::new("url").rename("URL").verbatim();
+/// assert_eq!(URL.name, "url");
+/// assert_eq!(URL.key, "URL");
+/// assert!(URL.verbatim);
+/// assert!(!URL.required);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FieldInfo {
+    /// The name of the field in the Rust type.
+    pub name: &'static str,
+    /// The field key in BibTeX, which is compared case-insensitively.
+    pub key: &'static str,
+    /// Whether the value is verbatim, such as a URL or a file path, so that its contents should
+    /// not be interpreted as TeX.
+    pub verbatim: bool,
+    /// Whether the field must have a value.
+    pub required: bool,
+}
+
+impl FieldInfo {
+    /// The metadata of an optional field whose field key is the same as its name.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            key: name,
+            verbatim: false,
+            required: false,
+        }
+    }
+
+    /// Use a field key which is different from the name of the field.
+    pub const fn rename(mut self, key: &'static str) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Mark the field as verbatim.
+    pub const fn verbatim(mut self) -> Self {
+        self.verbatim = true;
+        self
+    }
+
+    /// Mark the field as required.
+    pub const fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+}
+
+/// The fields of a strongly typed entry, along with their metadata.
+///
+/// This trait is intended to be implemented alongside [`Serialize`](serde::Serialize) and
+/// [`Deserialize`](serde::Deserialize), and can be implemented by hand or by a derive macro.
+/// ```
+/// use serde_bibtex::entry::{BibFields, FieldInfo};
+///
+/// struct Fields {
+///     title: String,
+///     url: Option<String>,
+/// }
+///
+/// impl BibFields for Fields {
+///     const FIELDS: &'static [FieldInfo] = &[
+///         FieldInfo::new("title").required(),
+///         FieldInfo::new("url").verbatim(),
+///     ];
+///
+///     fn has_field(&self, name: &str) -> bool {
+///         match name {
+///             "title" => !self.title.is_empty(),
+///             "url" => self.url.is_some(),
+///             _ => false,
+///         }
+///     }
+/// }
+///
+/// assert!(Fields::field_info("URL").unwrap().verbatim);
+///
+/// let fields = Fields {
+///     title: String::new(),
+///     url: None,
+/// };
+/// assert_eq!(fields.missing_fields(), vec!["title"]);
+/// ```
+pub trait BibFields {
+    /// The metadata of each field, in the order in which the fields are serialized.
+    const FIELDS: &'static [FieldInfo];
+
+    /// Whether the field with the given [name](FieldInfo::name) has a value.
+    fn has_field(&self, name: &str) -> bool;
+
+    /// The metadata of the field with the given field key, compared case-insensitively.
+    fn field_info(key: &str) -> Option<&'static FieldInfo> {
+        let key = UniCase::new(key);
+        Self::FIELDS
+            .iter()
+            .find(|info| UniCase::new(info.key) == key)
+    }
+
+    /// The field keys of the required fields which do not have a value.
+    fn missing_fields(&self) -> Vec<&'static str> {
+        Self::FIELDS
+            .iter()
+            .filter(|info| info.required && !self.has_field(info.name))
+            .map(|info| info.key)
+            .collect()
+    }
+}
+
+/// A strongly typed entry, consisting of an entry type, an entry key, and [`BibFields`].
+///
+/// Use [`BibEntry::check`] to validate the entry against its metadata, for instance before
+/// serializing it or after deserializing it.
+/// ```
+/// use serde_bibtex::entry::{BibEntry, BibFields, EntryCheckError, FieldInfo};
+///
+/// struct Fields {
+///     author: Option<String>,
+/// }
+///
+/// impl BibFields for Fields {
+///     const FIELDS: &'static [FieldInfo] = &[FieldInfo::new("author").required()];
+///
+///     fn has_field(&self, name: &str) -> bool {
+///         name == "author" && self.author.is_some()
+///     }
+/// }
+///
+/// struct Book {
+///     entry_type: String,
+///     entry_key: String,
+///     fields: Fields,
+/// }
+///
+/// impl BibEntry for Book {
+///     type Fields = Fields;
+///     const ENTRY_TYPES: &'static [&'static str] = &["book", "mvbook"];
+///
+///     fn entry_type(&self) -> &str {
+///         &self.entry_type
+///     }
+///
+///     fn entry_key(&self) -> &str {
+///         &self.entry_key
+///     }
+///
+///     fn fields(&self) -> &Fields {
+///         &self.fields
+///     }
+/// }
+///
+/// let mut book = Book {
+///     entry_type: "Book".into(),
+///     entry_key: "knuth".into(),
+///     fields: Fields { author: None },
+/// };
+/// assert_eq!(book.check(), Err(EntryCheckError::MissingField("author")));
+///
+/// book.fields.author = Some("Knuth".into());
+/// assert_eq!(book.check(), Ok(()));
+///
+/// book.entry_type = "article".into();
+/// assert_eq!(
+///     book.check(),
+///     Err(EntryCheckError::EntryType("article".into()))
+/// );
+/// ```
+pub trait BibEntry {
+    /// The fields of the entry.
+    type Fields: BibFields;
+
+    /// The entry types which are accepted, compared case-insensitively. If empty, every entry
+    /// type is accepted.
+    const ENTRY_TYPES: &'static [&'static str] = &[];
+
+    /// The entry type.
+    fn entry_type(&self) -> &str;
+
+    /// The entry key.
+    fn entry_key(&self) -> &str;
+
+    /// The fields.
+    fn fields(&self) -> &Self::Fields;
+
+    /// Check that the entry type is accepted and that every required field has a value.
+    fn check(&self) -> Result<(), EntryCheckError> {
+        let entry_type = UniCase::new(self.entry_type());
+        if !Self::ENTRY_TYPES.is_empty()
+            && !Self::ENTRY_TYPES
+                .iter()
+                .any(|accepted| UniCase::new(*accepted) == entry_type)
+        {
+            return Err(EntryCheckError::EntryType(self.entry_type().to_owned()));
+        }
+        match self.fields().missing_fields().first() {
+            Some(key) => Err(EntryCheckError::MissingField(key)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The reason that an entry is not valid according to its [`BibEntry`] metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryCheckError {
+    /// The entry type is not one of the [accepted entry types](BibEntry::ENTRY_TYPES).
+    EntryType(String),
+    /// The required field with the given field key does not have a value.
+    MissingField(&'static str),
+}
+
+impl fmt::Display for EntryCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EntryType(entry_type) => write!(f, "unexpected entry type '{entry_type}'"),
+            Self::MissingField(key) => write!(f, "missing required field '{key}'"),
+        }
+    }
+}
+
+impl std::error::Error for EntryCheckError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fields {
+        doi: Option<String>,
+        title: Option<String>,
+        year: Option<u16>,
+    }
+
+    impl BibFields for Fields {
+        const FIELDS: &'static [FieldInfo] = &[
+            FieldInfo::new("doi").rename("DOI").verbatim(),
+            FieldInfo::new("title").required(),
+            FieldInfo::new("year").required(),
+        ];
+
+        fn has_field(&self, name: &str) -> bool {
+            match name {
+                "doi" => self.doi.is_some(),
+                "title" => self.title.is_some(),
+                "year" => self.year.is_some(),
+                _ => false,
+            }
+        }
+    }
+
+    struct Any(Fields);
+
+    impl BibEntry for Any {
+        type Fields = Fields;
+
+        fn entry_type(&self) -> &str {
+            "anything"
+        }
+
+        fn entry_key(&self) -> &str {
+            "key"
+        }
+
+        fn fields(&self) -> &Fields {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn test_bib_fields() {
+        assert_eq!(Fields::field_info("doi").unwrap().name, "doi");
+        assert_eq!(Fields::field_info("Title").unwrap().key, "title");
+        assert!(Fields::field_info("author").is_none());
+
+        let mut fields = Fields {
+            doi: None,
+            title: None,
+            year: None,
+        };
+        assert_eq!(fields.missing_fields(), vec!["title", "year"]);
+
+        fields.year = Some(2024);
+        let entry = Any(fields);
+        assert_eq!(entry.check(), Err(EntryCheckError::MissingField("title")));
+        assert_eq!(
+            entry.check().unwrap_err().to_string(),
+            "missing required field 'title'"
+        );
+    }
+}
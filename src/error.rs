@@ -1,5 +1,6 @@
 //! # Errors for serialization and deserialization.
 use std::io;
+use std::ops::Range;
 use std::result;
 use std::str::Utf8Error;
 
@@ -16,6 +17,8 @@ pub enum Category {
     Data,
     /// Unexpected end of input.
     Eof,
+    /// Deserialization was cancelled with a [`CancellationToken`](crate::de::CancellationToken).
+    Cancelled,
 }
 
 /// The main error type as used by [`de::Deserializer`](crate::de::Deserializer) and
@@ -24,6 +27,96 @@ pub enum Category {
 pub struct Error {
     /// The underlying error type.
     pub(crate) code: ErrorCode,
+    /// The byte offset in the input at which a syntax error occurred, if known.
+    pub(crate) position: Option<usize>,
+    /// The character at the position of a syntax error, if known.
+    pub(crate) found: Option<char>,
+    /// The key of the entry in which the error occurred, if known.
+    pub(crate) entry_key: Option<String>,
+    /// The key of the field in which the error occurred, if known.
+    pub(crate) field_key: Option<String>,
+}
+
+/// The kind of an [`Error`], as returned by [`Error::kind`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind<'a> {
+    /// The input is not valid BibTeX.
+    Syntax {
+        /// A description of what was expected.
+        expected: &'static str,
+        /// The character which was found instead. This is `None` at the end of the input, if the
+        /// input is not valid UTF-8 at the position of the error, or if the position is not
+        /// known.
+        found: Option<char>,
+        /// The byte range of the character which was found in the input, if known. This is empty
+        /// if no character was found.
+        span: Option<Range<usize>>,
+    },
+    /// The input ended unexpectedly.
+    UnexpectedEof {
+        /// A description of what was expected.
+        expected: &'static str,
+    },
+    /// A macro was not defined, so its value could not be converted to text.
+    UnresolvedMacro {
+        /// The name of the macro.
+        name: &'a str,
+    },
+    /// A field key appeared more than once in an entry.
+    DuplicateField {
+        /// The duplicated field key.
+        key: &'a str,
+        /// The byte range of the duplicate field key in the input.
+        span: Range<usize>,
+    },
+    /// Text between entries which is neither whitespace nor a comment, when junk is denied with
+    /// [`Deserializer::deny_junk`](crate::de::Deserializer::deny_junk).
+    Junk {
+        /// The byte range of the junk in the input, up to the next entry or the end of the line.
+        span: Range<usize>,
+    },
+    /// A field key was not expected by the type being deserialized, such as a struct with
+    /// `#[serde(deny_unknown_fields)]`.
+    UnknownField {
+        /// The unexpected field key.
+        key: &'a str,
+        /// The field keys which were expected.
+        expected: &'static [&'static str],
+    },
+    /// A macro was defined with different values in two macro dictionaries which were merged.
+    DuplicateMacro {
+        /// The name of the macro.
+        name: &'a str,
+    },
+    /// An entry key appeared more than once in bibliographies which were merged.
+    DuplicateKey {
+        /// The duplicated entry key.
+        key: &'a str,
+    },
+    /// The input begins with a byte order mark for an encoding other than UTF-8.
+    UnsupportedEncoding {
+        /// The name of the encoding, such as `UTF-16LE`.
+        encoding: &'static str,
+    },
+    /// Text was not valid UTF-8.
+    InvalidUtf8(&'a Utf8Error),
+    /// An error occurred while writing the output.
+    Io(&'a io::Error),
+    /// Deserialization was cancelled with a
+    /// [`CancellationToken`](crate::de::CancellationToken).
+    Cancelled,
+    /// The value could not be serialized as BibTeX.
+    Serialization {
+        /// A description of the problem.
+        message: &'a str,
+    },
+    /// A custom error, typically raised by a [`Deserialize`](serde::Deserialize) or
+    /// [`Serialize`](serde::Serialize) implementation.
+    Custom {
+        /// The error message.
+        message: &'a str,
+    },
 }
 
 /// An alias for a [`Result`](std::result::Result) with error type [`serde_bibtex::Error`](Error).
@@ -41,58 +134,245 @@ impl Error {
             | ErrorCode::InvalidStartOfEntry
             | ErrorCode::ExpectedFieldSep
             | ErrorCode::Empty
-            | ErrorCode::ExpectedEndOfEntry => Category::Syntax,
-            ErrorCode::UnclosedQuote | ErrorCode::UnexpectedEof | ErrorCode::UnclosedBracket => {
-                Category::Eof
-            }
+            | ErrorCode::ExpectedEndOfEntry
+            | ErrorCode::TrailingCharacters
+            | ErrorCode::TrailingCharactersAfterValue
+            | ErrorCode::Junk(_) => Category::Syntax,
+            ErrorCode::UnclosedQuote
+            | ErrorCode::UnexpectedEof
+            | ErrorCode::UnclosedBracket
+            | ErrorCode::ExpectedEntry => Category::Eof,
             ErrorCode::InvalidUtf8(_)
             | ErrorCode::UnexpandedMacro(_)
+            | ErrorCode::DuplicateField(..)
+            | ErrorCode::UnknownField(..)
+            | ErrorCode::DuplicateMacro(_)
+            | ErrorCode::DuplicateKey(_)
+            | ErrorCode::UnsupportedEncoding(_)
             | ErrorCode::InvalidSerializationFormat(_) => Category::Data,
             ErrorCode::Io(_) => Category::Io,
+            ErrorCode::Cancelled => Category::Cancelled,
+        }
+    }
+
+    /// The byte offset in the input at which a syntax error occurred, if known.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// The key of the entry in which the error occurred, if known.
+    /// ```
+    /// use serde::Deserialize;
+    /// use serde_bibtex::error::ErrorKind;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// #[serde(deny_unknown_fields)]
+    /// struct Fields {
+    ///     title: String,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// let input = "@book{Knuth1984, title = {The TeXbook}, yeer = 1984}";
+    /// let err = serde_bibtex::from_str::<Vec<Record>>(input).unwrap_err();
+    /// assert_eq!(err.entry_key(), Some("Knuth1984"));
+    /// assert!(matches!(err.kind(), ErrorKind::UnknownField { key: "yeer", .. }));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "unknown field `yeer`, expected `title` in entry `Knuth1984`"
+    /// );
+    /// ```
+    pub fn entry_key(&self) -> Option<&str> {
+        self.entry_key.as_deref()
+    }
+
+    /// The key of the field in which the error occurred, if known.
+    ///
+    /// This is only recorded for errors in a field value, so that an error such as an unknown field
+    /// key is not associated with a field.
+    /// ```
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Fields {
+    ///     year: String,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Record {
+    ///     fields: Fields,
+    /// }
+    ///
+    /// let input = "@book{Knuth1984, year = nineteen # 84}";
+    /// let err = serde_bibtex::from_str::<Vec<Record>>(input).unwrap_err();
+    /// assert_eq!(err.entry_key(), Some("Knuth1984"));
+    /// assert_eq!(err.field_key(), Some("year"));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "expected text, got unresolved macro nineteen in field `year` of entry `Knuth1984`"
+    /// );
+    /// ```
+    pub fn field_key(&self) -> Option<&str> {
+        self.field_key.as_deref()
+    }
+
+    /// The kind of the error, for matching on specific errors.
+    pub fn kind(&self) -> ErrorKind<'_> {
+        let syntax = |expected| ErrorKind::Syntax {
+            expected,
+            found: self.found,
+            span: self
+                .position
+                .map(|start| start..start + self.found.map_or(0, char::len_utf8)),
+        };
+        match &self.code {
+            ErrorCode::Message(message) => ErrorKind::Custom { message },
+            ErrorCode::VariableStartsWithDigit => {
+                syntax("variable which does not start with an ASCII digit")
+            }
+            ErrorCode::UnexpectedClosingBracket => syntax("balanced brackets"),
+            ErrorCode::ExpectedNextTokenOrEndOfField => {
+                syntax("another token or a field terminator")
+            }
+            ErrorCode::UnterminatedTextToken => syntax("closing bracket"),
+            ErrorCode::InvalidStartOfEntry => syntax("start of entry '{' or '('"),
+            ErrorCode::ExpectedEndOfEntry => syntax("end of entry"),
+            ErrorCode::ExpectedFieldSep => syntax("field separator '='"),
+            ErrorCode::Empty => syntax("identifier"),
+            ErrorCode::TrailingCharacters | ErrorCode::TrailingCharactersAfterValue => {
+                syntax("end of input")
+            }
+            ErrorCode::UnclosedBracket => ErrorKind::UnexpectedEof { expected: "'}'" },
+            ErrorCode::UnclosedQuote => ErrorKind::UnexpectedEof { expected: "'\"'" },
+            ErrorCode::UnexpectedEof => ErrorKind::UnexpectedEof {
+                expected: "more input",
+            },
+            ErrorCode::ExpectedEntry => ErrorKind::UnexpectedEof {
+                expected: "an entry",
+            },
+            ErrorCode::UnexpandedMacro(name) => ErrorKind::UnresolvedMacro { name },
+            ErrorCode::DuplicateField(key, span) => ErrorKind::DuplicateField {
+                key,
+                span: span.clone(),
+            },
+            ErrorCode::Junk(span) => ErrorKind::Junk { span: span.clone() },
+            ErrorCode::UnknownField(key, expected) => ErrorKind::UnknownField { key, expected },
+            ErrorCode::DuplicateMacro(name) => ErrorKind::DuplicateMacro { name },
+            ErrorCode::DuplicateKey(key) => ErrorKind::DuplicateKey { key },
+            ErrorCode::InvalidSerializationFormat(message) => ErrorKind::Serialization { message },
+            ErrorCode::InvalidUtf8(err) => ErrorKind::InvalidUtf8(err),
+            ErrorCode::UnsupportedEncoding(encoding) => ErrorKind::UnsupportedEncoding { encoding },
+            ErrorCode::Io(err) => ErrorKind::Io(err),
+            ErrorCode::Cancelled => ErrorKind::Cancelled,
         }
     }
 
+    #[inline]
+    fn new(code: ErrorCode) -> Self {
+        Self {
+            code,
+            position: None,
+            found: None,
+            entry_key: None,
+            field_key: None,
+        }
+    }
+
+    /// Record the position of a syntax error, unless a position was already recorded.
+    pub(crate) fn at(mut self, position: usize) -> Self {
+        if matches!(self.classify(), Category::Syntax | Category::Eof)
+            && !matches!(self.code, ErrorCode::Message(_))
+        {
+            self.position.get_or_insert(position);
+        }
+        self
+    }
+
+    /// Record the position of a syntax error along with the character found there, which is the
+    /// first character of `rest`, unless a position was already recorded.
+    pub(crate) fn at_found(mut self, position: usize, rest: &[u8]) -> Self {
+        if self.position.is_none() {
+            self = self.at(position);
+            if self.position.is_some() {
+                self.found = first_char(rest);
+            }
+        }
+        self
+    }
+
+    /// Record the key of the entry in which the error occurred, unless an entry key was already
+    /// recorded.
+    pub(crate) fn in_entry(mut self, entry_key: &str) -> Self {
+        self.entry_key.get_or_insert_with(|| entry_key.to_owned());
+        self
+    }
+
+    /// Record the key of the field in which the error occurred, unless a field key was already
+    /// recorded.
+    pub(crate) fn in_field(mut self, field_key: &str) -> Self {
+        self.field_key.get_or_insert_with(|| field_key.to_owned());
+        self
+    }
+
     #[inline]
     pub(crate) fn syntax(code: ErrorCode) -> Self {
-        Self { code }
+        Self::new(code)
     }
 
     #[inline]
     pub(crate) fn utf8(err: Utf8Error) -> Self {
-        Self {
-            code: ErrorCode::InvalidUtf8(err),
-        }
+        Self::new(ErrorCode::InvalidUtf8(err))
     }
 
     #[inline]
     pub(crate) fn ser(msg: String) -> Self {
-        Self {
-            code: ErrorCode::InvalidSerializationFormat(msg),
-        }
+        Self::new(ErrorCode::InvalidSerializationFormat(msg))
     }
 
     #[inline]
     pub(crate) fn io(err: io::Error) -> Self {
-        Self {
-            code: ErrorCode::Io(err),
-        }
+        Self::new(ErrorCode::Io(err))
+    }
+
+    #[inline]
+    pub(crate) fn duplicate_macro(name: String) -> Self {
+        Self::new(ErrorCode::DuplicateMacro(name))
+    }
+
+    #[inline]
+    #[cfg(feature = "entry")]
+    pub(crate) fn duplicate_key(key: String) -> Self {
+        Self::new(ErrorCode::DuplicateKey(key))
+    }
+
+    #[inline]
+    pub(crate) fn cancelled() -> Self {
+        Self::new(ErrorCode::Cancelled)
     }
 
     #[inline]
     pub(crate) fn eof() -> Self {
-        Self {
-            code: ErrorCode::UnexpectedEof,
-        }
+        Self::new(ErrorCode::UnexpectedEof)
     }
 }
 
+/// The first character of `bytes`, if `bytes` begins with valid UTF-8.
+fn first_char(bytes: &[u8]) -> Option<char> {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(s) => s,
+        Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default(),
+    };
+    valid.chars().next()
+}
+
 impl From<ConversionError> for Error {
     #[inline]
     fn from(value: ConversionError) -> Self {
         match value {
-            ConversionError::UnexpandedMacro(s) => Self {
-                code: ErrorCode::UnexpandedMacro(s),
-            },
+            ConversionError::UnexpandedMacro(s) => Self::new(ErrorCode::UnexpandedMacro(s)),
             ConversionError::InvalidUtf8(err) => Self::utf8(err),
         }
     }
@@ -101,9 +381,7 @@ impl From<ConversionError> for Error {
 impl From<Utf8Error> for Error {
     #[inline]
     fn from(err: Utf8Error) -> Self {
-        Self {
-            code: ErrorCode::InvalidUtf8(err),
-        }
+        Self::new(ErrorCode::InvalidUtf8(err))
     }
 }
 
@@ -119,6 +397,10 @@ impl serde::de::Error for Error {
     fn custom<T: std::fmt::Display>(msg: T) -> Self {
         Self::syntax(ErrorCode::Message(msg.to_string()))
     }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Self::new(ErrorCode::UnknownField(field.to_owned(), expected))
+    }
 }
 
 impl serde::ser::Error for Error {
@@ -129,7 +411,19 @@ impl serde::ser::Error for Error {
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.code.fmt(f)
+        self.code.fmt(f)?;
+        match (&self.field_key, &self.entry_key) {
+            (Some(field_key), Some(entry_key)) => {
+                write!(f, " in field `{field_key}` of entry `{entry_key}`")?
+            }
+            (Some(field_key), None) => write!(f, " in field `{field_key}`")?,
+            (None, Some(entry_key)) => write!(f, " in entry `{entry_key}`")?,
+            (None, None) => {}
+        }
+        if let Some(position) = self.position {
+            write!(f, " at byte {position}")?;
+        }
+        Ok(())
     }
 }
 
@@ -143,13 +437,25 @@ pub(crate) enum ErrorCode {
     UnterminatedTextToken,
     InvalidStartOfEntry,
     ExpectedEndOfEntry,
+    ExpectedEntry,
+    TrailingCharacters,
+    TrailingCharactersAfterValue,
+    Junk(Range<usize>),
     UnexpandedMacro(String),
+    DuplicateField(String, Range<usize>),
+    UnknownField(String, &'static [&'static str]),
+    DuplicateMacro(String),
+    // only constructed when merging bibliographies
+    #[cfg_attr(not(feature = "entry"), allow(dead_code))]
+    DuplicateKey(String),
     UnclosedBracket,
     UnclosedQuote,
     UnexpectedEof,
     ExpectedFieldSep,
     InvalidUtf8(Utf8Error),
+    UnsupportedEncoding(&'static str),
     Io(io::Error),
+    Cancelled,
     Empty,
 }
 
@@ -162,6 +468,9 @@ impl std::fmt::Display for ErrorCode {
             Self::UnexpectedClosingBracket => f.write_str("unmatched closing bracket"),
             Self::UnterminatedTextToken => f.write_str("unmatched opening bracket"),
             Self::InvalidUtf8(err) => err.fmt(f),
+            Self::UnsupportedEncoding(encoding) => {
+                write!(f, "unsupported encoding {encoding}: input must be UTF-8")
+            }
             Self::Empty => f.write_str("identifier missing or length 0"),
             Self::Message(msg) => f.write_str(msg),
             Self::UnexpectedEof => f.write_str("unexpected end of input"),
@@ -171,8 +480,34 @@ impl std::fmt::Display for ErrorCode {
             Self::UnclosedBracket => f.write_str("unclosed '{' in token"),
             Self::UnclosedQuote => f.write_str("unclosed '\"' in token"),
             Self::ExpectedEndOfEntry => f.write_str("expected end of entry"),
+            Self::ExpectedEntry => f.write_str("expected an entry"),
+            Self::TrailingCharacters => f.write_str("trailing characters after entry"),
+            Self::TrailingCharactersAfterValue => f.write_str("trailing characters after value"),
+            Self::Junk(_) => f.write_str("unexpected text between entries"),
             Self::Io(err) => write!(f, "IO error: {err}"),
+            Self::Cancelled => f.write_str("deserialization was cancelled"),
             Self::UnexpandedMacro(s) => write!(f, "expected text, got unresolved macro {s}"),
+            Self::DuplicateField(key, span) => write!(
+                f,
+                "duplicate field '{key}' at bytes {}..{}",
+                span.start, span.end
+            ),
+            Self::UnknownField(key, expected) => {
+                write!(f, "unknown field `{key}`, ")?;
+                match expected {
+                    [] => f.write_str("there are no fields"),
+                    [field] => write!(f, "expected `{field}`"),
+                    [first, rest @ ..] => {
+                        write!(f, "expected one of `{first}`")?;
+                        for field in rest {
+                            write!(f, ", `{field}`")?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            Self::DuplicateMacro(name) => write!(f, "conflicting definitions of macro '{name}'"),
+            Self::DuplicateKey(key) => write!(f, "duplicate entry key '{key}'"),
             Self::InvalidSerializationFormat(msg) => {
                 write!(f, "invalid serialization format: {msg}")
             }
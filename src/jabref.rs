@@ -0,0 +1,425 @@
+//! # JabRef library metadata
+//!
+//! [JabRef](https://www.jabref.org/) stores library metadata, such as the file directories and
+//! the groups tree, inside comment entries of the form
+//! ```bib
+//! @comment{jabref-meta: databaseType:bibtex;}
+//! @comment{jabref-meta: grouping:
+//! 0 AllEntriesGroup:;
+//! 1 StaticGroup:Reading\;0\;1\;\;\;\;;
+//! }
+//! ```
+//! This module parses the contents of such comments into a [`JabrefMetadata`], which can be
+//! inspected, modified, and converted back into comments.
+//! ```
+//! use serde::Deserialize;
+//! use serde_bibtex::{de::Deserializer, jabref::JabrefMetadata};
+//!
+//! #[derive(Deserialize)]
+//! enum Entry<'a> {
+//!     Regular,
+//!     Macro,
+//!     Preamble,
+//!     Comment(&'a str),
+//! }
+//!
+//! let input = r"
+//!     @comment{jabref-meta: fileDirectory:/home/user/papers;}
+//!     @comment{jabref-meta: grouping:
+//!     0 AllEntriesGroup:;
+//!     1 StaticGroup:Reading\;0\;1\;\;\;\;;
+//!     }
+//! ";
+//!
+//! let comments: Vec<&str> = Deserializer::from_str(input)
+//!     .into_iter::<Entry>()
+//!     .filter_map(|entry| match entry {
+//!         Ok(Entry::Comment(comment)) => Some(comment),
+//!         _ => None,
+//!     })
+//!     .collect();
+//!
+//! let metadata = JabrefMetadata::from_comments(comments);
+//! assert_eq!(metadata.file_directories[0].path, "/home/user/papers");
+//! assert_eq!(metadata.groups[1].name, "Reading");
+//! ```
+use std::fmt;
+
+/// The prefix of a JabRef metadata comment.
+const META_PREFIX: &str = "jabref-meta:";
+
+/// The metadata key of the groups tree.
+const GROUPING_KEY: &str = "grouping";
+
+/// The metadata key of the database type.
+const DATABASE_TYPE_KEY: &str = "databaseType";
+
+/// The metadata key of the file directories.
+const FILE_DIRECTORY_KEY: &str = "fileDirectory";
+
+/// A single JabRef metadata comment, such as `jabref-meta: fileDirectory:/home/user/papers;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetaEntry {
+    /// The metadata key, such as `fileDirectory`.
+    pub key: String,
+    /// The values, which are separated by unescaped `;` in the comment. Escape sequences are
+    /// kept as they appear in the comment.
+    pub values: Vec<String>,
+}
+
+impl MetaEntry {
+    /// Parse the contents of a comment entry, returning `None` if the comment is not a JabRef
+    /// metadata comment.
+    pub fn parse(comment: &str) -> Option<Self> {
+        let contents = comment.trim_start().strip_prefix(META_PREFIX)?;
+        let (key, contents) = contents.split_once(':')?;
+
+        let mut values = Vec::new();
+        let mut current = String::new();
+        let mut chars = contents.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    current.push(c);
+                    if let Some(next) = chars.next() {
+                        current.push(next);
+                    }
+                }
+                ';' => values.push(std::mem::take(&mut current).trim().to_owned()),
+                _ => current.push(c),
+            }
+        }
+        if !current.trim().is_empty() {
+            values.push(current.trim().to_owned());
+        }
+
+        Some(Self {
+            key: key.trim().to_owned(),
+            values,
+        })
+    }
+
+    /// The contents of a comment entry representing this metadata.
+    pub fn to_comment(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for MetaEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{META_PREFIX} {}:", self.key)?;
+        if self.values.len() > 1 {
+            for value in &self.values {
+                write!(f, "\n{value};")?;
+            }
+            f.write_str("\n")
+        } else {
+            for value in &self.values {
+                write!(f, "{value};")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// A file directory, as set by the `fileDirectory` metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDirectory {
+    /// The user and host to which the directory applies, if any, such as `alice-laptop` for the
+    /// `fileDirectory-alice-laptop` metadata key.
+    pub user: Option<String>,
+    /// The directory.
+    pub path: String,
+}
+
+/// A group in the JabRef groups tree.
+///
+/// For example, the line `1 StaticGroup:Reading\;0\;1\;\;\;\;` is a group at level `1` of kind
+/// `StaticGroup` with name `Reading` and properties `["0", "1", "", "", ""]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    /// The depth of the group in the tree, where the root is at level `0`.
+    pub level: usize,
+    /// The kind of the group, such as `StaticGroup` or `KeywordGroup`.
+    pub kind: String,
+    /// The name of the group.
+    pub name: String,
+    /// The remaining properties of the group, whose meaning depends on the kind of the group.
+    pub properties: Vec<String>,
+}
+
+impl Group {
+    /// Parse a single value of the `grouping` metadata.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (level, rest) = value.trim().split_once(' ')?;
+        let level = level.parse().ok()?;
+        let (kind, rest) = rest.split_once(':')?;
+
+        // The group fields are separated by `;`, and then escaped a second time as a metadata
+        // value.
+        let rest = unescape(rest);
+        let mut fields = Vec::new();
+        let mut current = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => current.extend(chars.next()),
+                ';' => fields.push(std::mem::take(&mut current)),
+                _ => current.push(c),
+            }
+        }
+        if !current.is_empty() {
+            fields.push(current);
+        }
+
+        let mut fields = fields.into_iter();
+        Some(Self {
+            level,
+            kind: kind.to_owned(),
+            name: fields.next().unwrap_or_default(),
+            properties: fields.collect(),
+        })
+    }
+}
+
+/// Remove one level of `\` escapes.
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Escape `\` and `;` with a `\`.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | ';') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl fmt::Display for Group {
+    /// Write the group as a value of the `grouping` metadata.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}:", self.level, self.kind)?;
+        if self.name.is_empty() && self.properties.is_empty() {
+            return Ok(());
+        }
+        let mut fields = String::new();
+        for field in std::iter::once(&self.name).chain(&self.properties) {
+            fields.push_str(&escape(field));
+            fields.push(';');
+        }
+        f.write_str(&escape(&fields))
+    }
+}
+
+/// A node in the groups tree, as returned by [`JabrefMetadata::group_tree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupNode<'a> {
+    /// The group.
+    pub group: &'a Group,
+    /// The subgroups of the group.
+    pub children: Vec<GroupNode<'a>>,
+}
+
+/// The JabRef metadata of a library.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JabrefMetadata {
+    /// The database type, such as `bibtex` or `biblatex`.
+    pub database_type: Option<String>,
+    /// The file directories.
+    pub file_directories: Vec<FileDirectory>,
+    /// The groups, in the order in which they appear in the groups tree.
+    pub groups: Vec<Group>,
+    /// Any other metadata, which is preserved but not interpreted.
+    pub other: Vec<MetaEntry>,
+}
+
+impl JabrefMetadata {
+    /// Collect the metadata from the contents of comment entries. Comments which are not JabRef
+    /// metadata comments are ignored.
+    pub fn from_comments<'a, I: IntoIterator<Item = &'a str>>(comments: I) -> Self {
+        let mut metadata = Self::default();
+        for entry in comments.into_iter().filter_map(MetaEntry::parse) {
+            metadata.insert(entry);
+        }
+        metadata
+    }
+
+    /// Interpret a single metadata comment.
+    pub fn insert(&mut self, entry: MetaEntry) {
+        if entry.key == DATABASE_TYPE_KEY && entry.values.len() == 1 {
+            self.database_type = entry.values.into_iter().next();
+        } else if let Some(user) = entry.key.strip_prefix(FILE_DIRECTORY_KEY) {
+            let user = match user.strip_prefix('-') {
+                Some(user) => Some(user.to_owned()),
+                None if user.is_empty() => None,
+                None => return self.other.push(entry),
+            };
+            for path in entry.values {
+                self.file_directories.push(FileDirectory {
+                    user: user.clone(),
+                    path,
+                });
+            }
+        } else if entry.key == GROUPING_KEY {
+            match entry.values.iter().map(|v| Group::parse(v)).collect() {
+                Some(groups) => self.groups = groups,
+                None => self.other.push(entry),
+            }
+        } else {
+            self.other.push(entry);
+        }
+    }
+
+    /// The groups arranged as a tree, returning the top-level groups.
+    ///
+    /// A group is a child of the closest preceding group with a smaller level.
+    pub fn group_tree(&self) -> Vec<GroupNode<'_>> {
+        fn children<'a>(
+            groups: &mut std::iter::Peekable<std::slice::Iter<'a, Group>>,
+            level: usize,
+        ) -> Vec<GroupNode<'a>> {
+            let mut nodes = Vec::new();
+            while let Some(group) = groups.next_if(|group| group.level >= level) {
+                nodes.push(GroupNode {
+                    group,
+                    children: children(groups, group.level + 1),
+                });
+            }
+            nodes
+        }
+
+        children(&mut self.groups.iter().peekable(), 0)
+    }
+
+    /// The metadata as comment entries, which can be written back to the library.
+    pub fn to_meta_entries(&self) -> Vec<MetaEntry> {
+        let mut entries = Vec::new();
+        if let Some(database_type) = &self.database_type {
+            entries.push(MetaEntry {
+                key: DATABASE_TYPE_KEY.to_owned(),
+                values: vec![database_type.clone()],
+            });
+        }
+        for directory in &self.file_directories {
+            entries.push(MetaEntry {
+                key: match &directory.user {
+                    Some(user) => format!("{FILE_DIRECTORY_KEY}-{user}"),
+                    None => FILE_DIRECTORY_KEY.to_owned(),
+                },
+                values: vec![directory.path.clone()],
+            });
+        }
+        if !self.groups.is_empty() {
+            entries.push(MetaEntry {
+                key: GROUPING_KEY.to_owned(),
+                values: self.groups.iter().map(Group::to_string).collect(),
+            });
+        }
+        entries.extend(self.other.iter().cloned());
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_entry() {
+        let entry = MetaEntry::parse(" jabref-meta: fileDirectory:/a\\;b;").unwrap();
+        assert_eq!(entry.key, "fileDirectory");
+        assert_eq!(entry.values, vec!["/a\\;b"]);
+        assert_eq!(entry.to_comment(), "jabref-meta: fileDirectory:/a\\;b;");
+
+        assert_eq!(
+            MetaEntry::parse("jabref-meta: saveActions:disabled;\nall-text-fields[]\n;")
+                .unwrap()
+                .values,
+            vec!["disabled", "all-text-fields[]"]
+        );
+        assert!(MetaEntry::parse("not metadata").is_none());
+        assert!(MetaEntry::parse("jabref-meta: no separator").is_none());
+    }
+
+    #[test]
+    fn test_group() {
+        let group = Group::parse(r"1 KeywordGroup:Foo\;0\;keywords\;a\\\;b\;0\;").unwrap();
+        assert_eq!(group.level, 1);
+        assert_eq!(group.kind, "KeywordGroup");
+        assert_eq!(group.name, "Foo");
+        assert_eq!(group.properties, vec!["0", "keywords", "a;b", "0"]);
+        assert_eq!(
+            group.to_string(),
+            r"1 KeywordGroup:Foo\;0\;keywords\;a\\\;b\;0\;"
+        );
+
+        let group = Group::parse(r"1 StaticGroup:Reading\;0\;1\;\;\;\;").unwrap();
+        assert_eq!(group.name, "Reading");
+        assert_eq!(group.properties, vec!["0", "1", "", "", ""]);
+
+        let root = Group::parse("0 AllEntriesGroup:").unwrap();
+        assert_eq!(root.name, "");
+        assert_eq!(root.to_string(), "0 AllEntriesGroup:");
+
+        assert!(Group::parse("x StaticGroup:A").is_none());
+        assert!(Group::parse("1StaticGroup:A").is_none());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let comments = [
+            "jabref-meta: databaseType:biblatex;",
+            "jabref-meta: fileDirectory:/papers;",
+            "jabref-meta: fileDirectory-alice-laptop:/home/alice;",
+            "jabref-meta: grouping:\n0 AllEntriesGroup:;\n1 StaticGroup:A\\;0\\;;\n2 StaticGroup:B\\;0\\;;\n1 StaticGroup:C\\;0\\;;\n",
+            "jabref-meta: saveOrderConfig:specified;author;false;",
+            "an ordinary comment",
+        ];
+        let metadata = JabrefMetadata::from_comments(comments);
+        assert_eq!(metadata.database_type.as_deref(), Some("biblatex"));
+        assert_eq!(
+            metadata.file_directories,
+            vec![
+                FileDirectory {
+                    user: None,
+                    path: "/papers".into()
+                },
+                FileDirectory {
+                    user: Some("alice-laptop".into()),
+                    path: "/home/alice".into()
+                },
+            ]
+        );
+        assert_eq!(metadata.groups.len(), 4);
+        assert_eq!(metadata.other.len(), 1);
+
+        let tree = metadata.group_tree();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].children.len(), 2);
+        assert_eq!(tree[0].children[0].group.name, "A");
+        assert_eq!(tree[0].children[0].children[0].group.name, "B");
+        assert_eq!(tree[0].children[1].group.name, "C");
+
+        let comments: Vec<String> = metadata
+            .to_meta_entries()
+            .iter()
+            .map(MetaEntry::to_comment)
+            .collect();
+        assert_eq!(
+            JabrefMetadata::from_comments(comments.iter().map(String::as_str)),
+            metadata
+        );
+    }
+}
@@ -116,13 +116,16 @@
 //! BibTeX bibliography in your own code. This module also contains a variety of methods for
 //! checking if some given input satisfies the requirements of a certain BibTeX component.
 //! ```
-//! use serde_bibtex::token::{check_variable, is_variable, TokenError, Variable};
+//! use serde_bibtex::token::{check_variable, is_variable, TokenErrorKind, Variable};
 //!
 //! // `var` is a valid variable name
 //! assert!(Variable::new("var").is_ok());
 //!
 //! // A variable cannot contain a character in `{}(),=\#%"`
-//! assert_eq!(check_variable("var{"), Err(TokenError::InvalidChar('{')));
+//! assert_eq!(
+//!     check_variable("var{"),
+//!     Err(TokenErrorKind::InvalidChar { ch: '{', index: 3 })
+//! );
 //!
 //! // A variable cannot be empty
 //! assert!(!is_variable(""));
@@ -130,11 +133,13 @@
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+pub mod citations;
 pub mod de;
 #[cfg(feature = "entry")]
 #[cfg_attr(docsrs, doc(cfg(feature = "entry")))]
 pub mod entry;
 pub mod error;
+pub mod jabref;
 pub(crate) mod naming;
 pub(crate) mod parse;
 pub mod ser;
@@ -142,6 +147,13 @@ pub mod ser;
 #[cfg_attr(docsrs, doc(cfg(feature = "syntax")))]
 pub mod syntax;
 pub mod token;
+#[cfg(feature = "entry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "entry")))]
+pub mod transform;
+pub mod validate;
+#[cfg(feature = "entry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "entry")))]
+pub mod value;
 
 use std::io;
 
@@ -150,7 +162,9 @@ use serde::{Deserialize, Serialize};
 use crate::{de::Deserializer, ser::Serializer};
 pub use crate::{
     error::{Error, Result},
-    parse::{MacroDictionary, Read, SliceReader, StrReader},
+    parse::{
+        ConflictPolicy, LineEnding, MacroDictionary, Read, SliceReader, StrReader, TexComments,
+    },
 };
 
 /// Deserialize an instance of type `D` from string of BibTeX.
@@ -163,6 +177,45 @@ where
     D::deserialize(&mut deserializer)
 }
 
+/// Deserialize an instance of type `D` from a string of BibTeX containing exactly one entry.
+///
+/// Unlike [`from_str`], `D` is the type of the entry itself rather than a sequence of entries.
+/// Only whitespace and comments may follow the entry: see [`Deserializer::single_entry`].
+/// ```
+/// use serde::Deserialize;
+/// use serde_bibtex::from_entry_str;
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Record<'a> {
+///     entry_type: &'a str,
+///     entry_key: &'a str,
+///     fields: Fields<'a>,
+/// }
+///
+/// #[derive(Debug, Deserialize, PartialEq)]
+/// struct Fields<'a> {
+///     title: &'a str,
+/// }
+///
+/// let record: Record = from_entry_str("@article{key, title = {Title}}").unwrap();
+/// assert_eq!(
+///     record,
+///     Record {
+///         entry_type: "article",
+///         entry_key: "key",
+///         fields: Fields { title: "Title" },
+///     }
+/// );
+///
+/// assert!(from_entry_str::<Record>("@article{a, title = {A}}\n@article{b, title = {B}}").is_err());
+/// ```
+pub fn from_entry_str<'r, D>(s: &'r str) -> Result<D>
+where
+    D: Deserialize<'r>,
+{
+    Deserializer::from_str(s).single_entry()
+}
+
 /// Deserialize an instance of type `D` from bytes of BibTeX.
 pub fn from_bytes<'r, D>(s: &'r [u8]) -> Result<D>
 where
@@ -241,6 +294,13 @@ where
     Ok(writer)
 }
 
+/// Convert serialized output into a string, which fails only if text was serialized as bytes
+/// which are not valid UTF-8.
+fn into_string(vec: Vec<u8>) -> Result<String> {
+    String::from_utf8(vec)
+        .map_err(|_| Error::ser("serialized output is not valid UTF-8".to_owned()))
+}
+
 /// Serialize as BibTeX into a string.
 #[inline]
 pub fn to_string<T>(value: &T) -> Result<String>
@@ -248,11 +308,36 @@ where
     T: ?Sized + Serialize,
 {
     let vec = to_vec(value)?;
-    let string = unsafe {
-        // We do not emit invalid UTF-8.
-        String::from_utf8_unchecked(vec)
-    };
-    Ok(string)
+    into_string(vec)
+}
+
+/// Serialize a single entry as BibTeX into a string.
+///
+/// Unlike [`to_string`], `value` is the entry itself rather than a sequence of entries. See
+/// [`Serializer::serialize_entry`] for more detail.
+pub fn to_string_entry<T>(value: &T) -> Result<String>
+where
+    T: ?Sized + Serialize,
+{
+    let mut ser = Serializer::new(Vec::with_capacity(128));
+    ser.serialize_entry(value)?;
+    into_string(ser.into_inner())
+}
+
+/// Serialize as BibTeX into a string, preceded by the macros in the dictionary as `@string`
+/// entries.
+///
+/// See [`Serializer::write_macros`] for more detail.
+pub fn to_string_with_macros<S, B, T>(macros: &MacroDictionary<S, B>, value: &T) -> Result<String>
+where
+    S: AsRef<str> + Eq,
+    B: AsRef<[u8]>,
+    T: ?Sized + Serialize,
+{
+    let mut ser = Serializer::new(Vec::with_capacity(128));
+    ser.write_macros(macros)?;
+    value.serialize(&mut ser)?;
+    into_string(ser.into_inner())
 }
 
 /// Serialize the given data structure as BibTeX into a string without checking that the output is
@@ -263,11 +348,7 @@ where
     T: ?Sized + Serialize,
 {
     let vec = to_vec_unchecked(value)?;
-    let string = unsafe {
-        // We do not emit invalid UTF-8.
-        String::from_utf8_unchecked(vec)
-    };
-    Ok(string)
+    into_string(vec)
 }
 
 /// Serialize the given data structure as BibTeX into a string with no extra whitespace.
@@ -277,9 +358,5 @@ where
     T: ?Sized + Serialize,
 {
     let vec = to_vec_compact(value)?;
-    let string = unsafe {
-        // We do not emit invalid UTF-8.
-        String::from_utf8_unchecked(vec)
-    };
-    Ok(string)
+    into_string(vec)
 }
@@ -9,3 +9,5 @@ pub const REGULAR_ENTRY_VARIANT_NAME: &str = "Regular";
 pub const MACRO_ENTRY_VARIANT_NAME: &str = "Macro";
 pub const COMMENT_ENTRY_VARIANT_NAME: &str = "Comment";
 pub const PREAMBLE_ENTRY_VARIANT_NAME: &str = "Preamble";
+
+pub const RAW_VALUE_NAME: &str = "$serde_bibtex::private::RawValue";
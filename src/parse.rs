@@ -1,19 +1,50 @@
 mod macros;
 mod read;
 
+use std::ops::Range;
+
 use crate::error::{Error, ErrorCode, Result};
 
 use crate::token::{EntryKey, EntryType, FieldKey, Text, Token, Variable};
-pub use macros::MacroDictionary;
-pub use read::{Read, SliceReader, StrReader};
+pub use macros::{ConflictPolicy, MacroDictionary};
+pub use read::{LineEnding, Read, Repair, SliceReader, StrReader, TexComments};
 
 pub trait BibtexParse<'r>: Read<'r> {
+    /// The bytes of the input in the given range.
+    fn source(&self, span: Range<usize>) -> &'r [u8];
+
+    /// The line ending used by the input.
+    fn line_ending(&self) -> LineEnding;
+
+    /// The length of the input in bytes.
+    fn input_len(&self) -> usize;
+
+    /// Record the current position in the input, and the character found there, on a syntax
+    /// error.
+    fn locate(&self, err: Error) -> Error {
+        let position = self.position();
+        // a character is at most 4 bytes long
+        let end = self.input_len().min(position + 4);
+        err.at_found(position, self.source(position..end))
+    }
+
     /// Read the entry type, returning None if EOF was reached.
     fn entry_type(&mut self) -> Result<Option<EntryType<&'r str>>> {
-        if self.next_entry_or_eof() {
+        Ok(self
+            .entry_type_with_start()?
+            .map(|(_, entry_type)| entry_type))
+    }
+
+    /// Read the entry type along with the position of the `@` which starts the entry, returning
+    /// None if EOF was reached.
+    fn entry_type_with_start(&mut self) -> Result<Option<(usize, EntryType<&'r str>)>> {
+        self.byte_order_mark()?;
+        if self.next_entry_or_eof()? {
+            // `next_entry_or_eof` consumes the `@`
+            let start = self.position() - 1;
             self.comment();
             let id = self.identifier()?;
-            Ok(Some(id.into()))
+            Ok(Some((start, id.into())))
         } else {
             Ok(None)
         }
@@ -114,15 +145,11 @@ pub trait BibtexParse<'r>: Read<'r> {
         match self.peek() {
             Some(b'{') => {
                 self.discard();
-                let result = self.balanced()?;
-                self.expect(b'}', Error::syntax(ErrorCode::UnclosedBracket))?;
-                Ok(Some(Token::Text(result)))
+                Ok(Some(Token::Text(self.text_token(b'}')?)))
             }
             Some(b'"') => {
                 self.discard();
-                let result = self.protected(b'"')?;
-                self.expect(b'"', Error::syntax(ErrorCode::UnclosedQuote))?;
-                Ok(Some(Token::Text(result)))
+                Ok(Some(Token::Text(self.text_token(b'"')?)))
             }
             Some(b'0'..=b'9') => Ok(Some(Token::Text(Text::Str(self.number()?)))),
             Some(_) => Ok(Some(Token::Variable(self.identifier()?.into()))),
@@ -130,6 +157,35 @@ pub trait BibtexParse<'r>: Read<'r> {
         }
     }
 
+    /// Skip a token without constructing it, returning false if there are no more tokens.
+    fn skip_token(&mut self, is_first_token: &mut bool) -> Result<bool> {
+        if *is_first_token {
+            *is_first_token = false;
+        } else if !self.next_token_or_end()? {
+            return Ok(false);
+        }
+
+        self.comment();
+        match self.peek() {
+            Some(b'{') => {
+                self.discard();
+                self.text_token(b'}')?;
+            }
+            Some(b'"') => {
+                self.discard();
+                self.text_token(b'"')?;
+            }
+            Some(b'0'..=b'9') => {
+                self.number()?;
+            }
+            Some(_) => {
+                self.identifier()?;
+            }
+            _ => return Err(Error::eof()),
+        }
+        Ok(true)
+    }
+
     /// Parse a comma and field key together to determine if there is another field.
     fn field_or_terminal(&mut self) -> Result<Option<FieldKey<&'r str>>> {
         self.comment();
@@ -166,15 +222,23 @@ pub trait BibtexParse<'r>: Read<'r> {
         Ok(())
     }
 
-    /// Read tokens until there are no more remaining in the buffer.
-    fn value_into(&mut self, scratch: &mut Vec<Token<&'r str, &'r [u8]>>) -> Result<()> {
+    /// Read tokens until there are no more remaining in the buffer, returning the source of the
+    /// value without surrounding whitespace and comments.
+    fn value_into(&mut self, scratch: &mut Vec<Token<&'r str, &'r [u8]>>) -> Result<&'r [u8]> {
         scratch.clear();
         let mut is_first_token = true;
+        self.comment();
+        let start = self.position();
+        let mut end = start;
 
         while let Some(token) = self.token(&mut is_first_token)? {
-            scratch.push(token)
+            if self.is_cancelled() {
+                return Err(Error::cancelled());
+            }
+            scratch.push(token);
+            end = self.position();
         }
-        Ok(())
+        Ok(self.source(start..end))
     }
 
     /// Ignore an entire bibliography, while still checking validity.
@@ -195,20 +259,6 @@ pub trait BibtexParse<'r>: Read<'r> {
         }
     }
 
-    /// Ignore a single entry, but capture any macros.
-    fn ignore_entry_captured(
-        &mut self,
-        chunk: EntryType<&'r str>,
-        abbrevs: &mut MacroDictionary<&'r str, &'r [u8]>,
-    ) -> Result<()> {
-        match chunk {
-            EntryType::Preamble => self.ignore_preamble(),
-            EntryType::Comment => self.ignore_comment(),
-            EntryType::Macro => self.ignore_macro_captured(abbrevs),
-            EntryType::Regular(_) => self.ignore_regular_entry(),
-        }
-    }
-
     /// Ignore the contents of a comment.
     fn ignore_comment(&mut self) -> Result<()> {
         let _ = self.comment_contents()?;
@@ -271,7 +321,11 @@ pub trait BibtexParse<'r>: Read<'r> {
     /// Ignore a single value for a field.
     fn ignore_value(&mut self) -> Result<()> {
         let mut is_first_token = true;
-        while (self.token(&mut is_first_token)?).is_some() {}
+        while self.skip_token(&mut is_first_token)? {
+            if self.is_cancelled() {
+                return Err(Error::cancelled());
+            }
+        }
         Ok(())
     }
 }
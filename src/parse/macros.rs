@@ -1,35 +1,69 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use super::{Token, Variable};
+use super::{Text, Token, Variable};
+use crate::error::{Error, Result};
+use crate::token::ToOwnedToken;
 
 /// A dictionary used to expand uncaptured macros during deserialization.
 ///
 /// See the [macro expansion and capturing](de/index.html#manual-capturing) section
 /// for more details on how to use a macro dictionary during deserialization.
+///
+/// The dictionary remembers the order in which macros were first defined, which is the order used
+/// by [`MacroDictionary::iter`].
 #[derive(Debug, Clone)]
 pub struct MacroDictionary<S: AsRef<str>, B: AsRef<[u8]>> {
-    map: HashMap<Variable<S>, Vec<Token<S, B>>>,
+    map: HashMap<Variable<S>, Definition<S, B>>,
+    next_index: usize,
     scratch: Vec<Token<S, B>>,
 }
 
+/// The tokens associated with a variable, along with the insertion index of the variable.
+type Definition<S, B> = (usize, Vec<Token<S, B>>);
+
+/// How to handle a macro which is defined in both dictionaries passed to
+/// [`MacroDictionary::merge`].
+///
+/// Macros are only considered to conflict if their values differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Keep the value in the dictionary being merged into.
+    KeepExisting,
+    /// Replace the value with the value from the other dictionary.
+    Overwrite,
+    /// Return an error, without modifying the dictionary.
+    Error,
+}
+
 impl<S: AsRef<str>, B: AsRef<[u8]>> Default for MacroDictionary<S, B> {
     fn default() -> Self {
-        Self::new(HashMap::default())
+        Self {
+            map: HashMap::default(),
+            next_index: 0,
+            scratch: Vec::default(),
+        }
     }
 }
 
 impl<S: AsRef<str>, B: AsRef<[u8]>> MacroDictionary<S, B> {
-    /// Construct a new macro dictionary from the provided lookup table.
-    pub fn new(map: HashMap<Variable<S>, Vec<Token<S, B>>>) -> Self {
-        Self {
-            map,
-            scratch: Vec::default(),
-        }
+    /// The number of macros in the dictionary.
+    pub fn len(&self) -> usize {
+        self.map.len()
     }
 
-    /// Recover the internal lookup table.
-    pub fn into_inner(self) -> HashMap<Variable<S>, Vec<Token<S, B>>> {
-        self.map
+    /// Whether the dictionary contains no macros.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterate over the macros in the order in which they were first inserted.
+    pub fn iter(&self) -> impl Iterator<Item = (&Variable<S>, &[Token<S, B>])> {
+        let mut entries: Vec<_> = self.map.iter().collect();
+        entries.sort_unstable_by_key(|(_, (idx, _))| *idx);
+        entries
+            .into_iter()
+            .map(|(variable, (_, tokens))| (variable, tokens.as_slice()))
     }
 }
 
@@ -42,14 +76,35 @@ where
     ///
     /// Note that this clones the underlying values even if they are already owned.
     pub fn own(&self) -> MacroDictionary<String, Vec<u8>> {
-        let new_map = HashMap::from_iter(self.map.iter().map(|(variable, val)| {
-            (
-                Variable::new_unchecked(variable.as_ref().to_string()),
-                val.iter().map(|t| Token::<S, B>::own(t)).collect(),
-            )
-        }));
+        MacroDictionary {
+            map: HashMap::from_iter(self.map.iter().map(|(variable, (idx, val))| {
+                (
+                    variable.to_owned_token(),
+                    (*idx, val.iter().map(ToOwnedToken::to_owned_token).collect()),
+                )
+            })),
+            next_index: self.next_index,
+            scratch: Vec::new(),
+        }
+    }
+}
 
-        MacroDictionary::new(new_map)
+impl<'a> From<MacroDictionary<&'a str, &'a [u8]>> for MacroDictionary<String, Vec<u8>> {
+    fn from(dict: MacroDictionary<&'a str, &'a [u8]>) -> Self {
+        Self {
+            map: dict
+                .map
+                .into_iter()
+                .map(|(variable, (idx, val))| {
+                    (
+                        variable.into(),
+                        (idx, val.into_iter().map(Into::into).collect()),
+                    )
+                })
+                .collect(),
+            next_index: dict.next_index,
+            scratch: Vec::new(),
+        }
     }
 }
 
@@ -89,17 +144,167 @@ where
     S: AsRef<str> + Eq + std::hash::Hash,
     B: AsRef<[u8]>,
 {
+    /// Construct a new macro dictionary from the provided lookup table.
+    ///
+    /// The insertion order of the macros is the iteration order of the lookup table.
+    pub fn new(map: HashMap<Variable<S>, Vec<Token<S, B>>>) -> Self {
+        let next_index = map.len();
+        Self {
+            map: map
+                .into_iter()
+                .enumerate()
+                .map(|(idx, (variable, tokens))| (variable, (idx, tokens)))
+                .collect(),
+            next_index,
+            scratch: Vec::default(),
+        }
+    }
+
+    /// Recover the internal lookup table.
+    pub fn into_inner(self) -> HashMap<Variable<S>, Vec<Token<S, B>>> {
+        self.map
+            .into_iter()
+            .map(|(variable, (_, tokens))| (variable, tokens))
+            .collect()
+    }
+
     pub(crate) fn insert_raw_tokens(
         &mut self,
         identifier: Variable<S>,
         tokens: Vec<Token<S, B>>,
     ) -> Option<Vec<Token<S, B>>> {
-        self.map.insert(identifier, tokens)
+        match self.map.get_mut(&identifier) {
+            Some((_, existing)) => Some(std::mem::replace(existing, tokens)),
+            None => {
+                self.map.insert(identifier, (self.next_index, tokens));
+                self.next_index += 1;
+                None
+            }
+        }
     }
 
     /// Get the tokens associated with an identifier.
     pub fn get(&self, identifier: &Variable<S>) -> Option<&[Token<S, B>]> {
-        self.map.get(identifier).map(|v| v.as_slice())
+        self.map.get(identifier).map(|(_, v)| v.as_slice())
+    }
+
+    /// Get the text associated with an identifier, recursively expanding any variables and
+    /// concatenating the resulting text.
+    ///
+    /// Returns `None` if the identifier is not defined, if the expansion refers to an undefined
+    /// variable or contains a cycle, or if the text is not valid UTF-8. The result only borrows
+    /// from the dictionary if the value is a single text token.
+    /// ```
+    /// use serde_bibtex::MacroDictionary;
+    /// use serde_bibtex::token::{Token, Variable};
+    ///
+    /// let mut macros = MacroDictionary::<&str, &[u8]>::default();
+    /// macros.insert(Variable::new("a").unwrap(), vec![Token::str("A").unwrap()]);
+    /// macros.insert(
+    ///     Variable::new("b").unwrap(),
+    ///     vec![Token::variable("a").unwrap(), Token::str("B").unwrap()],
+    /// );
+    ///
+    /// assert_eq!(macros.get_expanded(&Variable::new("b").unwrap()).unwrap(), "AB");
+    /// assert_eq!(macros.get_expanded(&Variable::new("c").unwrap()), None);
+    /// ```
+    pub fn get_expanded(&self, identifier: &Variable<S>) -> Option<Cow<'_, str>> {
+        let (variable, (_, tokens)) = self.map.get_key_value(identifier)?;
+        if let [Token::Text(text)] = tokens.as_slice() {
+            return text_as_str(text).map(Cow::Borrowed);
+        }
+
+        let mut expanded = String::new();
+        let mut stack = vec![variable];
+        self.expand_into(tokens, &mut expanded, &mut stack)?;
+        Some(Cow::Owned(expanded))
+    }
+
+    /// Append the expansion of `tokens` to `expanded`, where `stack` contains the variables
+    /// currently being expanded.
+    fn expand_into<'a>(
+        &'a self,
+        tokens: &'a [Token<S, B>],
+        expanded: &mut String,
+        stack: &mut Vec<&'a Variable<S>>,
+    ) -> Option<()> {
+        for token in tokens {
+            match token {
+                Token::Text(text) => expanded.push_str(text_as_str(text)?),
+                Token::Variable(variable) => {
+                    if stack.contains(&variable) {
+                        return None;
+                    }
+                    let (_, sub) = self.map.get(variable)?;
+                    stack.push(variable);
+                    self.expand_into(sub, expanded, stack)?;
+                    stack.pop();
+                }
+            }
+        }
+        Some(())
+    }
+}
+
+fn text_as_str<S: AsRef<str>, B: AsRef<[u8]>>(text: &Text<S, B>) -> Option<&str> {
+    match text {
+        Text::Str(s) => Some(s.as_ref()),
+        Text::Bytes(b) => std::str::from_utf8(b.as_ref()).ok(),
+    }
+}
+
+impl<S, B> MacroDictionary<S, B>
+where
+    S: AsRef<str> + Eq + std::hash::Hash,
+    B: AsRef<[u8]> + PartialEq,
+{
+    /// Merge the macros from another dictionary into this dictionary, using the policy to handle
+    /// macros which are defined in both dictionaries with different values.
+    ///
+    /// Macros from `other` which are new to this dictionary are inserted in the order in which
+    /// they were inserted into `other`. The tokens are inserted as-is, without resolving variables.
+    /// ```
+    /// use serde_bibtex::{ConflictPolicy, MacroDictionary};
+    /// use serde_bibtex::token::{Token, Variable};
+    ///
+    /// let mut first = MacroDictionary::<&str, &[u8]>::default();
+    /// first.insert(Variable::new("a").unwrap(), vec![Token::str("1").unwrap()]);
+    ///
+    /// let mut second = MacroDictionary::default();
+    /// second.insert(Variable::new("A").unwrap(), vec![Token::str("2").unwrap()]);
+    /// second.insert(Variable::new("b").unwrap(), vec![Token::str("3").unwrap()]);
+    ///
+    /// assert!(first.clone().merge(second.clone(), ConflictPolicy::Error).is_err());
+    ///
+    /// first.merge(second, ConflictPolicy::KeepExisting).unwrap();
+    /// assert_eq!(
+    ///     first.iter().map(|(var, _)| var.as_ref()).collect::<Vec<_>>(),
+    ///     vec!["a", "b"]
+    /// );
+    /// assert_eq!(
+    ///     first.get(&Variable::new("a").unwrap()),
+    ///     Some(&[Token::str("1").unwrap()][..])
+    /// );
+    /// ```
+    pub fn merge(&mut self, other: Self, policy: ConflictPolicy) -> Result<()> {
+        if policy == ConflictPolicy::Error {
+            if let Some((variable, _)) = other.map.iter().find(|(variable, (_, tokens))| {
+                self.get(variable)
+                    .is_some_and(|existing| existing != tokens.as_slice())
+            }) {
+                return Err(Error::duplicate_macro(variable.as_ref().to_owned()));
+            }
+        }
+
+        let mut entries: Vec<_> = other.map.into_iter().collect();
+        entries.sort_unstable_by_key(|(_, (idx, _))| *idx);
+        for (variable, (_, tokens)) in entries {
+            if policy == ConflictPolicy::KeepExisting && self.map.contains_key(&variable) {
+                continue;
+            }
+            self.insert_raw_tokens(variable, tokens);
+        }
+        Ok(())
     }
 }
 
@@ -119,20 +324,40 @@ where
 
     /// Resolve tokens in-place using the macros stored in the dictionary.
     pub fn resolve(&mut self, tokens: &mut Vec<Token<S, B>>) {
-        self.scratch.clear();
+        let mut scratch = std::mem::take(&mut self.scratch);
+        self.resolve_with(tokens, &mut scratch);
+        self.scratch = scratch;
+    }
+
+    /// Resolve tokens in-place, using `scratch` as temporary storage so that the dictionary can
+    /// be shared.
+    pub(crate) fn resolve_with(
+        &self,
+        tokens: &mut Vec<Token<S, B>>,
+        scratch: &mut Vec<Token<S, B>>,
+    ) {
+        // most values contain no macros, so avoid moving the tokens
+        if !tokens
+            .iter()
+            .any(|token| matches!(token, Token::Variable(_)))
+        {
+            return;
+        }
+
+        scratch.clear();
         for token in tokens.drain(..) {
             if let Token::Variable(ref identifier) = token {
                 match self.map.get(identifier) {
-                    Some(sub) => {
-                        self.scratch.extend(sub.iter().cloned());
+                    Some((_, sub)) => {
+                        scratch.extend(sub.iter().cloned());
                     }
-                    None => self.scratch.push(token),
+                    None => scratch.push(token),
                 };
             } else {
-                self.scratch.push(token);
+                scratch.push(token);
             }
         }
-        tokens.append(&mut self.scratch);
+        tokens.append(scratch);
     }
 }
 
@@ -242,4 +467,132 @@ mod tests {
             Some(&[Token::str_unchecked("2")][..])
         );
     }
+
+    #[test]
+    fn test_into_owned() {
+        let mut abbrevs = MacroDictionary::<&str, &[u8]>::default();
+        abbrevs.insert(
+            Variable::new_unchecked("a"),
+            vec![Token::str_unchecked("1")],
+        );
+        let owned: MacroDictionary<String, Vec<u8>> = abbrevs.clone().into();
+        assert_eq!(
+            owned.get(&Variable::new_unchecked("A".to_owned())),
+            Some(&[Token::str_unchecked("1".to_owned())][..])
+        );
+        assert_eq!(abbrevs.own().into_inner(), owned.into_inner());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut first = MacroDictionary::<&str, &[u8]>::default();
+        first.insert(
+            Variable::new_unchecked("b"),
+            vec![Token::str_unchecked("1")],
+        );
+        first.insert(
+            Variable::new_unchecked("a"),
+            vec![Token::str_unchecked("2")],
+        );
+        first.insert(
+            Variable::new_unchecked("b"),
+            vec![Token::str_unchecked("3")],
+        );
+
+        let mut second = MacroDictionary::<&str, &[u8]>::default();
+        second.insert(
+            Variable::new_unchecked("d"),
+            vec![Token::str_unchecked("4")],
+        );
+        second.insert(
+            Variable::new_unchecked("B"),
+            vec![Token::str_unchecked("5")],
+        );
+        second.insert(
+            Variable::new_unchecked("c"),
+            vec![Token::str_unchecked("6")],
+        );
+        second.insert(
+            Variable::new_unchecked("a"),
+            vec![Token::str_unchecked("2")],
+        );
+
+        // conflicting macros leave the dictionary unchanged
+        let mut merged = first.clone();
+        let err = merged
+            .merge(second.clone(), ConflictPolicy::Error)
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            crate::error::ErrorKind::DuplicateMacro { name: "B" }
+        ));
+        assert_eq!(merged.len(), 2);
+
+        first.merge(second, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(
+            first
+                .iter()
+                .map(|(var, tokens)| (var.as_ref(), tokens))
+                .collect::<Vec<_>>(),
+            vec![
+                ("b", &[Token::str_unchecked("5")][..]),
+                ("a", &[Token::str_unchecked("2")][..]),
+                ("d", &[Token::str_unchecked("4")][..]),
+                ("c", &[Token::str_unchecked("6")][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_expanded() {
+        let mut abbrevs = MacroDictionary::<&str, &[u8]>::default();
+        abbrevs.insert_raw_tokens(
+            Variable::new_unchecked("a"),
+            vec![Token::str_unchecked("1"), Token::variable_unchecked("B")],
+        );
+        abbrevs.insert_raw_tokens(
+            Variable::new_unchecked("b"),
+            vec![Token::Text(Text::Bytes(b"2"))],
+        );
+        abbrevs.insert_raw_tokens(
+            Variable::new_unchecked("c"),
+            vec![Token::variable_unchecked("d")],
+        );
+        abbrevs.insert_raw_tokens(
+            Variable::new_unchecked("d"),
+            vec![Token::variable_unchecked("C")],
+        );
+        abbrevs.insert_raw_tokens(
+            Variable::new_unchecked("e"),
+            vec![Token::variable_unchecked("f")],
+        );
+        abbrevs.insert_raw_tokens(
+            Variable::new_unchecked("g"),
+            vec![
+                Token::variable_unchecked("b"),
+                Token::variable_unchecked("b"),
+            ],
+        );
+
+        assert_eq!(
+            abbrevs.get_expanded(&Variable::new_unchecked("A")),
+            Some(Cow::Owned("12".into()))
+        );
+        assert!(matches!(
+            abbrevs.get_expanded(&Variable::new_unchecked("b")),
+            Some(Cow::Borrowed("2"))
+        ));
+        // repeated variables are not cycles
+        assert_eq!(
+            abbrevs
+                .get_expanded(&Variable::new_unchecked("g"))
+                .as_deref(),
+            Some("22")
+        );
+        // cycle
+        assert_eq!(abbrevs.get_expanded(&Variable::new_unchecked("c")), None);
+        // undefined
+        assert_eq!(abbrevs.get_expanded(&Variable::new_unchecked("e")), None);
+        assert_eq!(abbrevs.get_expanded(&Variable::new_unchecked("f")), None);
+    }
 }
@@ -5,9 +5,81 @@ mod str_impl;
 pub use slice_impl::SliceReader;
 pub use str_impl::StrReader;
 
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
 use crate::error::Error;
 use crate::token::{Identifier, Text};
 
+/// Where a `%` begins a comment which extends to the end of the line.
+///
+/// Classic BibTeX does not treat `%` as a comment character, so that for instance a `%` which
+/// appears between two entries does not hide an `@` which follows it on the same line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TexComments {
+    /// A `%` begins a comment both between entries and inside entries, outside of field values.
+    #[default]
+    Everywhere,
+    /// A `%` begins a comment only between entries.
+    BetweenEntries,
+    /// A `%` never begins a comment.
+    Never,
+}
+
+/// A repair to a text token with unbalanced brackets, as reported to the function passed to
+/// [`Deserializer::repair_brackets`](crate::de::Deserializer::repair_brackets).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// A text token was not closed, so it was closed at the end of the field.
+    Unclosed {
+        /// The byte range of the text of the token in the input.
+        span: Range<usize>,
+    },
+    /// A closing bracket `}` without a matching opening bracket was kept as text.
+    StrayClosingBracket {
+        /// The byte offset of the bracket in the input.
+        position: usize,
+    },
+}
+
+/// The convention used to terminate lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// Lines end with `\n`.
+    #[default]
+    Lf,
+    /// Lines end with `\r\n`, as is common on Windows.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Detect the line ending of the input from its first line break.
+    ///
+    /// If the input does not contain a line break, this is [`LineEnding::Lf`].
+    /// ```
+    /// use serde_bibtex::LineEnding;
+    ///
+    /// assert_eq!(LineEnding::detect(b"@string{a = 1}\r\n"), LineEnding::CrLf);
+    /// assert_eq!(LineEnding::detect(b"@string{a = 1}\n"), LineEnding::Lf);
+    /// assert_eq!(LineEnding::detect(b""), LineEnding::Lf);
+    /// ```
+    pub fn detect(input: &[u8]) -> Self {
+        match memchr::memchr(b'\n', input) {
+            Some(idx) if idx > 0 && input[idx - 1] == b'\r' => Self::CrLf,
+            _ => Self::Lf,
+        }
+    }
+
+    /// The bytes which terminate a line.
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            Self::Lf => b"\n",
+            Self::CrLf => b"\r\n",
+        }
+    }
+}
+
 /// A trait to represent a type which can be parsed as BibTeX.
 ///
 /// This trait is implemented by [`SliceReader`] and [`StrReader`].
@@ -18,12 +90,23 @@ pub trait Read<'r> {
     /// Discard a single byte. This is only valid after a previous .peek() returned a value!
     fn discard(&mut self);
 
+    /// Set where a `%` begins a comment.
+    fn set_tex_comments(&mut self, tex_comments: TexComments);
+
     /// Discard comments and whitespace.
     fn comment(&mut self);
 
+    /// At the start of the input, skip a UTF-8 byte order mark, or return an error if the byte
+    /// order mark indicates an unsupported encoding.
+    fn byte_order_mark(&mut self) -> Result<(), Error>;
+
+    /// Set whether junk characters between entries, other than whitespace and comments, are an
+    /// error.
+    fn set_deny_junk(&mut self, enable: bool);
+
     /// Discard junk characters between entries, and return true if another entry is found and
     /// false otherwise.
-    fn next_entry_or_eof(&mut self) -> bool;
+    fn next_entry_or_eof(&mut self) -> Result<bool, Error>;
 
     /// Parse a unicode identifier.
     fn identifier(&mut self) -> Result<Identifier<&'r str>, Error>;
@@ -34,6 +117,30 @@ pub trait Read<'r> {
     /// Parse a quoted or bracketed text token.
     fn protected(&mut self, until: u8) -> Result<Text<&'r str, &'r [u8]>, Error>;
 
+    /// Set whether unbalanced brackets in text tokens are repaired rather than returning an
+    /// error.
+    fn set_repair_brackets(&mut self, enable: bool);
+
+    /// Parse a bracketed or quoted text token terminated by `closing`, along with the closing
+    /// delimiter.
+    fn text_token(&mut self, closing: u8) -> Result<Text<&'r str, &'r [u8]>, Error>;
+
+    /// Take the repairs made since the repairs were last taken.
+    fn take_repairs(&mut self) -> Vec<Repair>;
+
+    /// Set the flag which cancels parsing when it is set, checked between the tokens of a value.
+    fn set_cancellation(&mut self, flag: Arc<AtomicBool>);
+
+    /// Whether the cancellation flag is set.
+    fn is_cancelled(&self) -> bool;
+
     /// Parse a text number token.
     fn number(&mut self) -> Result<&'r str, Error>;
+
+    /// The current byte offset in the input.
+    fn position(&self) -> usize;
+
+    /// Move to a byte offset in the input, which must have been returned by
+    /// [`Read::position`] or follow a line break.
+    fn seek(&mut self, position: usize);
 }
@@ -4,12 +4,25 @@ macro_rules! read_impl {
         pub struct $name<'r> {
             pub(crate) input: &'r $target,
             pub(crate) pos: usize,
+            pub(crate) tex_comments: TexComments,
+            pub(crate) deny_junk: bool,
+            /// The repairs to unbalanced brackets, if repairs are enabled.
+            pub(crate) repairs: Option<Vec<Repair>>,
+            /// The flag which cancels parsing, if cancellation is enabled.
+            pub(crate) cancellation: Option<Arc<AtomicBool>>,
         }
 
         impl<'r> $name<'r> {
             /// Create a new reader from the provided input buffer.
             pub fn new(input: &'r $target) -> Self {
-                Self { input, pos: 0 }
+                Self {
+                    input,
+                    pos: 0,
+                    tex_comments: TexComments::default(),
+                    deny_junk: false,
+                    repairs: None,
+                    cancellation: None,
+                }
             }
 
             /// Apply `parser` to `self.input` and `self.pos`, updating `self.pos` and returning `O`.
@@ -40,15 +53,46 @@ macro_rules! read_impl {
             }
 
             #[inline]
-            fn next_entry_or_eof(&mut self) -> bool {
-                let (new, res) = next_entry_or_eof(self.input, self.pos);
+            fn byte_order_mark(&mut self) -> Result<(), Error> {
+                if self.pos == 0 {
+                    self.pos = byte_order_mark(self.input)?;
+                }
+                Ok(())
+            }
+
+            #[inline]
+            fn set_tex_comments(&mut self, tex_comments: TexComments) {
+                self.tex_comments = tex_comments;
+            }
+
+            #[inline]
+            fn set_deny_junk(&mut self, enable: bool) {
+                self.deny_junk = enable;
+            }
+
+            #[inline]
+            fn next_entry_or_eof(&mut self) -> Result<bool, Error> {
+                let (new, res) = match (self.deny_junk, self.tex_comments) {
+                    (true, tex_comments) => next_entry_or_eof_strict(
+                        self.input,
+                        self.pos,
+                        tex_comments != TexComments::Never,
+                    )?,
+                    (false, TexComments::Never) => {
+                        next_entry_or_eof_uncommented(self.input, self.pos)
+                    }
+                    (false, _) => next_entry_or_eof(self.input, self.pos),
+                };
                 self.pos = new;
-                res
+                Ok(res)
             }
 
             #[inline]
             fn comment(&mut self) {
-                self.pos = comment(self.input, self.pos)
+                self.pos = match self.tex_comments {
+                    TexComments::Everywhere => comment(self.input, self.pos),
+                    _ => whitespace(self.input, self.pos),
+                }
             }
 
             #[inline]
@@ -66,12 +110,80 @@ macro_rules! read_impl {
                 Ok(Text::$var(self.apply(protected(until))?))
             }
 
+            #[inline]
+            fn set_repair_brackets(&mut self, enable: bool) {
+                self.repairs = enable.then(Vec::new);
+            }
+
+            fn text_token(&mut self, closing: u8) -> Result<Text<&'r str, &'r [u8]>, Error> {
+                if let Some(repairs) = &mut self.repairs {
+                    let (new, text) = repaired_text(self.input, self.pos, closing, repairs)?;
+                    self.pos = new;
+                    return Ok(Text::$var(text));
+                }
+
+                let (text, err) = match closing {
+                    b'}' => (self.balanced()?, ErrorCode::UnclosedBracket),
+                    _ => (self.protected(closing)?, ErrorCode::UnclosedQuote),
+                };
+                if self.peek() == Some(closing) {
+                    self.discard();
+                    Ok(text)
+                } else {
+                    Err(Error::syntax(err))
+                }
+            }
+
+            #[inline]
+            fn set_cancellation(&mut self, flag: Arc<AtomicBool>) {
+                self.cancellation = Some(flag);
+            }
+
+            #[inline]
+            fn is_cancelled(&self) -> bool {
+                self.cancellation
+                    .as_ref()
+                    .is_some_and(|flag| flag.load(Ordering::Relaxed))
+            }
+
+            #[inline]
+            fn take_repairs(&mut self) -> Vec<Repair> {
+                self.repairs
+                    .as_mut()
+                    .map(std::mem::take)
+                    .unwrap_or_default()
+            }
+
             #[inline]
             fn number(&mut self) -> Result<&'r str, Error> {
                 self.apply(number)
             }
+
+            #[inline]
+            fn position(&self) -> usize {
+                self.pos
+            }
+
+            #[inline]
+            fn seek(&mut self, position: usize) {
+                self.pos = position;
+            }
+        }
+        impl<'r> BibtexParse<'r> for $name<'r> {
+            #[inline]
+            fn source(&self, span: std::ops::Range<usize>) -> &'r [u8] {
+                &$convert(self.input)[span]
+            }
+
+            fn line_ending(&self) -> LineEnding {
+                LineEnding::detect($convert(self.input))
+            }
+
+            #[inline]
+            fn input_len(&self) -> usize {
+                $convert(self.input).len()
+            }
         }
-        impl<'r> BibtexParse<'r> for $name<'r> {}
     };
 }
 
@@ -1,10 +1,12 @@
 //! Warning: the `super::str_impl` module depends heavily on the implementation in this crate for
 //! safety! All of the cuts must be performed either immediately before or after an ascii codepoint,
 //! so the resulting slices are valid str if they began as valid str.
-use super::Read;
 use super::{Identifier, Text};
+use super::{LineEnding, Read, Repair, TexComments};
 use memchr::{memchr2_iter, memchr3_iter};
 use std::str::{from_utf8, from_utf8_unchecked};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use crate::{
     error::{Error, ErrorCode},
@@ -12,6 +14,26 @@ use crate::{
     token::IDENTIFIER_ALLOWED,
 };
 
+/// Determine the length of the UTF-8 byte order mark at the start of the input, if any.
+///
+/// Returns an error if the input begins with a UTF-16 or UTF-32 byte order mark.
+pub fn byte_order_mark(input: &[u8]) -> Result<usize, Error> {
+    const UNSUPPORTED: [(&[u8], &str); 4] = [
+        (b"\xFF\xFE\x00\x00", "UTF-32LE"),
+        (b"\x00\x00\xFE\xFF", "UTF-32BE"),
+        (b"\xFF\xFE", "UTF-16LE"),
+        (b"\xFE\xFF", "UTF-16BE"),
+    ];
+
+    if input.starts_with(b"\xEF\xBB\xBF") {
+        return Ok(3);
+    }
+    match UNSUPPORTED.iter().find(|(bom, _)| input.starts_with(bom)) {
+        Some((_, encoding)) => Err(Error::syntax(ErrorCode::UnsupportedEncoding(encoding))),
+        None => Ok(0),
+    }
+}
+
 /// Ignore junk characters between entries.
 ///
 /// Returns (updated_pos, true) if an entry was found; otherwise (input.len(), false) if hit EOF.
@@ -37,6 +59,53 @@ pub fn next_entry_or_eof(input: &[u8], mut pos: usize) -> (usize, bool) {
     (input.len(), false)
 }
 
+/// Ignore junk characters between entries, without treating `%` as the start of a comment.
+///
+/// Returns (updated_pos, true) if an entry was found; otherwise (input.len(), false) if hit EOF.
+pub fn next_entry_or_eof_uncommented(input: &[u8], pos: usize) -> (usize, bool) {
+    match memchr::memchr(b'@', &input[pos..]) {
+        Some(offset) => (pos + offset + 1, true),
+        None => (input.len(), false),
+    }
+}
+
+/// Ignore whitespace, and comments if `tex_comments` is true, between entries, returning an
+/// error with the span of any other junk characters.
+///
+/// Returns (updated_pos, true) if an entry was found; otherwise (input.len(), false) if hit EOF.
+pub fn next_entry_or_eof_strict(
+    input: &[u8],
+    pos: usize,
+    tex_comments: bool,
+) -> Result<(usize, bool), Error> {
+    let pos = if tex_comments {
+        comment(input, pos)
+    } else {
+        whitespace(input, pos)
+    };
+    match input.get(pos) {
+        None => Ok((input.len(), false)),
+        Some(b'@') => Ok((pos + 1, true)),
+        Some(_) => {
+            // the junk extends to the next entry or the end of the line
+            let end = memchr::memchr2(b'@', b'\n', &input[pos..]).map_or(input.len(), |o| pos + o);
+            let end = pos + input[pos..end].trim_ascii_end().len();
+            Err(Error::syntax(ErrorCode::Junk(pos..end)).at_found(pos, &input[pos..]))
+        }
+    }
+}
+
+/// Ignore whitespace within entries, without treating `%` as the start of a comment.
+pub fn whitespace(input: &[u8], mut pos: usize) -> usize {
+    while pos < input.len() {
+        match input[pos] {
+            b'\t' | b'\n' | b'\x0C' | b'\r' | b' ' => pos += 1,
+            _ => return pos,
+        }
+    }
+    input.len()
+}
+
 /// Ignore whitespace and comments within entries.
 ///
 /// Note that this follows the same convention as the built-in `u8::is_ascii_whitespace`
@@ -151,12 +220,158 @@ pub fn protected(until: u8) -> impl FnMut(&[u8], usize) -> Result<(usize, &[u8])
     }
 }
 
+/// Consume a text token terminated by `closing`, which is either `}` or `"`, along with the
+/// closing delimiter, repairing unbalanced brackets and recording each repair in `repairs`.
+///
+/// A `}` which would close the token, or which is not matched inside a quoted token, is kept as
+/// text unless it is followed by the end of the value. If the token is still open at a line which
+/// begins a new field or ends the entry, it is closed at the end of the preceding field.
+pub fn repaired_text<'r>(
+    input: &'r [u8],
+    start: usize,
+    closing: u8,
+    repairs: &mut Vec<Repair>,
+) -> Result<(usize, &'r [u8]), Error> {
+    let mut bracket_depth = 0;
+    let mut pos = start;
+
+    while pos < input.len() {
+        match input[pos] {
+            b'{' => bracket_depth += 1,
+            b'}' if bracket_depth > 0 => bracket_depth -= 1,
+            b'}' if closing == b'}' && ends_value(input, pos + 1) => {
+                return Ok((pos + 1, &input[start..pos]));
+            }
+            b'}' => repairs.push(Repair::StrayClosingBracket { position: pos }),
+            b'"' if closing == b'"' && bracket_depth == 0 => {
+                return Ok((pos + 1, &input[start..pos]));
+            }
+            b'\n' => {
+                if let Some((end, resume)) = field_boundary(input, start, pos) {
+                    let mut text = &input[start..end];
+                    if closing == b'"' {
+                        text = text.strip_suffix(b"\"").unwrap_or(text);
+                    }
+                    repairs.push(Repair::Unclosed {
+                        span: start..start + text.len(),
+                    });
+                    return Ok((resume, text));
+                }
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+
+    Err(Error::syntax(ErrorCode::UnterminatedTextToken))
+}
+
+/// Whether the remainder of a value at `pos` is empty, so that a `}` which precedes it closes a
+/// text token.
+fn ends_value(input: &[u8], pos: usize) -> bool {
+    let next = whitespace(input, pos);
+    matches!(
+        input.get(next),
+        None | Some(b',' | b'}' | b')' | b'#' | b'@')
+    ) || (input[pos..next].contains(&b'\n') && starts_field(input, next))
+}
+
+/// Whether the line at `pos`, after leading whitespace, begins a field `key =`.
+fn starts_field(input: &[u8], pos: usize) -> bool {
+    let start = whitespace(input, pos);
+    let mut end = start;
+    while end < input.len() && IDENTIFIER_ALLOWED[input[end] as usize] {
+        end += 1
+    }
+    end > start && input.get(whitespace(input, end)) == Some(&b'=')
+}
+
+/// If the line break at `newline` separates two fields, or precedes the end of the entry, return
+/// the end of the text of a token which began at `start` and the position at which to resume.
+fn field_boundary(input: &[u8], start: usize, newline: usize) -> Option<(usize, usize)> {
+    let line_start = input[start..newline]
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(start, |offset| start + offset + 1);
+    let end = line_start + input[line_start..newline].trim_ascii_end().len();
+
+    if end > start && input[end - 1] == b',' && starts_field(input, newline + 1) {
+        // resume at the comma, which separates the fields
+        let end = end - 1;
+        return Some((start + input[start..end].trim_ascii_end().len(), end));
+    }
+
+    let next = whitespace(input, newline + 1);
+    if matches!(input.get(next), Some(b'}' | b')'))
+        && matches!(input.get(whitespace(input, next + 1)), None | Some(b'@'))
+    {
+        return Some((end, newline));
+    }
+    None
+}
+
 super::create_input_impl::read_impl!([u8], SliceReader, Bytes, std::convert::identity);
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_byte_order_mark() {
+        assert_eq!(byte_order_mark(b"\xEF\xBB\xBF@a{k}").unwrap(), 3);
+        assert_eq!(byte_order_mark(b"@a{k}").unwrap(), 0);
+        assert_eq!(byte_order_mark(b"").unwrap(), 0);
+        assert!(byte_order_mark(b"\xFF\xFE@\x00").is_err());
+        assert!(byte_order_mark(b"\xFE\xFF\x00@").is_err());
+        assert!(byte_order_mark(b"\x00\x00\xFE\xFF").is_err());
+    }
+
+    #[test]
+    fn test_uncommented() {
+        assert_eq!(next_entry_or_eof_uncommented(b"junk", 0), (4, false));
+        assert_eq!(next_entry_or_eof_uncommented(b"", 0), (0, false));
+        assert_eq!(next_entry_or_eof_uncommented(b"%@a", 0), (2, true));
+        assert_eq!(next_entry_or_eof_uncommented(b"%@a", 2), (3, false));
+        assert_eq!(whitespace(b" \t\n% a\n", 0), 3);
+        assert_eq!(whitespace(b"  ", 0), 2);
+        assert_eq!(whitespace(b"", 0), 0);
+    }
+
+    #[test]
+    fn test_next_entry_or_eof_strict() {
+        assert_eq!(next_entry_or_eof_strict(b"", 0, true).unwrap(), (0, false));
+        assert_eq!(
+            next_entry_or_eof_strict(b" \n", 0, true).unwrap(),
+            (2, false)
+        );
+        assert_eq!(
+            next_entry_or_eof_strict(b" %c\n@a", 0, true).unwrap(),
+            (5, true)
+        );
+        assert!(matches!(
+            next_entry_or_eof_strict(b" %c\n@a", 0, false),
+            Err(Error {
+                code: ErrorCode::Junk(span),
+                ..
+            }) if span == (1..3)
+        ));
+        assert!(matches!(
+            next_entry_or_eof_strict(b"\n a{k} \n@a", 0, true),
+            Err(Error {
+                code: ErrorCode::Junk(span),
+                position: Some(2),
+                ..
+            }) if span == (2..6)
+        ));
+        assert!(matches!(
+            next_entry_or_eof_strict(b"junk@a", 0, true),
+            Err(Error {
+                code: ErrorCode::Junk(span),
+                ..
+            }) if span == (0..4)
+        ));
+    }
+
     #[test]
     fn test_next_entry_or_eof() {
         assert_eq!(next_entry_or_eof(b"junk", 0), (4, false));
@@ -200,18 +415,71 @@ mod tests {
         assert!(matches!(
             protected(b'"')(b"{\"", 0),
             Err(Error {
-                code: ErrorCode::UnterminatedTextToken
+                code: ErrorCode::UnterminatedTextToken,
+                ..
             })
         ));
         // unexpected closing
         assert!(matches!(
             protected(b'"')(b"}\"", 0),
             Err(Error {
-                code: ErrorCode::UnexpectedClosingBracket
+                code: ErrorCode::UnexpectedClosingBracket,
+                ..
             })
         ));
     }
 
+    #[test]
+    fn test_repaired_text() {
+        fn repaired(input: &[u8], closing: u8) -> (usize, &[u8], Vec<Repair>) {
+            let mut repairs = Vec::new();
+            let (pos, text) = repaired_text(input, 0, closing, &mut repairs).unwrap();
+            (pos, text, repairs)
+        }
+
+        // balanced tokens are unchanged
+        assert_eq!(repaired(b"a {b}}, c", b'}'), (6, &b"a {b}"[..], vec![]));
+        assert_eq!(repaired(b"a {\"}\"}", b'"'), (6, &b"a {\"}"[..], vec![]));
+        assert_eq!(
+            repaired(b"a\n  b = c}\n}", b'}'),
+            (10, &b"a\n  b = c"[..], vec![])
+        );
+
+        // a stray closing bracket is kept as text
+        assert_eq!(
+            repaired(b"a} b},", b'}'),
+            (
+                5,
+                &b"a} b"[..],
+                vec![Repair::StrayClosingBracket { position: 1 }]
+            )
+        );
+        assert_eq!(
+            repaired(b"a}\"", b'"'),
+            (
+                3,
+                &b"a}"[..],
+                vec![Repair::StrayClosingBracket { position: 1 }]
+            )
+        );
+
+        // an unclosed token is closed at the end of the field
+        assert_eq!(
+            repaired(b"a {b},\n  year = 2024}", b'}'),
+            (5, &b"a {b}"[..], vec![Repair::Unclosed { span: 0..5 }])
+        );
+        assert_eq!(
+            repaired(b"a {b\",\n  year = 2024}", b'"'),
+            (5, &b"a {b"[..], vec![Repair::Unclosed { span: 0..4 }])
+        );
+        assert_eq!(
+            repaired(b"{a\n}\n\n@misc{b}", b'}'),
+            (2, &b"{a"[..], vec![Repair::Unclosed { span: 0..2 }])
+        );
+
+        assert!(repaired_text(b"{a", 0, b'}', &mut Vec::new()).is_err());
+    }
+
     #[test]
     fn test_balanced() {
         assert!(matches!(balanced(b"url}abc", 0), Ok((3, b"url"))));
@@ -224,13 +492,15 @@ mod tests {
         assert!(matches!(
             balanced(b"none", 0),
             Err(Error {
-                code: ErrorCode::UnterminatedTextToken
+                code: ErrorCode::UnterminatedTextToken,
+                ..
             })
         ));
         assert!(matches!(
             balanced(b"{no}e", 0),
             Err(Error {
-                code: ErrorCode::UnterminatedTextToken
+                code: ErrorCode::UnterminatedTextToken,
+                ..
             })
         ));
     }
@@ -5,24 +5,50 @@
 //! performed in `super::slice_impl` is adjacent to ascii codepoints, so the resulting slices are valid
 //! str if they began as valid str.
 use super::slice_impl;
-use super::Read;
 use super::{Identifier, Text};
+use super::{LineEnding, Read, Repair, TexComments};
 use crate::error::{Error, ErrorCode};
 use crate::token::IDENTIFIER_ALLOWED;
 use std::str::from_utf8_unchecked;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use crate::parse::BibtexParse;
 
+#[inline]
+pub fn byte_order_mark(input: &str) -> Result<usize, Error> {
+    slice_impl::byte_order_mark(input.as_bytes())
+}
+
 #[inline]
 pub fn next_entry_or_eof(input: &str, pos: usize) -> (usize, bool) {
     slice_impl::next_entry_or_eof(input.as_bytes(), pos)
 }
 
+#[inline]
+pub fn next_entry_or_eof_uncommented(input: &str, pos: usize) -> (usize, bool) {
+    slice_impl::next_entry_or_eof_uncommented(input.as_bytes(), pos)
+}
+
+#[inline]
+pub fn next_entry_or_eof_strict(
+    input: &str,
+    pos: usize,
+    tex_comments: bool,
+) -> Result<(usize, bool), Error> {
+    slice_impl::next_entry_or_eof_strict(input.as_bytes(), pos, tex_comments)
+}
+
 #[inline]
 pub fn comment(input: &str, pos: usize) -> usize {
     slice_impl::comment(input.as_bytes(), pos)
 }
 
+#[inline]
+pub fn whitespace(input: &str, pos: usize) -> usize {
+    slice_impl::whitespace(input.as_bytes(), pos)
+}
+
 #[inline]
 pub fn identifier(input: &str, start: usize) -> Result<(usize, Identifier<&str>), Error> {
     let mut end = start;
@@ -49,6 +75,17 @@ pub fn balanced(input: &str, pos: usize) -> Result<(usize, &str), Error> {
     unsafe { Ok((new, from_utf8_unchecked(res))) }
 }
 
+#[inline]
+pub fn repaired_text<'r>(
+    input: &'r str,
+    pos: usize,
+    closing: u8,
+    repairs: &mut Vec<Repair>,
+) -> Result<(usize, &'r str), Error> {
+    let (new, res) = slice_impl::repaired_text(input.as_bytes(), pos, closing, repairs)?;
+    unsafe { Ok((new, from_utf8_unchecked(res))) }
+}
+
 #[inline]
 pub fn protected(until: u8) -> impl FnMut(&str, usize) -> Result<(usize, &str), Error> {
     debug_assert!(until.is_ascii());
@@ -97,13 +134,15 @@ mod tests {
         assert!(matches!(
             balanced("none", 2),
             Err(Error {
-                code: ErrorCode::UnterminatedTextToken
+                code: ErrorCode::UnterminatedTextToken,
+                ..
             })
         ));
         assert!(matches!(
             balanced("{n🍄}e", 0),
             Err(Error {
-                code: ErrorCode::UnterminatedTextToken
+                code: ErrorCode::UnterminatedTextToken,
+                ..
             })
         ));
     }
@@ -119,6 +158,8 @@ mod tests {
             let _ = balanced(&s, 0);
             let _ = protected(b'"')(&s, 0);
             let _ = protected(b')')(&s, 0);
+            let _ = repaired_text(&s, 0, b'}', &mut Vec::new());
+            let _ = repaired_text(&s, 0, b'"', &mut Vec::new());
         }
     }
 }
@@ -76,6 +76,8 @@
 //! 3. For compatibility with the deserializer implementation, the `Macro` variant can be optional.
 //!    If the value is `None`, the corresponding macro entry will be skipped.
 //! 4. Variants with valid names can be omitted and the corresponding entry will not be written.
+//!    In particular, a unit `Comment` variant writes nothing: use a newtype variant carrying the
+//!    text to write a comment, or [`Serializer::write_comment`] between entries.
 //!    To omit other names, use the serde [`skip_serializing_field`](https://serde.rs/attr-skip-serializing.html)
 //!    macro attribute.
 //! 5. Of course, you can simply not include a variant in the enum.
@@ -221,6 +223,33 @@
 //! # );
 //! ```
 //!
+//! Text can also be serialized as bytes, for instance using
+//! [`serde_bytes`](https://docs.rs/serde_bytes), so that values which were deserialized from
+//! input which is not valid UTF-8 can be written back. Bytes are written verbatim: they are not
+//! [fixed](Serializer::fixup_text) or [escaped](Serializer::escape_text), and
+//! the only check is that the brackets are balanced.
+//! ```
+//! use serde::Serialize;
+//! use serde_bibtex::to_vec;
+//!
+//! #[derive(Debug, Serialize)]
+//! enum Token {
+//!     #[serde(with = "serde_bytes")]
+//!     Text(Vec<u8>),
+//!     Variable(String),
+//! }
+//!
+//! let value = vec![Token::Text(b"Jos\xe9".to_vec()), Token::Variable("et_al".to_owned())];
+//! let bibliography = vec![("book", "key", [("author", value)])];
+//! assert_eq!(
+//!     to_vec(&bibliography).unwrap(),
+//!     b"@book{key,\n  author = {Jos\xe9} # et_al,\n}\n"
+//! );
+//!
+//! let unbalanced = vec![("book", "key", [("title", serde_bytes::Bytes::new(b"{\xe9"))])];
+//! assert!(to_vec(&unbalanced).is_err());
+//! ```
+//!
 //! ## Serialization variants
 //! You can configure the [`Serializer`] with a custom formatter with the
 //! [`Serializer::new_with_formatter`] method.
@@ -245,23 +274,162 @@
 //! This section describes the allowed input data formats which support serialization into the
 //! various bibliography components. The naming convention for the components is as described in
 //! the [syntax](crate::syntax) module.
+mod builder;
+#[cfg(feature = "entry")]
+mod entries;
 mod entry;
+#[cfg(feature = "erased")]
+mod erased;
 mod formatter;
 mod macros;
+mod preamble;
+mod prologue;
 mod value;
 
+use std::collections::HashSet;
 use std::io;
 
 use serde::ser;
+use unicase::UniCase;
 
-pub use self::formatter::{CompactFormatter, Formatter, PrettyFormatter, ValidatingFormatter};
+pub use self::builder::SerializerBuilder;
+#[cfg(feature = "erased")]
+#[cfg_attr(docsrs, doc(cfg(feature = "erased")))]
+pub use self::erased::DynSerializer;
+pub use self::formatter::{
+    BodyDelimiters, CompactFormatter, Formatter, PrettyFormatter, ValidatingFormatter,
+};
 use self::{entry::EntrySerializer, formatter::FormatBuffer, macros::serialize_err};
 use crate::error::{Error, Result};
+use crate::{LineEnding, MacroDictionary};
 
 /// The main serializer, when you already have a [`std::io::Write`] and a [`Formatter`].
 pub struct Serializer<W, F = PrettyFormatter> {
     writer: W,
     buffer: FormatBuffer<F>,
+    entry_keys: Option<EntryKeyTracker>,
+    variables: Option<VariableTracker>,
+    entry_types: Vec<(UniCase<String>, String)>,
+    /// Whether entries were already written, so that the next entry must begin with a separator.
+    pending_separator: bool,
+    /// Whether fields provided by a map are sorted by key.
+    sort_fields: bool,
+    /// Whether each part of a preamble is written as a separate entry.
+    split_preamble: bool,
+    /// The comment lines which are written before the first entry.
+    prologue: Vec<String>,
+    /// Whether the prologue was already written.
+    prologue_written: bool,
+    key_generator: Option<EntryKeyGenerator>,
+}
+
+/// Entry type aliases which convert classic BibTeX entry types into their biblatex equivalents.
+///
+/// For use with [`Serializer::with_entry_type_aliases`].
+pub const BIBTEX_TO_BIBLATEX: [(&str, &str); 6] = [
+    ("conference", "inproceedings"),
+    ("electronic", "online"),
+    ("mastersthesis", "thesis"),
+    ("phdthesis", "thesis"),
+    ("techreport", "report"),
+    ("www", "online"),
+];
+
+/// Entry type aliases which convert biblatex entry types into the closest classic BibTeX
+/// equivalents.
+///
+/// For use with [`Serializer::with_entry_type_aliases`].
+pub const BIBLATEX_TO_BIBTEX: [(&str, &str); 9] = [
+    ("online", "misc"),
+    ("report", "techreport"),
+    ("thesis", "phdthesis"),
+    ("mvbook", "book"),
+    ("bookinbook", "inbook"),
+    ("suppbook", "inbook"),
+    ("collection", "book"),
+    ("mvcollection", "book"),
+    ("mvproceedings", "proceedings"),
+];
+
+/// How a [`Serializer`] handles an entry key which was already written.
+///
+/// Entry keys are compared case-insensitively.
+pub enum DuplicateEntryKeys {
+    /// Return an error.
+    Error,
+    /// Call the provided function with the duplicate entry key, and continue serializing.
+    Warn(Box<dyn FnMut(&str) + Send>),
+}
+
+/// How a [`Serializer`] handles a variable token which is not a defined macro, as set with
+/// [`Serializer::check_variables`].
+///
+/// Macro names are compared case-insensitively.
+pub enum UndefinedVariables {
+    /// Return an error.
+    Error,
+    /// Call the provided function with the name of the variable, and continue serializing.
+    Warn(Box<dyn FnMut(&str) + Send>),
+}
+
+/// How a [`Serializer`] handles a text token with unbalanced `{}` brackets, as set with
+/// [`Serializer::fixup_text`].
+///
+/// Text tokens are always written in brackets, so quotes and other characters in the text never
+/// need to be escaped. However, the brackets within the text must be balanced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FixupPolicy {
+    /// Write the text unchanged, so that a validating formatter returns an error.
+    #[default]
+    Error,
+    /// Replace each unmatched bracket with the corresponding LaTeX command, so that `a}b` is
+    /// written as `a\textbraceright{}b`. The text renders as the original text.
+    Escape,
+    /// Insert brackets at the start and end of the text to match the unmatched brackets, so that
+    /// `a}b{c` is written as `{a}b{c}`.
+    Balance,
+}
+
+/// What to do with a field, as decided by a filter set with [`Serializer::with_field_filter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldAction {
+    /// Write the field unchanged.
+    Keep,
+    /// Do not write the field.
+    Drop,
+    /// Write the field value with a new field key.
+    Rename(String),
+    /// Replace the field with a new field key and a value consisting of a single text token.
+    Rewrite {
+        /// The new field key.
+        key: String,
+        /// The new field value.
+        value: String,
+    },
+}
+
+/// The macros which are defined in the output of a [`Serializer`].
+struct VariableTracker {
+    policy: UndefinedVariables,
+    defined: HashSet<UniCase<String>>,
+}
+
+/// A function which generates an entry key from the entry type and the fields of an entry.
+pub(crate) type KeyGenerator = Box<dyn FnMut(&str, &[(String, String)]) -> String + Send>;
+
+/// The state used to generate the key of an entry whose entry key is empty.
+struct EntryKeyGenerator {
+    generate: KeyGenerator,
+    /// The entry type of the current entry.
+    entry_type: String,
+    /// Whether the key of the current entry must be generated.
+    pending: bool,
+}
+
+/// The entry keys which were written by a [`Serializer`].
+struct EntryKeyTracker {
+    policy: DuplicateEntryKeys,
+    seen: HashSet<UniCase<String>>,
 }
 
 impl<W, F> Serializer<W, F> {
@@ -270,7 +438,509 @@ impl<W, F> Serializer<W, F> {
         Self {
             writer,
             buffer: FormatBuffer::new(formatter),
+            entry_keys: None,
+            variables: None,
+            entry_types: Vec::new(),
+            pending_separator: false,
+            sort_fields: false,
+            split_preamble: false,
+            prologue: Vec::new(),
+            prologue_written: false,
+            key_generator: None,
+        }
+    }
+
+    /// Replace regular entry types using the provided `(from, to)` pairs when writing.
+    ///
+    /// Entry types are matched case-insensitively, and later aliases take precedence over earlier
+    /// ones. Standard tables are provided as
+    /// [`BIBTEX_TO_BIBLATEX`] and [`BIBLATEX_TO_BIBTEX`].
+    pub fn with_entry_type_aliases<I, K, V>(mut self, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.entry_types.extend(
+            aliases
+                .into_iter()
+                .map(|(from, to)| (UniCase::new(from.into()), to.into())),
+        );
+        self
+    }
+
+    /// Write the provided comment lines at the start of the output, before the first entry.
+    ///
+    /// Each line is written after `% `, such as the encoding marker `% Encoding: UTF-8` which is
+    /// written by JabRef, or a banner naming the program which generated the file. BibTeX ignores
+    /// text outside of entries, so the lines are not part of the bibliography. The lines are
+    /// written once, when the first entry is written or when the first bibliography ends, and are
+    /// separated from the first entry as determined by the [`Formatter`]. A line which contains
+    /// `@` or a line break is an error, since it would change the entries which are read back.
+    /// ```
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// let mut ser = Serializer::new(Vec::new())
+    ///     .with_prologue(["Encoding: UTF-8", "Generated by my-tool"]);
+    /// ser.serialize_entry(&("misc", "key", [("title", "T")])).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "% Encoding: UTF-8\n% Generated by my-tool\n\n@misc{key,\n  title = {T},\n}\n"
+    /// );
+    /// ```
+    pub fn with_prologue<I, S>(mut self, lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prologue.extend(lines.into_iter().map(Into::into));
+        self
+    }
+
+    /// Track the entry keys which are written, and handle duplicates according to `policy`.
+    pub fn check_entry_keys(mut self, policy: DuplicateEntryKeys) -> Self {
+        self.entry_keys = Some(EntryKeyTracker {
+            policy,
+            seen: HashSet::new(),
+        });
+        self
+    }
+
+    /// Check that every variable token which is written is a macro defined in `macros`, or by a
+    /// macro entry which was already written, and handle undefined variables according to
+    /// `policy`.
+    ///
+    /// BibTeX reports an error for each undefined macro, so this catches the error when the
+    /// output is written rather than when it is used. The month macros `jan` to `dec` are
+    /// defined by the standard styles, but are only considered defined here if they are in
+    /// `macros`; see [`MacroDictionary::set_month_macros`]. Variables within a
+    /// [`RawValue`](crate::token::RawValue) are also checked, and variables are checked before
+    /// a field filter is applied.
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_bibtex::{
+    ///     ser::{Serializer, UndefinedVariables},
+    ///     MacroDictionary,
+    /// };
+    ///
+    /// #[derive(Serialize)]
+    /// enum Token {
+    ///     Variable(&'static str),
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// enum Entry {
+    ///     Macro(&'static str, &'static str),
+    ///     Regular(&'static str, &'static str, [(&'static str, Vec<Token>); 1]),
+    /// }
+    ///
+    /// let mut macros = MacroDictionary::<&str, &[u8]>::default();
+    /// macros.set_month_macros();
+    ///
+    /// let bibliography = [
+    ///     Entry::Macro("ams", "American Mathematical Society"),
+    ///     Entry::Regular("book", "a", [("publisher", vec![Token::Variable("AMS")])]),
+    ///     Entry::Regular("book", "b", [("month", vec![Token::Variable("apr")])]),
+    /// ];
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).check_variables(&macros, UndefinedVariables::Error);
+    /// assert!(bibliography.serialize(&mut ser).is_ok());
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).check_variables(&macros, UndefinedVariables::Error);
+    /// assert!(bibliography[1..].serialize(&mut ser).is_err());
+    /// ```
+    pub fn check_variables<S, B>(
+        mut self,
+        macros: &MacroDictionary<S, B>,
+        policy: UndefinedVariables,
+    ) -> Self
+    where
+        S: AsRef<str>,
+        B: AsRef<[u8]>,
+    {
+        self.variables = Some(VariableTracker {
+            policy,
+            defined: macros
+                .iter()
+                .map(|(variable, _)| UniCase::new(variable.as_ref().to_owned()))
+                .collect(),
+        });
+        self
+    }
+
+    /// Set whether fields provided by a map, such as a [`HashMap`](std::collections::HashMap), are
+    /// sorted by field key before they are written. The default is `false`.
+    ///
+    /// The iteration order of a `HashMap` can differ between runs, so sorting makes the output
+    /// deterministic. Keys are compared case-insensitively, and then case-sensitively to break
+    /// ties. Fields provided by a struct or a sequence are written in the order in which they
+    /// are provided.
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use serde::Serialize;
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Record {
+    ///     entry_type: &'static str,
+    ///     entry_key: &'static str,
+    ///     fields: HashMap<&'static str, &'static str>,
+    /// }
+    ///
+    /// let record = Record {
+    ///     entry_type: "book",
+    ///     entry_key: "key",
+    ///     fields: HashMap::from([("year", "2024"), ("Title", "T"), ("author", "A")]),
+    /// };
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).sort_fields(true);
+    /// ser.serialize_entry(&record).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@book{key,\n  author = {A},\n  Title = {T},\n  year = {2024},\n}\n"
+    /// );
+    /// ```
+    pub fn sort_fields(mut self, enable: bool) -> Self {
+        self.sort_fields = enable;
+        self
+    }
+
+    /// Set whether each part of a preamble is written as a separate `@preamble` entry. The default
+    /// is `false`, in which case the parts are joined with `#` in a single entry.
+    ///
+    /// A preamble is written in parts when its value is a sequence of values, where each value is
+    /// a string or a sequence of tokens.
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// enum Entry {
+    ///     Preamble(Vec<&'static str>),
+    /// }
+    ///
+    /// let preamble = Entry::Preamble(vec![r"\newcommand{\A}{a}", r"\newcommand{\B}{b}"]);
+    ///
+    /// let mut ser = Serializer::compact(Vec::new());
+    /// ser.serialize_entry(&preamble).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     r"@preamble{{\newcommand{\A}{a}}#{\newcommand{\B}{b}}}"
+    /// );
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).split_preamble(true);
+    /// ser.serialize_entry(&preamble).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@preamble{{\\newcommand{\\A}{a}}}\n\n@preamble{{\\newcommand{\\B}{b}}}\n"
+    /// );
+    /// ```
+    pub fn split_preamble(mut self, enable: bool) -> Self {
+        self.split_preamble = enable;
+        self
+    }
+
+    /// Set the line ending used by the output. The default is [`LineEnding::Lf`].
+    ///
+    /// Only the line breaks written by the formatter are affected: line breaks within keys and
+    /// values are written unchanged. Use [`Deserializer::line_ending`](crate::de::Deserializer::line_ending)
+    /// to write the output with the same convention as the input.
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_bibtex::{ser::Serializer, LineEnding};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Record {
+    ///     entry_type: &'static str,
+    ///     entry_key: &'static str,
+    ///     fields: Vec<(&'static str, &'static str)>,
+    /// }
+    ///
+    /// let record = Record {
+    ///     entry_type: "book",
+    ///     entry_key: "key",
+    ///     fields: vec![("title", "T")],
+    /// };
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).line_ending(LineEnding::CrLf);
+    /// ser.serialize_entry(&record).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@book{key,\r\n  title = {T},\r\n}\r\n"
+    /// );
+    /// ```
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.buffer.set_line_ending(line_ending);
+        self
+    }
+
+    /// Set how text tokens with unbalanced `{}` brackets are fixed before they are written. The
+    /// default is [`FixupPolicy::Error`].
+    ///
+    /// This allows arbitrary text, for instance text which is provided by a user, to be written as
+    /// valid BibTeX.
+    /// ```
+    /// use serde_bibtex::ser::{FixupPolicy, Serializer};
+    ///
+    /// let record = ("misc", "key", [("title", "Sets {x | x > 0")]);
+    ///
+    /// let mut ser = Serializer::new(Vec::new());
+    /// assert!(ser.serialize_entry(&record).is_err());
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).fixup_text(FixupPolicy::Escape);
+    /// ser.serialize_entry(&record).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@misc{key,\n  title = {Sets \\textbraceleft{}x | x > 0},\n}\n"
+    /// );
+    /// ```
+    pub fn fixup_text(mut self, policy: FixupPolicy) -> Self {
+        self.buffer.set_fixup_policy(policy);
+        self
+    }
+
+    /// Set whether every `@` and `#` in a text token is enclosed in brackets, as in
+    /// [`escape_text`](crate::token::escape_text). The default is `false`.
+    ///
+    /// This is useful when writing text from an untrusted source, such as scraped metadata, which
+    /// may be read by tools other than BibTeX. The escaping is applied after the
+    /// [`FixupPolicy`].
+    /// ```
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// let mut ser = Serializer::compact(Vec::new()).escape_text(true);
+    /// ser.serialize_entry(&("misc", "key", [("note", "Contact: a@b.org")]))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@misc{key,note={Contact: a{@}b.org}}"
+    /// );
+    /// ```
+    pub fn escape_text(mut self, enable: bool) -> Self {
+        self.buffer.set_escape_text(enable);
+        self
+    }
+
+    /// Set whether each entry is written with a single call to
+    /// [`write_vectored`](io::Write::write_vectored). The default is `false`, in which case the
+    /// parts of each entry are written with separate calls to
+    /// [`write_all`](io::Write::write_all).
+    ///
+    /// This reduces the number of system calls when writing a large bibliography directly to a
+    /// file or socket. It has no benefit if the writer is buffered, or if the writer does not
+    /// support vectored writes.
+    /// ```
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// let mut ser = Serializer::compact(Vec::new()).write_vectored(true);
+    /// ser.serialize_entry(&("misc", "key", [("title", "T")])).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@misc{key,title={T}}"
+    /// );
+    /// ```
+    pub fn write_vectored(mut self, enable: bool) -> Self {
+        self.buffer.set_write_vectored(enable);
+        self
+    }
+
+    /// Set whether fields whose value is empty or contains only whitespace are skipped. The
+    /// default is `false`.
+    ///
+    /// A value is empty if its text tokens are empty and it has no variable tokens, so that
+    /// `note = {}` and `note = { } # {}` are skipped but `note = empty` is written. If a field
+    /// filter is set with [`Serializer::with_field_filter`], the filter is applied first and the
+    /// field is skipped if the value which the filter would write is empty.
+    /// ```
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).skip_empty_fields(true);
+    /// ser.serialize_entry(&("misc", "key", [("title", "T"), ("note", ""), ("doi", " ")]))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@misc{key,\n  title = {T},\n}\n"
+    /// );
+    /// ```
+    pub fn skip_empty_fields(mut self, enable: bool) -> Self {
+        self.buffer.set_skip_empty_fields(enable);
+        self
+    }
+
+    /// Apply `filter` to every field before it is written.
+    ///
+    /// The filter is called with the field key and the field value, which is the contents of the
+    /// value tokens concatenated. Variable tokens contribute their name.
+    pub fn with_field_filter<G>(mut self, filter: G) -> Self
+    where
+        G: Fn(&str, &str) -> FieldAction + Send + 'static,
+    {
+        self.buffer.set_field_filter(Box::new(filter));
+        self
+    }
+
+    /// Generate the key of each regular entry whose entry key is empty or only whitespace, rather
+    /// than writing the empty key.
+    ///
+    /// The generator is called with the entry type and the key and value of each field which is
+    /// written, after any field filter is applied. As with a field filter, the value is the
+    /// contents of the value tokens concatenated. The generated key is validated and checked for
+    /// duplicates like any other entry key.
+    /// ```
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// let mut ser = Serializer::new(Vec::new()).with_key_generator(|_, fields| {
+    ///     let field = |name| {
+    ///         fields
+    ///             .iter()
+    ///             .find(|(key, _)| key == name)
+    ///             .map_or("", |(_, value)| value.as_str())
+    ///     };
+    ///     let author = field("author").split(',').next().unwrap_or_default();
+    ///     format!("{author}{}", field("year"))
+    /// });
+    ///
+    /// ser.serialize_entry(&("article", "", [("author", "Knuth, Donald"), ("year", "1984")]))
+    ///     .unwrap();
+    /// ser.serialize_entry(&("misc", "key", [("year", "2024")]))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@article{Knuth1984,\n  author = {Knuth, Donald},\n  year = {1984},\n}\n\
+    ///      @misc{key,\n  year = {2024},\n}\n"
+    /// );
+    /// ```
+    pub fn with_key_generator<G>(mut self, generator: G) -> Self
+    where
+        G: FnMut(&str, &[(String, String)]) -> String + Send + 'static,
+    {
+        self.set_key_generator(Box::new(generator));
+        self
+    }
+
+    /// Set the function which generates empty entry keys.
+    pub(crate) fn set_key_generator(&mut self, generate: KeyGenerator) {
+        self.buffer.capture_fields();
+        self.key_generator = Some(EntryKeyGenerator {
+            generate,
+            entry_type: String::new(),
+            pending: false,
+        });
+    }
+
+    /// Defer writing an entry key which is empty until the end of the entry, returning whether
+    /// the key will be generated.
+    pub(crate) fn defer_entry_key(&mut self, key: &str) -> bool {
+        match &mut self.key_generator {
+            Some(generator) if key.trim().is_empty() => {
+                generator.pending = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Record a macro which is defined by a macro entry.
+    pub(crate) fn define_variable(&mut self, name: &str) {
+        if let Some(tracker) = &mut self.variables {
+            tracker.defined.insert(UniCase::new(name.to_owned()));
+        }
+    }
+
+    /// Check that a variable is defined, returning an error if it is not and this is
+    /// disallowed.
+    pub(crate) fn check_variable(&mut self, name: &str) -> Result<()> {
+        if let Some(tracker) = &mut self.variables {
+            if !tracker.defined.contains(&UniCase::new(name.to_owned())) {
+                match &mut tracker.policy {
+                    UndefinedVariables::Error => {
+                        return Err(Error::ser(format!("undefined macro '{name}'")))
+                    }
+                    UndefinedVariables::Warn(warn) => warn(name),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Record an entry key, returning an error if it is a disallowed duplicate.
+    pub(crate) fn record_entry_key(&mut self, key: &str) -> Result<()> {
+        if let Some(tracker) = &mut self.entry_keys {
+            if !tracker.seen.insert(UniCase::new(key.to_owned())) {
+                match &mut tracker.policy {
+                    DuplicateEntryKeys::Error => {
+                        return Err(Error::ser(format!("duplicate entry key '{key}'")))
+                    }
+                    DuplicateEntryKeys::Warn(warn) => warn(key),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Complete the current entry, writing the entry key if it was deferred.
+    pub(crate) fn finish_entry(&mut self) -> Result<()>
+    where
+        F: Formatter,
+    {
+        let Some(generator) = &mut self.key_generator else {
+            return Ok(());
+        };
+        let fields = self.buffer.take_captured_fields();
+        if !std::mem::take(&mut generator.pending) {
+            return Ok(());
+        }
+        let key = (generator.generate)(&generator.entry_type, &fields);
+        self.record_entry_key(&key)?;
+        self.buffer.write_entry_key(&key)?;
+        self.buffer.write_entry_key_end()?;
+        Ok(())
+    }
+
+    /// Write the buffered entry, preceded by an entry separator if an entry was already written.
+    /// Nothing is written if the entry was skipped, so that skipped entries do not leave behind
+    /// extra separators.
+    pub(crate) fn flush_entry(&mut self) -> Result<()>
+    where
+        W: io::Write,
+        F: Formatter,
+    {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        if self.pending_separator {
+            self.buffer.write_entry_separator(&mut self.writer)?;
+        }
+        self.buffer.write(&mut self.writer)?;
+        self.pending_separator = true;
+        Ok(())
+    }
+
+    /// Write the prologue if it was not yet written, returning whether anything was written.
+    ///
+    /// The prologue is followed by the [`Formatter::write_prologue_end`] separator rather than
+    /// an entry separator, so the first entry does not need a separator.
+    pub(crate) fn write_prologue(&mut self) -> Result<bool>
+    where
+        W: io::Write,
+        F: Formatter,
+    {
+        if std::mem::replace(&mut self.prologue_written, true) || self.prologue.is_empty() {
+            return Ok(false);
+        }
+        if let Some(line) = self
+            .prologue
+            .iter()
+            .find(|line| line.contains(['@', '\n', '\r']))
+        {
+            return Err(Error::ser(format!("invalid prologue line '{line}'")));
         }
+        for line in &self.prologue {
+            self.buffer.write_prologue_line(&mut self.writer, line)?;
+        }
+        self.buffer.write_prologue_end(&mut self.writer)?;
+        Ok(true)
     }
 
     /// Recover the interval writer.
@@ -280,6 +950,14 @@ impl<W, F> Serializer<W, F> {
     }
 }
 
+impl Serializer<()> {
+    /// Construct a [`SerializerBuilder`] with the default configuration, which writes with a
+    /// [`PrettyFormatter`] and validates the output.
+    pub fn builder() -> SerializerBuilder {
+        SerializerBuilder::new()
+    }
+}
+
 impl<W> Serializer<W, ValidatingFormatter<PrettyFormatter>>
 where
     W: io::Write,
@@ -310,19 +988,63 @@ where
     }
 }
 
+impl<W, F> Serializer<W, F>
+where
+    W: io::Write,
+    F: Formatter,
+{
+    /// Serialize a single entry, rather than a sequence of entries.
+    ///
+    /// The output is the same as serializing a bibliography containing only `value`, except that
+    /// nothing is written if the entry is skipped, such as an empty macro entry.
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Record {
+    ///     entry_type: &'static str,
+    ///     entry_key: &'static str,
+    ///     fields: Vec<(&'static str, &'static str)>,
+    /// }
+    ///
+    /// let record = Record {
+    ///     entry_type: "book",
+    ///     entry_key: "key",
+    ///     fields: vec![("title", "Title")],
+    /// };
+    ///
+    /// let mut ser = Serializer::new(Vec::new());
+    /// ser.serialize_entry(&record).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@book{key,\n  title = {Title},\n}\n"
+    /// );
+    /// ```
+    pub fn serialize_entry<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let mut seq = BibliographySerializer::new(self);
+        ser::SerializeSeq::serialize_element(&mut seq, value)?;
+        if seq.ser.pending_separator {
+            ser::SerializeSeq::end(seq)
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// The compound serializer type used for stateful serialization of a bibliograhy.
 pub struct BibliographySerializer<'a, W, F> {
     ser: &'a mut Serializer<W, F>,
-    skip_newline: bool,
 }
 
 impl<'a, W, F> BibliographySerializer<'a, W, F> {
     /// Create a new [`BibliographySerializer`].
     pub fn new(ser: &'a mut Serializer<W, F>) -> Self {
-        Self {
-            ser,
-            skip_newline: true,
-        }
+        Self { ser }
     }
 }
 
@@ -398,23 +1120,21 @@ macro_rules! bibliography_serializer_impl {
             where
                 T: ?Sized + serde::Serialize,
             {
-                if self.skip_newline {
-                    self.skip_newline = false;
-                } else {
-                    self.ser
-                        .buffer
-                        .write_entry_separator(&mut self.ser.writer)?;
-                }
-                self.skip_newline = value.serialize(EntrySerializer::new(&mut *self.ser))?;
-                self.ser.buffer.write(&mut self.ser.writer)?;
-                Ok(())
+                self.ser.write_prologue()?;
+                value.serialize(EntrySerializer::new(&mut *self.ser))?;
+                self.ser.finish_entry()?;
+                self.ser.flush_entry()
             }
 
             #[inline]
             fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-                self.ser
-                    .buffer
-                    .write_bibliography_end(&mut self.ser.writer)?;
+                // an empty bibliography is terminated by the prologue
+                if !self.ser.write_prologue()? {
+                    self.ser
+                        .buffer
+                        .write_bibliography_end(&mut self.ser.writer)?;
+                }
+                self.ser.pending_separator = false;
                 Ok(())
             }
         }
@@ -425,12 +1145,58 @@ bibliography_serializer_impl!(serialize_element, SerializeSeq);
 bibliography_serializer_impl!(serialize_element, SerializeTuple);
 bibliography_serializer_impl!(serialize_field, SerializeTupleStruct);
 
+/// Serialize a bibliography into the owned document model of the [value module](crate::value),
+/// rather than into bytes.
+///
+/// The entries can then be modified programmatically, for instance sorted or merged, before they
+/// are written with [`to_string`](crate::to_string) or a [`Serializer`]. The value is checked as
+/// with [`Serializer::new`], and macros are not expanded.
+///
+/// The entries are built directly from the serialized value without writing any BibTeX, so text
+/// tokens in the result are exactly the text which was serialized. As with the default
+/// [`Serializer`], the parts of a preamble are joined into a single value, and skipped entries,
+/// such as an empty macro entry, are omitted.
+/// ```
+/// use serde::Serialize;
+/// use serde_bibtex::{ser::to_entries, value::{Entry, Value}};
+///
+/// #[derive(Serialize)]
+/// struct Record {
+///     entry_type: &'static str,
+///     entry_key: &'static str,
+///     fields: Vec<(&'static str, &'static str)>,
+/// }
+///
+/// let bibliography = vec![
+///     Record { entry_type: "book", entry_key: "b", fields: vec![("title", "B")] },
+///     Record { entry_type: "article", entry_key: "a", fields: vec![("title", "A")] },
+/// ];
+///
+/// let mut entries = to_entries(&bibliography).unwrap();
+/// entries.sort_by(|a, b| match (a, b) {
+///     (Entry::Regular(a), Entry::Regular(b)) => a.entry_key.cmp(&b.entry_key),
+///     _ => std::cmp::Ordering::Equal,
+/// });
+///
+/// let Entry::Regular(first) = &entries[0] else { panic!() };
+/// assert_eq!(first.field("title"), Some(&Value::text("A")));
+/// ```
+#[cfg(feature = "entry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "entry")))]
+pub fn to_entries<T>(value: &T) -> Result<Vec<crate::value::Entry>>
+where
+    T: ?Sized + ser::Serialize,
+{
+    value.serialize(entries::EntriesSerializer)
+}
+
 #[cfg(test)]
+#[allow(clippy::type_complexity)]
 mod tests {
     use serde::Serialize;
     use std::collections::BTreeMap;
 
-    use crate::{to_string, to_string_compact};
+    use crate::{to_string, to_string_compact, to_string_entry, to_vec};
 
     #[derive(Serialize)]
     struct Record {
@@ -491,6 +1257,504 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_entry() {
+        let record = Record {
+            entry_type: "article",
+            entry_key: "1",
+            fields: vec![("author", "Auth")],
+        };
+        let expected = "@article{1,\n  author = {Auth},\n}\n";
+        assert_eq!(to_string_entry(&record).unwrap(), expected);
+        assert_eq!(to_string(&[&record]).unwrap(), expected);
+
+        assert_eq!(
+            to_string_entry(&Entry::Macro("a", "b")).unwrap(),
+            "@string{a = {b}}\n"
+        );
+        // a skipped entry produces no output
+        assert_eq!(to_string_entry(&EntryOptMacro::Macro(None)).unwrap(), "");
+        // a sequence is not an entry
+        assert!(to_string_entry(&[&record]).is_err());
+    }
+
+    #[test]
+    fn test_raw_value() {
+        use serde::Deserialize;
+
+        use super::{FieldAction, Serializer};
+        use crate::{from_entry_str, token::RawValue};
+
+        #[derive(Serialize, Deserialize)]
+        struct RawRecord<'a> {
+            entry_type: &'a str,
+            entry_key: &'a str,
+            #[serde(borrow)]
+            fields: Vec<(&'a str, RawValue<'a>)>,
+        }
+
+        let input = "@article{key,\n  title = {A } # var # \"B\",\n  year = 2024,\n}\n";
+        let record: RawRecord = from_entry_str(input).unwrap();
+        assert_eq!(to_string_entry(&record).unwrap(), input);
+        // the source is written verbatim, regardless of the formatter
+        assert_eq!(
+            crate::to_string_compact(&[&record]).unwrap(),
+            "@article{key,title={A } # var # \"B\",year=2024}"
+        );
+
+        // the field filter sees the contents of the tokens
+        let mut ser =
+            Serializer::new(Vec::new()).with_field_filter(|key, value| match (key, value) {
+                ("title", "A varB") => FieldAction::Rename("booktitle".to_owned()),
+                _ => FieldAction::Drop,
+            });
+        ser.serialize_entry(&record).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@article{key,\n  booktitle = {A } # var # \"B\",\n}\n"
+        );
+
+        // invalid raw values are rejected when the output is checked
+        #[derive(Serialize)]
+        struct Invalid {
+            entry_type: &'static str,
+            entry_key: &'static str,
+            fields: Vec<(&'static str, Raw)>,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename = "$serde_bibtex::private::RawValue")]
+        struct Raw(&'static str);
+
+        let invalid = Invalid {
+            entry_type: "article",
+            entry_key: "key",
+            fields: vec![("title", Raw("{A} # "))],
+        };
+        assert!(to_string_entry(&invalid).is_err());
+        assert!(crate::to_string_unchecked(&[&invalid]).is_ok());
+    }
+
+    #[test]
+    fn test_fixup_text() {
+        use super::{FieldAction, FixupPolicy, Serializer};
+
+        let write = |policy, text: &str| {
+            let mut ser = Serializer::compact(Vec::new()).fixup_text(policy);
+            ser.serialize_entry(&("misc", "k", [("t", text)]))
+                .map(|_| String::from_utf8(ser.into_inner()).unwrap())
+        };
+
+        for (text, escaped, balanced) in [
+            ("{a}", "{a}", "{a}"),
+            ("\"a\"", "\"a\"", "\"a\""),
+            ("a}b", "a\\textbraceright{}b", "{a}b"),
+            ("a{b", "a\\textbraceleft{}b", "a{b}"),
+            ("}{a}{", "\\textbraceright{}{a}\\textbraceleft{}", "{}{a}{}"),
+            ("{{a}", "\\textbraceleft{}{a}", "{{a}}"),
+        ] {
+            assert_eq!(
+                write(FixupPolicy::Escape, text).unwrap(),
+                format!("@misc{{k,t={{{escaped}}}}}")
+            );
+            assert_eq!(
+                write(FixupPolicy::Balance, text).unwrap(),
+                format!("@misc{{k,t={{{balanced}}}}}")
+            );
+        }
+        assert!(write(FixupPolicy::Error, "a}b").is_err());
+
+        // text rewritten by a field filter is also fixed
+        let mut ser = Serializer::compact(Vec::new())
+            .fixup_text(FixupPolicy::Balance)
+            .with_field_filter(|_, _| FieldAction::Rewrite {
+                key: "t".to_owned(),
+                value: "a}".to_owned(),
+            });
+        ser.serialize_entry(&("misc", "k", [("t", "x")])).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,t={{a}}}"
+        );
+    }
+
+    #[test]
+    fn test_escape_text() {
+        use super::{FixupPolicy, Serializer};
+
+        let mut ser = Serializer::new(Vec::new()).escape_text(true);
+        ser.serialize_entry(&("misc", "k", [("a", "x@y"), ("b", "{#}1 #2")]))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,\n  a = {x{@}y},\n  b = {{#}1 {#}2},\n}\n"
+        );
+
+        // the fixup policy is applied before escaping
+        let mut ser = Serializer::compact(Vec::new())
+            .escape_text(true)
+            .fixup_text(FixupPolicy::Escape);
+        ser.serialize_entry(&("misc", "k", [("a", "@}")])).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,a={{@}\\textbraceright{}}}"
+        );
+    }
+
+    #[test]
+    fn test_bytes() {
+        use serde_bytes::Bytes;
+
+        use super::{FieldAction, FixupPolicy, Formatter, Serializer};
+        use crate::token::Token;
+
+        // bytes are written verbatim, and only checked for balanced brackets
+        fn write<F: Formatter>(ser: Serializer<Vec<u8>, F>, text: &[u8]) -> crate::Result<Vec<u8>> {
+            let mut ser = ser.escape_text(true).fixup_text(FixupPolicy::Escape);
+            ser.serialize_entry(&("misc", "k", [("t", Bytes::new(text))]))?;
+            Ok(ser.into_inner())
+        }
+        assert_eq!(
+            write(Serializer::new(Vec::new()), b"\xe9 @{a}").unwrap(),
+            b"@misc{k,\n  t = {\xe9 @{a}},\n}\n"
+        );
+        assert!(write(Serializer::new(Vec::new()), b"\xe9}").is_err());
+        assert_eq!(
+            write(Serializer::unchecked(Vec::new()), b"\xe9}").unwrap(),
+            b"@misc{k,\n  t = {\xe9}},\n}\n"
+        );
+
+        // bytes are kept when the field is held back for a field filter
+        let mut ser = Serializer::compact(Vec::new()).with_field_filter(|_, value| {
+            assert_eq!(value, "\u{fffd}a");
+            FieldAction::Rename("u".to_owned())
+        });
+        let value: [Token<&str, &[u8]>; 2] = [
+            Token::bytes(&b"\xff"[..]).unwrap(),
+            Token::variable("a").unwrap(),
+        ];
+        ser.serialize_entry(&("misc", "k", [("t", value)])).unwrap();
+        assert_eq!(ser.into_inner(), b"@misc{k,u={\xff}#a}");
+
+        // other targets accept bytes which are valid UTF-8
+        assert_eq!(
+            to_string(&[(Bytes::new(b"misc"), 'k', [('t', 'x')])]).unwrap(),
+            "@misc{k,\n  t = {x},\n}\n"
+        );
+        assert!(to_vec(&[(Bytes::new(b"m\xffisc"), "k", [("t", "x")])]).is_err());
+
+        // a string cannot hold invalid UTF-8
+        assert!(to_string(&[("misc", "k", [("t", Bytes::new(b"\xff"))])]).is_err());
+    }
+
+    #[test]
+    fn test_validation_limits() {
+        use super::{PrettyFormatter, Serializer};
+        use crate::{token::RawValue, LineEnding};
+
+        let write = |formatter, value: &str| {
+            let mut ser =
+                Serializer::new_with_formatter(Vec::new(), formatter).line_ending(LineEnding::CrLf);
+            ser.serialize_entry(&("misc", "k", [("t", value)]))
+        };
+
+        // the longest line is `  t = {0123456789},`, and the line break is not counted
+        let limited = || PrettyFormatter {}.validate().max_line_length(19);
+        assert!(write(limited(), "0123456789").is_ok());
+        assert!(write(limited(), "0123456789a").is_err());
+        // characters are counted rather than bytes
+        assert!(write(limited(), "éééééééééé").is_ok());
+        // a line break in a value starts a new line
+        assert!(write(limited(), "0123456789\n0123456789").is_ok());
+        assert!(write(limited(), "0123456789\n0123456789abcdefghij").is_err());
+
+        let limited = || PrettyFormatter {}.validate().max_value_size(10);
+        assert!(write(limited(), "0123456789").is_ok());
+        assert!(write(limited(), "0123456789a").is_err());
+
+        // the size of a value includes every token, but not the delimiters
+        let raw = |source| {
+            let mut ser = Serializer::new_with_formatter(Vec::new(), limited());
+            ser.serialize_entry(&("misc", "k", [("t", RawValue::new(source).unwrap())]))
+        };
+        assert!(raw("{01234} # \"567\" # v9").is_ok());
+        assert!(raw("{01234} # \"567\" # v9a").is_err());
+    }
+
+    #[cfg(feature = "entry")]
+    #[test]
+    fn test_to_entries() {
+        use super::to_entries;
+        use crate::token::RawValue;
+        use crate::value::{Bibliography, Entry, RegularEntry, Token, Value};
+
+        let input = "@string{a = {A}}\n\n@comment{c}\n\n@preamble{a # { b }}\n\n@misc{k,\n  t = {x} # a,\n  u = { y  z},\n}\n";
+        let bibliography: Bibliography = crate::from_str(input).unwrap();
+        assert_eq!(to_entries(&bibliography).unwrap(), bibliography.0);
+
+        let raw = RawValue::new("{x} # \"y\" # v").unwrap();
+        let mut expected = RegularEntry::new("misc", "k");
+        expected.set_field(
+            "t",
+            Value(vec![
+                Token::Text("x".into()),
+                Token::Text("y".into()),
+                Token::Variable("v".into()),
+            ]),
+        );
+        assert_eq!(
+            to_entries(&[("misc", "k", [("t", raw)])]).unwrap(),
+            vec![Entry::Regular(expected)]
+        );
+
+        assert!(to_entries(&[("misc", "k", [("t", "}")])]).is_err());
+        assert!(to_entries(&[("misc", "a b", [("t", "x")])]).is_err());
+
+        let entries = vec![
+            Entry::Macro(None),
+            Entry::Preamble(Value::text("p")),
+            Entry::Regular(RegularEntry::new("misc", "k")),
+        ];
+        assert_eq!(to_entries(&entries).unwrap(), entries[1..]);
+    }
+
+    #[test]
+    fn test_line_ending() {
+        use super::{FieldAction, Serializer};
+        use crate::LineEnding;
+
+        let bibliography = [
+            ("misc", "a", vec![("title", "A\r\nB"), ("year", "2024")]),
+            ("misc", "b", vec![]),
+        ];
+        let mut ser = Serializer::new(Vec::new()).line_ending(LineEnding::CrLf);
+        bibliography.serialize(&mut ser).unwrap();
+        // line breaks inside values are unchanged
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{a,\r\n  title = {A\r\nB},\r\n  year = {2024},\r\n}\r\n\r\n@misc{b,\r\n}\r\n"
+        );
+
+        let mut ser = Serializer::unchecked(Vec::new())
+            .line_ending(LineEnding::CrLf)
+            .with_field_filter(|_, _| FieldAction::Keep);
+        ser.serialize_entry(&("misc", "a", [("title", "T")]))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{a,\r\n  title = {T},\r\n}\r\n"
+        );
+
+        let mut ser = Serializer::compact(Vec::new()).line_ending(LineEnding::CrLf);
+        bibliography.serialize(&mut ser).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{a,title={A\r\nB},year={2024}}@misc{b}"
+        );
+    }
+
+    #[test]
+    fn test_sort_fields() {
+        use std::collections::HashMap;
+
+        use super::{FieldAction, Serializer};
+
+        let fields: HashMap<String, String> = (0..20)
+            .map(|idx| (format!("k{:02}", 19 - idx), idx.to_string()))
+            .chain([("K05".to_owned(), "upper".to_owned())])
+            .collect();
+        let bib = vec![("article", "a", fields.clone()), ("book", "b", fields)];
+
+        let mut expected = String::new();
+        for (idx, entry_type) in ["article", "book"].into_iter().enumerate() {
+            if idx > 0 {
+                expected.push_str("\n\n");
+            }
+            expected.push_str(&format!("@{entry_type}{{{},\n", ["a", "b"][idx]));
+            for key in 0..20 {
+                if key == 5 {
+                    expected.push_str("  K05 = {upper},\n");
+                }
+                expected.push_str(&format!("  k{key:02} = {{{}}},\n", 19 - key));
+            }
+            expected.push('}');
+        }
+        expected.push('\n');
+
+        let mut ser = Serializer::new(Vec::new()).sort_fields(true);
+        bib.serialize(&mut ser).unwrap();
+        assert_eq!(String::from_utf8(ser.into_inner()).unwrap(), expected);
+
+        // fields which are dropped or renamed by a filter are sorted by their new key
+        let fields = BTreeMap::from([("a", "1"), ("b", "2"), ("c", "3")]);
+        let mut ser = Serializer::compact(Vec::new())
+            .sort_fields(true)
+            .with_field_filter(|key, _| match key {
+                "a" => FieldAction::Rename("d".to_owned()),
+                "b" => FieldAction::Drop,
+                _ => FieldAction::Keep,
+            });
+        [("misc", "key", fields)].serialize(&mut ser).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{key,c={3},d={1}}"
+        );
+
+        // fields in a sequence are not sorted
+        let out = {
+            let mut ser = Serializer::compact(Vec::new()).sort_fields(true);
+            [("misc", "key", [("b", "1"), ("a", "2")])]
+                .serialize(&mut ser)
+                .unwrap();
+            String::from_utf8(ser.into_inner()).unwrap()
+        };
+        assert_eq!(out, "@misc{key,b={1},a={2}}");
+    }
+
+    #[test]
+    fn test_duplicate_entry_keys() {
+        use std::sync::{Arc, Mutex};
+
+        use super::{DuplicateEntryKeys, Serializer};
+
+        let bib = vec![
+            ("article", "key", [("title", "A")]),
+            ("book", "other", [("title", "B")]),
+            ("book", "KEY", [("title", "C")]),
+        ];
+
+        // duplicates are permitted by default
+        let mut ser = Serializer::compact(Vec::new());
+        assert!(bib.serialize(&mut ser).is_ok());
+
+        let mut ser = Serializer::compact(Vec::new()).check_entry_keys(DuplicateEntryKeys::Error);
+        let err = bib.serialize(&mut ser).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid serialization format: duplicate entry key 'KEY'"
+        );
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&warnings);
+        let mut ser = Serializer::compact(Vec::new()).check_entry_keys(DuplicateEntryKeys::Warn(
+            Box::new(move |key| sink.lock().unwrap().push(key.to_owned())),
+        ));
+        bib.serialize(&mut ser).unwrap();
+        assert_eq!(*warnings.lock().unwrap(), vec!["KEY".to_owned()]);
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@article{key,title={A}}@book{other,title={B}}@book{KEY,title={C}}"
+        );
+    }
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_send() {
+        // callbacks must not prevent moving a serializer to another thread
+        assert_send::<super::DuplicateEntryKeys>();
+        assert_send::<super::formatter::FieldFilter>();
+        assert_send::<super::KeyGenerator>();
+        assert_send::<super::UndefinedVariables>();
+    }
+
+    #[test]
+    fn test_field_filter() {
+        use super::{FieldAction, Serializer};
+
+        let bib = vec![
+            Entry::Regular(Record {
+                entry_type: "article",
+                entry_key: "key",
+                fields: vec![
+                    ("title", "Title"),
+                    ("abstract", "Long"),
+                    ("url", "https://example.com"),
+                    ("journal", "J"),
+                ],
+            }),
+            Entry::Macro("apr", "04"),
+        ];
+
+        let mut ser = Serializer::compact(Vec::new()).with_field_filter(|key, value| match key {
+            "abstract" | "file" => FieldAction::Drop,
+            "journal" => FieldAction::Rename("journaltitle".into()),
+            "url" => FieldAction::Rewrite {
+                key: "howpublished".into(),
+                value: format!("\\url{{{value}}}"),
+            },
+            _ => FieldAction::Keep,
+        });
+        bib.serialize(&mut ser).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@article{key,title={Title},howpublished={\\url{https://example.com}},journaltitle={J}}@string{apr={04}}"
+        );
+
+        // token lists are passed to the filter as the concatenated token contents
+        let bib = vec![EntryFullValue::Regular(
+            "article",
+            "key",
+            BTreeMap::from([("title", vec![Value::Text("A"), Value::Variable("b")])]),
+        )];
+        let mut ser = Serializer::compact(Vec::new()).with_field_filter(|_, value| {
+            assert_eq!(value, "Ab");
+            FieldAction::Keep
+        });
+        bib.serialize(&mut ser).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@article{key,title={A}#b}"
+        );
+
+        // renamed fields are still validated
+        let bib = vec![("article", "key", [("title", "A")])];
+        let mut ser = Serializer::compact(Vec::new())
+            .with_field_filter(|_, _| FieldAction::Rename("bad key".into()));
+        assert!(bib.serialize(&mut ser).is_err());
+    }
+
+    #[test]
+    fn test_entry_type_aliases() {
+        use super::{Serializer, BIBLATEX_TO_BIBTEX, BIBTEX_TO_BIBLATEX};
+
+        let bib = vec![
+            ("PhdThesis", "a", []),
+            ("electronic", "b", []),
+            ("article", "c", []),
+        ];
+        type Bibliography = Vec<(
+            &'static str,
+            &'static str,
+            [(&'static str, &'static str); 0],
+        )>;
+        let to_string_with = |bib: &Bibliography, aliases: &[(&str, &str)]| {
+            let mut ser =
+                Serializer::compact(Vec::new()).with_entry_type_aliases(aliases.iter().copied());
+            bib.serialize(&mut ser).unwrap();
+            String::from_utf8(ser.into_inner()).unwrap()
+        };
+
+        assert_eq!(
+            to_string_with(&bib, &BIBTEX_TO_BIBLATEX),
+            "@thesis{a}@online{b}@article{c}"
+        );
+        assert_eq!(
+            to_string_with(&bib, &BIBLATEX_TO_BIBTEX),
+            "@PhdThesis{a}@electronic{b}@article{c}"
+        );
+
+        // later aliases take precedence
+        let mut ser = Serializer::compact(Vec::new())
+            .with_entry_type_aliases(BIBTEX_TO_BIBLATEX)
+            .with_entry_type_aliases([("electronic", "misc")]);
+        bib.serialize(&mut ser).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@thesis{a}@misc{b}@article{c}"
+        );
+    }
+
     #[test]
     fn test_enum_skip() {
         let bib = vec![
@@ -510,6 +1774,10 @@ mod tests {
             out,
             "@article{1,\n  author = {Auth},\n  year = {2022},\n}\n\n@preamble{{preamble}}\n\n@string{apr = {04}}\n"
         );
+
+        // skipped entries at the end do not leave separators behind
+        let bib = vec![Entry::Preamble("preamble"), Entry::Comment, Entry::Comment];
+        assert_eq!(to_string(&bib).unwrap(), "@preamble{{preamble}}\n");
     }
 
     #[test]
@@ -522,6 +1790,13 @@ mod tests {
 
         let out = to_string(&bib).unwrap();
         assert_eq!(out, "@string{apr = {04}}\n\n@comment{}\n");
+
+        let bib = vec![
+            EntryOptMacro::Comment("a"),
+            EntryOptMacro::Macro(None),
+            EntryOptMacro::Macro(None),
+        ];
+        assert_eq!(to_string(&bib).unwrap(), "@comment{a}\n");
     }
 
     #[test]
@@ -596,4 +1871,650 @@ mod tests {
             "@preamble{a # {txt}}\n\n@preprint{1,\n  author = {First} # sep # {Last},\n}\n\n@preamble{}\n"
         );
     }
+
+    #[test]
+    fn test_preamble_parts() {
+        use super::Serializer;
+
+        #[derive(Serialize)]
+        enum Parts {
+            Preamble(Vec<Vec<Value>>),
+        }
+
+        #[derive(Serialize)]
+        enum Strs {
+            Preamble(Vec<&'static str>),
+        }
+
+        let parts = vec![
+            Parts::Preamble(vec![
+                vec![Value::Variable("a"), Value::Text("b")],
+                Vec::new(),
+                vec![Value::Text("c")],
+            ]),
+            Parts::Preamble(Vec::new()),
+        ];
+        assert_eq!(
+            to_string_compact(&parts).unwrap(),
+            "@preamble{a#{b}#{c}}@preamble{}"
+        );
+
+        let mut ser = Serializer::new(Vec::new()).split_preamble(true);
+        for entry in &parts {
+            ser.serialize_entry(entry).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@preamble{a # {b}}\n\n@preamble{{c}}\n@preamble{}\n"
+        );
+
+        let mut ser = Serializer::new(Vec::new()).split_preamble(true);
+        ser.serialize_entry(&Strs::Preamble(vec!["", "x", "", "y"]))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@preamble{{x}}\n\n@preamble{{y}}\n"
+        );
+
+        // a single sequence of tokens is never split
+        let mut ser = Serializer::compact(Vec::new()).split_preamble(true);
+        ser.serialize_entry(&EntryFullValue::Preamble(vec![
+            Value::Variable("a"),
+            Value::Text("txt"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@preamble{a#{txt}}"
+        );
+
+        assert!(to_string(&Strs::Preamble(vec!["}"])).is_err());
+    }
+
+    #[test]
+    fn test_write_macros() {
+        use crate::token::{Text, Token, Variable};
+        use crate::{to_string_with_macros, MacroDictionary};
+
+        let mut macros: MacroDictionary<&str, &[u8]> = MacroDictionary::default();
+        macros.insert(
+            Variable::new("b").unwrap(),
+            vec![Token::variable("A").unwrap(), Token::str("x").unwrap()],
+        );
+        macros.insert(
+            Variable::new("A").unwrap(),
+            vec![Token::Text(Text::Bytes(b"bytes"))],
+        );
+        macros.insert(Variable::new("c").unwrap(), Vec::new());
+
+        let bib = vec![Record {
+            entry_key: "1",
+            entry_type: "misc",
+            fields: vec![("key", "val")],
+        }];
+        assert_eq!(
+            to_string_with_macros(&macros, &bib).unwrap(),
+            "@string{A = {bytes}}\n\n@string{b = A # {x}}\n\n@string{c = }\n\n@misc{1,\n  key = {val},\n}\n"
+        );
+
+        let empty: Vec<Record> = Vec::new();
+        assert_eq!(
+            to_string_with_macros(&macros, &empty).unwrap(),
+            "@string{A = {bytes}}\n\n@string{b = A # {x}}\n\n@string{c = }\n"
+        );
+        assert_eq!(
+            to_string_with_macros(&MacroDictionary::<&str, &[u8]>::default(), &bib).unwrap(),
+            to_string(&bib).unwrap()
+        );
+
+        // macros follow the macros they refer to, and are otherwise in insertion order
+        let mut macros: MacroDictionary<&str, &[u8]> = MacroDictionary::default();
+        macros.insert(Variable::new("z").unwrap(), vec![Token::str("z").unwrap()]);
+        macros.insert(
+            Variable::new("b").unwrap(),
+            vec![Token::variable("a").unwrap(), Token::str("x").unwrap()],
+        );
+        macros.insert(Variable::new("a").unwrap(), vec![Token::str("y").unwrap()]);
+        assert_eq!(
+            to_string_with_macros(&macros, &empty).unwrap(),
+            "@string{z = {z}}\n\n@string{a = {y}}\n\n@string{b = a # {x}}\n"
+        );
+    }
+
+    #[test]
+    fn test_write_comment() {
+        use super::Serializer;
+
+        let record = Record {
+            entry_key: "1",
+            entry_type: "misc",
+            fields: vec![("key", "val")],
+        };
+
+        let mut ser = Serializer::new(Vec::new());
+        ser.write_comment("Section {A}").unwrap();
+        ser.write_comment("").unwrap();
+        ser.serialize_entry(&record).unwrap();
+        ser.write_comment("Section B").unwrap();
+        ser.serialize_entry(&record).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@comment{Section {A}}\n\n@comment{}\n\n@misc{1,\n  key = {val},\n}\n@comment{Section B}\n\n@misc{1,\n  key = {val},\n}\n"
+        );
+
+        let mut ser = Serializer::new(Vec::new());
+        assert!(ser.write_comment("}{").is_err());
+    }
+
+    #[cfg(feature = "entry")]
+    #[test]
+    fn test_macro_delimiters() {
+        use std::io;
+
+        use super::{BodyDelimiters, CompactFormatter, Formatter, Serializer, ValidatingFormatter};
+
+        struct Parentheses;
+
+        impl Formatter for Parentheses {
+            fn macro_delimiters(&self) -> BodyDelimiters {
+                BodyDelimiters::Parentheses
+            }
+        }
+
+        /// A formatter which does not close macro bodies with the matching delimiter.
+        struct Mismatched;
+
+        impl Formatter for Mismatched {
+            fn write_macro_body_start<W>(&mut self, writer: &mut W) -> io::Result<()>
+            where
+                W: ?Sized + io::Write,
+            {
+                writer.write_all(b"(")
+            }
+        }
+
+        let bib = vec![
+            Entry::Macro("a", "A"),
+            Entry::Regular(Record {
+                entry_type: "misc",
+                entry_key: "k",
+                fields: vec![("t", "x)")],
+            }),
+        ];
+
+        let mut ser =
+            Serializer::new_with_formatter(Vec::new(), ValidatingFormatter::new(Parentheses));
+        bib.serialize(&mut ser).unwrap();
+        let output = String::from_utf8(ser.into_inner()).unwrap();
+        assert_eq!(output, "@string(a = {A})\n\n@misc{k,\n  t = {x)},\n}\n");
+        assert_eq!(
+            crate::from_str::<Vec<crate::value::Entry>>(&output).unwrap(),
+            crate::from_str::<Vec<crate::value::Entry>>(&to_string(&bib).unwrap()).unwrap()
+        );
+
+        // the end of a regular entry may be written before the start
+        #[derive(Serialize)]
+        struct Reversed {
+            fields: Vec<(&'static str, &'static str)>,
+            entry_key: &'static str,
+            entry_type: &'static str,
+        }
+        let reversed = Reversed {
+            fields: vec![("t", "x")],
+            entry_key: "k",
+            entry_type: "misc",
+        };
+        let mut ser = Serializer::compact(Vec::new());
+        ser.serialize_entry(&reversed).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,t={x}}"
+        );
+
+        let mut ser =
+            Serializer::new_with_formatter(Vec::new(), ValidatingFormatter::new(Mismatched));
+        assert!(ser.serialize_entry(&Entry::Macro("a", "A")).is_err());
+        let mut ser = Serializer::new_with_formatter(Vec::new(), Mismatched);
+        ser.serialize_entry(&Entry::Macro("a", "A")).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@string(a = {A}}\n"
+        );
+
+        let formatter = ValidatingFormatter::new(Mismatched).check_syntax(false);
+        let mut ser = Serializer::new_with_formatter(Vec::new(), formatter);
+        assert!(ser.serialize_entry(&Entry::Macro("a", "A")).is_ok());
+
+        let mut ser = Serializer::new_with_formatter(Vec::new(), CompactFormatter {}.validate());
+        ser.serialize_entry(&Entry::Macro("a", "A")).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@string{a={A}}"
+        );
+    }
+
+    #[test]
+    fn test_check_variables() {
+        use std::sync::{Arc, Mutex};
+
+        use super::{Serializer, UndefinedVariables};
+        use crate::token::{RawValue, Token, Variable};
+        use crate::MacroDictionary;
+
+        #[derive(Serialize)]
+        enum Entry {
+            Macro(&'static str, Vec<Value>),
+            Regular(
+                &'static str,
+                &'static str,
+                BTreeMap<&'static str, Vec<Value>>,
+            ),
+        }
+
+        let mut macros: MacroDictionary<&str, &[u8]> = MacroDictionary::default();
+        macros.insert(
+            Variable::new("Known").unwrap(),
+            vec![Token::str("K").unwrap()],
+        );
+
+        let bib = vec![
+            Entry::Regular(
+                "misc",
+                "a",
+                BTreeMap::from([
+                    (
+                        "t",
+                        vec![Value::Variable("KNOWN"), Value::Variable("later")],
+                    ),
+                    ("u", vec![Value::Text("x"), Value::Variable("undefined")]),
+                ]),
+            ),
+            Entry::Macro("later", vec![Value::Variable("known"), Value::Text("L")]),
+            Entry::Regular(
+                "misc",
+                "b",
+                BTreeMap::from([("t", vec![Value::Variable("Later")])]),
+            ),
+        ];
+
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&warnings);
+        let mut ser = Serializer::compact(Vec::new()).check_variables(
+            &macros,
+            UndefinedVariables::Warn(Box::new(move |name| {
+                sink.lock().unwrap().push(name.to_owned())
+            })),
+        );
+        bib.serialize(&mut ser).unwrap();
+        // a macro must be defined before it is used
+        assert_eq!(*warnings.lock().unwrap(), vec!["later", "undefined"]);
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{a,t=KNOWN#later,u={x}#undefined}@string{later=known#{L}}@misc{b,t=Later}"
+        );
+
+        let mut ser =
+            Serializer::compact(Vec::new()).check_variables(&macros, UndefinedVariables::Error);
+        let err = bib.serialize(&mut ser).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid serialization format: undefined macro 'later'"
+        );
+
+        // macros written from a dictionary are defined
+        let mut ser = Serializer::compact(Vec::new()).check_variables(
+            &MacroDictionary::<&str, &[u8]>::default(),
+            UndefinedVariables::Error,
+        );
+        ser.write_macros(&macros).unwrap();
+        ser.serialize_entry(&bib[2]).unwrap_err();
+        ser.serialize_entry(&bib[1]).unwrap();
+
+        // variables in raw values are checked
+        let raw = |source| ("misc", "k", [("t", RawValue::new(source).unwrap())]);
+        let mut ser =
+            Serializer::compact(Vec::new()).check_variables(&macros, UndefinedVariables::Error);
+        ser.serialize_entry(&raw("{x} # known")).unwrap();
+        assert!(ser.serialize_entry(&raw("{x} # other")).is_err());
+    }
+
+    #[test]
+    fn test_key_generator() {
+        use std::collections::HashMap;
+
+        use super::{DuplicateEntryKeys, FieldAction, Serializer};
+
+        #[derive(Serialize)]
+        struct Reversed {
+            fields: HashMap<&'static str, &'static str>,
+            entry_key: &'static str,
+            entry_type: &'static str,
+        }
+
+        let serializer = || {
+            let mut count = 0;
+            Serializer::compact(Vec::new())
+                .with_entry_type_aliases([("electronic", "online")])
+                .with_field_filter(|key, _| match key {
+                    "abstract" => FieldAction::Drop,
+                    _ => FieldAction::Keep,
+                })
+                .sort_fields(true)
+                .with_key_generator(move |entry_type, fields| {
+                    count += 1;
+                    let fields: Vec<_> = fields.iter().map(|(k, v)| format!("{k}{v}")).collect();
+                    format!("{entry_type}{count}:{}", fields.join(":"))
+                })
+        };
+
+        let bib = vec![
+            Entry::Regular(Record {
+                entry_key: "",
+                entry_type: "electronic",
+                fields: vec![("title", "T"), ("abstract", "A")],
+            }),
+            Entry::Macro("a", "A"),
+            Entry::Regular(Record {
+                entry_key: "given",
+                entry_type: "misc",
+                fields: vec![("title", "U")],
+            }),
+            Entry::Regular(Record {
+                entry_key: " ",
+                entry_type: "book",
+                fields: Vec::new(),
+            }),
+        ];
+        let mut ser = serializer();
+        bib.serialize(&mut ser).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@online{online1:titleT,title={T}}@string{a={A}}@misc{given,title={U}}@book{book2:}"
+        );
+
+        // the fields may be written before the entry type and key
+        let mut ser = serializer();
+        ser.serialize_entry(&Reversed {
+            fields: HashMap::from([("year", "2024"), ("author", "B")]),
+            entry_key: "",
+            entry_type: "article",
+        })
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@article{article1:authorB:year2024,author={B},year={2024}}"
+        );
+
+        // generated keys are validated and checked for duplicates
+        let mut ser = Serializer::compact(Vec::new()).with_key_generator(|_, _| "a b".to_owned());
+        assert!(ser.serialize_entry(&("misc", "", [("t", "T")])).is_err());
+        let mut ser = Serializer::compact(Vec::new())
+            .check_entry_keys(DuplicateEntryKeys::Error)
+            .with_key_generator(|_, _| "k".to_owned());
+        ser.serialize_entry(&("misc", "", [("t", "T")])).unwrap();
+        assert!(ser.serialize_entry(&("misc", "", [("t", "T")])).is_err());
+
+        // without a generator, an empty key is an error
+        assert!(to_string(&[("misc", "", [("t", "T")])]).is_err());
+    }
+
+    #[test]
+    fn test_skip_empty_fields() {
+        use super::{FieldAction, Serializer};
+        use crate::token::RawValue;
+
+        let bib = vec![EntryFullValue::Regular(
+            "misc",
+            "k",
+            BTreeMap::from([
+                ("a", vec![Value::Text(" "), Value::Text("")]),
+                ("b", vec![Value::Text("")]),
+                ("c", vec![Value::Text(""), Value::Variable("v")]),
+                ("d", Vec::new()),
+                ("e", vec![Value::Text("\n\tx")]),
+            ]),
+        )];
+        let mut ser = Serializer::compact(Vec::new()).skip_empty_fields(true);
+        bib.serialize(&mut ser).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,c={}#v,e={\n\tx}}"
+        );
+
+        // the value written by the field filter is checked
+        let mut ser = Serializer::compact(Vec::new())
+            .skip_empty_fields(true)
+            .with_field_filter(|key, _| match key {
+                "a" => FieldAction::Rewrite {
+                    key: "a".to_owned(),
+                    value: "filled".to_owned(),
+                },
+                "b" => FieldAction::Rewrite {
+                    key: "b".to_owned(),
+                    value: " ".to_owned(),
+                },
+                _ => FieldAction::Keep,
+            });
+        ser.serialize_entry(&("misc", "k", [("a", ""), ("b", "B"), ("c", "")]))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,a={filled}}"
+        );
+
+        let mut ser = Serializer::compact(Vec::new()).skip_empty_fields(true);
+        ser.serialize_entry(&(
+            "misc",
+            "k",
+            [
+                ("a", RawValue::new("{} # \" \"").unwrap()),
+                ("b", RawValue::new("{} # v").unwrap()),
+            ],
+        ))
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,b={} # v}"
+        );
+    }
+
+    #[cfg(feature = "entry")]
+    #[test]
+    fn test_trailing_comma() {
+        use std::collections::HashMap;
+
+        use super::{FieldAction, Formatter, Serializer, ValidatingFormatter};
+        use crate::LineEnding;
+
+        struct NoTrailingComma;
+
+        impl Formatter for NoTrailingComma {
+            fn trailing_comma(&self) -> bool {
+                false
+            }
+        }
+
+        let serializer = || {
+            Serializer::new_with_formatter(Vec::new(), ValidatingFormatter::new(NoTrailingComma))
+        };
+
+        let bib = vec![
+            (
+                "misc",
+                "a",
+                HashMap::from([("year", "2024"), ("title", "T")]),
+            ),
+            ("misc", "b", HashMap::new()),
+            (
+                "misc",
+                "c",
+                HashMap::from([("abstract", "X"), ("note", "N")]),
+            ),
+        ];
+        let mut ser = serializer()
+            .sort_fields(true)
+            .line_ending(LineEnding::CrLf)
+            .with_field_filter(|key, _| match key {
+                "note" => FieldAction::Drop,
+                _ => FieldAction::Keep,
+            });
+        bib.serialize(&mut ser).unwrap();
+        let output = String::from_utf8(ser.into_inner()).unwrap();
+        assert_eq!(
+            output,
+            "@misc{a,\r\n  title = {T},\r\n  year = {2024}\r\n}\r\n\r\n@misc{b,\r\n}\r\n\r\n@misc{c,\r\n  abstract = {X}\r\n}\r\n"
+        );
+
+        // both forms are read back as the same bibliography
+        let mut ser = Serializer::new(Vec::new())
+            .sort_fields(true)
+            .with_field_filter(|key, _| match key {
+                "note" => FieldAction::Drop,
+                _ => FieldAction::Keep,
+            });
+        bib.serialize(&mut ser).unwrap();
+        let with_comma = String::from_utf8(ser.into_inner()).unwrap();
+        assert_eq!(
+            crate::from_str::<Vec<crate::value::Entry>>(&output).unwrap(),
+            crate::from_str::<Vec<crate::value::Entry>>(&with_comma).unwrap()
+        );
+
+        // a comma within the last value is kept
+        let mut ser = serializer();
+        ser.serialize_entry(&("misc", "k", [("author", "A,")]))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "@misc{k,\n  author = {A,}\n}\n"
+        );
+    }
+
+    #[cfg(feature = "entry")]
+    #[test]
+    fn test_prologue() {
+        use super::Serializer;
+        use crate::token::{Token, Variable};
+        use crate::{LineEnding, MacroDictionary};
+
+        let record = Record {
+            entry_key: "1",
+            entry_type: "misc",
+            fields: vec![("key", "val")],
+        };
+
+        // the prologue is written once, before the macros and the entries
+        let mut macros: MacroDictionary<&str, &[u8]> = MacroDictionary::default();
+        macros.insert(Variable::new("a").unwrap(), vec![Token::str("A").unwrap()]);
+        let mut ser = Serializer::compact(Vec::new()).with_prologue(["Encoding: UTF-8"]);
+        ser.write_macros(&macros).unwrap();
+        ser.serialize_entry(&record).unwrap();
+        // lines added after the prologue was written are not written
+        let mut ser = ser.with_prologue(["late"]);
+        ser.serialize_entry(&record).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            "% Encoding: UTF-8\n@string{a={A}}@misc{1,key={val}}@misc{1,key={val}}"
+        );
+
+        let empty: Vec<Record> = Vec::new();
+        let mut ser = Serializer::builder()
+            .with_prologue(["a", ""])
+            .line_ending(LineEnding::CrLf)
+            .build(Vec::new());
+        empty.serialize(&mut ser).unwrap();
+        let output = String::from_utf8(ser.into_inner()).unwrap();
+        assert_eq!(output, "% a\r\n% \r\n\r\n");
+        assert!(crate::from_str::<Vec<crate::value::Entry>>(&output)
+            .unwrap()
+            .is_empty());
+
+        // an empty prologue writes nothing
+        let mut ser = Serializer::new(Vec::new()).with_prologue(Vec::<String>::new());
+        ser.serialize_entry(&record).unwrap();
+        assert_eq!(ser.into_inner(), to_string(&[&record]).unwrap().as_bytes());
+
+        for line in ["by user@host", "two\nlines"] {
+            let mut ser = Serializer::new(Vec::new()).with_prologue([line]);
+            assert!(ser.serialize_entry(&record).is_err());
+        }
+    }
+
+    #[test]
+    fn test_write_vectored() {
+        use std::collections::HashMap;
+        use std::io::{self, IoSlice, Write};
+
+        use super::{FieldAction, Serializer};
+
+        /// A writer which accepts at most 7 bytes per write, counting the writes.
+        #[derive(Default)]
+        struct Partial {
+            output: Vec<u8>,
+            writes: usize,
+            vectored: usize,
+        }
+
+        impl Write for Partial {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.writes += 1;
+                let n = buf.len().min(7);
+                self.output.extend_from_slice(&buf[..n]);
+                Ok(n)
+            }
+
+            fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+                self.vectored += 1;
+                let mut n = 0;
+                for buf in bufs {
+                    let len = buf.len().min(7 - n);
+                    self.output.extend_from_slice(&buf[..len]);
+                    n += len;
+                }
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let entries = [
+            (
+                "misc",
+                "a",
+                HashMap::from([("year", "2024"), ("title", "T")]),
+            ),
+            (
+                "book",
+                "bc",
+                HashMap::from([("note", "N"), ("author", "A B")]),
+            ),
+        ];
+        let serialize = |vectored| {
+            let mut ser = Serializer::new(Partial::default())
+                .sort_fields(true)
+                .with_field_filter(|key, _| match key {
+                    "note" => FieldAction::Drop,
+                    _ => FieldAction::Keep,
+                })
+                .write_vectored(vectored);
+            for entry in &entries {
+                ser.serialize_entry(entry).unwrap();
+            }
+            ser.into_inner()
+        };
+
+        let expected =
+            "@misc{a,\n  title = {T},\n  year = {2024},\n}\n@book{bc,\n  author = {A B},\n}\n";
+        let written = serialize(false);
+        assert_eq!(String::from_utf8(written.output).unwrap(), expected);
+        assert_eq!(written.vectored, 0);
+        let writes = written.writes;
+
+        // partial vectored writes are resumed, with fewer writes in total
+        let written = serialize(true);
+        assert_eq!(String::from_utf8(written.output).unwrap(), expected);
+        assert!(written.vectored > 0);
+        assert!(written.vectored + written.writes < writes);
+    }
 }
@@ -0,0 +1,342 @@
+use std::io;
+
+use unicase::UniCase;
+
+use crate::{LineEnding, MacroDictionary};
+
+use super::formatter::FieldFilter;
+use super::{
+    DuplicateEntryKeys, FieldAction, FixupPolicy, KeyGenerator, PrettyFormatter, Serializer,
+    UndefinedVariables, ValidatingFormatter, VariableTracker,
+};
+
+/// A builder for a [`Serializer`], which collects the configuration before the writer is
+/// provided.
+///
+/// Each method corresponds to the [`Serializer`] or [`ValidatingFormatter`] method of the same
+/// name, with the same defaults. The output is written by a [`PrettyFormatter`] unless another
+/// formatter is set with [`SerializerBuilder::formatter`], and the formatter is always wrapped in
+/// a [`ValidatingFormatter`]. Construct a builder using [`Serializer::builder`], and then
+/// construct the serializer using [`SerializerBuilder::build`].
+/// ```
+/// use std::collections::HashMap;
+///
+/// use serde_bibtex::ser::{CompactFormatter, FieldAction, Serializer};
+///
+/// let fields = HashMap::from([("year", "2024"), ("title", "T"), ("abstract", "...")]);
+///
+/// let mut ser = Serializer::builder()
+///     .formatter(CompactFormatter {})
+///     .validate(true)
+///     .sort_fields(true)
+///     .with_field_filter(|key, _| match key {
+///         "abstract" => FieldAction::Drop,
+///         _ => FieldAction::Keep,
+///     })
+///     .build(Vec::new());
+///
+/// ser.serialize_entry(&("book", "key", fields)).unwrap();
+/// assert_eq!(
+///     String::from_utf8(ser.into_inner()).unwrap(),
+///     "@book{key,title={T},year={2024}}"
+/// );
+/// ```
+pub struct SerializerBuilder<F = PrettyFormatter> {
+    formatter: F,
+    validate: bool,
+    max_line_length: Option<usize>,
+    max_value_size: Option<usize>,
+    entry_keys: Option<DuplicateEntryKeys>,
+    /// The names of the defined macros, and the policy for undefined variables.
+    variables: Option<(Vec<String>, UndefinedVariables)>,
+    entry_types: Vec<(String, String)>,
+    prologue: Vec<String>,
+    sort_fields: bool,
+    split_preamble: bool,
+    line_ending: LineEnding,
+    fixup_text: FixupPolicy,
+    escape_text: bool,
+    write_vectored: bool,
+    skip_empty_fields: bool,
+    field_filter: Option<FieldFilter>,
+    key_generator: Option<KeyGenerator>,
+}
+
+impl Default for SerializerBuilder {
+    fn default() -> Self {
+        Self {
+            formatter: PrettyFormatter {},
+            validate: true,
+            max_line_length: None,
+            max_value_size: None,
+            entry_keys: None,
+            variables: None,
+            entry_types: Vec::new(),
+            prologue: Vec::new(),
+            sort_fields: false,
+            split_preamble: false,
+            line_ending: LineEnding::default(),
+            fixup_text: FixupPolicy::default(),
+            escape_text: false,
+            write_vectored: false,
+            skip_empty_fields: false,
+            field_filter: None,
+            key_generator: None,
+        }
+    }
+}
+
+impl SerializerBuilder {
+    /// Construct a builder with the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<F> SerializerBuilder<F> {
+    /// Set the formatter which writes the output.
+    pub fn formatter<G>(self, formatter: G) -> SerializerBuilder<G> {
+        SerializerBuilder {
+            formatter,
+            validate: self.validate,
+            max_line_length: self.max_line_length,
+            max_value_size: self.max_value_size,
+            entry_keys: self.entry_keys,
+            variables: self.variables,
+            entry_types: self.entry_types,
+            prologue: self.prologue,
+            sort_fields: self.sort_fields,
+            split_preamble: self.split_preamble,
+            line_ending: self.line_ending,
+            fixup_text: self.fixup_text,
+            escape_text: self.escape_text,
+            write_vectored: self.write_vectored,
+            skip_empty_fields: self.skip_empty_fields,
+            field_filter: self.field_filter,
+            key_generator: self.key_generator,
+        }
+    }
+
+    /// Set whether the output is checked to be valid BibTeX. The default is `true`.
+    ///
+    /// See [`ValidatingFormatter::check_syntax`].
+    pub fn validate(mut self, enable: bool) -> Self {
+        self.validate = enable;
+        self
+    }
+
+    /// See [`ValidatingFormatter::max_line_length`].
+    pub fn max_line_length(mut self, max: usize) -> Self {
+        self.max_line_length = Some(max);
+        self
+    }
+
+    /// See [`ValidatingFormatter::max_value_size`].
+    pub fn max_value_size(mut self, max: usize) -> Self {
+        self.max_value_size = Some(max);
+        self
+    }
+
+    /// See [`Serializer::with_entry_type_aliases`].
+    pub fn with_entry_type_aliases<I, K, V>(mut self, aliases: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.entry_types.extend(
+            aliases
+                .into_iter()
+                .map(|(from, to)| (from.into(), to.into())),
+        );
+        self
+    }
+
+    /// See [`Serializer::with_prologue`].
+    pub fn with_prologue<I, S>(mut self, lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.prologue.extend(lines.into_iter().map(Into::into));
+        self
+    }
+
+    /// See [`Serializer::check_entry_keys`].
+    pub fn check_entry_keys(mut self, policy: DuplicateEntryKeys) -> Self {
+        self.entry_keys = Some(policy);
+        self
+    }
+
+    /// See [`Serializer::check_variables`].
+    pub fn check_variables<S, B>(
+        mut self,
+        macros: &MacroDictionary<S, B>,
+        policy: UndefinedVariables,
+    ) -> Self
+    where
+        S: AsRef<str>,
+        B: AsRef<[u8]>,
+    {
+        let names = macros.iter().map(|(name, _)| name.as_ref().to_owned());
+        self.variables = Some((names.collect(), policy));
+        self
+    }
+
+    /// See [`Serializer::sort_fields`].
+    pub fn sort_fields(mut self, enable: bool) -> Self {
+        self.sort_fields = enable;
+        self
+    }
+
+    /// See [`Serializer::split_preamble`].
+    pub fn split_preamble(mut self, enable: bool) -> Self {
+        self.split_preamble = enable;
+        self
+    }
+
+    /// See [`Serializer::line_ending`].
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// See [`Serializer::fixup_text`].
+    pub fn fixup_text(mut self, policy: FixupPolicy) -> Self {
+        self.fixup_text = policy;
+        self
+    }
+
+    /// See [`Serializer::escape_text`].
+    pub fn escape_text(mut self, enable: bool) -> Self {
+        self.escape_text = enable;
+        self
+    }
+
+    /// See [`Serializer::write_vectored`].
+    pub fn write_vectored(mut self, enable: bool) -> Self {
+        self.write_vectored = enable;
+        self
+    }
+
+    /// See [`Serializer::skip_empty_fields`].
+    pub fn skip_empty_fields(mut self, enable: bool) -> Self {
+        self.skip_empty_fields = enable;
+        self
+    }
+
+    /// See [`Serializer::with_field_filter`].
+    pub fn with_field_filter<G>(mut self, filter: G) -> Self
+    where
+        G: Fn(&str, &str) -> FieldAction + Send + 'static,
+    {
+        self.field_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// See [`Serializer::with_key_generator`].
+    pub fn with_key_generator<G>(mut self, generator: G) -> Self
+    where
+        G: FnMut(&str, &[(String, String)]) -> String + Send + 'static,
+    {
+        self.key_generator = Some(Box::new(generator));
+        self
+    }
+
+    /// Construct a serializer which writes to `writer`.
+    pub fn build<W: io::Write>(self, writer: W) -> Serializer<W, ValidatingFormatter<F>> {
+        let mut formatter = ValidatingFormatter::new(self.formatter).check_syntax(self.validate);
+        if let Some(max) = self.max_line_length {
+            formatter = formatter.max_line_length(max);
+        }
+        if let Some(max) = self.max_value_size {
+            formatter = formatter.max_value_size(max);
+        }
+
+        let mut ser = Serializer::new_with_formatter(writer, formatter)
+            .with_entry_type_aliases(self.entry_types)
+            .with_prologue(self.prologue)
+            .sort_fields(self.sort_fields)
+            .split_preamble(self.split_preamble)
+            .line_ending(self.line_ending)
+            .fixup_text(self.fixup_text)
+            .escape_text(self.escape_text)
+            .write_vectored(self.write_vectored)
+            .skip_empty_fields(self.skip_empty_fields);
+        if let Some(policy) = self.entry_keys {
+            ser = ser.check_entry_keys(policy);
+        }
+        if let Some((names, policy)) = self.variables {
+            ser.variables = Some(VariableTracker {
+                policy,
+                defined: names.into_iter().map(UniCase::new).collect(),
+            });
+        }
+        if let Some(filter) = self.field_filter {
+            ser.buffer.set_field_filter(filter);
+        }
+        if let Some(generator) = self.key_generator {
+            ser.set_key_generator(generator);
+        }
+        ser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::CompactFormatter;
+
+    fn output<W: AsRef<[u8]>>(writer: W) -> String {
+        String::from_utf8(writer.as_ref().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_builder() {
+        let record = ("conference", "a key", [("title", "T}"), ("note", "N")]);
+
+        // by default the output is validated
+        let mut ser = Serializer::builder().build(Vec::new());
+        assert!(ser.serialize_entry(&record).is_err());
+
+        let mut ser = Serializer::builder()
+            .formatter(CompactFormatter {})
+            .validate(false)
+            .with_entry_type_aliases([("conference", "inproceedings")])
+            .with_field_filter(|key, value| match key {
+                "note" => FieldAction::Rewrite {
+                    key: "addendum".into(),
+                    value: value.to_lowercase(),
+                },
+                _ => FieldAction::Keep,
+            })
+            .build(Vec::new());
+        ser.serialize_entry(&record).unwrap();
+        assert_eq!(
+            output(ser.into_inner()),
+            "@inproceedings{a key,title={T}},addendum={n}}"
+        );
+
+        // the limits apply even without validation
+        let mut ser = Serializer::builder()
+            .validate(false)
+            .max_value_size(1)
+            .build(Vec::new());
+        assert!(ser.serialize_entry(&record).is_err());
+
+        let mut ser = SerializerBuilder::new()
+            .fixup_text(FixupPolicy::Balance)
+            .line_ending(LineEnding::CrLf)
+            .check_entry_keys(DuplicateEntryKeys::Error)
+            .skip_empty_fields(true)
+            .with_key_generator(|_, _| "key".to_owned())
+            .build(Vec::new());
+        let record = ("misc", "", [("title", "T}"), ("note", "")]);
+        ser.serialize_entry(&record).unwrap();
+        assert!(ser.serialize_entry(&record).is_err());
+        assert_eq!(
+            output(ser.into_inner()),
+            "@misc{key,\r\n  title = {{T}},\r\n}\r\n"
+        );
+    }
+}
@@ -0,0 +1,1338 @@
+//! Serialization into the owned document model of the [value module](crate::value).
+//!
+//! The serializers in this module accept the same representations as the
+//! [`Serializer`](crate::ser::Serializer), but build the entries directly rather than writing
+//! bytes.
+use serde::ser::{self, Error as _, Serialize as _};
+
+use super::macros::{serialize_err, serialize_err_custom_newtype};
+use crate::error::{Error, Result};
+use crate::naming::{
+    COMMENT_ENTRY_VARIANT_NAME as CVN, ENTRY_KEY_NAME, ENTRY_TYPE_NAME, FIELDS_NAME,
+    MACRO_ENTRY_VARIANT_NAME as MVN, MACRO_TOKEN_VARIANT_NAME as MTVN,
+    PREAMBLE_ENTRY_VARIANT_NAME as PVN, RAW_VALUE_NAME, REGULAR_ENTRY_VARIANT_NAME as RVN,
+    TEXT_TOKEN_VARIANT_NAME as TTVN,
+};
+use crate::token::validate::{
+    check_balanced, check_entry_key, check_field_key, check_regular_entry_type, check_variable,
+};
+use crate::token::{self, RawValue, TokenErrorKind};
+use crate::value::{Entry, RegularEntry, Token, Value};
+
+/// The serializer for a bibliography, which is a sequence of entries.
+pub(crate) struct EntriesSerializer;
+
+impl ser::Serializer for EntriesSerializer {
+    type Ok = Vec<Entry>;
+
+    type SerializeSeq = EntryListSerializer;
+    type SerializeTuple = EntryListSerializer;
+    type SerializeTupleStruct = EntryListSerializer;
+
+    serialize_err!(
+        "bibliography",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        char,
+        str,
+        bytes,
+        bool,
+        tuple_variant,
+        map,
+        option,
+        struct,
+        struct_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(EntryListSerializer {
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+}
+
+pub(crate) struct EntryListSerializer {
+    entries: Vec<Entry>,
+}
+
+macro_rules! entry_list_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl ser::$trait for EntryListSerializer {
+            type Ok = Vec<Entry>;
+            type Error = Error;
+
+            fn $fn<T>(&mut self, value: &T) -> Result<()>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                if let Some(entry) = value.serialize(EntrySerializer)? {
+                    self.entries.push(entry);
+                }
+                Ok(())
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(self.entries)
+            }
+        }
+    };
+}
+
+entry_list_serializer_impl!(serialize_element, SerializeSeq);
+entry_list_serializer_impl!(serialize_element, SerializeTuple);
+entry_list_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// The serializer for a single entry, which returns `None` if the entry is skipped.
+pub(crate) struct EntrySerializer;
+
+impl ser::Serializer for EntrySerializer {
+    type Ok = Option<Entry>;
+
+    type SerializeTuple = RegularEntryTupleSerializer;
+    type SerializeTupleVariant = RegularOrMacroEntrySerializer;
+    type SerializeTupleStruct = RegularEntryTupleSerializer;
+    type SerializeStruct = RegularEntryStructSerializer;
+    type SerializeStructVariant = RegularEntryStructSerializer;
+
+    serialize_err!(
+        "entry",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        char,
+        seq,
+        str,
+        bytes,
+        bool,
+        map,
+        option,
+        unit,
+        unit_struct
+    );
+
+    /// A unit variant is simply skipped. However, the variant name must be valid.
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        match variant {
+            RVN | MVN | CVN | PVN => Ok(None),
+            var => Err(Error::custom(format!("Unexpected enum variant {var}"))),
+        }
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        RegularEntryTupleSerializer::new(len)
+    }
+
+    /// A tuple struct is treated as a regular entry.
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        RegularEntryTupleSerializer::new(len)
+    }
+
+    /// A tuple variant is either a regular entry or a macro entry
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        match (variant, len) {
+            (RVN, 3) => Ok(RegularOrMacroEntrySerializer::Regular(
+                RegularEntryTupleSerializer::new(len)?,
+            )),
+            (MVN, 2) => Ok(RegularOrMacroEntrySerializer::Macro(
+                MacroTupleSerializer::default(),
+            )),
+            (RVN, _) => Err(Error::custom("regular entry from tuple not of length 3")),
+            (MVN, _) => Err(Error::custom("macro entry from tuple not of length 2")),
+            (CVN, _) => Err(Error::custom(
+                "tuple serialization not supported for comment",
+            )),
+            (PVN, _) => Err(Error::custom(
+                "tuple serialization not supported for preamble",
+            )),
+            _ => Err(Error::custom("unrecognized entry variant")),
+        }
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        match variant {
+            RVN => value.serialize(RegularEntrySerializer),
+            MVN => Ok(value
+                .serialize(MacroRuleSerializer)?
+                .map(|rule| Entry::Macro(Some(rule)))),
+            CVN => Ok(Some(Entry::Comment(value.serialize(TextTokenSerializer)?))),
+            PVN => Ok(Some(Entry::Preamble(value.serialize(PreambleSerializer)?))),
+            _ => Err(Error::custom(format!("Invalid variant name `{variant}`"))),
+        }
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(RegularEntryStructSerializer::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        match variant {
+            RVN => Ok(RegularEntryStructSerializer::default()),
+            _ => Err(Error::custom(
+                "struct serialization only supported for regular entry",
+            )),
+        }
+    }
+}
+
+/// The contents of a regular entry variant.
+struct RegularEntrySerializer;
+
+impl ser::Serializer for RegularEntrySerializer {
+    type Ok = Option<Entry>;
+
+    serialize_err!(
+        "regular entry",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        char,
+        str,
+        seq,
+        bytes,
+        bool,
+        tuple_variant,
+        map,
+        option,
+        struct_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    type SerializeTuple = RegularEntryTupleSerializer;
+    type SerializeTupleStruct = RegularEntryTupleSerializer;
+    type SerializeStruct = RegularEntryStructSerializer;
+
+    #[inline]
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        RegularEntryTupleSerializer::new(len)
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        RegularEntryTupleSerializer::new(len)
+    }
+
+    #[inline]
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(RegularEntryStructSerializer::default())
+    }
+}
+
+/// A regular entry as a tuple `(entry_type, entry_key, fields)`.
+pub(crate) struct RegularEntryTupleSerializer {
+    entry: RegularEntry,
+    index: usize,
+}
+
+impl RegularEntryTupleSerializer {
+    fn new(len: usize) -> Result<Self> {
+        if len == 3 {
+            Ok(Self {
+                entry: RegularEntry::new(String::new(), String::new()),
+                index: 0,
+            })
+        } else {
+            Err(Error::custom("regular entry from tuple not of length 3"))
+        }
+    }
+
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.index += 1;
+        match self.index {
+            1 => self.entry.entry_type = value.serialize(EntryTypeSerializer)?,
+            2 => self.entry.entry_key = value.serialize(EntryKeySerializer)?,
+            3 => self.entry.fields = value.serialize(EntryFieldsSerializer)?,
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+}
+
+macro_rules! regular_entry_tuple_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl ser::$trait for RegularEntryTupleSerializer {
+            type Ok = Option<Entry>;
+            type Error = Error;
+
+            #[inline]
+            fn $fn<T>(&mut self, value: &T) -> Result<()>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                self.push(value)
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(Some(Entry::Regular(self.entry)))
+            }
+        }
+    };
+}
+
+regular_entry_tuple_serializer_impl!(serialize_element, SerializeTuple);
+regular_entry_tuple_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// A regular entry as a struct with fields `entry_type`, `entry_key`, and `fields`.
+#[derive(Default)]
+pub(crate) struct RegularEntryStructSerializer {
+    entry_type: Option<String>,
+    entry_key: Option<String>,
+    fields: Option<Vec<(String, Value)>>,
+}
+
+impl RegularEntryStructSerializer {
+    fn push<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        match key {
+            ENTRY_TYPE_NAME => {
+                if self.entry_type.is_some() {
+                    Err(Error::custom("Duplicate entry type"))
+                } else {
+                    self.entry_type = Some(value.serialize(EntryTypeSerializer)?);
+                    Ok(())
+                }
+            }
+            ENTRY_KEY_NAME => {
+                if self.entry_key.is_some() {
+                    Err(Error::custom("Duplicate entry key"))
+                } else {
+                    self.entry_key = Some(value.serialize(EntryKeySerializer)?);
+                    Ok(())
+                }
+            }
+            FIELDS_NAME => {
+                if self.fields.is_some() {
+                    Err(Error::custom("Duplicate fields"))
+                } else {
+                    self.fields = Some(value.serialize(EntryFieldsSerializer)?);
+                    Ok(())
+                }
+            }
+            var => Err(Error::custom(format!("Unexpected struct field {var}"))),
+        }
+    }
+
+    fn finish(self) -> Result<RegularEntry> {
+        match self {
+            Self {
+                entry_type: None, ..
+            } => Err(Error::custom("Missing entry type")),
+            Self {
+                entry_key: None, ..
+            } => Err(Error::custom("Missing entry key")),
+            Self { fields: None, .. } => Err(Error::custom("Missing fields")),
+            Self {
+                entry_type: Some(entry_type),
+                entry_key: Some(entry_key),
+                fields: Some(fields),
+            } => Ok(RegularEntry {
+                entry_type,
+                entry_key,
+                fields,
+            }),
+        }
+    }
+}
+
+impl ser::SerializeStruct for RegularEntryStructSerializer {
+    type Ok = Option<Entry>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.push(key, value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok> {
+        self.finish().map(|entry| Some(Entry::Regular(entry)))
+    }
+}
+
+impl ser::SerializeStructVariant for RegularEntryStructSerializer {
+    type Ok = Option<Entry>;
+    type Error = Error;
+
+    #[inline]
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.push(key, value)
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok> {
+        self.finish().map(|entry| Some(Entry::Regular(entry)))
+    }
+}
+
+pub(crate) enum RegularOrMacroEntrySerializer {
+    Regular(RegularEntryTupleSerializer),
+    Macro(MacroTupleSerializer),
+}
+
+impl ser::SerializeTupleVariant for RegularOrMacroEntrySerializer {
+    type Ok = Option<Entry>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        match self {
+            Self::Regular(ser) => ser.push(value),
+            Self::Macro(ser) => ser.push(value),
+        }
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        match self {
+            Self::Regular(ser) => Ok(Some(Entry::Regular(ser.entry))),
+            Self::Macro(ser) => Ok(Some(Entry::Macro(Some(ser.finish())))),
+        }
+    }
+}
+
+/// The contents of a macro entry variant, which is skipped if it is `None`.
+struct MacroRuleSerializer;
+
+impl ser::Serializer for MacroRuleSerializer {
+    type Ok = Option<(String, Value)>;
+
+    serialize_err!(
+        "macro entry",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        char,
+        str,
+        bytes,
+        bool,
+        seq,
+        tuple_variant,
+        map,
+        struct,
+        struct_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    type SerializeTuple = MacroTupleSerializer;
+    type SerializeTupleStruct = MacroTupleSerializer;
+
+    #[inline]
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    #[inline]
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        if len == 2 {
+            Ok(MacroTupleSerializer::default())
+        } else {
+            Err(Error::custom("macro entry from tuple not of length 2"))
+        }
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_tuple(len)
+    }
+}
+
+/// A macro entry as a tuple `(name, value)`.
+#[derive(Default)]
+pub(crate) struct MacroTupleSerializer {
+    name: String,
+    value: Value,
+    index: usize,
+}
+
+impl MacroTupleSerializer {
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.index += 1;
+        match self.index {
+            1 => self.name = value.serialize(MacroNameSerializer)?,
+            2 => self.value = value.serialize(ValueSerializer)?,
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> (String, Value) {
+        (self.name, self.value)
+    }
+}
+
+macro_rules! macro_tuple_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl ser::$trait for MacroTupleSerializer {
+            type Ok = Option<(String, Value)>;
+            type Error = Error;
+
+            #[inline]
+            fn $fn<T>(&mut self, value: &T) -> Result<()>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                self.push(value)
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(Some(self.finish()))
+            }
+        }
+    };
+}
+
+macro_tuple_serializer_impl!(serialize_element, SerializeTuple);
+macro_tuple_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// The fields of a regular entry, as a map, a struct, or a sequence of `(key, value)` pairs.
+struct EntryFieldsSerializer;
+
+impl ser::Serializer for EntryFieldsSerializer {
+    type Ok = Vec<(String, Value)>;
+
+    serialize_err!(
+        "regular entry fields",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        char,
+        str,
+        option,
+        bytes,
+        bool,
+        tuple_variant,
+        struct_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    type SerializeSeq = FieldListSerializer;
+    type SerializeTuple = FieldListSerializer;
+    type SerializeTupleStruct = FieldListSerializer;
+    type SerializeStruct = FieldListSerializer;
+    type SerializeMap = FieldListSerializer;
+
+    #[inline]
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(FieldListSerializer {
+            fields: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    #[inline]
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    #[inline]
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        self.serialize_seq(len)
+    }
+}
+
+pub(crate) struct FieldListSerializer {
+    fields: Vec<(String, Value)>,
+}
+
+impl ser::SerializeStruct for FieldListSerializer {
+    type Ok = Vec<(String, Value)>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let key = key.serialize(FieldKeySerializer)?;
+        self.fields.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.fields)
+    }
+}
+
+impl ser::SerializeMap for FieldListSerializer {
+    type Ok = Vec<(String, Value)>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let key = key.serialize(FieldKeySerializer)?;
+        self.fields.push((key, Value::default()));
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let value = value.serialize(ValueSerializer)?;
+        if let Some((_, last)) = self.fields.last_mut() {
+            *last = value;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.fields)
+    }
+}
+
+macro_rules! field_list_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl ser::$trait for FieldListSerializer {
+            type Ok = Vec<(String, Value)>;
+            type Error = Error;
+
+            fn $fn<T>(&mut self, value: &T) -> Result<()>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                self.fields.push(value.serialize(KeyValueSerializer)?);
+                Ok(())
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(self.fields)
+            }
+        }
+    };
+}
+
+field_list_serializer_impl!(serialize_element, SerializeSeq);
+field_list_serializer_impl!(serialize_element, SerializeTuple);
+field_list_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// A field as a tuple `(key, value)`.
+struct KeyValueSerializer;
+
+impl ser::Serializer for KeyValueSerializer {
+    type Ok = (String, Value);
+
+    serialize_err!(
+        "field key-value pair",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        char,
+        str,
+        bytes,
+        option,
+        bool,
+        seq,
+        tuple_variant,
+        map,
+        struct,
+        struct_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    type SerializeTuple = KeyValueTupleSerializer;
+    type SerializeTupleStruct = KeyValueTupleSerializer;
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        if len == 2 {
+            Ok(KeyValueTupleSerializer::default())
+        } else {
+            Err(Error::custom("key value from tuple not of length 2"))
+        }
+    }
+
+    #[inline]
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_tuple(len)
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct KeyValueTupleSerializer {
+    key: String,
+    value: Value,
+    index: usize,
+}
+
+macro_rules! key_value_tuple_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl ser::$trait for KeyValueTupleSerializer {
+            type Ok = (String, Value);
+            type Error = Error;
+
+            fn $fn<T>(&mut self, value: &T) -> Result<()>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                self.index += 1;
+                match self.index {
+                    1 => self.key = value.serialize(FieldKeySerializer)?,
+                    2 => self.value = value.serialize(ValueSerializer)?,
+                    _ => unreachable!(),
+                }
+                Ok(())
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok((self.key, self.value))
+            }
+        }
+    };
+}
+
+key_value_tuple_serializer_impl!(serialize_element, SerializeTuple);
+key_value_tuple_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// A value, as a string, a sequence of tokens, or a raw value.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+
+    serialize_err_custom_newtype!(
+        "value",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        option,
+        bool,
+        map,
+        struct,
+        struct_variant,
+        tuple_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    type SerializeSeq = TokenListSerializer;
+    type SerializeTuple = TokenListSerializer;
+    type SerializeTupleStruct = TokenListSerializer;
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(TokenListSerializer {
+            tokens: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        TextTokenSerializer.serialize_char(v).map(Value::text)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        TextTokenSerializer.serialize_str(v).map(Value::text)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        TextTokenSerializer.serialize_bytes(v).map(Value::text)
+    }
+
+    /// A [`RawValue`] is parsed into its tokens.
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        if name == RAW_VALUE_NAME {
+            value.serialize(RawValueSerializer).map(Value)
+        } else {
+            value.serialize(self)
+        }
+    }
+}
+
+pub(crate) struct TokenListSerializer {
+    tokens: Vec<Token>,
+}
+
+macro_rules! token_list_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl ser::$trait for TokenListSerializer {
+            type Ok = Value;
+            type Error = Error;
+
+            fn $fn<T>(&mut self, value: &T) -> Result<()>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                self.tokens.push(value.serialize(TokenSerializer)?);
+                Ok(())
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(Value(self.tokens))
+            }
+        }
+    };
+}
+
+token_list_serializer_impl!(serialize_element, SerializeSeq);
+token_list_serializer_impl!(serialize_element, SerializeTuple);
+token_list_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// A token, as a `Variable` or `Text` newtype variant.
+struct TokenSerializer;
+
+impl ser::Serializer for TokenSerializer {
+    type Ok = Token;
+
+    serialize_err!(
+        "value token",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        option,
+        char,
+        str,
+        bytes,
+        bool,
+        map,
+        seq,
+        tuple,
+        tuple_struct,
+        struct,
+        struct_variant,
+        tuple_variant,
+        unit_variant,
+        unit,
+        unit_struct
+    );
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        match variant {
+            MTVN => value
+                .serialize(VariableTokenSerializer)
+                .map(Token::Variable),
+            TTVN => value.serialize(TextTokenSerializer).map(Token::Text),
+            var => Err(Error::ser(format!("invalid token variant '{var}'"))),
+        }
+    }
+}
+
+/// The contents of a preamble entry. The parts of a preamble which is a sequence are joined into
+/// a single value.
+struct PreambleSerializer;
+
+impl ser::Serializer for PreambleSerializer {
+    type Ok = Value;
+
+    serialize_err_custom_newtype!(
+        "preamble",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        option,
+        bool,
+        map,
+        struct,
+        struct_variant,
+        tuple_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    type SerializeSeq = PreamblePartsSerializer;
+    type SerializeTuple = PreamblePartsSerializer;
+    type SerializeTupleStruct = PreamblePartsSerializer;
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(PreamblePartsSerializer { tokens: Vec::new() })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        ValueSerializer.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        ValueSerializer.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        ValueSerializer.serialize_bytes(v)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        if name == RAW_VALUE_NAME {
+            ValueSerializer.serialize_newtype_struct(name, value)
+        } else {
+            value.serialize(self)
+        }
+    }
+}
+
+/// The parts of a preamble, each of which is a value or a single token.
+pub(crate) struct PreamblePartsSerializer {
+    tokens: Vec<Token>,
+}
+
+macro_rules! preamble_parts_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl ser::$trait for PreamblePartsSerializer {
+            type Ok = Value;
+            type Error = Error;
+
+            fn $fn<T>(&mut self, value: &T) -> Result<()>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                let part = value.serialize(PreamblePartSerializer)?;
+                self.tokens.extend(part.0);
+                Ok(())
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(Value(self.tokens))
+            }
+        }
+    };
+}
+
+preamble_parts_serializer_impl!(serialize_element, SerializeSeq);
+preamble_parts_serializer_impl!(serialize_element, SerializeTuple);
+preamble_parts_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// A single part of a preamble, or a token of the current part.
+struct PreamblePartSerializer;
+
+impl ser::Serializer for PreamblePartSerializer {
+    type Ok = Value;
+
+    serialize_err_custom_newtype!(
+        "preamble part",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        option,
+        bool,
+        map,
+        struct,
+        struct_variant,
+        tuple_variant,
+        unit,
+        unit_struct,
+        unit_variant
+    );
+
+    type SerializeSeq = TokenListSerializer;
+    type SerializeTuple = TokenListSerializer;
+    type SerializeTupleStruct = TokenListSerializer;
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        ValueSerializer.serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        ValueSerializer.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        ValueSerializer.serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        ValueSerializer.serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        ValueSerializer.serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        ValueSerializer.serialize_bytes(v)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        ValueSerializer.serialize_newtype_struct(name, value)
+    }
+
+    /// A token is appended to the current part.
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        TokenSerializer
+            .serialize_newtype_variant(name, variant_index, variant, value)
+            .map(|token| Value(vec![token]))
+    }
+}
+
+/// A raw value, which is parsed into its tokens.
+struct RawValueSerializer;
+
+impl ser::Serializer for RawValueSerializer {
+    type Ok = Vec<Token>;
+
+    serialize_err!(
+        "raw value",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        char,
+        bool,
+        seq,
+        option,
+        tuple,
+        tuple_struct,
+        tuple_variant,
+        map,
+        struct,
+        struct_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+        let tokens = match RawValue::new(value) {
+            Ok(raw) if raw.as_str().len() == value.len() => raw.tokens()?,
+            _ => return Err(Error::ser(format!("invalid raw value '{value}'"))),
+        };
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                token::Token::Variable(variable) => {
+                    Ok(Token::Variable(variable.as_ref().to_owned()))
+                }
+                token::Token::Text(text) => match text.into_str() {
+                    Ok(text) => Ok(Token::Text(text.to_owned())),
+                    Err(_) => Err(Error::ser("raw value as non-UTF-8 bytes".to_string())),
+                },
+            })
+            .collect()
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+        match std::str::from_utf8(value) {
+            Ok(value) => self.serialize_str(value),
+            Err(_) => Err(Error::ser("raw value as non-UTF-8 bytes".to_string())),
+        }
+    }
+}
+
+/// A macro to define a serializer for a string which is checked with `$check`. Bytes are
+/// serialized as a string if they are valid UTF-8.
+macro_rules! checked_string_serializer {
+    ($err:expr, $name:ident, $check:expr) => {
+        struct $name;
+
+        impl ser::Serializer for $name {
+            type Ok = String;
+
+            serialize_err!(
+                $err,
+                i8,
+                i16,
+                i32,
+                i64,
+                u8,
+                u16,
+                u32,
+                u64,
+                f32,
+                f64,
+                bool,
+                seq,
+                option,
+                tuple,
+                tuple_struct,
+                tuple_variant,
+                map,
+                struct,
+                struct_variant,
+                unit,
+                unit_struct,
+                newtype_variant
+            );
+
+            #[inline]
+            fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+                let check: fn(&str) -> std::result::Result<(), TokenErrorKind> = $check;
+                match check(value) {
+                    Ok(()) => Ok(value.to_owned()),
+                    Err(err) => Err(Error::ser(format!(
+                        concat!("invalid ", $err, " '{}': {}"),
+                        value, err
+                    ))),
+                }
+            }
+
+            #[inline]
+            fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+                match std::str::from_utf8(value) {
+                    Ok(value) => self.serialize_str(value),
+                    Err(_) => Err(Error::ser(concat!($err, " as non-UTF-8 bytes").to_string())),
+                }
+            }
+
+            #[inline]
+            fn serialize_char(self, value: char) -> Result<Self::Ok> {
+                // A char encoded as UTF-8 takes 4 bytes at most.
+                let mut buf = [0; 4];
+                self.serialize_str(value.encode_utf8(&mut buf))
+            }
+
+            /// A unit variant is serialized using the name of the variant.
+            #[inline]
+            fn serialize_unit_variant(
+                self,
+                _name: &'static str,
+                _variant_index: u32,
+                variant: &'static str,
+            ) -> Result<Self::Ok> {
+                self.serialize_str(variant)
+            }
+        }
+    };
+}
+
+checked_string_serializer!("text token", TextTokenSerializer, |text| check_balanced(
+    text.as_bytes()
+));
+checked_string_serializer!("field key", FieldKeySerializer, check_field_key);
+checked_string_serializer!("variable token", VariableTokenSerializer, check_variable);
+checked_string_serializer!("macro name", MacroNameSerializer, check_variable);
+checked_string_serializer!("entry type", EntryTypeSerializer, check_regular_entry_type);
+checked_string_serializer!("entry key", EntryKeySerializer, check_entry_key);
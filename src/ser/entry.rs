@@ -4,9 +4,10 @@ use serde::ser::{self, Error as _, Serialize as _};
 
 use super::{
     macros::{ser_wrapper, serialize_err, serialize_trait_impl},
+    preamble::PreambleSerializer,
     value::{
-        EntryKeySerializer, EntryTypeSerializer, FieldKeySerializer, TextTokenSerializer,
-        ValueSerializer, VariableTokenSerializer,
+        EntryKeySerializer, EntryTypeSerializer, FieldKeySerializer, MacroNameSerializer,
+        TextTokenSerializer, ValueSerializer,
     },
     Formatter, Serializer,
 };
@@ -150,7 +151,7 @@ where
             PVN => {
                 self.ser.buffer.write_preamble_entry_type()?;
                 self.ser.buffer.write_body_start()?;
-                value.serialize(ValueSerializer::new(&mut *self.ser))?;
+                value.serialize(PreambleSerializer::new(&mut *self.ser))?;
                 self.ser.buffer.write_body_end()?;
                 Ok(false)
             }
@@ -417,13 +418,16 @@ where
                     .buffer
                     .write_macro_entry_type()
                     .map_err(Error::io)?;
-                self.ser.buffer.write_body_start().map_err(Error::io)?;
-                value.serialize(VariableTokenSerializer::new(&mut *self.ser))
+                self.ser
+                    .buffer
+                    .write_macro_body_start()
+                    .map_err(Error::io)?;
+                value.serialize(MacroNameSerializer::new(&mut *self.ser))
             }
             (TupleEntryVariant::Macro, 2) => {
                 self.ser.buffer.write_field_separator().map_err(Error::io)?;
                 value.serialize(ValueSerializer::new(&mut *self.ser))?;
-                self.ser.buffer.write_body_end().map_err(Error::io)
+                self.ser.buffer.write_macro_body_end().map_err(Error::io)
             }
             (TupleEntryVariant::Macro, _) => unreachable!(),
         }
@@ -529,13 +533,16 @@ macro_rules! macro_tuple_serializer_impl {
                             .buffer
                             .write_macro_entry_type()
                             .map_err(Error::io)?;
-                        self.ser.buffer.write_body_start().map_err(Error::io)?;
-                        value.serialize(VariableTokenSerializer::new(&mut *self.ser))
+                        self.ser
+                            .buffer
+                            .write_macro_body_start()
+                            .map_err(Error::io)?;
+                        value.serialize(MacroNameSerializer::new(&mut *self.ser))
                     }
                     2 => {
                         self.ser.buffer.write_field_separator().map_err(Error::io)?;
                         value.serialize(ValueSerializer::new(&mut *self.ser))?;
-                        self.ser.buffer.write_body_end().map_err(Error::io)
+                        self.ser.buffer.write_macro_body_end().map_err(Error::io)
                     }
                     _ => unreachable!(),
                 }
@@ -618,6 +625,9 @@ where
         self,
         _len: Option<usize>,
     ) -> std::result::Result<Self::SerializeMap, Self::Error> {
+        if self.ser.sort_fields {
+            self.ser.buffer.begin_sorted_fields();
+        }
         Ok(Self::SerializeMap::new(&mut *self.ser))
     }
 
@@ -656,7 +666,7 @@ where
     }
     #[inline]
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        self.ser.buffer.write_body_end()?;
+        self.ser.buffer.write_fields_end()?;
         Self::Ok::default();
         Ok(())
     }
@@ -691,7 +701,8 @@ where
 
     #[inline]
     fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        self.ser.buffer.write_body_end()?;
+        self.ser.buffer.sort_fields();
+        self.ser.buffer.write_fields_end()?;
         Self::Ok::default();
         Ok(())
     }
@@ -717,7 +728,7 @@ macro_rules! entry_fields_serializer_impl {
 
             #[inline]
             fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-                self.ser.buffer.write_body_end()?;
+                self.ser.buffer.write_fields_end()?;
                 Ok(Self::Ok::default())
             }
         }
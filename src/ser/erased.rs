@@ -0,0 +1,113 @@
+use std::io;
+
+use serde::Serialize;
+
+use super::{Formatter, Serializer};
+use crate::error::Result;
+
+/// An object-safe interface to a [`Serializer`], which writes entries provided as
+/// [`erased_serde::Serialize`] trait objects.
+///
+/// The writer and formatter of the serializer are hidden behind the trait object, so code which
+/// is not generic, such as a dynamically loaded exporter, can write entries of any type. The
+/// output is the same as serializing the entries directly.
+/// ```
+/// use serde::Serialize;
+/// use serde_bibtex::ser::{DynSerializer, Serializer};
+///
+/// #[derive(Serialize)]
+/// struct Record {
+///     entry_type: &'static str,
+///     entry_key: &'static str,
+///     fields: Vec<(&'static str, &'static str)>,
+/// }
+///
+/// #[derive(Serialize)]
+/// enum Entry {
+///     Macro(&'static str, &'static str),
+/// }
+///
+/// fn export(ser: &mut dyn DynSerializer, entries: &[Box<dyn erased_serde::Serialize>]) {
+///     for entry in entries {
+///         ser.serialize_dyn_entry(entry.as_ref()).unwrap();
+///     }
+/// }
+///
+/// let entries: Vec<Box<dyn erased_serde::Serialize>> = vec![
+///     Box::new(Entry::Macro("apr", "April")),
+///     Box::new(Record {
+///         entry_type: "misc",
+///         entry_key: "key",
+///         fields: vec![("title", "T")],
+///     }),
+/// ];
+///
+/// let mut ser = Serializer::new(Vec::new());
+/// export(&mut ser, &entries);
+/// assert_eq!(
+///     String::from_utf8(ser.into_inner()).unwrap(),
+///     "@string{apr = {April}}\n@misc{key,\n  title = {T},\n}\n"
+/// );
+/// ```
+pub trait DynSerializer {
+    /// Serialize a single entry, as with [`Serializer::serialize_entry`].
+    fn serialize_dyn_entry(&mut self, entry: &dyn erased_serde::Serialize) -> Result<()>;
+
+    /// Serialize a bibliography consisting of `entries`, which are separated as determined by
+    /// the formatter.
+    fn serialize_dyn_entries(&mut self, entries: &[&dyn erased_serde::Serialize]) -> Result<()>;
+}
+
+impl<W, F> DynSerializer for Serializer<W, F>
+where
+    W: io::Write,
+    F: Formatter,
+{
+    fn serialize_dyn_entry(&mut self, entry: &dyn erased_serde::Serialize) -> Result<()> {
+        self.serialize_entry(entry)
+    }
+
+    fn serialize_dyn_entries(&mut self, entries: &[&dyn erased_serde::Serialize]) -> Result<()> {
+        entries.serialize(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::to_string;
+
+    #[derive(Serialize)]
+    struct Record {
+        entry_type: &'static str,
+        entry_key: &'static str,
+        fields: BTreeMap<&'static str, &'static str>,
+    }
+
+    #[test]
+    fn test_dyn_entries() {
+        let record = Record {
+            entry_type: "article",
+            entry_key: "a",
+            fields: BTreeMap::from([("year", "2024"), ("title", "T")]),
+        };
+        let tuple = ("book", "b", [("author", "A")]);
+        let entries: [&dyn erased_serde::Serialize; 2] = [&record, &tuple];
+
+        let mut ser = Serializer::new(Vec::new());
+        let dyn_ser: &mut dyn DynSerializer = &mut ser;
+        dyn_ser.serialize_dyn_entries(&entries).unwrap();
+        assert_eq!(
+            String::from_utf8(ser.into_inner()).unwrap(),
+            to_string(&(&record, &tuple)).unwrap()
+        );
+
+        // the output is validated
+        let mut ser = Serializer::compact(Vec::new());
+        let invalid = ("misc", "a key", [("title", "T")]);
+        assert!(ser.serialize_dyn_entry(&invalid).is_err());
+        assert!(ser.serialize_dyn_entries(&[]).is_ok());
+    }
+}
@@ -1,12 +1,209 @@
-use std::io;
+use std::borrow::Cow;
+use std::io::{self, IoSlice};
+use std::ops::Range;
 
-use crate::token::{is_balanced, is_entry_key, is_field_key, is_regular_entry_type, is_variable};
+use unicase::UniCase;
+
+use super::{FieldAction, FixupPolicy};
+
+use crate::token::validate::{
+    check_balanced, check_entry_key, check_field_key, check_regular_entry_type, check_variable,
+};
+use crate::token::{escape_text, RawValue, Token};
+use crate::LineEnding;
 
 pub(crate) struct FormatBuffer<F> {
     formatter: F,
     entry_key: Vec<u8>,
     entry_type: Vec<u8>,
     fields: Vec<u8>,
+    field_filter: Option<FieldFilter>,
+    pending: Option<PendingField>,
+    /// The key and location in `fields` of each field written since sorting began, if the fields
+    /// are being sorted.
+    sorted: Option<Vec<(String, Range<usize>)>>,
+    /// Spare buffers whose allocations are reused by the next entry or field.
+    spare: SpareBuffers,
+    /// The location in `fields` at which the current field starts.
+    field_start: usize,
+    /// The key of the current field, if the fields are being sorted.
+    field_key: String,
+    /// The line ending which replaces each `\n` written by the formatter outside of keys and
+    /// values.
+    line_ending: LineEnding,
+    fixup_policy: FixupPolicy,
+    /// Whether `@` and `#` in text tokens are enclosed in brackets.
+    escape_text: bool,
+    /// Whether the buffers are written with a single vectored write.
+    write_vectored: bool,
+    /// Whether fields whose value is empty or only whitespace are skipped.
+    skip_empty_fields: bool,
+    /// The key and value of each field written in the current entry, if they are captured.
+    captured: Option<Vec<(String, String)>>,
+}
+
+/// Buffers which are not in use, kept so that their allocations can be reused.
+#[derive(Default)]
+struct SpareBuffers {
+    pending: PendingField,
+    sorted: Vec<(String, Range<usize>)>,
+    reordered: Vec<u8>,
+}
+
+/// A function which decides how each field is written.
+pub(crate) type FieldFilter = Box<dyn Fn(&str, &str) -> FieldAction + Send>;
+
+/// A field which is held back until it is complete, so that a [`FieldFilter`] can be applied.
+#[derive(Default)]
+struct PendingField {
+    key: String,
+    tokens: Vec<PendingToken>,
+}
+
+enum PendingToken {
+    Text(String),
+    /// A text token which is written verbatim.
+    Bytes(Vec<u8>),
+    Variable(String),
+    /// The source of a value, and the contents of its tokens.
+    Raw {
+        source: String,
+        contents: String,
+    },
+}
+
+impl PendingField {
+    /// The contents of the tokens, concatenated. Invalid UTF-8 in byte tokens is replaced by
+    /// `U+FFFD`.
+    fn value(&self) -> String {
+        self.tokens
+            .iter()
+            .map(|token| match token {
+                PendingToken::Text(s) | PendingToken::Variable(s) => Cow::Borrowed(s.as_str()),
+                PendingToken::Bytes(bytes) => String::from_utf8_lossy(bytes),
+                PendingToken::Raw { contents, .. } => Cow::Borrowed(contents.as_str()),
+            })
+            .collect()
+    }
+}
+
+/// A writer which replaces each `\n` with the line ending of the output.
+struct Eol<'a, W: ?Sized> {
+    writer: &'a mut W,
+    line_ending: LineEnding,
+}
+
+impl<'a, W: ?Sized + io::Write> Eol<'a, W> {
+    fn new(writer: &'a mut W, line_ending: LineEnding) -> Self {
+        Self {
+            writer,
+            line_ending,
+        }
+    }
+}
+
+impl<W: ?Sized + io::Write> io::Write for Eol<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.line_ending == LineEnding::Lf {
+            return self.writer.write_all(buf);
+        }
+        let mut lines = buf.split(|b| *b == b'\n');
+        if let Some(first) = lines.next() {
+            self.writer.write_all(first)?;
+        }
+        for line in lines {
+            self.writer.write_all(self.line_ending.as_bytes())?;
+            self.writer.write_all(line)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Write every buffer in `bufs`, using as few vectored writes as possible.
+fn write_all_vectored<W>(writer: &mut W, mut bufs: &mut [IoSlice<'_>]) -> io::Result<()>
+where
+    W: ?Sized + io::Write,
+{
+    // skip any leading empty buffers
+    IoSlice::advance_slices(&mut bufs, 0);
+    while !bufs.is_empty() {
+        match writer.write_vectored(bufs) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+            }
+            Ok(n) => IoSlice::advance_slices(&mut bufs, n),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Fix the unbalanced brackets in a text token according to `policy`.
+fn fixup(text: &str, policy: FixupPolicy) -> Cow<'_, str> {
+    if policy == FixupPolicy::Error || check_balanced(text.as_bytes()).is_ok() {
+        return Cow::Borrowed(text);
+    }
+
+    // the positions of the opening brackets which are not yet closed, and of the closing
+    // brackets which do not close anything
+    let mut open = Vec::new();
+    let mut unmatched = Vec::new();
+    for (pos, b) in text.bytes().enumerate() {
+        match b {
+            b'{' => open.push(pos),
+            b'}' => match open.pop() {
+                Some(_) => {}
+                None => unmatched.push(pos),
+            },
+            _ => {}
+        }
+    }
+    unmatched.extend(open);
+
+    match policy {
+        FixupPolicy::Error => unreachable!(),
+        FixupPolicy::Escape => {
+            unmatched.sort_unstable();
+            let mut fixed = String::with_capacity(text.len() + 16 * unmatched.len());
+            let mut last = 0;
+            for pos in unmatched {
+                fixed.push_str(&text[last..pos]);
+                fixed.push_str(if text.as_bytes()[pos] == b'{' {
+                    "\\textbraceleft{}"
+                } else {
+                    "\\textbraceright{}"
+                });
+                last = pos + 1;
+            }
+            fixed.push_str(&text[last..]);
+            Cow::Owned(fixed)
+        }
+        FixupPolicy::Balance => {
+            let closing = unmatched
+                .iter()
+                .filter(|pos| text.as_bytes()[**pos] == b'}')
+                .count();
+            let opening = unmatched.len() - closing;
+            let mut fixed = String::with_capacity(text.len() + unmatched.len());
+            fixed.extend(std::iter::repeat_n('{', closing));
+            fixed.push_str(text);
+            fixed.extend(std::iter::repeat_n('}', opening));
+            Cow::Owned(fixed)
+        }
+    }
 }
 
 /// A wrapper struct for a [`Formatter`] which writes to an internal buffer. This struct is needed
@@ -18,19 +215,134 @@ impl<F> FormatBuffer<F> {
             entry_key: Vec::with_capacity(16),
             entry_type: Vec::with_capacity(16),
             fields: Vec::with_capacity(128),
+            field_filter: None,
+            pending: None,
+            sorted: None,
+            spare: SpareBuffers::default(),
+            field_start: 0,
+            field_key: String::new(),
+            line_ending: LineEnding::Lf,
+            fixup_policy: FixupPolicy::Error,
+            escape_text: false,
+            write_vectored: false,
+            skip_empty_fields: false,
+            captured: None,
         }
     }
 
+    /// Set how text tokens with unbalanced brackets are fixed.
+    pub fn set_fixup_policy(&mut self, policy: FixupPolicy) {
+        self.fixup_policy = policy;
+    }
+
+    /// Set whether `@` and `#` in text tokens are enclosed in brackets.
+    pub fn set_escape_text(&mut self, enable: bool) {
+        self.escape_text = enable;
+    }
+
+    /// Apply the fixup policy and escaping to a text token.
+    fn prepare_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let text = fixup(text, self.fixup_policy);
+        if !self.escape_text {
+            return text;
+        }
+        match text {
+            Cow::Borrowed(text) => escape_text(text),
+            Cow::Owned(text) => Cow::Owned(escape_text(&text).into_owned()),
+        }
+    }
+
+    /// Set whether the buffers are written with a single vectored write.
+    pub fn set_write_vectored(&mut self, enable: bool) {
+        self.write_vectored = enable;
+    }
+
+    /// Set whether fields whose value is empty or only whitespace are skipped.
+    pub fn set_skip_empty_fields(&mut self, enable: bool) {
+        self.skip_empty_fields = enable;
+    }
+
+    /// Start capturing the key and value of each field which is written.
+    pub fn capture_fields(&mut self) {
+        self.captured.get_or_insert_with(Vec::new);
+    }
+
+    /// The key and value of each field written since this method was last called, if the fields
+    /// are captured.
+    pub fn take_captured_fields(&mut self) -> Vec<(String, String)> {
+        self.captured
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Set the line ending used by the output.
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Set the filter which is applied to every field before it is written.
+    pub fn set_field_filter(&mut self, filter: FieldFilter) {
+        self.field_filter = Some(filter);
+    }
+
+    /// Start recording the fields which are written, so that they can be sorted by
+    /// [`FormatBuffer::sort_fields`].
+    pub fn begin_sorted_fields(&mut self) {
+        self.sorted = Some(std::mem::take(&mut self.spare.sorted));
+    }
+
+    /// Sort the fields written since [`FormatBuffer::begin_sorted_fields`] by key, comparing
+    /// case-insensitively first.
+    pub fn sort_fields(&mut self) {
+        let Some(mut sorted) = self.sorted.take() else {
+            return;
+        };
+        let compare =
+            |a: &String, b: &String| UniCase::new(a).cmp(&UniCase::new(b)).then_with(|| a.cmp(b));
+        // the captured fields are reported in the order in which they are written
+        if let Some(captured) = &mut self.captured {
+            captured.sort_by(|(a, _), (b, _)| compare(a, b));
+        }
+        if let Some(base) = sorted.first().map(|(_, span)| span.start) {
+            sorted.sort_by(|(a, _), (b, _)| compare(a, b));
+            let reordered = &mut self.spare.reordered;
+            reordered.clear();
+            for (_, span) in sorted.drain(..) {
+                reordered.extend_from_slice(&self.fields[span]);
+            }
+            self.fields.truncate(base);
+            self.fields.extend_from_slice(reordered);
+        }
+        self.spare.sorted = sorted;
+    }
+
+    /// Whether nothing has been written to the buffers since they were last written out.
+    pub fn is_empty(&self) -> bool {
+        self.entry_type.is_empty() && self.entry_key.is_empty() && self.fields.is_empty()
+    }
+
     /// Write the contents of the buffers in order
     pub fn write<W>(&mut self, writer: &mut W) -> io::Result<()>
     where
         W: ?Sized + io::Write,
     {
-        writer.write_all(&self.entry_type)?;
+        if self.write_vectored {
+            write_all_vectored(
+                writer,
+                &mut [
+                    IoSlice::new(&self.entry_type),
+                    IoSlice::new(&self.entry_key),
+                    IoSlice::new(&self.fields),
+                ],
+            )?;
+        } else {
+            writer.write_all(&self.entry_type)?;
+            writer.write_all(&self.entry_key)?;
+            writer.write_all(&self.fields)?;
+        }
         self.entry_type.clear();
-        writer.write_all(&self.entry_key)?;
         self.entry_key.clear();
-        writer.write_all(&self.fields)?;
         self.fields.clear();
         Ok(())
     }
@@ -43,7 +355,28 @@ impl<F: Formatter> FormatBuffer<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.formatter.write_entry_separator(writer)
+        self.formatter
+            .write_entry_separator(&mut Eol::new(writer, self.line_ending))
+    }
+
+    /// Write a line of the prologue, which is outside of any entry.
+    #[inline]
+    pub fn write_prologue_line<W>(&mut self, writer: &mut W, line: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.formatter
+            .write_prologue_line(&mut Eol::new(writer, self.line_ending), line)
+    }
+
+    /// The separator between the prologue and the first entry.
+    #[inline]
+    pub fn write_prologue_end<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.formatter
+            .write_prologue_end(&mut Eol::new(writer, self.line_ending))
     }
 
     /// Write the entry type, including the `@` symbol.
@@ -56,27 +389,36 @@ impl<F: Formatter> FormatBuffer<F> {
     /// Write the macro entry type, including the `@` symbol.
     #[inline]
     pub fn write_macro_entry_type(&mut self) -> io::Result<()> {
-        self.formatter.write_macro_entry_type(&mut self.entry_type)
+        self.formatter
+            .write_macro_entry_type(&mut Eol::new(&mut self.entry_type, self.line_ending))
     }
 
     /// Write the comment entry type, including the `@` symbol.
     #[inline]
     pub fn write_comment_entry_type(&mut self) -> io::Result<()> {
         self.formatter
-            .write_comment_entry_type(&mut self.entry_type)
+            .write_comment_entry_type(&mut Eol::new(&mut self.entry_type, self.line_ending))
     }
 
     /// Write the preamble entry type, including the `@` symbol.
     #[inline]
     pub fn write_preamble_entry_type(&mut self) -> io::Result<()> {
         self.formatter
-            .write_preamble_entry_type(&mut self.entry_type)
+            .write_preamble_entry_type(&mut Eol::new(&mut self.entry_type, self.line_ending))
     }
 
     /// Write the body start character, typically `{`.
     #[inline]
     pub fn write_body_start(&mut self) -> io::Result<()> {
-        self.formatter.write_body_start(&mut self.entry_type)
+        self.formatter
+            .write_body_start(&mut Eol::new(&mut self.entry_type, self.line_ending))
+    }
+
+    /// Write the body start character of a macro entry, typically `{`.
+    #[inline]
+    pub fn write_macro_body_start(&mut self) -> io::Result<()> {
+        self.formatter
+            .write_macro_body_start(&mut Eol::new(&mut self.entry_type, self.line_ending))
     }
 
     /// Write an entry key.
@@ -88,57 +430,230 @@ impl<F: Formatter> FormatBuffer<F> {
     /// Write the terminator for an entry key, often `,\n`.
     #[inline]
     pub fn write_entry_key_end(&mut self) -> io::Result<()> {
-        self.formatter.write_entry_key_end(&mut self.entry_key)
+        self.formatter
+            .write_entry_key_end(&mut Eol::new(&mut self.entry_key, self.line_ending))
     }
 
     /// Write the start of a field, such as indentation `  `.
     #[inline]
     pub fn write_field_start(&mut self) -> io::Result<()> {
-        self.formatter.write_field_start(&mut self.fields)
+        self.field_start = self.fields.len();
+        if self.field_filter.is_some() || self.skip_empty_fields || self.captured.is_some() {
+            let mut pending = std::mem::take(&mut self.spare.pending);
+            pending.key.clear();
+            pending.tokens.clear();
+            self.pending = Some(pending);
+            return Ok(());
+        }
+        self.formatter
+            .write_field_start(&mut Eol::new(&mut self.fields, self.line_ending))
     }
 
     /// Write a field key.
     #[inline]
     pub fn write_field_key(&mut self, key: &str) -> io::Result<()> {
+        if let Some(pending) = &mut self.pending {
+            pending.key.push_str(key);
+            return Ok(());
+        }
+        if self.sorted.is_some() {
+            key.clone_into(&mut self.field_key);
+        }
         self.formatter.write_field_key(&mut self.fields, key)
     }
 
     /// Write a field separator, such as ` = `.
     #[inline]
     pub fn write_field_separator(&mut self) -> io::Result<()> {
-        self.formatter.write_field_separator(&mut self.fields)
+        if self.pending.is_some() {
+            return Ok(());
+        }
+        self.formatter
+            .write_field_separator(&mut Eol::new(&mut self.fields, self.line_ending))
     }
 
     /// Write a token separator, such as ` # `.
     #[inline]
     pub fn write_token_separator(&mut self) -> io::Result<()> {
-        self.formatter.write_token_separator(&mut self.fields)
+        if self.pending.is_some() {
+            return Ok(());
+        }
+        self.formatter
+            .write_token_separator(&mut Eol::new(&mut self.fields, self.line_ending))
     }
 
     /// Write a bracketed token `{text}`.
     #[inline]
     pub fn write_bracketed_token(&mut self, token: &str) -> io::Result<()> {
+        if let Some(pending) = &mut self.pending {
+            pending.tokens.push(PendingToken::Text(token.to_owned()));
+            return Ok(());
+        }
+        let token = self.prepare_text(token);
         self.formatter
-            .write_bracketed_token(&mut self.fields, token)
+            .write_bracketed_token(&mut self.fields, &token)
+    }
+
+    /// Write a bracketed token `{text}` verbatim, without fixing or escaping the text.
+    #[inline]
+    pub fn write_bracketed_bytes(&mut self, token: &[u8]) -> io::Result<()> {
+        if let Some(pending) = &mut self.pending {
+            pending.tokens.push(PendingToken::Bytes(token.to_owned()));
+            return Ok(());
+        }
+        self.formatter
+            .write_bracketed_bytes(&mut self.fields, token)
     }
 
     /// Write a variable token `text`.
     #[inline]
     pub fn write_variable_token(&mut self, variable: &str) -> io::Result<()> {
+        if let Some(pending) = &mut self.pending {
+            pending
+                .tokens
+                .push(PendingToken::Variable(variable.to_owned()));
+            return Ok(());
+        }
         self.formatter
             .write_variable_token(&mut self.fields, variable)
     }
 
+    /// Write the source of a value verbatim.
+    pub fn write_raw_value(&mut self, source: &str) -> io::Result<()> {
+        if let Some(pending) = &mut self.pending {
+            let tokens = RawValue::new(source)
+                .and_then(|raw| raw.tokens())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+            let mut contents = String::new();
+            for token in tokens {
+                match token {
+                    Token::Variable(var) => contents.push_str(var.as_ref()),
+                    Token::Text(text) => contents.push_str(text.into_str().unwrap_or_default()),
+                }
+            }
+            pending.tokens.push(PendingToken::Raw {
+                source: source.to_owned(),
+                contents,
+            });
+            return Ok(());
+        }
+        self.formatter.write_raw_value(&mut self.fields, source)
+    }
+
     /// Write the terminator for a field, often `,\n`.
     #[inline]
     pub fn write_field_end(&mut self) -> io::Result<()> {
-        self.formatter.write_field_end(&mut self.fields)
+        match self.pending.take() {
+            Some(pending) => {
+                let value = pending.value();
+                let action = match &self.field_filter {
+                    Some(filter) => filter(&pending.key, &value),
+                    None => FieldAction::Keep,
+                };
+                let (key, rewritten) = match action {
+                    FieldAction::Keep => (None, None),
+                    FieldAction::Drop => {
+                        self.spare.pending = pending;
+                        return Ok(());
+                    }
+                    FieldAction::Rename(key) => (Some(key), None),
+                    FieldAction::Rewrite { key, value } => {
+                        (Some(key), Some([PendingToken::Text(value)]))
+                    }
+                };
+                let key = key.as_deref().unwrap_or(&pending.key);
+                let (tokens, value) = match &rewritten {
+                    Some(tokens @ [PendingToken::Text(text)]) => (tokens.as_slice(), text.as_str()),
+                    _ => (pending.tokens.as_slice(), value.as_str()),
+                };
+                // the field is skipped if the value which would be written is empty
+                if self.skip_empty_fields && value.trim().is_empty() {
+                    self.spare.pending = pending;
+                    return Ok(());
+                }
+                let result = self.write_field(key, tokens);
+                if let Some(captured) = &mut self.captured {
+                    captured.push((key.to_owned(), value.to_owned()));
+                }
+                self.spare.pending = pending;
+                result?
+            }
+            None => self
+                .formatter
+                .write_field_end(&mut Eol::new(&mut self.fields, self.line_ending))?,
+        }
+        if let Some(sorted) = &mut self.sorted {
+            sorted.push((
+                std::mem::take(&mut self.field_key),
+                self.field_start..self.fields.len(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Write an entire field, after the field filter has been applied.
+    fn write_field(&mut self, key: &str, tokens: &[PendingToken]) -> io::Result<()> {
+        if self.sorted.is_some() {
+            key.clone_into(&mut self.field_key);
+        }
+        self.formatter
+            .write_field_start(&mut Eol::new(&mut self.fields, self.line_ending))?;
+        self.formatter.write_field_key(&mut self.fields, key)?;
+        self.formatter
+            .write_field_separator(&mut Eol::new(&mut self.fields, self.line_ending))?;
+        for (idx, token) in tokens.iter().enumerate() {
+            if idx > 0 {
+                self.formatter
+                    .write_token_separator(&mut Eol::new(&mut self.fields, self.line_ending))?;
+            }
+            match token {
+                PendingToken::Text(text) => {
+                    let text = self.prepare_text(text);
+                    self.formatter
+                        .write_bracketed_token(&mut self.fields, &text)?
+                }
+                PendingToken::Bytes(bytes) => self
+                    .formatter
+                    .write_bracketed_bytes(&mut self.fields, bytes)?,
+                PendingToken::Variable(variable) => self
+                    .formatter
+                    .write_variable_token(&mut self.fields, variable)?,
+                PendingToken::Raw { source, .. } => {
+                    self.formatter.write_raw_value(&mut self.fields, source)?
+                }
+            }
+        }
+        self.formatter
+            .write_field_end(&mut Eol::new(&mut self.fields, self.line_ending))
     }
 
     /// Write the terminator for the body, often `}`.
     #[inline]
     pub fn write_body_end(&mut self) -> io::Result<()> {
-        self.formatter.write_body_end(&mut self.fields)
+        self.formatter
+            .write_body_end(&mut Eol::new(&mut self.fields, self.line_ending))
+    }
+
+    /// Write the terminator for the body of a regular entry, after removing the comma which
+    /// follows the last field if [`Formatter::trailing_comma`] is `false`.
+    pub fn write_fields_end(&mut self) -> io::Result<()> {
+        if !self.formatter.trailing_comma() {
+            // a field always ends with a value token, so any comma after the last token was
+            // written by the formatter
+            if let Some(pos) = self.fields.iter().rposition(|b| !b.is_ascii_whitespace()) {
+                if self.fields[pos] == b',' {
+                    self.fields.remove(pos);
+                }
+            }
+        }
+        self.write_body_end()
+    }
+
+    /// Write the terminator for the body of a macro entry, typically `}`.
+    #[inline]
+    pub fn write_macro_body_end(&mut self) -> io::Result<()> {
+        self.formatter
+            .write_macro_body_end(&mut Eol::new(&mut self.fields, self.line_ending))
     }
 
     /// Write the terminator for the bibliography, such as a newline.
@@ -147,7 +662,38 @@ impl<F: Formatter> FormatBuffer<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.formatter.write_bibliography_end(writer)
+        self.formatter
+            .write_bibliography_end(&mut Eol::new(writer, self.line_ending))
+    }
+}
+
+/// The delimiters which enclose the body of an entry.
+///
+/// BibTeX accepts both `@string{...}` and `@string(...)`; the choice is a matter of style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyDelimiters {
+    /// Enclose the body in `{}` brackets.
+    #[default]
+    Braces,
+    /// Enclose the body in `()` parentheses.
+    Parentheses,
+}
+
+impl BodyDelimiters {
+    /// The opening delimiter.
+    pub fn opening(self) -> u8 {
+        match self {
+            Self::Braces => b'{',
+            Self::Parentheses => b'(',
+        }
+    }
+
+    /// The closing delimiter.
+    pub fn closing(self) -> u8 {
+        match self {
+            Self::Braces => b'}',
+            Self::Parentheses => b')',
+        }
     }
 }
 
@@ -182,6 +728,14 @@ impl Formatter for CompactFormatter {
         Ok(())
     }
 
+    #[inline]
+    fn write_prologue_end<W>(&mut self, _writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        Ok(())
+    }
+
     #[inline]
     fn write_entry_key_end<W>(&mut self, _writer: &mut W) -> io::Result<()>
     where
@@ -232,12 +786,211 @@ impl Formatter for CompactFormatter {
 }
 
 /// A wrapper to convert an arbitrary formatter into one which also performs validation.
-pub struct ValidatingFormatter<F>(F);
+///
+/// Optionally, the output can also be limited with [`ValidatingFormatter::max_line_length`] and
+/// [`ValidatingFormatter::max_value_size`].
+pub struct ValidatingFormatter<F> {
+    formatter: F,
+    max_line_length: Option<usize>,
+    max_value_size: Option<usize>,
+    /// Whether entry types, keys, and tokens are checked.
+    check_syntax: bool,
+    /// The number of characters written since the last line break.
+    column: usize,
+    /// The number of bytes in the contents of the current value.
+    value_size: usize,
+    /// The delimiters of the current body, and whether they were found at the start rather than
+    /// the end of the body, if only one of them was written.
+    body: Option<(BodyDelimiters, bool)>,
+    /// The output of the wrapped formatter for a body delimiter, before it is checked.
+    delimiter: Vec<u8>,
+}
 
 impl<F> ValidatingFormatter<F> {
     /// Create a `ValidatingFormatter` by wrapping another formatter.
     pub fn new(formatter: F) -> Self {
-        Self(formatter)
+        Self {
+            formatter,
+            max_line_length: None,
+            max_value_size: None,
+            check_syntax: true,
+            column: 0,
+            value_size: 0,
+            body: None,
+            delimiter: Vec::new(),
+        }
+    }
+
+    /// Return an error if a line of the output is longer than `max` characters, not including
+    /// the line break.
+    ///
+    /// Lines are measured as they are written by the formatter, so a value containing a line
+    /// break starts a new line.
+    /// ```
+    /// use serde_bibtex::ser::{PrettyFormatter, Serializer};
+    ///
+    /// let formatter = PrettyFormatter {}.validate().max_line_length(20);
+    /// let mut ser = Serializer::new_with_formatter(Vec::new(), formatter);
+    ///
+    /// assert!(ser.serialize_entry(&("misc", "key", [("title", "Short")])).is_ok());
+    /// assert!(ser
+    ///     .serialize_entry(&("misc", "key", [("title", "A title which is too long")]))
+    ///     .is_err());
+    /// ```
+    pub fn max_line_length(mut self, max: usize) -> Self {
+        self.max_line_length = Some(max);
+        self
+    }
+
+    /// Return an error if the contents of a value are longer than `max` bytes.
+    ///
+    /// The size of a value is the total size of its tokens, not including delimiters or `#`
+    /// separators.
+    /// ```
+    /// use serde_bibtex::ser::{CompactFormatter, Serializer};
+    ///
+    /// let formatter = CompactFormatter {}.validate().max_value_size(4);
+    /// let mut ser = Serializer::new_with_formatter(Vec::new(), formatter);
+    ///
+    /// assert!(ser.serialize_entry(&("misc", "key", [("year", "2024")])).is_ok());
+    /// assert!(ser.serialize_entry(&("misc", "key", [("abstract", "Long")])).is_ok());
+    /// assert!(ser.serialize_entry(&("misc", "key", [("abstract", "Longer")])).is_err());
+    /// ```
+    pub fn max_value_size(mut self, max: usize) -> Self {
+        self.max_value_size = Some(max);
+        self
+    }
+
+    /// Set whether entry types, entry keys, field keys, and value tokens are checked. The default
+    /// is `true`.
+    ///
+    /// Disabling the checks retains the limits set by [`ValidatingFormatter::max_line_length`]
+    /// and [`ValidatingFormatter::max_value_size`].
+    /// ```
+    /// use serde_bibtex::ser::{PrettyFormatter, Serializer};
+    ///
+    /// let formatter = PrettyFormatter {}.validate().check_syntax(false);
+    /// let mut ser = Serializer::new_with_formatter(Vec::new(), formatter);
+    ///
+    /// assert!(ser.serialize_entry(&("misc", "a key", [("title", "}")])).is_ok());
+    /// ```
+    pub fn check_syntax(mut self, enable: bool) -> Self {
+        self.check_syntax = enable;
+        self
+    }
+
+    /// The wrapped formatter, and a writer which checks the length of the lines written to
+    /// `writer`.
+    fn split<'a, W: ?Sized>(&'a mut self, writer: &'a mut W) -> (&'a mut F, LineCheck<'a, W>) {
+        (
+            &mut self.formatter,
+            LineCheck {
+                writer,
+                column: &mut self.column,
+                max: self.max_line_length,
+            },
+        )
+    }
+
+    /// Write a body delimiter with `write`, checking that it matches the other delimiter of the
+    /// body.
+    ///
+    /// The fields of a regular entry may be written before its entry type, so the end of a body
+    /// can be written before its start.
+    fn write_body_delimiter<W>(
+        &mut self,
+        writer: &mut W,
+        start: bool,
+        write: impl FnOnce(&mut F, &mut Vec<u8>) -> io::Result<()>,
+    ) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let mut delimiter = std::mem::take(&mut self.delimiter);
+        delimiter.clear();
+        write(&mut self.formatter, &mut delimiter)?;
+        if self.check_syntax {
+            let found = match (start, delimiter.trim_ascii()) {
+                (true, b"{") | (false, b"}") => BodyDelimiters::Braces,
+                (true, b"(") | (false, b")") => BodyDelimiters::Parentheses,
+                (_, other) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "invalid body {} '{}'",
+                            if start { "start" } else { "end" },
+                            String::from_utf8_lossy(other)
+                        ),
+                    ))
+                }
+            };
+            self.body = match self.body.take() {
+                Some((other, written)) if other != found && written != start => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "body end '{}' does not match body start '{}'",
+                            char::from(other.closing()),
+                            char::from(other.opening())
+                        ),
+                    ))
+                }
+                Some((_, written)) if written != start => None,
+                _ => Some((found, start)),
+            };
+        }
+        let (_, mut writer) = self.split(writer);
+        let result = io::Write::write_all(&mut writer, &delimiter);
+        self.delimiter = delimiter;
+        result
+    }
+
+    /// Add to the size of the current value, returning an error if it is too large.
+    fn add_value_size(&mut self, size: usize) -> io::Result<()> {
+        self.value_size += size;
+        match self.max_value_size {
+            Some(max) if self.value_size > max => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("value exceeds the maximum size of {max} bytes"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A writer which tracks the length of the current line, returning an error if it is too long.
+struct LineCheck<'a, W: ?Sized> {
+    writer: &'a mut W,
+    column: &'a mut usize,
+    max: Option<usize>,
+}
+
+impl<W: ?Sized + io::Write> io::Write for LineCheck<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(max) = self.max {
+            for b in buf {
+                match b {
+                    b'\n' => *self.column = 0,
+                    // do not count UTF-8 continuation bytes or the `\r` in `\r\n`
+                    0x80..=0xbf | b'\r' => {}
+                    _ => {
+                        *self.column += 1;
+                        if *self.column > max {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!("line exceeds the maximum length of {max} characters"),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        self.writer.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
     }
 }
 
@@ -247,7 +1000,26 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_entry_separator(writer)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_entry_separator(&mut writer)
+    }
+
+    #[inline]
+    fn write_prologue_line<W>(&mut self, writer: &mut W, line: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_prologue_line(&mut writer, line)
+    }
+
+    #[inline]
+    fn write_prologue_end<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_prologue_end(&mut writer)
     }
 
     #[inline]
@@ -255,13 +1027,43 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        if !is_regular_entry_type(entry_type) {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("invalid entry type: '{entry_type}'"),
-            ));
+        if self.check_syntax {
+            if let Err(err) = check_regular_entry_type(entry_type) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid entry type '{entry_type}': {err}"),
+                ));
+            }
         }
-        self.0.write_regular_entry_type(writer, entry_type)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_regular_entry_type(&mut writer, entry_type)
+    }
+
+    #[inline]
+    fn write_macro_entry_type<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_macro_entry_type(&mut writer)
+    }
+
+    #[inline]
+    fn write_comment_entry_type<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_comment_entry_type(&mut writer)
+    }
+
+    #[inline]
+    fn write_preamble_entry_type<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_preamble_entry_type(&mut writer)
     }
 
     #[inline]
@@ -269,7 +1071,26 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_body_start(writer)
+        self.value_size = 0;
+        self.write_body_delimiter(writer, true, |formatter, buf| {
+            formatter.write_body_start(buf)
+        })
+    }
+
+    #[inline]
+    fn macro_delimiters(&self) -> BodyDelimiters {
+        self.formatter.macro_delimiters()
+    }
+
+    #[inline]
+    fn write_macro_body_start<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.value_size = 0;
+        self.write_body_delimiter(writer, true, |formatter, buf| {
+            formatter.write_macro_body_start(buf)
+        })
     }
 
     #[inline]
@@ -277,13 +1098,16 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        if !is_entry_key(key) {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("invalid entry key: '{key}'"),
-            ));
+        if self.check_syntax {
+            if let Err(err) = check_entry_key(key) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid entry key '{key}': {err}"),
+                ));
+            }
         }
-        self.0.write_entry_key(writer, key)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_entry_key(&mut writer, key)
     }
 
     #[inline]
@@ -291,7 +1115,8 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_entry_key_end(writer)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_entry_key_end(&mut writer)
     }
 
     #[inline]
@@ -299,7 +1124,8 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_field_start(writer)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_field_start(&mut writer)
     }
 
     #[inline]
@@ -307,13 +1133,16 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        if !is_field_key(key) {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("invalid field key: '{key}'"),
-            ));
+        if self.check_syntax {
+            if let Err(err) = check_field_key(key) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid field key '{key}': {err}"),
+                ));
+            }
         }
-        self.0.write_field_key(writer, key)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_field_key(&mut writer, key)
     }
 
     #[inline]
@@ -321,7 +1150,9 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_field_separator(writer)
+        self.value_size = 0;
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_field_separator(&mut writer)
     }
 
     #[inline]
@@ -329,7 +1160,8 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_token_separator(writer)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_token_separator(&mut writer)
     }
 
     #[inline]
@@ -337,13 +1169,38 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        if !is_balanced(text.as_bytes()) {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("unbalanced text token: '{text}'"),
-            ));
+        if self.check_syntax {
+            if let Err(err) = check_balanced(text.as_bytes()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unbalanced text token '{text}': {err}"),
+                ));
+            }
         }
-        self.0.write_bracketed_token(writer, text)
+        self.add_value_size(text.len())?;
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_bracketed_token(&mut writer, text)
+    }
+
+    #[inline]
+    fn write_bracketed_bytes<W>(&mut self, writer: &mut W, token: &[u8]) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        if self.check_syntax {
+            if let Err(err) = check_balanced(token) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "unbalanced text token '{}': {err}",
+                        String::from_utf8_lossy(token)
+                    ),
+                ));
+            }
+        }
+        self.add_value_size(token.len())?;
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_bracketed_bytes(&mut writer, token)
     }
 
     #[inline]
@@ -351,13 +1208,49 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        if !is_variable(variable) {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("invalid variable: '{variable}'"),
-            ));
+        if self.check_syntax {
+            if let Err(err) = check_variable(variable) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid variable '{variable}': {err}"),
+                ));
+            }
         }
-        self.0.write_variable_token(writer, variable)
+        self.add_value_size(variable.len())?;
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_variable_token(&mut writer, variable)
+    }
+
+    #[inline]
+    fn write_raw_value<W>(&mut self, writer: &mut W, source: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        let tokens = match RawValue::new(source) {
+            Ok(raw) if raw.as_str().len() == source.len() => raw.tokens(),
+            _ if !self.check_syntax => {
+                self.add_value_size(source.len())?;
+                let (formatter, mut writer) = self.split(writer);
+                return formatter.write_raw_value(&mut writer, source);
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid raw value '{source}'"),
+                ))
+            }
+        };
+        if self.max_value_size.is_some() {
+            // a valid raw value can always be parsed into tokens
+            for token in tokens.unwrap_or_default() {
+                self.add_value_size(match token {
+                    Token::Variable(var) => var.as_ref().len(),
+                    Token::Text(text) => text.into_bytes().len(),
+                })?;
+            }
+        }
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_raw_value(&mut writer, source)
     }
 
     #[inline]
@@ -365,7 +1258,13 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_field_end(writer)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_field_end(&mut writer)
+    }
+
+    #[inline]
+    fn trailing_comma(&self) -> bool {
+        self.formatter.trailing_comma()
     }
 
     #[inline]
@@ -373,7 +1272,19 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_body_end(writer)
+        self.write_body_delimiter(writer, false, |formatter, buf| {
+            formatter.write_body_end(buf)
+        })
+    }
+
+    #[inline]
+    fn write_macro_body_end<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        self.write_body_delimiter(writer, false, |formatter, buf| {
+            formatter.write_macro_body_end(buf)
+        })
     }
 
     #[inline]
@@ -381,7 +1292,8 @@ impl<F: Formatter> Formatter for ValidatingFormatter<F> {
     where
         W: ?Sized + io::Write,
     {
-        self.0.write_bibliography_end(writer)
+        let (formatter, mut writer) = self.split(writer);
+        formatter.write_bibliography_end(&mut writer)
     }
 }
 
@@ -401,6 +1313,26 @@ pub trait Formatter {
         writer.write_all(b"\n\n")
     }
 
+    /// Write a line of the prologue, often `% ` followed by the line and `\n`.
+    #[inline]
+    fn write_prologue_line<W>(&mut self, writer: &mut W, line: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b"% ")?;
+        writer.write_all(line.as_bytes())?;
+        writer.write_all(b"\n")
+    }
+
+    /// The separator between the prologue and the first entry, often `\n`.
+    #[inline]
+    fn write_prologue_end<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(b"\n")
+    }
+
     /// Write the entry type, including the `@` symbol.
     #[inline]
     fn write_regular_entry_type<W>(&mut self, writer: &mut W, entry_type: &str) -> io::Result<()>
@@ -446,6 +1378,54 @@ pub trait Formatter {
         writer.write_all(b"{")
     }
 
+    /// The delimiters which enclose the body of a macro entry. The default is
+    /// [`BodyDelimiters::Braces`].
+    ///
+    /// Some house styles write macros as `@string(...)`; return
+    /// [`BodyDelimiters::Parentheses`] to do so.
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_bibtex::ser::{BodyDelimiters, Formatter, Serializer, ValidatingFormatter};
+    ///
+    /// struct ParenthesizedMacros;
+    ///
+    /// impl Formatter for ParenthesizedMacros {
+    ///     fn macro_delimiters(&self) -> BodyDelimiters {
+    ///         BodyDelimiters::Parentheses
+    ///     }
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// enum Entry {
+    ///     Macro(&'static str, &'static str),
+    ///     Regular(&'static str, &'static str, [(&'static str, &'static str); 1]),
+    /// }
+    ///
+    /// let formatter = ValidatingFormatter::new(ParenthesizedMacros);
+    /// let mut ser = Serializer::new_with_formatter(Vec::new(), formatter);
+    /// ser.serialize_entry(&Entry::Macro("apr", "April")).unwrap();
+    /// ser.serialize_entry(&Entry::Regular("misc", "key", [("month", "4")]))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@string(apr = {April})\n@misc{key,\n  month = {4},\n}\n"
+    /// );
+    /// ```
+    #[inline]
+    fn macro_delimiters(&self) -> BodyDelimiters {
+        BodyDelimiters::Braces
+    }
+
+    /// Write the body start character of a macro entry, by default the opening delimiter of
+    /// [`Formatter::macro_delimiters`].
+    #[inline]
+    fn write_macro_body_start<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(&[self.macro_delimiters().opening()])
+    }
+
     /// Write an entry key.
     #[inline]
     fn write_entry_key<W>(&mut self, writer: &mut W, key: &str) -> io::Result<()>
@@ -511,6 +1491,26 @@ pub trait Formatter {
         writer.write_all(b"}")
     }
 
+    /// Write a bracketed token `{text}` whose contents are bytes, which need not be valid UTF-8.
+    ///
+    /// By default, a token which is valid UTF-8 is written with
+    /// [`write_bracketed_token`](Formatter::write_bracketed_token), and any other token is
+    /// written verbatim between brackets.
+    #[inline]
+    fn write_bracketed_bytes<W>(&mut self, writer: &mut W, token: &[u8]) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        match std::str::from_utf8(token) {
+            Ok(token) => self.write_bracketed_token(writer, token),
+            Err(_) => {
+                writer.write_all(b"{")?;
+                writer.write_all(token)?;
+                writer.write_all(b"}")
+            }
+        }
+    }
+
     /// Write a variable token `text`.
     #[inline]
     fn write_variable_token<W>(&mut self, writer: &mut W, variable: &str) -> io::Result<()>
@@ -520,6 +1520,15 @@ pub trait Formatter {
         writer.write_all(variable.as_bytes())
     }
 
+    /// Write the source of a value verbatim, such as `{A} # var`.
+    #[inline]
+    fn write_raw_value<W>(&mut self, writer: &mut W, source: &str) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(source.as_bytes())
+    }
+
     /// Write the terminator for a field, often `,\n`.
     #[inline]
     fn write_field_end<W>(&mut self, writer: &mut W) -> io::Result<()>
@@ -529,6 +1538,36 @@ pub trait Formatter {
         writer.write_all(b",\n")
     }
 
+    /// Whether the comma written by [`Formatter::write_field_end`] is kept after the last field
+    /// of an entry. The default is `true`.
+    ///
+    /// Some parsers and style guides do not accept a comma after the last field; return `false`
+    /// to remove it. Both forms are valid BibTeX.
+    /// ```
+    /// use serde_bibtex::ser::{Formatter, Serializer, ValidatingFormatter};
+    ///
+    /// struct NoTrailingComma;
+    ///
+    /// impl Formatter for NoTrailingComma {
+    ///     fn trailing_comma(&self) -> bool {
+    ///         false
+    ///     }
+    /// }
+    ///
+    /// let formatter = ValidatingFormatter::new(NoTrailingComma);
+    /// let mut ser = Serializer::new_with_formatter(Vec::new(), formatter);
+    /// ser.serialize_entry(&("misc", "key", [("author", "A"), ("title", "T")]))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@misc{key,\n  author = {A},\n  title = {T}\n}\n"
+    /// );
+    /// ```
+    #[inline]
+    fn trailing_comma(&self) -> bool {
+        true
+    }
+
     /// Write the terminator for the body, often `}`.
     #[inline]
     fn write_body_end<W>(&mut self, writer: &mut W) -> io::Result<()>
@@ -538,6 +1577,16 @@ pub trait Formatter {
         writer.write_all(b"}")
     }
 
+    /// Write the terminator for the body of a macro entry, by default the closing delimiter of
+    /// [`Formatter::macro_delimiters`].
+    #[inline]
+    fn write_macro_body_end<W>(&mut self, writer: &mut W) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(&[self.macro_delimiters().closing()])
+    }
+
     /// Write the terminator for the bibliography, such as a newline.
     #[inline]
     fn write_bibliography_end<W>(&mut self, writer: &mut W) -> io::Result<()>
@@ -287,9 +287,30 @@ macro_rules! serialize_err {
     };
 }
 
-/// A macro to defer serialization to an implementation for bytes
+/// Like `serialize_err`, but without the default implementation of `serialize_newtype_struct`.
+macro_rules! serialize_err_custom_newtype {
+    ($err:expr, $($es:tt),+) => {
+        type Error = Error;
+
+        $(crate::ser::macros::serialize_err_helper!($err, $es);)+
+    };
+}
+
+/// A macro to defer serialization to an implementation for strings. Bytes are serialized as a
+/// string if they are valid UTF-8, unless an implementation for bytes is also provided.
 macro_rules! serialize_as_bytes {
     ($err:expr, $name:ident, {$($str_impl:tt)*}) => {
+        crate::ser::macros::serialize_as_bytes!($err, $name, {$($str_impl)*}, {
+            fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+                match std::str::from_utf8(value) {
+                    Ok(value) => ser::Serializer::serialize_str(self, value),
+                    Err(_) => Err(Error::ser(concat!($err, " as non-UTF-8 bytes").to_string())),
+                }
+            }
+        });
+    };
+
+    ($err:expr, $name:ident, {$($str_impl:tt)*}, {$($bytes_impl:tt)*}) => {
         pub(crate) struct $name<'a, W, F> {
             ser: &'a mut Serializer<W, F>,
         }
@@ -321,7 +342,6 @@ macro_rules! serialize_as_bytes {
                 f64,
                 bool,
                 seq,
-                bytes,
                 option,
                 tuple,
                 tuple_struct,
@@ -337,11 +357,14 @@ macro_rules! serialize_as_bytes {
             #[inline]
             $($str_impl)*
 
+            #[inline]
+            $($bytes_impl)*
+
             #[inline]
             fn serialize_char(self, value: char) -> Result<Self::Ok> {
                 // A char encoded as UTF-8 takes 4 bytes at most.
                 let mut buf = [0; 4];
-                self.serialize_bytes(value.encode_utf8(&mut buf).as_bytes())
+                self.serialize_str(value.encode_utf8(&mut buf))
             }
 
             /// A unit variant is serialized using the name of the variant.
@@ -352,7 +375,7 @@ macro_rules! serialize_as_bytes {
                 _variant_index: u32,
                 variant: &'static str,
             ) -> std::result::Result<Self::Ok, Self::Error> {
-                self.serialize_bytes(variant.as_bytes())
+                self.serialize_str(variant)
             }
         }
     };
@@ -407,5 +430,6 @@ macro_rules! ser_wrapper {
 }
 
 pub(crate) use {
-    ser_wrapper, serialize_as_bytes, serialize_err, serialize_err_helper, serialize_trait_impl,
+    ser_wrapper, serialize_as_bytes, serialize_err, serialize_err_custom_newtype,
+    serialize_err_helper, serialize_trait_impl,
 };
@@ -0,0 +1,314 @@
+use std::io;
+
+use serde::ser;
+
+use super::macros::{ser_wrapper, serialize_err, serialize_err_custom_newtype};
+use super::value::{TextTokenSerializer, TokenSerializer, ValueSerializer};
+use super::{Formatter, Serializer};
+use crate::error::{Error, Result};
+use crate::naming::RAW_VALUE_NAME;
+
+ser_wrapper!(PreambleSerializer);
+
+/// The contents of a preamble entry. A sequence is a list of parts, which are joined with `#` or
+/// written as separate entries according to [`Serializer::split_preamble`]; anything else is
+/// written as a single value.
+impl<'a, W, F> ser::Serializer for PreambleSerializer<'a, W, F>
+where
+    W: io::Write,
+    F: Formatter,
+{
+    type Ok = ();
+
+    serialize_err_custom_newtype!(
+        "preamble",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        option,
+        bool,
+        map,
+        struct,
+        struct_variant,
+        tuple_variant,
+        unit,
+        unit_struct,
+        unit_variant,
+        newtype_variant
+    );
+
+    type SerializeSeq = PreamblePartsSerializer<'a, W, F>;
+    type SerializeTuple = PreamblePartsSerializer<'a, W, F>;
+    type SerializeTupleStruct = PreamblePartsSerializer<'a, W, F>;
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(PreamblePartsSerializer::new(self.ser))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(PreamblePartsSerializer::new(self.ser))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(PreamblePartsSerializer::new(self.ser))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        ser::Serializer::serialize_char(ValueSerializer::new(self.ser), v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        ser::Serializer::serialize_str(ValueSerializer::new(self.ser), v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        ser::Serializer::serialize_bytes(ValueSerializer::new(self.ser), v)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        if name == RAW_VALUE_NAME {
+            ser::Serializer::serialize_newtype_struct(ValueSerializer::new(self.ser), name, value)
+        } else {
+            value.serialize(self)
+        }
+    }
+}
+
+/// The parts of a preamble.
+///
+/// Each part is either a value, which is a sequence of tokens, a string, or a raw value. A token
+/// which is not contained in a part is appended to the current part, so that a preamble which is a
+/// single sequence of tokens is written as a single value. Empty parts are skipped, so that they do
+/// not start an empty preamble entry.
+pub(crate) struct PreamblePartsSerializer<'a, W, F> {
+    ser: &'a mut Serializer<W, F>,
+    /// Whether no token has been written in the current entry.
+    first: bool,
+    /// Whether a part has been written.
+    started: bool,
+}
+
+impl<'a, W, F> PreamblePartsSerializer<'a, W, F>
+where
+    W: io::Write,
+    F: Formatter,
+{
+    fn new(ser: &'a mut Serializer<W, F>) -> Self {
+        Self {
+            ser,
+            first: true,
+            started: false,
+        }
+    }
+
+    /// Prepare to write a new part, starting a new preamble entry if the parts are split.
+    fn start_part(&mut self) -> Result<()> {
+        if self.started && self.ser.split_preamble {
+            self.ser.buffer.write_body_end()?;
+            self.ser.flush_entry()?;
+            self.ser.buffer.write_preamble_entry_type()?;
+            self.ser.buffer.write_body_start()?;
+            self.first = true;
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    /// Prepare to write a token in the current part.
+    fn start_token(&mut self) -> Result<()> {
+        if self.first {
+            self.first = false;
+        } else {
+            self.ser.buffer.write_token_separator()?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! preamble_parts_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl<'a, W, F> ser::$trait for PreamblePartsSerializer<'a, W, F>
+        where
+            W: io::Write,
+            F: Formatter,
+        {
+            type Ok = ();
+            type Error = Error;
+
+            fn $fn<T>(&mut self, value: &T) -> Result<Self::Ok>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                value.serialize(PreamblePartSerializer { parts: self })
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(())
+            }
+        }
+    };
+}
+
+preamble_parts_serializer_impl!(serialize_element, SerializeSeq);
+preamble_parts_serializer_impl!(serialize_element, SerializeTuple);
+preamble_parts_serializer_impl!(serialize_field, SerializeTupleStruct);
+
+/// A single part of a preamble, or a token of the current part.
+struct PreamblePartSerializer<'b, 'a, W, F> {
+    parts: &'b mut PreamblePartsSerializer<'a, W, F>,
+}
+
+impl<'b, 'a, W, F> ser::Serializer for PreamblePartSerializer<'b, 'a, W, F>
+where
+    W: io::Write,
+    F: Formatter,
+{
+    type Ok = ();
+
+    serialize_err!(
+        "preamble part",
+        i8,
+        i16,
+        i32,
+        i64,
+        u8,
+        u16,
+        u32,
+        u64,
+        f32,
+        f64,
+        option,
+        bool,
+        map,
+        struct,
+        struct_variant,
+        tuple_variant,
+        unit,
+        unit_struct,
+        unit_variant
+    );
+
+    type SerializeSeq = PreambleTokensSerializer<'b, 'a, W, F>;
+    type SerializeTuple = PreambleTokensSerializer<'b, 'a, W, F>;
+    type SerializeTupleStruct = PreambleTokensSerializer<'b, 'a, W, F>;
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(PreambleTokensSerializer {
+            parts: self.parts,
+            started: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        self.parts.start_part()?;
+        self.parts.start_token()?;
+        ser::Serializer::serialize_char(TextTokenSerializer::new(&mut *self.parts.ser), v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        if v.is_empty() {
+            return Ok(());
+        }
+        self.parts.start_part()?;
+        self.parts.start_token()?;
+        ser::Serializer::serialize_str(TextTokenSerializer::new(&mut *self.parts.ser), v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        if v.is_empty() {
+            return Ok(());
+        }
+        self.parts.start_part()?;
+        self.parts.start_token()?;
+        ser::Serializer::serialize_bytes(TextTokenSerializer::new(&mut *self.parts.ser), v)
+    }
+
+    /// A token is appended to the current part.
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        self.parts.started = true;
+        self.parts.start_token()?;
+        ser::Serializer::serialize_newtype_variant(
+            TokenSerializer::new(&mut *self.parts.ser),
+            name,
+            variant_index,
+            variant,
+            value,
+        )
+    }
+}
+
+/// The tokens of a part of a preamble. The part is only started by its first token.
+pub(crate) struct PreambleTokensSerializer<'b, 'a, W, F> {
+    parts: &'b mut PreamblePartsSerializer<'a, W, F>,
+    started: bool,
+}
+
+macro_rules! preamble_tokens_serializer_impl {
+    ($fn:ident, $trait:ident) => {
+        impl<'b, 'a, W, F> ser::$trait for PreambleTokensSerializer<'b, 'a, W, F>
+        where
+            W: io::Write,
+            F: Formatter,
+        {
+            type Ok = ();
+            type Error = Error;
+
+            fn $fn<T>(&mut self, value: &T) -> Result<Self::Ok>
+            where
+                T: ?Sized + ser::Serialize,
+            {
+                if !self.started {
+                    self.parts.start_part()?;
+                    self.started = true;
+                }
+                self.parts.start_token()?;
+                value.serialize(TokenSerializer::new(&mut *self.parts.ser))
+            }
+
+            #[inline]
+            fn end(self) -> Result<Self::Ok> {
+                Ok(())
+            }
+        }
+    };
+}
+
+preamble_tokens_serializer_impl!(serialize_element, SerializeSeq);
+preamble_tokens_serializer_impl!(serialize_element, SerializeTuple);
+preamble_tokens_serializer_impl!(serialize_field, SerializeTupleStruct);
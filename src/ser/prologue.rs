@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::io;
+
+use serde::ser::{self, Serialize, SerializeSeq};
+use unicase::UniCase;
+
+use super::{entry::EntrySerializer, Formatter, Serializer};
+use crate::error::Result;
+use crate::naming::{
+    COMMENT_ENTRY_VARIANT_NAME as CVN, MACRO_ENTRY_VARIANT_NAME as MVN,
+    MACRO_TOKEN_VARIANT_NAME as MTVN, TEXT_TOKEN_VARIANT_NAME as TTVN,
+};
+use crate::token::{Text, Token, Variable};
+use crate::MacroDictionary;
+
+/// A macro definition, which serializes as a macro entry.
+struct MacroEntry<'a, S: AsRef<str>, B: AsRef<[u8]>> {
+    variable: &'a Variable<S>,
+    tokens: &'a [Token<S, B>],
+}
+
+/// Comment text, which serializes as a comment entry.
+struct CommentEntry<'a>(&'a str);
+
+/// A sequence of value tokens.
+struct Tokens<'a, S: AsRef<str>, B: AsRef<[u8]>>(&'a [Token<S, B>]);
+
+/// A single value token.
+struct TokenRef<'a, S: AsRef<str>, B: AsRef<[u8]>>(&'a Token<S, B>);
+
+/// A macro name and its value, as returned by [`MacroDictionary::iter`].
+type MacroDefinition<'a, S, B> = (&'a Variable<S>, &'a [Token<S, B>]);
+
+/// Append the index of the macro `idx` to `order`, after the macros which its value refers to.
+///
+/// Macros which refer to each other are appended in the order in which they are visited.
+fn dependency_order<S: AsRef<str>, B: AsRef<[u8]>>(
+    idx: usize,
+    entries: &[MacroDefinition<'_, S, B>],
+    index: &HashMap<UniCase<&str>, usize>,
+    visited: &mut [bool],
+    order: &mut Vec<usize>,
+) {
+    if std::mem::replace(&mut visited[idx], true) {
+        return;
+    }
+    for token in entries[idx].1 {
+        if let Token::Variable(variable) = token {
+            if let Some(&dependency) = index.get(&UniCase::new(variable.as_ref())) {
+                dependency_order(dependency, entries, index, visited, order);
+            }
+        }
+    }
+    order.push(idx);
+}
+
+impl<S: AsRef<str>, B: AsRef<[u8]>> Serialize for MacroEntry<'_, S, B> {
+    fn serialize<Ser: serde::Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> std::result::Result<Ser::Ok, Ser::Error> {
+        serializer.serialize_newtype_variant(
+            "Entry",
+            1,
+            MVN,
+            &(self.variable.as_ref(), Tokens(self.tokens)),
+        )
+    }
+}
+
+impl Serialize for CommentEntry<'_> {
+    fn serialize<Ser: serde::Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> std::result::Result<Ser::Ok, Ser::Error> {
+        serializer.serialize_newtype_variant("Entry", 2, CVN, self.0)
+    }
+}
+
+impl<S: AsRef<str>, B: AsRef<[u8]>> Serialize for Tokens<'_, S, B> {
+    fn serialize<Ser: serde::Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> std::result::Result<Ser::Ok, Ser::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for token in self.0 {
+            seq.serialize_element(&TokenRef(token))?;
+        }
+        seq.end()
+    }
+}
+
+impl<S: AsRef<str>, B: AsRef<[u8]>> Serialize for TokenRef<'_, S, B> {
+    fn serialize<Ser: serde::Serializer>(
+        &self,
+        serializer: Ser,
+    ) -> std::result::Result<Ser::Ok, Ser::Error> {
+        match self.0 {
+            Token::Variable(var) => {
+                serializer.serialize_newtype_variant("Token", 0, MTVN, var.as_ref())
+            }
+            Token::Text(Text::Str(s)) => {
+                serializer.serialize_newtype_variant("Token", 1, TTVN, s.as_ref())
+            }
+            Token::Text(Text::Bytes(b)) => match std::str::from_utf8(b.as_ref()) {
+                Ok(s) => serializer.serialize_newtype_variant("Token", 1, TTVN, s),
+                Err(err) => Err(ser::Error::custom(err)),
+            },
+        }
+    }
+}
+
+impl<W, F> Serializer<W, F>
+where
+    W: io::Write,
+    F: Formatter,
+{
+    /// Write the macros in a dictionary as `@string` entries.
+    ///
+    /// Each macro is written after the macros which its value refers to, so that the output
+    /// expands the same way when read back. Otherwise, the macros are written in the order in
+    /// which they were first inserted into the dictionary.
+    ///
+    /// This is intended to be called before serializing the bibliography, so that the macros are
+    /// written at the top of the output.
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_bibtex::{ser::Serializer, MacroDictionary};
+    /// use serde_bibtex::token::{Token, Variable};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Record {
+    ///     entry_type: &'static str,
+    ///     entry_key: &'static str,
+    ///     fields: Vec<(&'static str, &'static str)>,
+    /// }
+    ///
+    /// let mut macros = MacroDictionary::<&str, &[u8]>::default();
+    /// macros.insert(Variable::new("pub").unwrap(), vec![Token::str("AMS").unwrap()]);
+    /// macros.insert(Variable::new("Addr").unwrap(), vec![Token::str("Providence").unwrap()]);
+    ///
+    /// let bibliography = vec![Record {
+    ///     entry_type: "book",
+    ///     entry_key: "key",
+    ///     fields: vec![("title", "Title")],
+    /// }];
+    ///
+    /// let mut ser = Serializer::new(Vec::new());
+    /// ser.write_macros(&macros).unwrap();
+    /// bibliography.serialize(&mut ser).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@string{pub = {AMS}}\n\n@string{Addr = {Providence}}\n\n@book{key,\n  title = {Title},\n}\n"
+    /// );
+    /// ```
+    pub fn write_macros<S, B>(&mut self, macros: &MacroDictionary<S, B>) -> Result<()>
+    where
+        S: AsRef<str> + Eq,
+        B: AsRef<[u8]>,
+    {
+        let entries: Vec<_> = macros.iter().collect();
+        let index: HashMap<UniCase<&str>, usize> = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, (variable, _))| (UniCase::new(variable.as_ref()), idx))
+            .collect();
+        let mut visited = vec![false; entries.len()];
+        let mut order = Vec::with_capacity(entries.len());
+        for idx in 0..entries.len() {
+            dependency_order(idx, &entries, &index, &mut visited, &mut order);
+        }
+
+        for idx in order {
+            let (variable, tokens) = entries[idx];
+            self.write_standalone_entry(&MacroEntry { variable, tokens })?;
+        }
+        Ok(())
+    }
+
+    /// Write a `@comment` entry containing the given text.
+    ///
+    /// This can be called between calls which serialize entries, for instance to separate the
+    /// output into sections with human-readable headers. The text must have balanced brackets.
+    /// Within a serialized sequence, use a newtype `Comment` variant instead: see the
+    /// [module documentation](crate::ser).
+    /// ```
+    /// use serde::Serialize;
+    /// use serde_bibtex::ser::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Record {
+    ///     entry_type: &'static str,
+    ///     entry_key: &'static str,
+    ///     fields: Vec<(&'static str, &'static str)>,
+    /// }
+    ///
+    /// let mut ser = Serializer::new(Vec::new());
+    /// ser.write_comment("Books").unwrap();
+    /// vec![Record {
+    ///     entry_type: "book",
+    ///     entry_key: "key",
+    ///     fields: vec![("title", "Title")],
+    /// }]
+    /// .serialize(&mut ser)
+    /// .unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(ser.into_inner()).unwrap(),
+    ///     "@comment{Books}\n\n@book{key,\n  title = {Title},\n}\n"
+    /// );
+    ///
+    /// let mut ser = Serializer::new(Vec::new());
+    /// assert!(ser.write_comment("unbalanced {").is_err());
+    /// ```
+    pub fn write_comment(&mut self, text: &str) -> Result<()> {
+        self.write_standalone_entry(&CommentEntry(text))
+    }
+
+    /// Write a single entry outside of a serialized bibliography, separated from the entries
+    /// which follow it.
+    fn write_standalone_entry<T: Serialize>(&mut self, entry: &T) -> Result<()> {
+        self.write_prologue()?;
+        entry.serialize(EntrySerializer::new(&mut *self))?;
+        self.flush_entry()
+    }
+}
@@ -1,12 +1,17 @@
 use std::io;
 
 use serde::ser;
+use unicase::UniCase;
 
-use super::macros::{ser_wrapper, serialize_as_bytes, serialize_err, serialize_trait_impl};
+use super::macros::{
+    ser_wrapper, serialize_as_bytes, serialize_err, serialize_err_custom_newtype,
+    serialize_trait_impl,
+};
 use super::{Formatter, Serializer};
 use crate::{
     error::{Error, Result},
-    naming::{MACRO_TOKEN_VARIANT_NAME as MTVN, TEXT_TOKEN_VARIANT_NAME as TTVN},
+    naming::{MACRO_TOKEN_VARIANT_NAME as MTVN, RAW_VALUE_NAME, TEXT_TOKEN_VARIANT_NAME as TTVN},
+    token::{RawValue, Token},
 };
 
 ser_wrapper!(ValueSerializer);
@@ -18,7 +23,7 @@ where
 {
     type Ok = ();
 
-    serialize_err!(
+    serialize_err_custom_newtype!(
         "value",
         i8,
         i16,
@@ -73,6 +78,18 @@ where
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
         TextTokenSerializer::new(&mut *self.ser).serialize_bytes(v)
     }
+
+    /// A [`RawValue`](crate::token::RawValue) is written verbatim.
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        if name == RAW_VALUE_NAME {
+            value.serialize(RawValueSerializer::new(&mut *self.ser))
+        } else {
+            value.serialize(self)
+        }
+    }
 }
 
 pub(crate) struct TokenListSerializer<'a, W, F> {
@@ -166,9 +183,36 @@ where
     }
 }
 
-serialize_as_bytes!("text token", TextTokenSerializer, {
+serialize_as_bytes!(
+    "text token",
+    TextTokenSerializer,
+    {
+        fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+            self.ser.buffer.write_bracketed_token(value)?;
+            Ok(())
+        }
+    },
+    {
+        /// Bytes are written verbatim, and need not be valid UTF-8.
+        fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+            self.ser.buffer.write_bracketed_bytes(value)?;
+            Ok(())
+        }
+    }
+);
+
+serialize_as_bytes!("raw value", RawValueSerializer, {
     fn serialize_str(self, value: &str) -> Result<Self::Ok> {
-        self.ser.buffer.write_bracketed_token(value)?;
+        if self.ser.variables.is_some() {
+            // an invalid raw value is reported when it is written
+            let tokens = RawValue::new(value).and_then(|raw| raw.tokens());
+            for token in tokens.unwrap_or_default() {
+                if let Token::Variable(variable) = token {
+                    self.ser.check_variable(variable.as_ref())?;
+                }
+            }
+        }
+        self.ser.buffer.write_raw_value(value)?;
         Ok(())
     }
 });
@@ -182,6 +226,16 @@ serialize_as_bytes!("field key", FieldKeySerializer, {
 
 serialize_as_bytes!("variable token", VariableTokenSerializer, {
     fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+        self.ser.check_variable(value)?;
+        self.ser.buffer.write_variable_token(value)?;
+        Ok(())
+    }
+});
+
+serialize_as_bytes!("macro name", MacroNameSerializer, {
+    /// Serialize the name of a macro entry, which defines the macro
+    fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+        self.ser.define_variable(value);
         self.ser.buffer.write_variable_token(value)?;
         Ok(())
     }
@@ -190,7 +244,18 @@ serialize_as_bytes!("variable token", VariableTokenSerializer, {
 serialize_as_bytes!("entry type", EntryTypeSerializer, {
     /// Serialize the entry type, and also write the body start
     fn serialize_str(self, value: &str) -> Result<Self::Ok> {
-        self.ser.buffer.write_regular_entry_type(value)?;
+        let entry_type = self
+            .ser
+            .entry_types
+            .iter()
+            .rev()
+            .find(|(from, _)| *from == UniCase::new(value))
+            .map_or(value, |(_, to)| to.as_str());
+        self.ser.buffer.write_regular_entry_type(entry_type)?;
+        // recorded in case the entry key is generated
+        if let Some(generator) = &mut self.ser.key_generator {
+            entry_type.clone_into(&mut generator.entry_type);
+        }
         self.ser.buffer.write_body_start()?;
         Ok(())
     }
@@ -199,6 +264,10 @@ serialize_as_bytes!("entry type", EntryTypeSerializer, {
 serialize_as_bytes!("entry key", EntryKeySerializer, {
     /// Serialize the entry type, and also the trailing comma
     fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+        if self.ser.defer_entry_key(value) {
+            return Ok(());
+        }
+        self.ser.record_entry_key(value)?;
         self.ser.buffer.write_entry_key(value)?;
         self.ser.buffer.write_entry_key_end()?;
         Ok(())
@@ -152,7 +152,8 @@
 //!    an unquoted number.
 //!
 //! ### Differences from bibtex
-//! 1. Bibtex does not support `%`-style comments.
+//! 1. Bibtex does not support `%`-style comments. This difference can be removed using
+//!    [`Deserializer::tex_comments`](crate::de::Deserializer::tex_comments).
 //! 2. Bibtex does not capture `@comment` strings: instead, upon reading an `@comment` entry, it
 //!    immediately resets and applies 'junk' parsing. For example
 //!    ```bib
@@ -170,13 +171,256 @@
 
 #![allow(missing_docs)]
 
+use std::fmt;
+use std::ops::Range;
+
+use pest::error::{ErrorVariant, InputLocation, LineColLocation};
+#[cfg(feature = "entry")]
+use pest::iterators::Pair;
 use pest_derive::Parser;
 
+#[cfg(feature = "entry")]
+use crate::value::{Bibliography, Entry, RegularEntry, Token, Value};
+
 /// A simple automatically derived pest parser.
 #[derive(Parser)]
 #[grammar = "syntax/bibtex.pest"] // relative to src
 pub struct BibtexParser;
 
+/// The location of a syntax error found by [`check`], along with the grammar rules which were
+/// expected at that location.
+///
+/// Rules are identified by their names in the grammar described in this module, such as
+/// `"value"` or `"entry_key"`. Only named rules are reported, at the furthest position at which
+/// a named rule was attempted. As a result, an error such as a missing `,` between fields is
+/// reported at the start of the preceding value rather than at the exact character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxReport {
+    /// The byte range in the input at which parsing failed. This is usually empty.
+    pub span: Range<usize>,
+    /// The line of the start of the span, starting from 1.
+    pub line: usize,
+    /// The column of the start of the span in characters, starting from 1.
+    pub column: usize,
+    /// The rules which would have allowed parsing to continue.
+    pub expected: Vec<String>,
+    /// The rules which matched, but were not allowed at this location.
+    pub unexpected: Vec<String>,
+}
+
+impl fmt::Display for SyntaxReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "syntax error at line {}, column {}",
+            self.line, self.column
+        )?;
+        if !self.expected.is_empty() {
+            write!(f, ": expected {}", self.expected.join(", "))?;
+        }
+        if !self.unexpected.is_empty() {
+            let sep = if self.expected.is_empty() { ": " } else { "; " };
+            write!(f, "{sep}unexpected {}", self.unexpected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SyntaxReport {}
+
+impl From<pest::error::Error<Rule>> for SyntaxReport {
+    fn from(err: pest::error::Error<Rule>) -> Self {
+        let span = match err.location {
+            InputLocation::Pos(pos) => pos..pos,
+            InputLocation::Span((start, end)) => start..end,
+        };
+        let (line, column) = match err.line_col {
+            LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+        };
+        let names = |rules: Vec<Rule>| rules.iter().map(|rule| format!("{rule:?}")).collect();
+        let (expected, unexpected) = match err.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => (names(positives), names(negatives)),
+            ErrorVariant::CustomError { .. } => (Vec::new(), Vec::new()),
+        };
+        Self {
+            span,
+            line,
+            column,
+            expected,
+            unexpected,
+        }
+    }
+}
+
+/// Check that `input` is a bibliography which is accepted by the grammar described in this
+/// module.
+///
+/// The check uses the grammar directly, independently of the parser used by the
+/// [`Deserializer`](crate::de::Deserializer), so it can be used to cross-validate the parser. No
+/// macros are expanded, and TeX comments are always ignored.
+/// ```
+/// use serde_bibtex::syntax::check;
+///
+/// assert!(check("@article{key, title = {Title}}").is_ok());
+///
+/// let report = check("@article{key, title = {Title}}\n@book{key, title = }").unwrap_err();
+/// assert_eq!(report.span, 50..50);
+/// assert_eq!((report.line, report.column), (2, 20));
+/// assert_eq!(report.expected, vec!["value"]);
+/// ```
+pub fn check(input: &str) -> Result<(), SyntaxReport> {
+    use pest::Parser;
+
+    BibtexParser::parse(Rule::bib, input)?;
+    Ok(())
+}
+
+/// A difference between the parser used by the [`Deserializer`](crate::de::Deserializer) and
+/// the grammar described in this module, as found by [`crosscheck`].
+#[cfg(feature = "entry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "entry")))]
+#[derive(Debug)]
+pub enum Divergence {
+    /// The input was accepted by the parser, but rejected by the grammar.
+    GrammarRejected(SyntaxReport),
+    /// The input was accepted by the grammar, but rejected by the parser.
+    ParserRejected(crate::Error),
+    /// The entry at position `index` was read differently, or was only read by one of the two.
+    Entry {
+        /// The position of the entry in the bibliography.
+        index: usize,
+        /// The entry as read by the parser.
+        parser: Option<Box<Entry>>,
+        /// The entry as read by the grammar.
+        grammar: Option<Box<Entry>>,
+    },
+}
+
+#[cfg(feature = "entry")]
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GrammarRejected(report) => {
+                write!(
+                    f,
+                    "input accepted by the parser but not the grammar: {report}"
+                )
+            }
+            Self::ParserRejected(err) => {
+                write!(f, "input accepted by the grammar but not the parser: {err}")
+            }
+            Self::Entry {
+                index,
+                parser,
+                grammar,
+            } => write!(
+                f,
+                "entry {index} differs: parser read {parser:?}, grammar read {grammar:?}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "entry")]
+impl std::error::Error for Divergence {}
+
+/// Parse `input` with both the parser used by the [`Deserializer`](crate::de::Deserializer) and
+/// the grammar described in this module, and return the first difference between the two.
+///
+/// The input is read by the parser with the default configuration as a
+/// [`Bibliography`](crate::value::Bibliography), so that macros are not expanded. Inputs which
+/// are rejected by both are considered to agree. This is intended for differential testing, for
+/// instance as the body of a fuzz target.
+/// ```
+/// use serde_bibtex::syntax::crosscheck;
+///
+/// assert!(crosscheck("@string{a = {A}} @book{key, title = a # {B} # 12}").is_ok());
+/// assert!(crosscheck("@book{key, title = }").is_ok());
+/// ```
+#[cfg(feature = "entry")]
+#[cfg_attr(docsrs, doc(cfg(feature = "entry")))]
+pub fn crosscheck(input: &str) -> Result<(), Divergence> {
+    let parsed = crate::from_str::<Bibliography>(input);
+    let grammar = grammar_entries(input);
+    let (parsed, grammar) = match (parsed, grammar) {
+        (Ok(parsed), Ok(grammar)) => (parsed.0, grammar),
+        (Err(_), Err(_)) => return Ok(()),
+        (Ok(_), Err(report)) => return Err(Divergence::GrammarRejected(report)),
+        (Err(err), Ok(_)) => return Err(Divergence::ParserRejected(err)),
+    };
+
+    let mut parsed = parsed.into_iter();
+    let mut grammar = grammar.into_iter();
+    for index in 0.. {
+        match (parsed.next(), grammar.next()) {
+            (None, None) => break,
+            (parser, grammar) if parser != grammar => {
+                return Err(Divergence::Entry {
+                    index,
+                    parser: parser.map(Box::new),
+                    grammar: grammar.map(Box::new),
+                })
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Read the entries of `input` using the grammar.
+#[cfg(feature = "entry")]
+fn grammar_entries(input: &str) -> Result<Vec<Entry>, SyntaxReport> {
+    use pest::Parser;
+
+    let pairs = BibtexParser::parse(Rule::bib, input)?;
+    Ok(pairs
+        .filter(|pair| pair.as_rule() == Rule::entry)
+        .filter_map(|pair| pair.into_inner().next())
+        .map(|pair| match pair.as_rule() {
+            Rule::entry_comment => Entry::Comment(pair.into_inner().as_str().to_owned()),
+            Rule::entry_preamble => Entry::Preamble(grammar_value(pair.into_inner().next())),
+            Rule::entry_macro => {
+                let mut inner = pair.into_inner();
+                Entry::Macro(
+                    inner.next().map(|variable| {
+                        (variable.as_str().to_owned(), grammar_value(inner.next()))
+                    }),
+                )
+            }
+            _ => {
+                let mut inner = pair.into_inner();
+                let mut entry = RegularEntry::new(
+                    inner.next().map_or("", |pair| pair.as_str()),
+                    inner.next().map_or("", |pair| pair.as_str()),
+                );
+                while let Some(key) = inner.next() {
+                    entry
+                        .fields
+                        .push((key.as_str().to_owned(), grammar_value(inner.next())));
+                }
+                Entry::Regular(entry)
+            }
+        })
+        .collect())
+}
+
+/// Convert a `value` pair into a [`Value`].
+#[cfg(feature = "entry")]
+fn grammar_value(pair: Option<Pair<Rule>>) -> Value {
+    Value(
+        pair.into_iter()
+            .flat_map(|pair| pair.into_inner())
+            .map(|token| match token.as_rule() {
+                Rule::variable => Token::Variable(token.as_str().to_owned()),
+                _ => Token::Text(token.as_str().to_owned()),
+            })
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,5 +445,69 @@ mod tests {
         let parsed = BibtexParser::parse(Rule::bib, input);
 
         assert!(parsed.is_ok());
+        assert_eq!(check(input), Ok(()));
+    }
+
+    #[test]
+    fn test_check() {
+        assert_eq!(check(""), Ok(()));
+
+        let report = check("% comment\n@book{key,\n  title = {A}\n  author = {B}}").unwrap_err();
+        assert_eq!(report.span, 31..31);
+        assert_eq!((report.line, report.column), (3, 11));
+        assert_eq!(report.expected, vec!["token_number"]);
+        assert_eq!(
+            report.to_string(),
+            "syntax error at line 3, column 11: expected token_number"
+        );
+
+        // an unterminated entry is reported at the start of the last value
+        let report = check("@book{key, title = {A}").unwrap_err();
+        assert_eq!(report.span, 19..19);
+    }
+
+    #[test]
+    #[cfg(feature = "entry")]
+    fn test_crosscheck() {
+        let input = r#"
+            junk % @comment{hidden}
+            @article (2:k,
+               @et= { Text} #
+                1234,
+            )
+            @a{k}
+            @string{k=1 # {Text} # var,}
+            @string()
+            @comment(a {)} b)
+            @preamble{ "{"}" # expand }
+        "#;
+        assert!(crosscheck(input).is_ok());
+        assert!(crosscheck("@a{k, title = {unbalanced}").is_ok());
+
+        let entries = grammar_entries(input).unwrap();
+        assert_eq!(entries.len(), 6);
+        assert_eq!(
+            entries[0],
+            Entry::Regular(RegularEntry {
+                entry_type: "article".into(),
+                entry_key: "2:k".into(),
+                fields: vec![(
+                    "@et".into(),
+                    Value(vec![
+                        Token::Text(" Text".into()),
+                        Token::Text("1234".into())
+                    ])
+                )],
+            })
+        );
+        assert_eq!(entries[3], Entry::Macro(None));
+        assert_eq!(entries[4], Entry::Comment("a {)} b".into()));
+        assert_eq!(
+            entries[5],
+            Entry::Preamble(Value(vec![
+                Token::Text("{\"}".into()),
+                Token::Variable("expand".into())
+            ]))
+        );
     }
 }
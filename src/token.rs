@@ -15,10 +15,17 @@
 //! | [`Variable`]   | `variable`                                    |
 //! | [`Token`]      | `token`                                       |
 //! | [`Text`]       | `token_number`, `token_curly`, `token_quoted` |
+//! | [`RawValue`]   | `value`                                       |
+mod convert;
 mod error;
+mod escape;
+mod raw;
 mod types;
-mod validate;
+pub mod validate;
 
+pub use convert::*;
 pub use error::*;
+pub use escape::{escape_text, is_escaped};
+pub use raw::RawValue;
 pub use types::*;
 pub use validate::*;
@@ -0,0 +1,190 @@
+//! Conversions between owned and borrowed token representations.
+use std::borrow::Cow;
+
+use super::{Text, Token, Variable};
+
+/// Convert a token type into a version which owns its data.
+///
+/// This clones the underlying values, even if they are already owned.
+pub trait ToOwnedToken {
+    /// The owned version of the type.
+    type Owned;
+
+    /// Construct an owned copy.
+    fn to_owned_token(&self) -> Self::Owned;
+}
+
+/// Borrow the data of a token type.
+pub trait Borrowed {
+    /// The borrowed version of the type.
+    type Target<'a>
+    where
+        Self: 'a;
+
+    /// Construct a copy which borrows from `self`.
+    fn borrowed(&self) -> Self::Target<'_>;
+}
+
+impl<S: AsRef<str>> ToOwnedToken for Variable<S> {
+    type Owned = Variable<String>;
+
+    fn to_owned_token(&self) -> Self::Owned {
+        Variable::new_unchecked(self.as_ref().to_owned())
+    }
+}
+
+impl<S: AsRef<str>, B: AsRef<[u8]>> ToOwnedToken for Text<S, B> {
+    type Owned = Text<String, Vec<u8>>;
+
+    fn to_owned_token(&self) -> Self::Owned {
+        match self {
+            Text::Str(s) => Text::Str(s.as_ref().to_owned()),
+            Text::Bytes(b) => Text::Bytes(b.as_ref().to_vec()),
+        }
+    }
+}
+
+impl<S: AsRef<str>, B: AsRef<[u8]>> ToOwnedToken for Token<S, B> {
+    type Owned = Token<String, Vec<u8>>;
+
+    fn to_owned_token(&self) -> Self::Owned {
+        match self {
+            Token::Variable(var) => Token::Variable(var.to_owned_token()),
+            Token::Text(text) => Token::Text(text.to_owned_token()),
+        }
+    }
+}
+
+impl<S: AsRef<str>> Borrowed for Variable<S> {
+    type Target<'a>
+        = Variable<&'a str>
+    where
+        Self: 'a;
+
+    fn borrowed(&self) -> Self::Target<'_> {
+        Variable::new_unchecked(self.as_ref())
+    }
+}
+
+impl<S: AsRef<str>, B: AsRef<[u8]>> Borrowed for Text<S, B> {
+    type Target<'a>
+        = Text<&'a str, &'a [u8]>
+    where
+        Self: 'a;
+
+    fn borrowed(&self) -> Self::Target<'_> {
+        match self {
+            Text::Str(s) => Text::Str(s.as_ref()),
+            Text::Bytes(b) => Text::Bytes(b.as_ref()),
+        }
+    }
+}
+
+impl<S: AsRef<str>, B: AsRef<[u8]>> Borrowed for Token<S, B> {
+    type Target<'a>
+        = Token<&'a str, &'a [u8]>
+    where
+        Self: 'a;
+
+    fn borrowed(&self) -> Self::Target<'_> {
+        match self {
+            Token::Variable(var) => Token::Variable(var.borrowed()),
+            Token::Text(text) => Token::Text(text.borrowed()),
+        }
+    }
+}
+
+/// Implement `From` between two representations of `Variable`, `Text`, and `Token`, using the
+/// provided conversions of the string and byte types.
+macro_rules! convert_impls {
+    ($lt:lifetime, ($s1:ty, $b1:ty) => ($s2:ty, $b2:ty), $fs:expr, $fb:expr) => {
+        impl<$lt> From<Variable<$s1>> for Variable<$s2> {
+            fn from(var: Variable<$s1>) -> Self {
+                Variable::new_unchecked($fs(var.into_inner()))
+            }
+        }
+
+        impl<$lt> From<Text<$s1, $b1>> for Text<$s2, $b2> {
+            fn from(text: Text<$s1, $b1>) -> Self {
+                match text {
+                    Text::Str(s) => Text::Str($fs(s)),
+                    Text::Bytes(b) => Text::Bytes($fb(b)),
+                }
+            }
+        }
+
+        impl<$lt> From<Token<$s1, $b1>> for Token<$s2, $b2> {
+            fn from(token: Token<$s1, $b1>) -> Self {
+                match token {
+                    Token::Variable(var) => Token::Variable(var.into()),
+                    Token::Text(text) => Token::Text(text.into()),
+                }
+            }
+        }
+    };
+}
+
+convert_impls!('a, (&'a str, &'a [u8]) => (String, Vec<u8>), str::to_owned, <[u8]>::to_vec);
+convert_impls!(
+    'a,
+    (&'a str, &'a [u8]) => (Cow<'a, str>, Cow<'a, [u8]>),
+    Cow::Borrowed,
+    Cow::Borrowed
+);
+convert_impls!(
+    'a,
+    (String, Vec<u8>) => (Cow<'a, str>, Cow<'a, [u8]>),
+    Cow::Owned,
+    Cow::Owned
+);
+convert_impls!(
+    'a,
+    (Cow<'a, str>, Cow<'a, [u8]>) => (String, Vec<u8>),
+    Cow::into_owned,
+    Cow::into_owned
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let borrowed: Vec<Token<&str, &[u8]>> = vec![
+            Token::variable_unchecked("Var"),
+            Token::str_unchecked("text"),
+            Token::Text(Text::Bytes(b"bytes")),
+        ];
+
+        let owned: Vec<Token<String, Vec<u8>>> =
+            borrowed.iter().map(ToOwnedToken::to_owned_token).collect();
+        assert_eq!(
+            owned.iter().map(Borrowed::borrowed).collect::<Vec<_>>(),
+            borrowed
+        );
+        assert_eq!(
+            borrowed
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect::<Vec<Token<String, Vec<u8>>>>(),
+            owned
+        );
+
+        let cow: Vec<Token<Cow<str>, Cow<[u8]>>> =
+            borrowed.iter().cloned().map(Into::into).collect();
+        assert!(matches!(
+            cow[1],
+            Token::Text(Text::Str(Cow::Borrowed("text")))
+        ));
+        assert_eq!(
+            cow.into_iter()
+                .map(Into::into)
+                .collect::<Vec<Token<String, Vec<u8>>>>(),
+            owned
+        );
+
+        let cow: Token<Cow<str>, Cow<[u8]>> = owned[0].clone().into();
+        assert!(matches!(cow, Token::Variable(ref var) if var.as_ref() == "Var"));
+    }
+}
@@ -4,6 +4,7 @@ use std::str::Utf8Error;
 
 /// Possible syntax errors in BibTeX tokens and identifiers.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum TokenError {
     /// Expected to be non-empty.
     Empty,
@@ -15,6 +16,59 @@ pub enum TokenError {
     ExtraClosingBracket,
     /// Has too many opening brackets.
     ExtraOpeningBracket,
+    /// Is reserved for special entries.
+    ReservedEntryType,
+}
+
+/// The reason a token is invalid, along with the byte index at which the problem was detected.
+///
+/// This is returned by the checking functions in the [`validate`](super::validate) module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TokenErrorKind {
+    /// Expected to be non-empty.
+    Empty,
+    /// Contains an invalid char at the given byte index.
+    InvalidChar { ch: char, index: usize },
+    /// Expected to start with a non-ASCII digit.
+    StartsWithDigit,
+    /// Has a closing bracket at the given byte index without a matching opening bracket.
+    ExtraClosingBracket { index: usize },
+    /// Has an opening bracket at the given byte index without a matching closing bracket.
+    ExtraOpeningBracket { index: usize },
+    /// Is one of the reserved entry types `string`, `comment`, or `preamble`.
+    ReservedEntryType,
+}
+
+impl TokenErrorKind {
+    /// The byte index at which the problem was detected. This is `0` if the problem concerns the
+    /// input as a whole.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::InvalidChar { index, .. }
+            | Self::ExtraClosingBracket { index }
+            | Self::ExtraOpeningBracket { index } => *index,
+            Self::Empty | Self::StartsWithDigit | Self::ReservedEntryType => 0,
+        }
+    }
+
+    /// The reason the token is invalid, without the position.
+    pub fn reason(&self) -> TokenError {
+        match self {
+            Self::Empty => TokenError::Empty,
+            Self::InvalidChar { ch, .. } => TokenError::InvalidChar(*ch),
+            Self::StartsWithDigit => TokenError::StartsWithDigit,
+            Self::ExtraClosingBracket { .. } => TokenError::ExtraClosingBracket,
+            Self::ExtraOpeningBracket { .. } => TokenError::ExtraOpeningBracket,
+            Self::ReservedEntryType => TokenError::ReservedEntryType,
+        }
+    }
+}
+
+impl From<TokenErrorKind> for TokenError {
+    fn from(kind: TokenErrorKind) -> Self {
+        kind.reason()
+    }
 }
 
 /// An error which results when converting between text and variable tokens.
@@ -43,12 +97,29 @@ impl fmt::Display for TokenError {
             TokenError::ExtraOpeningBracket => {
                 f.write_str("text token has unclosed opening bracket")
             }
+            TokenError::ReservedEntryType => {
+                f.write_str("entry type is reserved for special entries")
+            }
         }
     }
 }
 
 impl Error for TokenError {}
 
+impl fmt::Display for TokenErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.reason().fmt(f)?;
+        match self {
+            Self::InvalidChar { index, .. }
+            | Self::ExtraClosingBracket { index }
+            | Self::ExtraOpeningBracket { index } => write!(f, " at byte {index}"),
+            Self::Empty | Self::StartsWithDigit | Self::ReservedEntryType => Ok(()),
+        }
+    }
+}
+
+impl Error for TokenErrorKind {}
+
 /// Errors which result while attempting to construct a token type from an input.
 #[derive(Debug, PartialEq)]
 pub struct TokenParseError<S> {
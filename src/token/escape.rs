@@ -0,0 +1,114 @@
+//! Protection of characters in text which some consumers treat as syntax.
+use std::borrow::Cow;
+
+use memchr::memchr2_iter;
+
+/// Whether the byte at `pos` is enclosed directly in brackets, as in `{@}`, or is part of a
+/// control symbol, as in `\#`.
+fn is_protected(input: &[u8], pos: usize) -> bool {
+    (pos > 0 && input[pos - 1] == b'{' && input.get(pos + 1) == Some(&b'}'))
+        || is_control_symbol(input, pos)
+}
+
+/// Whether the byte at `pos` follows a backslash which is not itself escaped, so that `\#` is a
+/// control symbol but `\\#` is a line break followed by `#`.
+fn is_control_symbol(input: &[u8], pos: usize) -> bool {
+    let backslashes = input[..pos]
+        .iter()
+        .rev()
+        .take_while(|b| **b == b'\\')
+        .count();
+    backslashes % 2 == 1
+}
+
+/// Whether every `@` and `#` in the text is enclosed directly in brackets or is part of a
+/// control symbol such as `\#`, so that [`escape_text`] returns the text unchanged.
+/// ```
+/// use serde_bibtex::token::is_escaped;
+///
+/// assert!(is_escaped("Issue {#}12"));
+/// assert!(is_escaped("Issue \\#12"));
+/// assert!(!is_escaped("user@example.com"));
+/// ```
+pub fn is_escaped(text: &str) -> bool {
+    let input = text.as_bytes();
+    memchr2_iter(b'@', b'#', input).all(|pos| is_protected(input, pos))
+}
+
+/// Enclose every `@` and `#` in the text in brackets, unless it is already enclosed or is part
+/// of a control symbol such as `\#` or `\@`.
+///
+/// Within a bracketed or quoted value, these characters are plain text for BibTeX. However, tools
+/// which do not track delimiters may treat them as syntax: for example, a parser which recovers
+/// from an error by searching for the next `@` could begin a new entry in the middle of a value,
+/// and a `#` may be read as concatenation once the delimiters are removed. Enclosing the
+/// characters in brackets does not change how the text is typeset, and the result is always
+/// accepted by [`is_escaped`].
+///
+/// Brackets in the text are left unchanged, so the result has balanced brackets if and only if
+/// the input does.
+/// ```
+/// use std::borrow::Cow;
+///
+/// use serde_bibtex::token::escape_text;
+///
+/// assert_eq!(escape_text("user@example.com"), "user{@}example.com");
+/// assert_eq!(escape_text("Issue #12 and {#}13"), "Issue {#}12 and {#}13");
+/// assert!(matches!(escape_text("Title"), Cow::Borrowed(_)));
+/// ```
+pub fn escape_text(text: &str) -> Cow<'_, str> {
+    let input = text.as_bytes();
+    let mut escaped = String::new();
+    let mut last = 0;
+    for pos in memchr2_iter(b'@', b'#', input) {
+        if !is_protected(input, pos) {
+            escaped.push_str(&text[last..pos]);
+            escaped.push('{');
+            escaped.push(input[pos] as char);
+            escaped.push('}');
+            last = pos + 1;
+        }
+    }
+
+    if last == 0 {
+        Cow::Borrowed(text)
+    } else {
+        escaped.push_str(&text[last..]);
+        Cow::Owned(escaped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_text() {
+        for (text, expected) in [
+            ("", ""),
+            ("plain text", "plain text"),
+            ("@", "{@}"),
+            ("#@", "{#}{@}"),
+            ("{@}", "{@}"),
+            ("{{@}}", "{{@}}"),
+            ("{@", "{{@}"),
+            ("@}", "{@}}"),
+            ("a @b {#} c#", "a {@}b {#} c{#}"),
+            ("é@ü", "é{@}ü"),
+            // control symbols are left unchanged
+            ("\\#1", "\\#1"),
+            ("\\%", "\\%"),
+            ("R\\&D", "R\\&D"),
+            ("Dr.\\@ Who", "Dr.\\@ Who"),
+            ("\\\\#", "\\\\{#}"),
+            ("\\\\\\#", "\\\\\\#"),
+        ] {
+            let escaped = escape_text(text);
+            assert_eq!(escaped, expected, "{text}");
+            assert!(is_escaped(&escaped));
+            assert_eq!(is_escaped(text), text == expected);
+            // escaping is idempotent
+            assert_eq!(escape_text(&escaped), escaped);
+        }
+    }
+}
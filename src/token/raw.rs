@@ -0,0 +1,148 @@
+//! Values captured exactly as they appear in the input.
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Token;
+use crate::error::{Error, ErrorCode, Result};
+use crate::naming::RAW_VALUE_NAME;
+use crate::parse::{BibtexParse, Read, StrReader};
+
+/// The source of a field value, such as `{A } # var # "B"`, exactly as it appears in the input.
+///
+/// A [`RawValue`] borrows from the input, and macros in the value are neither expanded nor
+/// required to be defined. In particular, deserializing a [`RawValue`] never fails because of an
+/// undefined macro. The tokens of the value can be recovered using [`RawValue::tokens`].
+///
+/// A [`RawValue`] can only be deserialized from the value of a field, a macro, or a preamble: it
+/// is not available for fields collected into a catch-all map.
+///
+/// A [`RawValue`] is serialized verbatim, keeping the original delimiters and `#` separators, so
+/// that a bibliography can be rewritten without changing the fields which are not modified.
+/// Other serializers receive the source as a string.
+/// ```
+/// use serde::Deserialize;
+/// use serde_bibtex::{from_entry_str, token::{RawValue, Token}};
+///
+/// #[derive(Deserialize)]
+/// struct Record<'a> {
+///     #[serde(borrow)]
+///     fields: Fields<'a>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Fields<'a> {
+///     #[serde(borrow)]
+///     title: RawValue<'a>,
+/// }
+///
+/// let record: Record = from_entry_str(r#"@article{key, title = {A } # var # "B"}"#).unwrap();
+///
+/// assert_eq!(record.fields.title.as_str(), r#"{A } # var # "B""#);
+/// assert_eq!(
+///     record.fields.title.tokens().unwrap(),
+///     vec![
+///         Token::str("A ").unwrap(),
+///         Token::variable("var").unwrap(),
+///         Token::str("B").unwrap(),
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawValue<'r>(&'r str);
+
+impl<'r> RawValue<'r> {
+    /// Construct a new raw value, checking that the input is a valid value.
+    ///
+    /// Whitespace surrounding the value is removed.
+    pub fn new(input: &'r str) -> Result<Self> {
+        let mut reader = StrReader::new(input);
+        let source = reader.value_into(&mut Vec::new())?;
+        reader.comment();
+        if reader.peek().is_some() {
+            return Err(reader.locate(Error::syntax(ErrorCode::TrailingCharactersAfterValue)));
+        }
+        // the source is a subslice of the input, so it is valid UTF-8
+        Ok(Self(std::str::from_utf8(source)?))
+    }
+
+    /// The source of the value.
+    pub fn as_str(&self) -> &'r str {
+        self.0
+    }
+
+    /// The tokens of the value, without expanding macros.
+    pub fn tokens(&self) -> Result<Vec<Token<&'r str, &'r [u8]>>> {
+        let mut tokens = Vec::new();
+        StrReader::new(self.0).value_into(&mut tokens)?;
+        Ok(tokens)
+    }
+}
+
+impl fmt::Display for RawValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Serialize for RawValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_VALUE_NAME, self.0)
+    }
+}
+
+struct RawValueVisitor<'r>(PhantomData<RawValue<'r>>);
+
+impl<'de: 'r, 'r> de::Visitor<'de> for RawValueVisitor<'r> {
+    type Value = RawValue<'r>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the source of a BibTeX value")
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        RawValue::new(v).map_err(E::custom)
+    }
+}
+
+impl<'de: 'r, 'r> Deserialize<'de> for RawValue<'r> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(RAW_VALUE_NAME, RawValueVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_value_new() {
+        for valid in ["{A} # b # \"C\"", "12", "{}", "var"] {
+            assert_eq!(RawValue::new(valid).unwrap().as_str(), valid);
+        }
+        assert_eq!(RawValue::new(" {A}\n").unwrap().as_str(), "{A}");
+
+        for invalid in ["", " ", "{A} # ", "{A", "{A} {B}", "{A},", "{A}}"] {
+            assert!(RawValue::new(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(
+            RawValue::new("{A},").unwrap_err().to_string(),
+            "trailing characters after value at byte 3"
+        );
+
+        assert_eq!(
+            RawValue::new("a # {b}").unwrap().tokens().unwrap(),
+            vec![Token::variable_unchecked("a"), Token::str_unchecked("b")]
+        );
+    }
+}
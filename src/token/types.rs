@@ -1,9 +1,16 @@
 //! Fundamental components of a bibliography.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use unicase::UniCase;
 
+use crate::naming::{MACRO_TOKEN_VARIANT_NAME, TEXT_TOKEN_VARIANT_NAME};
+
 use super::{
     check_balanced, check_entry_key, check_entry_type, check_field_key, check_identifier,
-    check_variable, ConversionError, TokenParseError,
+    check_variable, ConversionError, TokenErrorKind, TokenParseError,
 };
 
 /// An unspecialized identifier, which could be an [`EntryKey`], [`EntryType`], [`FieldKey`], or
@@ -21,7 +28,10 @@ impl<S: AsRef<str>> Identifier<S> {
     pub fn new(input: S) -> Result<Self, TokenParseError<S>> {
         match check_identifier(input.as_ref()) {
             Ok(()) => Ok(Self::new_unchecked(input)),
-            Err(error) => Err(TokenParseError { input, error }),
+            Err(kind) => Err(TokenParseError {
+                input,
+                error: kind.into(),
+            }),
         }
     }
 
@@ -70,6 +80,21 @@ impl<'r> Text<&'r str, &'r [u8]> {
     }
 }
 
+/// Text is serialized as a string or as bytes, so that bytes are written back verbatim by the
+/// [`Serializer`](crate::ser::Serializer).
+impl<S, B> Serialize for Text<S, B>
+where
+    S: AsRef<str>,
+    B: AsRef<[u8]>,
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        match self {
+            Self::Str(s) => serializer.serialize_str(s.as_ref()),
+            Self::Bytes(b) => serializer.serialize_bytes(b.as_ref()),
+        }
+    }
+}
+
 /// Entry type, such as `article` in `@article{...`.
 /// 1. Case-insensitive.
 /// 2. Does not contain a char in `"{}(),=\\#%\""`.
@@ -103,7 +128,10 @@ impl<S: AsRef<str>> EntryType<S> {
     pub fn new(input: S) -> Result<Self, TokenParseError<S>> {
         match check_entry_type(input.as_ref()) {
             Ok(()) => Ok(Self::new_unchecked(input)),
-            Err(error) => Err(TokenParseError { input, error }),
+            Err(kind) => Err(TokenParseError {
+                input,
+                error: kind.into(),
+            }),
         }
     }
 }
@@ -131,7 +159,10 @@ impl<S: AsRef<str>> Variable<S> {
     pub fn new(input: S) -> Result<Self, TokenParseError<S>> {
         match check_variable(input.as_ref()) {
             Ok(()) => Ok(Self::new_unchecked(input)),
-            Err(error) => Err(TokenParseError { input, error }),
+            Err(kind) => Err(TokenParseError {
+                input,
+                error: kind.into(),
+            }),
         }
     }
 
@@ -177,7 +208,10 @@ impl<S: AsRef<str>> EntryKey<S> {
     pub fn new(input: S) -> Result<Self, TokenParseError<S>> {
         match check_entry_key(input.as_ref()) {
             Ok(()) => Ok(Self::new_unchecked(input)),
-            Err(error) => Err(TokenParseError { input, error }),
+            Err(kind) => Err(TokenParseError {
+                input,
+                error: kind.into(),
+            }),
         }
     }
 
@@ -196,7 +230,7 @@ impl<S: AsRef<str>> AsRef<str> for EntryKey<S> {
 /// Field key, such as `key` in `... key = {value}, ...`.
 /// 1. Case-insensitive.
 /// 2. Does not contain a char in `"{}(),=\\#%\""`.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub struct FieldKey<S: AsRef<str>>(UniCase<S>);
 
 impl<S: AsRef<str>> FieldKey<S> {
@@ -209,7 +243,10 @@ impl<S: AsRef<str>> FieldKey<S> {
     pub fn new(input: S) -> Result<Self, TokenParseError<S>> {
         match check_field_key(input.as_ref()) {
             Ok(()) => Ok(Self::new_unchecked(input)),
-            Err(error) => Err(TokenParseError { input, error }),
+            Err(kind) => Err(TokenParseError {
+                input,
+                error: kind.into(),
+            }),
         }
     }
 
@@ -233,6 +270,85 @@ impl<S: AsRef<str>> From<Identifier<S>> for FieldKey<S> {
     }
 }
 
+/// Implement `Display`, `FromStr`, `Serialize`, and `Deserialize` for a validated string wrapper.
+macro_rules! string_impls {
+    ($name:ident, $check:ident) => {
+        impl<S: AsRef<str>> fmt::Display for $name<S> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_ref())
+            }
+        }
+
+        impl FromStr for $name<String> {
+            type Err = TokenErrorKind;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $check(s)?;
+                Ok(Self::new_unchecked(s.to_owned()))
+            }
+        }
+
+        impl<S: AsRef<str>> Serialize for $name<S> {
+            fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+                serializer.serialize_str(self.as_ref())
+            }
+        }
+
+        impl<'de, S: AsRef<str> + Deserialize<'de>> Deserialize<'de> for $name<S> {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = S::deserialize(deserializer)?;
+                match $check(s.as_ref()) {
+                    Ok(()) => Ok(Self::new_unchecked(s)),
+                    Err(err) => Err(de::Error::custom(err)),
+                }
+            }
+        }
+    };
+}
+
+string_impls!(EntryKey, check_entry_key);
+string_impls!(FieldKey, check_field_key);
+string_impls!(Variable, check_variable);
+
+impl<S: AsRef<str> + Eq> PartialOrd for EntryKey<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Entry keys are ordered by their bytes.
+impl<S: AsRef<str> + Eq> Ord for EntryKey<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_ref().cmp(other.as_ref())
+    }
+}
+
+impl<S: AsRef<str> + Eq> PartialOrd for FieldKey<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Field keys are ordered case-insensitively, consistent with equality.
+impl<S: AsRef<str> + Eq> Ord for FieldKey<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<S: AsRef<str> + Eq> PartialOrd for Variable<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Variables are ordered case-insensitively, consistent with equality.
+impl<S: AsRef<str> + Eq> Ord for Variable<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
 /// A value token representing one part of a value `{Title } # 2012 # var`.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token<S: AsRef<str>, B: AsRef<[u8]>> {
@@ -267,7 +383,10 @@ where
     pub fn str(input: S) -> Result<Self, TokenParseError<S>> {
         match check_balanced(input.as_ref().as_bytes()) {
             Ok(()) => Ok(Token::Text(Text::Str(input))),
-            Err(error) => Err(TokenParseError { input, error }),
+            Err(kind) => Err(TokenParseError {
+                input,
+                error: kind.into(),
+            }),
         }
     }
 
@@ -275,7 +394,10 @@ where
     pub fn bytes(input: B) -> Result<Self, TokenParseError<B>> {
         match check_balanced(input.as_ref()) {
             Ok(()) => Ok(Token::Text(Text::Bytes(input))),
-            Err(error) => Err(TokenParseError { input, error }),
+            Err(kind) => Err(TokenParseError {
+                input,
+                error: kind.into(),
+            }),
         }
     }
 
@@ -290,6 +412,36 @@ where
     }
 }
 
+/// A token is serialized as a `Variable` or `Text` newtype variant, which is the representation
+/// of value tokens expected by the [`Serializer`](crate::ser::Serializer).
+/// ```
+/// use serde_bibtex::token::Token;
+///
+/// let value = [
+///     Token::<&str, &[u8]>::bytes(b"caf\xe9").unwrap(),
+///     Token::variable("v").unwrap(),
+/// ];
+/// let bibliography = [("misc", "key", [("title", value)])];
+/// let output = serde_bibtex::to_vec(&bibliography).unwrap();
+/// assert_eq!(output, b"@misc{key,\n  title = {caf\xe9} # v,\n}\n");
+/// ```
+impl<S, B> Serialize for Token<S, B>
+where
+    S: AsRef<str>,
+    B: AsRef<[u8]>,
+{
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        match self {
+            Self::Variable(variable) => {
+                serializer.serialize_newtype_variant("Token", 0, MACRO_TOKEN_VARIANT_NAME, variable)
+            }
+            Self::Text(text) => {
+                serializer.serialize_newtype_variant("Token", 1, TEXT_TOKEN_VARIANT_NAME, text)
+            }
+        }
+    }
+}
+
 impl<'r> TryFrom<Token<&'r str, &'r [u8]>> for &'r str {
     type Error = ConversionError;
     fn try_from(token: Token<&'r str, &'r [u8]>) -> Result<Self, Self::Error> {
@@ -325,3 +477,77 @@ impl<S: AsRef<str>, B: AsRef<[u8]>> TryFrom<Token<S, B>> for Text<S, B> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_from_str_display() {
+        let key: EntryKey<String> = "Knuth1984".parse().unwrap();
+        assert_eq!(key.to_string(), "Knuth1984");
+        assert_eq!(
+            "a,b".parse::<EntryKey<String>>(),
+            Err(TokenErrorKind::InvalidChar { ch: ',', index: 1 })
+        );
+        assert_eq!(
+            "1a".parse::<Variable<String>>(),
+            Err(TokenErrorKind::StartsWithDigit)
+        );
+        assert_eq!(
+            "Title".parse::<FieldKey<String>>().unwrap().to_string(),
+            "Title"
+        );
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut map = BTreeMap::new();
+        map.insert(FieldKey::new_unchecked("Title"), 1);
+        map.insert(FieldKey::new_unchecked("author"), 2);
+        map.insert(FieldKey::new_unchecked("TITLE"), 3);
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            vec![
+                (FieldKey::new_unchecked("author"), 2),
+                (FieldKey::new_unchecked("Title"), 3)
+            ]
+        );
+
+        assert!(EntryKey::new_unchecked("B") < EntryKey::new_unchecked("a"));
+        assert!(Variable::new_unchecked("B") > Variable::new_unchecked("a"));
+
+        let mut map = HashMap::new();
+        map.insert(EntryKey::new_unchecked("key".to_owned()), ());
+        assert!(map.contains_key(&EntryKey::new_unchecked("key".to_owned())));
+    }
+
+    #[test]
+    fn test_serde() {
+        #[derive(Debug, Deserialize, Serialize, PartialEq)]
+        struct Record {
+            entry_type: String,
+            entry_key: EntryKey<String>,
+            fields: BTreeMap<FieldKey<String>, String>,
+        }
+
+        let input = "@article{key,\n  title = {Title},\n}\n";
+        let mut records: Vec<Record> = crate::from_str(input).unwrap();
+        let record = records.pop().unwrap();
+        assert_eq!(record.entry_key, EntryKey::new_unchecked("key".to_owned()));
+        assert_eq!(
+            record
+                .fields
+                .get(&FieldKey::new_unchecked("TITLE".to_owned())),
+            Some(&"Title".to_owned())
+        );
+        assert_eq!(crate::to_string(&vec![record]).unwrap(), input);
+
+        use serde::de::value::{BorrowedStrDeserializer, Error, StrDeserializer};
+        assert!(EntryKey::<String>::deserialize(StrDeserializer::<Error>::new("a,b")).is_err());
+        assert!(
+            Variable::<&str>::deserialize(BorrowedStrDeserializer::<Error>::new("var")).is_ok()
+        );
+    }
+}
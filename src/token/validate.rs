@@ -4,7 +4,7 @@
 // use crate::error::{Error, ErrorCode, Result};
 use memchr::memchr2_iter;
 
-use super::TokenError;
+use super::TokenErrorKind;
 
 /// Lookup table for bytes which could appear in an entry key. This includes the
 /// ascii printable characters with "{}(),= \t\n\\#%\"" removed, as well as bytes
@@ -37,33 +37,41 @@ pub(crate) static IDENTIFIER_ALLOWED: [bool; 256] = {
     ]
 };
 
-/// Returns `Some(ch)` if the input does not contain a disallowed char `ch`, and `None` otherwise.
+/// Returns `Some((index, ch))` if the input contains a disallowed char `ch` at byte index `index`,
+/// and `None` otherwise.
 ///
 /// A disallowed char is any char in `"{}(),=\\#%\""`.
-fn find_invalid_identifier_char(input: &str) -> Option<char> {
+fn find_invalid_identifier_char(input: &str) -> Option<(usize, char)> {
     input
         .as_bytes()
         .iter()
-        .find(|&b| !IDENTIFIER_ALLOWED[*b as usize])
-        .map(|b| unsafe { char::from_u32_unchecked(*b as u32) })
+        .position(|b| !IDENTIFIER_ALLOWED[*b as usize])
+        // SAFETY: every disallowed byte is ASCII
+        .map(|idx| {
+            (idx, unsafe {
+                char::from_u32_unchecked(input.as_bytes()[idx] as u32)
+            })
+        })
 }
 
 /// Check if a given string is as an identifer, returning an error if not.
-pub fn check_identifier(s: &str) -> Result<(), TokenError> {
+pub fn check_identifier(s: &str) -> Result<(), TokenErrorKind> {
     if s.is_empty() {
-        Err(TokenError::Empty)
+        Err(TokenErrorKind::Empty)
     } else {
-        find_invalid_identifier_char(s)
-            .map_or_else(|| Ok(()), |ch| Err(TokenError::InvalidChar(ch)))
+        find_invalid_identifier_char(s).map_or_else(
+            || Ok(()),
+            |(index, ch)| Err(TokenErrorKind::InvalidChar { ch, index }),
+        )
     }
 }
 
 /// Check if a given string is valid as a variable, returning an error if not.
-pub fn check_variable(s: &str) -> Result<(), TokenError> {
+pub fn check_variable(s: &str) -> Result<(), TokenErrorKind> {
     check_identifier(s)?;
     // SAFETY: if is_identifer(s) does not fail, then s is non-empty
     if s.as_bytes()[0].is_ascii_digit() {
-        Err(TokenError::StartsWithDigit)
+        Err(TokenErrorKind::StartsWithDigit)
     } else {
         Ok(())
     }
@@ -83,7 +91,7 @@ pub fn is_identifier(s: &str) -> bool {
 
 /// Check if a given string is valid as an identifier, returning an error if not.
 #[inline]
-pub fn check_field_key(s: &str) -> Result<(), TokenError> {
+pub fn check_field_key(s: &str) -> Result<(), TokenErrorKind> {
     check_identifier(s)
 }
 
@@ -95,7 +103,7 @@ pub fn is_field_key(s: &str) -> bool {
 
 /// Check if a given string is valid as an entry type, returning an error if not.
 #[inline]
-pub fn check_entry_type(s: &str) -> Result<(), TokenError> {
+pub fn check_entry_type(s: &str) -> Result<(), TokenErrorKind> {
     check_identifier(s)
 }
 
@@ -105,22 +113,28 @@ pub fn is_entry_type(s: &str) -> bool {
     check_entry_type(s).is_ok()
 }
 
-/// Check if a given string is valid as a regular entry type.
+/// Check if a given string is valid as a regular entry type, returning an error if not.
 #[inline]
-pub fn is_regular_entry_type(s: &str) -> bool {
+pub fn check_regular_entry_type(s: &str) -> Result<(), TokenErrorKind> {
     if s.eq_ignore_ascii_case("string")
         || s.eq_ignore_ascii_case("comment")
         || s.eq_ignore_ascii_case("preamble")
     {
-        false
+        Err(TokenErrorKind::ReservedEntryType)
     } else {
-        is_entry_type(s)
+        check_entry_type(s)
     }
 }
 
+/// Check if a given string is valid as a regular entry type.
+#[inline]
+pub fn is_regular_entry_type(s: &str) -> bool {
+    check_regular_entry_type(s).is_ok()
+}
+
 /// Check if a given string is valid as an entry key, returning an error if not.
 #[inline]
-pub fn check_entry_key(s: &str) -> Result<(), TokenError> {
+pub fn check_entry_key(s: &str) -> Result<(), TokenErrorKind> {
     check_identifier(s)
 }
 
@@ -131,16 +145,22 @@ pub fn is_entry_key(s: &str) -> bool {
 }
 
 /// Check if the given input has balanced `{}` brackets, returning the appropriate error if not.
-pub fn check_balanced(input: &[u8]) -> Result<(), TokenError> {
+///
+/// If there is an unclosed opening bracket, the error refers to the outermost such bracket.
+pub fn check_balanced(input: &[u8]) -> Result<(), TokenErrorKind> {
     let mut bracket_depth = 0;
+    let mut outermost = 0;
 
     for pos in memchr2_iter(b'{', b'}', input) {
         if input[pos] == b'{' {
+            if bracket_depth == 0 {
+                outermost = pos;
+            }
             bracket_depth += 1
         } else {
             // too many closing brackets
             if bracket_depth == 0 {
-                return Err(TokenError::ExtraClosingBracket);
+                return Err(TokenErrorKind::ExtraClosingBracket { index: pos });
             }
             bracket_depth -= 1;
         }
@@ -149,7 +169,7 @@ pub fn check_balanced(input: &[u8]) -> Result<(), TokenError> {
     if bracket_depth == 0 {
         Ok(())
     } else {
-        Err(TokenError::ExtraOpeningBracket)
+        Err(TokenErrorKind::ExtraOpeningBracket { index: outermost })
     }
 }
 
@@ -166,20 +186,32 @@ mod tests {
     #[test]
     fn test_variable() {
         assert_eq!(check_variable("a123"), Ok(()));
-        assert_eq!(check_variable("a1 23"), Err(TokenError::InvalidChar(' ')));
-        assert_eq!(check_variable("1234"), Err(TokenError::StartsWithDigit));
-        assert_eq!(check_variable("a{"), Err(TokenError::InvalidChar('{')));
-        assert_eq!(check_variable(" "), Err(TokenError::InvalidChar(' ')));
-        assert_eq!(check_variable(""), Err(TokenError::Empty));
+        assert_eq!(
+            check_variable("a1 23"),
+            Err(TokenErrorKind::InvalidChar { ch: ' ', index: 2 })
+        );
+        assert_eq!(check_variable("1234"), Err(TokenErrorKind::StartsWithDigit));
+        assert_eq!(
+            check_variable("a{"),
+            Err(TokenErrorKind::InvalidChar { ch: '{', index: 1 })
+        );
+        assert_eq!(
+            check_variable(" "),
+            Err(TokenErrorKind::InvalidChar { ch: ' ', index: 0 })
+        );
+        assert_eq!(check_variable(""), Err(TokenErrorKind::Empty));
     }
 
     #[test]
     fn test_field_key() {
         assert_eq!(check_variable("a123"), Ok(()));
-        assert_eq!(check_variable("1234"), Err(TokenError::StartsWithDigit));
-        assert_eq!(check_field_key("a)"), Err(TokenError::InvalidChar(')')));
+        assert_eq!(check_variable("1234"), Err(TokenErrorKind::StartsWithDigit));
+        assert_eq!(
+            check_field_key("🍄)"),
+            Err(TokenErrorKind::InvalidChar { ch: ')', index: 4 })
+        );
         assert_eq!(check_field_key("🍄"), Ok(()));
-        assert_eq!(check_field_key(""), Err(TokenError::Empty));
+        assert_eq!(check_field_key(""), Err(TokenErrorKind::Empty));
     }
 
     #[test]
@@ -188,7 +220,34 @@ mod tests {
         assert_eq!(check_balanced(b""), Ok(()));
         assert_eq!(check_balanced(b"{}"), Ok(()));
         assert_eq!(check_balanced(b"{}{{}}"), Ok(()));
-        assert_eq!(check_balanced(b"{"), Err(TokenError::ExtraOpeningBracket));
-        assert_eq!(check_balanced(b"{}}"), Err(TokenError::ExtraClosingBracket));
+        assert_eq!(
+            check_balanced(b"{"),
+            Err(TokenErrorKind::ExtraOpeningBracket { index: 0 })
+        );
+        assert_eq!(
+            check_balanced(b"{}a{{}"),
+            Err(TokenErrorKind::ExtraOpeningBracket { index: 3 })
+        );
+        assert_eq!(
+            check_balanced(b"{}}"),
+            Err(TokenErrorKind::ExtraClosingBracket { index: 2 })
+        );
+    }
+
+    #[test]
+    fn test_regular_entry_type() {
+        assert_eq!(check_regular_entry_type("article"), Ok(()));
+        assert_eq!(
+            check_regular_entry_type("STRING"),
+            Err(TokenErrorKind::ReservedEntryType)
+        );
+        assert_eq!(
+            check_regular_entry_type("a,b"),
+            Err(TokenErrorKind::InvalidChar { ch: ',', index: 1 })
+        );
+        assert_eq!(
+            TokenErrorKind::InvalidChar { ch: ',', index: 1 }.to_string(),
+            "identifier contains invalid character ',' at byte 1"
+        );
     }
 }
@@ -0,0 +1,485 @@
+//! # Transformations of bibliographies
+//!
+//! This module provides composable passes which modify the entries of a
+//! [`Bibliography`], in the spirit of `biber --tool`. A pass implements the [`Transform`] trait,
+//! and passes are combined using a [`Pipeline`].
+//!
+//! A pipeline can be run over a bibliography held in memory:
+//! ```
+//! use serde_bibtex::transform::{ExpandMacros, LowercaseKeys, Pipeline, StripFields, Transform};
+//! use serde_bibtex::value::Bibliography;
+//!
+//! let input = r#"
+//!     @string{j = {Journal}}
+//!     @Article{key,
+//!       Title = {Title},
+//!       Journal = j,
+//!       Abstract = {Long text},
+//!     }
+//! "#;
+//!
+//! let mut bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+//! let mut pipeline = Pipeline::new()
+//!     .with(LowercaseKeys)
+//!     .with(ExpandMacros::new())
+//!     .with(StripFields::new(["abstract"]));
+//! pipeline.run(&mut bibliography);
+//!
+//! assert_eq!(
+//!     serde_bibtex::to_string(&bibliography).unwrap(),
+//!     r#"@string{j = {Journal}}
+//!
+//! @article{key,
+//!   title = {Title},
+//!   journal = {Journal},
+//! }
+//! "#
+//! );
+//! ```
+//! or over a stream of entries, so that the entire bibliography need not be held in memory:
+//! ```
+//! use serde_bibtex::de::Deserializer;
+//! use serde_bibtex::transform::{ProtectTitle, Transform};
+//! use serde_bibtex::value::Entry;
+//!
+//! let input = "@article{a, title = {Title}} @article{b, title = {{Protected}}}";
+//!
+//! let entries = ProtectTitle::new()
+//!     .stream(Deserializer::from_str(input).into_iter::<Entry>())
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     serde_bibtex::to_string(&entries).unwrap(),
+//!     "@article{a,\n  title = {{Title}},\n}\n\n@article{b,\n  title = {{Protected}},\n}\n"
+//! );
+//! ```
+mod journals;
+mod keys;
+mod math;
+mod pages;
+mod title;
+
+use std::collections::{HashMap, HashSet};
+
+use unicase::UniCase;
+
+use crate::entry::CaseKey;
+use crate::error::Result;
+use crate::value::{Bibliography, Entry, Token, Value};
+
+pub use journals::{JournalAbbreviations, JOURNAL_FIELDS};
+pub use keys::{RewriteKeys, REFERENCE_FIELDS};
+pub use math::{map_outside_math, math_spans};
+pub use pages::{normalize_pages, suspicious_pages, NormalizePages};
+pub use title::{downcased_words, ProtectWords, StripRedundantBraces};
+
+/// A pass which modifies entries in place.
+///
+/// Passes are applied to entries in the order in which they appear in the bibliography, so a pass
+/// may accumulate state from earlier entries; see for instance [`ExpandMacros`].
+///
+/// This trait is implemented for closures of type `FnMut(&mut Entry)`.
+pub trait Transform {
+    /// Modify an entry.
+    fn apply(&mut self, entry: &mut Entry);
+
+    /// Modify every entry of a bibliography.
+    fn run(&mut self, bibliography: &mut Bibliography) {
+        for entry in bibliography.0.iter_mut() {
+            self.apply(entry);
+        }
+    }
+
+    /// Modify a stream of entries, such as the iterator returned by
+    /// [`Deserializer::into_iter`](crate::de::Deserializer::into_iter). Errors are passed through
+    /// unchanged.
+    fn stream<I>(self, entries: I) -> Stream<I::IntoIter, Self>
+    where
+        Self: Sized,
+        I: IntoIterator<Item = Result<Entry>>,
+    {
+        Stream {
+            entries: entries.into_iter(),
+            transform: self,
+        }
+    }
+}
+
+impl<F: FnMut(&mut Entry)> Transform for F {
+    fn apply(&mut self, entry: &mut Entry) {
+        self(entry)
+    }
+}
+
+/// An iterator which applies a [`Transform`] to a stream of entries.
+///
+/// This struct is created by [`Transform::stream`].
+#[derive(Debug)]
+pub struct Stream<I, T> {
+    entries: I,
+    transform: T,
+}
+
+impl<I, T> Iterator for Stream<I, T>
+where
+    I: Iterator<Item = Result<Entry>>,
+    T: Transform,
+{
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut entry = self.entries.next()?;
+        if let Ok(ref mut entry) = entry {
+            self.transform.apply(entry);
+        }
+        Some(entry)
+    }
+}
+
+/// A sequence of passes, applied in the order in which they were added.
+#[derive(Default)]
+pub struct Pipeline {
+    passes: Vec<Box<dyn Transform>>,
+}
+
+impl Pipeline {
+    /// Construct a pipeline without any passes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a pass to the pipeline.
+    pub fn with<T: Transform + 'static>(mut self, pass: T) -> Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// The number of passes in the pipeline.
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Whether the pipeline has no passes.
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+}
+
+impl std::fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("passes", &self.passes.len())
+            .finish()
+    }
+}
+
+impl Transform for Pipeline {
+    fn apply(&mut self, entry: &mut Entry) {
+        for pass in self.passes.iter_mut() {
+            pass.apply(entry);
+        }
+    }
+}
+
+/// Convert entry types and field keys to lowercase.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LowercaseKeys;
+
+impl Transform for LowercaseKeys {
+    fn apply(&mut self, entry: &mut Entry) {
+        if let Entry::Regular(regular) = entry {
+            regular.entry_type.make_ascii_lowercase();
+            for (key, _) in regular.fields.iter_mut() {
+                key.make_ascii_lowercase();
+            }
+        }
+    }
+}
+
+/// Expand macros in field values and preambles using the macros defined in earlier `@string`
+/// entries.
+///
+/// Macro names are compared case-insensitively, and macros which are not defined are left
+/// unchanged. The `@string` entries themselves are retained.
+#[derive(Debug, Clone, Default)]
+pub struct ExpandMacros {
+    macros: HashMap<UniCase<String>, Value>,
+}
+
+impl ExpandMacros {
+    /// Construct a pass without any predefined macros.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Predefine a macro, as if it were defined by an `@string` entry at the start of the
+    /// bibliography.
+    pub fn with_macro<S: Into<String>, V: Into<Value>>(mut self, name: S, value: V) -> Self {
+        self.macros.insert(UniCase::new(name.into()), value.into());
+        self
+    }
+
+    fn expand(&self, value: &mut Value) {
+        if !value
+            .0
+            .iter()
+            .any(|token| matches!(token, Token::Variable(_)))
+        {
+            return;
+        }
+
+        let mut expanded = Vec::with_capacity(value.0.len());
+        for token in value.0.drain(..) {
+            match token {
+                Token::Variable(name) => {
+                    let name = UniCase::new(name);
+                    match self.macros.get(&name) {
+                        Some(sub) => expanded.extend(sub.0.iter().cloned()),
+                        None => expanded.push(Token::Variable(name.into_inner())),
+                    }
+                }
+                text => expanded.push(text),
+            }
+        }
+        value.0 = expanded;
+    }
+}
+
+impl Transform for ExpandMacros {
+    fn apply(&mut self, entry: &mut Entry) {
+        match entry {
+            Entry::Regular(regular) => {
+                for (_, value) in regular.fields.iter_mut() {
+                    self.expand(value);
+                }
+            }
+            Entry::Macro(Some((name, value))) => {
+                self.expand(value);
+                self.macros
+                    .insert(UniCase::new(name.clone()), value.clone());
+            }
+            Entry::Preamble(value) => self.expand(value),
+            Entry::Macro(None) | Entry::Comment(_) => {}
+        }
+    }
+}
+
+/// Remove fields with the given keys from regular entries. Field keys are compared
+/// case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct StripFields {
+    keys: HashSet<UniCase<String>>,
+}
+
+impl StripFields {
+    /// Construct a pass which removes the given fields.
+    pub fn new<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            keys: keys
+                .into_iter()
+                .map(|key| UniCase::new(key.into()))
+                .collect(),
+        }
+    }
+}
+
+impl Transform for StripFields {
+    fn apply(&mut self, entry: &mut Entry) {
+        if let Entry::Regular(regular) = entry {
+            regular.fields.retain(|(key, _)| {
+                !self
+                    .keys
+                    .contains(&UniCase::new(key.as_str()) as &dyn CaseKey)
+            });
+        }
+    }
+}
+
+/// Protect the capitalization of titles by enclosing the text in an additional pair of brackets,
+/// so that BibTeX styles do not convert the title to lowercase.
+///
+/// Text which is already enclosed in brackets is left unchanged. To protect only the words which
+/// require it, use [`ProtectWords`] instead.
+#[derive(Debug, Clone)]
+pub struct ProtectTitle {
+    keys: HashSet<UniCase<String>>,
+}
+
+impl Default for ProtectTitle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtectTitle {
+    /// Construct a pass which protects the `title` field.
+    pub fn new() -> Self {
+        Self::with_fields(["title"])
+    }
+
+    /// Construct a pass which protects the given fields.
+    pub fn with_fields<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            keys: keys
+                .into_iter()
+                .map(|key| UniCase::new(key.into()))
+                .collect(),
+        }
+    }
+}
+
+/// Whether the text is entirely enclosed in a single pair of matching brackets.
+pub(crate) fn is_protected(text: &str) -> bool {
+    if !(text.starts_with('{') && text.ends_with('}')) {
+        return false;
+    }
+
+    let mut depth = 0usize;
+    for (idx, b) in text.bytes().enumerate() {
+        match b {
+            b'{' => depth += 1,
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return idx == text.len() - 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+impl Transform for ProtectTitle {
+    fn apply(&mut self, entry: &mut Entry) {
+        if let Entry::Regular(regular) = entry {
+            for (key, value) in regular.fields.iter_mut() {
+                if !self
+                    .keys
+                    .contains(&UniCase::new(key.as_str()) as &dyn CaseKey)
+                {
+                    continue;
+                }
+                for token in value.0.iter_mut() {
+                    if let Token::Text(text) = token {
+                        if !text.is_empty() && !is_protected(text) {
+                            *text = format!("{{{text}}}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::RegularEntry;
+
+    fn regular(entry: &Entry) -> &RegularEntry {
+        match entry {
+            Entry::Regular(regular) => regular,
+            _ => panic!("expected a regular entry"),
+        }
+    }
+
+    #[test]
+    fn test_expand_macros() {
+        let input = r#"
+            @string{a = {A}}
+            @string{b = a # {B}}
+            @preamble{b}
+            @article{key, title = b # c, month = JAN}
+        "#;
+        let mut bibliography: Bibliography = crate::from_str(input).unwrap();
+        ExpandMacros::new()
+            .with_macro("jan", "January")
+            .run(&mut bibliography);
+
+        assert_eq!(
+            bibliography.0[2],
+            Entry::Preamble(Value(vec![
+                Token::Text("A".into()),
+                Token::Text("B".into())
+            ]))
+        );
+        let entry = regular(&bibliography.0[3]);
+        assert_eq!(
+            entry.field("title"),
+            Some(&Value(vec![
+                Token::Text("A".into()),
+                Token::Text("B".into()),
+                Token::Variable("c".into()),
+            ]))
+        );
+        assert_eq!(entry.field("month"), Some(&Value::text("January")));
+    }
+
+    #[test]
+    fn test_strip_and_lowercase() {
+        let mut entry = Entry::Regular(RegularEntry::new("ARTICLE", "Key"));
+        if let Entry::Regular(regular) = &mut entry {
+            regular.set_field("Title", "T");
+            regular.set_field("ABSTRACT", "A");
+            regular.set_field("Note", "N");
+        }
+
+        let mut pipeline = Pipeline::new()
+            .with(StripFields::new(["abstract", "note"]))
+            .with(LowercaseKeys);
+        assert_eq!(pipeline.len(), 2);
+        pipeline.apply(&mut entry);
+
+        let regular = regular(&entry);
+        assert_eq!(regular.entry_type, "article");
+        assert_eq!(regular.entry_key, "Key");
+        assert_eq!(regular.fields, vec![("title".into(), Value::text("T"))]);
+    }
+
+    #[test]
+    fn test_is_protected() {
+        assert!(is_protected("{Title}"));
+        assert!(is_protected("{{A} b}"));
+        assert!(!is_protected("{A} {B}"));
+        assert!(!is_protected("Title"));
+        assert!(!is_protected("{A} b"));
+        assert!(!is_protected(""));
+    }
+
+    #[test]
+    fn test_stream() {
+        let input = "@article{a, title = {A} # m, note = {N}} @comment{x} @article{b,";
+        let entries: Vec<_> = Pipeline::new()
+            .with(ProtectTitle::new())
+            .with(|entry: &mut Entry| {
+                if let Entry::Comment(comment) = entry {
+                    comment.make_ascii_uppercase();
+                }
+            })
+            .stream(crate::de::Deserializer::from_str(input).into_iter::<Entry>())
+            .collect();
+
+        assert_eq!(entries.len(), 3);
+        let entry = regular(entries[0].as_ref().unwrap());
+        assert_eq!(
+            entry.field("title"),
+            Some(&Value(vec![
+                Token::Text("{A}".into()),
+                Token::Variable("m".into())
+            ]))
+        );
+        assert_eq!(entry.field("note"), Some(&Value::text("N")));
+        assert_eq!(entries[1].as_ref().unwrap(), &Entry::Comment("X".into()));
+        assert!(entries[2].is_err());
+    }
+}
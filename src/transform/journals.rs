@@ -0,0 +1,207 @@
+use std::collections::{HashMap, HashSet};
+
+use unicase::UniCase;
+
+use super::Transform;
+use crate::entry::CaseKey;
+use crate::value::{Entry, Value};
+
+/// The fields which contain journal names, and which are rewritten by default by
+/// [`JournalAbbreviations`].
+pub const JOURNAL_FIELDS: [&str; 2] = ["journal", "journaltitle"];
+
+/// Rewrite journal names between their full and abbreviated forms, using a table of
+/// abbreviations.
+///
+/// Journal names are compared case-insensitively, with runs of whitespace treated as a single
+/// space. Only values which consist entirely of text are rewritten: values which contain macros,
+/// and journal names which are not in the table, are left unchanged.
+/// ```
+/// use serde_bibtex::transform::{JournalAbbreviations, Transform};
+/// use serde_bibtex::value::Bibliography;
+///
+/// let table = "
+///     # full name; abbreviation
+///     Physical Review Letters; Phys. Rev. Lett.
+///     Journal of Number Theory; J. Number Theory
+/// ";
+///
+/// let input = r#"
+///     @article{a, journal = {Physical  review letters}}
+///     @article{b, journal = {Unknown Journal}}
+/// "#;
+///
+/// let mut bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+/// JournalAbbreviations::abbreviate()
+///     .with_list(table)
+///     .run(&mut bibliography);
+///
+/// assert_eq!(
+///     serde_bibtex::to_string(&bibliography).unwrap(),
+///     r#"@article{a,
+///   journal = {Phys. Rev. Lett.},
+/// }
+///
+/// @article{b,
+///   journal = {Unknown Journal},
+/// }
+/// "#
+/// );
+///
+/// JournalAbbreviations::expand()
+///     .with_list(table)
+///     .run(&mut bibliography);
+/// assert_eq!(
+///     bibliography.get("a").unwrap().field("journal").unwrap().to_text().unwrap(),
+///     "Physical Review Letters"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct JournalAbbreviations {
+    expand: bool,
+    replacements: HashMap<UniCase<String>, String>,
+    keys: HashSet<UniCase<String>>,
+}
+
+impl JournalAbbreviations {
+    fn new(expand: bool) -> Self {
+        Self {
+            expand,
+            replacements: HashMap::new(),
+            keys: JOURNAL_FIELDS
+                .into_iter()
+                .map(|key| UniCase::new(key.to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Construct a pass which replaces full journal names by their abbreviations.
+    pub fn abbreviate() -> Self {
+        Self::new(false)
+    }
+
+    /// Construct a pass which replaces abbreviated journal names by their full names.
+    pub fn expand() -> Self {
+        Self::new(true)
+    }
+
+    /// Add an abbreviation to the table.
+    pub fn with_abbreviation<F, A>(mut self, full: F, abbreviation: A) -> Self
+    where
+        F: AsRef<str>,
+        A: AsRef<str>,
+    {
+        let (full, abbreviation) = (normalize(full.as_ref()), normalize(abbreviation.as_ref()));
+        let (from, to) = if self.expand {
+            (abbreviation, full)
+        } else {
+            (full, abbreviation)
+        };
+        self.replacements.entry(UniCase::new(from)).or_insert(to);
+        self
+    }
+
+    /// Add the abbreviations in a list to the table.
+    ///
+    /// Each line of the list contains a full journal name and its abbreviation, separated by a
+    /// semicolon, as in the abbreviation lists used by JabRef. Empty lines, lines starting with
+    /// `#`, and lines without a semicolon are ignored. If a journal name occurs more than once,
+    /// the first occurrence is used.
+    pub fn with_list(mut self, list: &str) -> Self {
+        for line in list.lines().map(str::trim) {
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((full, abbreviation)) = line.split_once(';') {
+                self = self.with_abbreviation(full, abbreviation);
+            }
+        }
+        self
+    }
+
+    /// Set the fields which contain journal names, replacing the default [`JOURNAL_FIELDS`].
+    /// Field keys are compared case-insensitively.
+    pub fn with_fields<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keys = keys
+            .into_iter()
+            .map(|key| UniCase::new(key.into()))
+            .collect();
+        self
+    }
+
+    /// The replacement for a journal name, if any.
+    pub fn lookup(&self, journal: &str) -> Option<&str> {
+        self.replacements
+            .get(&UniCase::new(normalize(journal)))
+            .map(String::as_str)
+    }
+}
+
+/// Trim the text and replace runs of whitespace by a single space.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Transform for JournalAbbreviations {
+    fn apply(&mut self, entry: &mut Entry) {
+        if let Entry::Regular(regular) = entry {
+            for (key, value) in regular.fields.iter_mut() {
+                if !self
+                    .keys
+                    .contains(&UniCase::new(key.as_str()) as &dyn CaseKey)
+                {
+                    continue;
+                }
+                let replacement = value
+                    .to_text()
+                    .and_then(|text| self.lookup(&text).map(str::to_owned));
+                if let Some(replacement) = replacement {
+                    *value = Value::text(replacement);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::RegularEntry;
+
+    #[test]
+    fn test_journal_abbreviations() {
+        let list = "Journal A; J. A\n#Journal B; J. B\nno separator\n Journal C ;J.  C \nJournal A; J. Dup";
+        let abbreviate = JournalAbbreviations::abbreviate().with_list(list);
+        assert_eq!(abbreviate.lookup("journal a"), Some("J. A"));
+        assert_eq!(abbreviate.lookup("Journal B"), None);
+        assert_eq!(abbreviate.lookup("Journal\nC"), Some("J. C"));
+        assert_eq!(abbreviate.lookup("J. A"), None);
+
+        let expand = JournalAbbreviations::expand().with_list(list);
+        assert_eq!(expand.lookup("j. c"), Some("Journal C"));
+        assert_eq!(expand.lookup("J. Dup"), Some("Journal A"));
+
+        let mut entry = RegularEntry::new("article", "key");
+        entry.set_field("Journal", "Journal A");
+        entry.set_field("booktitle", "Journal A");
+        entry.set_field("journaltitle", Value::variable("ja"));
+        let mut entry = Entry::Regular(entry);
+        abbreviate.clone().apply(&mut entry);
+        let Entry::Regular(regular) = &entry else {
+            unreachable!()
+        };
+        assert_eq!(regular.field("journal"), Some(&Value::text("J. A")));
+        assert_eq!(regular.field("booktitle"), Some(&Value::text("Journal A")));
+        assert_eq!(regular.field("journaltitle"), Some(&Value::variable("ja")));
+
+        abbreviate.with_fields(["booktitle"]).apply(&mut entry);
+        let Entry::Regular(regular) = &entry else {
+            unreachable!()
+        };
+        assert_eq!(regular.field("booktitle"), Some(&Value::text("J. A")));
+    }
+}
@@ -0,0 +1,193 @@
+use std::collections::HashSet;
+
+use unicase::UniCase;
+
+use super::Transform;
+use crate::entry::CaseKey;
+pub use crate::validate::REFERENCE_FIELDS;
+use crate::value::{Entry, Token};
+
+/// Rewrite the entry key of every regular entry, along with the references to other entries in
+/// the [`REFERENCE_FIELDS`], so that links between entries remain consistent.
+///
+/// This is useful when merging bibliographies whose keys clash. A reference field may contain a
+/// comma-separated list of keys, and each key is rewritten separately while the separators and
+/// surrounding whitespace are retained. Only text tokens are rewritten: macros in reference
+/// fields are left unchanged.
+/// ```
+/// use serde_bibtex::transform::{RewriteKeys, Transform};
+/// use serde_bibtex::value::Bibliography;
+///
+/// let input = r#"
+///     @book{main, title = {Proceedings}}
+///     @inproceedings{part, crossref = {main}, related = {main, other}}
+/// "#;
+///
+/// let mut bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+/// RewriteKeys::prefix("a:").run(&mut bibliography);
+///
+/// assert_eq!(
+///     serde_bibtex::to_string(&bibliography).unwrap(),
+///     r#"@book{a:main,
+///   title = {Proceedings},
+/// }
+///
+/// @inproceedings{a:part,
+///   crossref = {a:main},
+///   related = {a:main, a:other},
+/// }
+/// "#
+/// );
+/// ```
+pub struct RewriteKeys {
+    rewrite: Box<dyn FnMut(&str) -> String>,
+    keys: HashSet<UniCase<String>>,
+}
+
+impl RewriteKeys {
+    /// Construct a pass which replaces every key by the result of `rewrite`.
+    pub fn new<F>(rewrite: F) -> Self
+    where
+        F: FnMut(&str) -> String + 'static,
+    {
+        Self {
+            rewrite: Box::new(rewrite),
+            keys: REFERENCE_FIELDS
+                .into_iter()
+                .map(|key| UniCase::new(key.to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Construct a pass which prepends `prefix` to every key.
+    pub fn prefix<S: Into<String>>(prefix: S) -> Self {
+        let prefix = prefix.into();
+        Self::new(move |key| format!("{prefix}{key}"))
+    }
+
+    /// Construct a pass which appends `suffix` to every key.
+    pub fn suffix<S: Into<String>>(suffix: S) -> Self {
+        let suffix = suffix.into();
+        Self::new(move |key| format!("{key}{suffix}"))
+    }
+
+    /// Set the fields which contain references to other entries, replacing the default
+    /// [`REFERENCE_FIELDS`]. Field keys are compared case-insensitively.
+    pub fn with_fields<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keys = keys
+            .into_iter()
+            .map(|key| UniCase::new(key.into()))
+            .collect();
+        self
+    }
+
+    /// Rewrite each key in a comma-separated list of keys.
+    fn rewrite_list(&mut self, text: &str) -> String {
+        let mut rewritten = String::with_capacity(text.len());
+        for (idx, part) in text.split(',').enumerate() {
+            if idx > 0 {
+                rewritten.push(',');
+            }
+            let key = part.trim();
+            if key.is_empty() {
+                rewritten.push_str(part);
+            } else {
+                let start = part.len() - part.trim_start().len();
+                rewritten.push_str(&part[..start]);
+                rewritten.push_str(&(self.rewrite)(key));
+                rewritten.push_str(&part[start + key.len()..]);
+            }
+        }
+        rewritten
+    }
+}
+
+impl std::fmt::Debug for RewriteKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RewriteKeys")
+            .field("keys", &self.keys)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transform for RewriteKeys {
+    fn apply(&mut self, entry: &mut Entry) {
+        if let Entry::Regular(regular) = entry {
+            regular.entry_key = (self.rewrite)(&regular.entry_key);
+            for (key, value) in regular.fields.iter_mut() {
+                if !self
+                    .keys
+                    .contains(&UniCase::new(key.as_str()) as &dyn CaseKey)
+                {
+                    continue;
+                }
+                for token in value.0.iter_mut() {
+                    if let Token::Text(text) = token {
+                        *text = self.rewrite_list(text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{RegularEntry, Value};
+
+    #[test]
+    fn test_rewrite_keys() {
+        let mut entry = Entry::Regular(RegularEntry::new("article", "key"));
+        if let Entry::Regular(regular) = &mut entry {
+            regular.set_field("CrossRef", "main");
+            regular.set_field("xref", " a ,b,, c");
+            regular.set_field(
+                "related",
+                Value(vec![Token::Variable("m".into()), Token::Text(",d".into())]),
+            );
+            regular.set_field("note", "main");
+        }
+
+        RewriteKeys::suffix("-2").apply(&mut entry);
+        let Entry::Regular(regular) = &entry else {
+            panic!("expected a regular entry");
+        };
+        assert_eq!(regular.entry_key, "key-2");
+        assert_eq!(regular.field("crossref"), Some(&Value::text("main-2")));
+        assert_eq!(regular.field("xref"), Some(&Value::text(" a-2 ,b-2,, c-2")));
+        assert_eq!(
+            regular.field("related"),
+            Some(&Value(vec![
+                Token::Variable("m".into()),
+                Token::Text(",d-2".into())
+            ]))
+        );
+        assert_eq!(regular.field("note"), Some(&Value::text("main")));
+
+        // a mapping function with custom reference fields
+        let mut entry = Entry::Regular(RegularEntry::new("article", "Key"));
+        if let Entry::Regular(regular) = &mut entry {
+            regular.set_field("crossref", "Main");
+            regular.set_field("parent", "Main");
+        }
+        RewriteKeys::new(|key| key.to_lowercase())
+            .with_fields(["parent"])
+            .apply(&mut entry);
+        let Entry::Regular(regular) = &entry else {
+            panic!("expected a regular entry");
+        };
+        assert_eq!(regular.entry_key, "key");
+        assert_eq!(regular.field("crossref"), Some(&Value::text("Main")));
+        assert_eq!(regular.field("parent"), Some(&Value::text("main")));
+
+        // other entries are unchanged
+        let mut comment = Entry::Comment("key".into());
+        RewriteKeys::prefix("a:").apply(&mut comment);
+        assert_eq!(comment, Entry::Comment("key".into()));
+    }
+}
@@ -0,0 +1,143 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// The position just after the first occurrence of `close` at or after `pos`, skipping escaped
+/// characters such as `\$`.
+fn find_close(input: &[u8], mut pos: usize, close: &[u8]) -> Option<usize> {
+    while pos < input.len() {
+        if input[pos..].starts_with(close) {
+            return Some(pos + close.len());
+        }
+        pos += if input[pos] == b'\\' { 2 } else { 1 };
+    }
+    None
+}
+
+/// The byte ranges of the math in TeX text, including the delimiters.
+///
+/// Math is delimited by `$...$`, `$$...$$`, `\(...\)`, or `\[...\]`. Escaped dollar signs, such as
+/// in `\$5`, do not begin or end math, and an opening delimiter without a matching closing
+/// delimiter is treated as text.
+/// ```
+/// use serde_bibtex::transform::math_spans;
+///
+/// let text = r"Bounds on $\mathcal{O}(n)$ and \(x\) for \$5";
+/// let spans = math_spans(text);
+/// assert_eq!(spans.len(), 2);
+/// assert_eq!(&text[spans[0].clone()], r"$\mathcal{O}(n)$");
+/// assert_eq!(&text[spans[1].clone()], r"\(x\)");
+/// ```
+pub fn math_spans(text: &str) -> Vec<Range<usize>> {
+    let input = text.as_bytes();
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    while pos < input.len() {
+        let (open, close): (usize, &[u8]) = match (input[pos], input.get(pos + 1)) {
+            (b'\\', Some(b'(')) => (2, b"\\)"),
+            (b'\\', Some(b'[')) => (2, b"\\]"),
+            (b'\\', _) => {
+                pos += 2;
+                continue;
+            }
+            (b'$', Some(b'$')) => (2, b"$$"),
+            (b'$', _) => (1, b"$"),
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+        match find_close(input, pos + open, close) {
+            Some(end) => {
+                spans.push(pos..end);
+                pos = end;
+            }
+            None => pos += open,
+        }
+    }
+    spans
+}
+
+/// Apply `f` to the parts of TeX text which are not [math](math_spans), leaving the math
+/// unchanged.
+///
+/// This is useful when decoding TeX, for instance in a
+/// [value filter](crate::de::Deserializer::with_value_filter), since the same escapes have a
+/// different meaning in math.
+/// ```
+/// use std::borrow::Cow;
+///
+/// use serde_bibtex::transform::map_outside_math;
+///
+/// let decoded = map_outside_math(r"Sums \& products: $a \& b$", |text| {
+///     Cow::Owned(text.replace(r"\&", "&"))
+/// });
+/// assert_eq!(decoded, r"Sums & products: $a \& b$");
+/// ```
+pub fn map_outside_math<F>(text: &str, mut f: F) -> Cow<'_, str>
+where
+    F: FnMut(&str) -> Cow<'_, str>,
+{
+    let mut mapped = String::new();
+    let mut changed = false;
+    let mut last = 0;
+    let end = text.len()..text.len();
+    for span in math_spans(text).into_iter().chain(std::iter::once(end)) {
+        let part = &text[last..span.start];
+        if !part.is_empty() {
+            let part = f(part);
+            changed |= matches!(part, Cow::Owned(_));
+            mapped.push_str(&part);
+        }
+        mapped.push_str(&text[span.clone()]);
+        last = span.end;
+    }
+
+    if changed {
+        Cow::Owned(mapped)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_math_spans() {
+        for (text, expected) in [
+            ("", vec![]),
+            ("no math", vec![]),
+            ("$a$ $$b$$", vec![(0, 3), (4, 9)]),
+            (r"\[a\] \\(b\)", vec![(0, 5)]),
+            (r"$a\$b$ \$", vec![(0, 6)]),
+            ("$unterminated", vec![]),
+            (r"\(a$b$", vec![(3, 6)]),
+            ("$$a$", vec![]),
+            ("é$ü$", vec![(2, 6)]),
+        ] {
+            let spans: Vec<_> = math_spans(text)
+                .into_iter()
+                .map(|span| (span.start, span.end))
+                .collect();
+            assert_eq!(spans, expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_map_outside_math() {
+        fn upper(text: &str) -> Cow<'_, str> {
+            Cow::Owned(text.to_uppercase())
+        }
+        fn unchanged(text: &str) -> Cow<'_, str> {
+            Cow::Borrowed(text)
+        }
+
+        assert_eq!(map_outside_math("a $b$ c", upper), "A $b$ C");
+        assert_eq!(map_outside_math("$b$", upper), "$b$");
+        assert!(matches!(
+            map_outside_math("a $b$", unchanged),
+            Cow::Borrowed(_)
+        ));
+    }
+}
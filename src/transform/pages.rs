@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+
+use unicase::UniCase;
+
+use super::Transform;
+use crate::entry::CaseKey;
+use crate::value::{Entry, Value};
+
+/// The callback which reports suspicious page ranges.
+type Report<'a> = dyn FnMut(&str, &str) + 'a;
+
+/// Whether the character is a hyphen or a dash.
+fn is_dash(c: char) -> bool {
+    matches!(
+        c,
+        '-' | '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2212}'
+    )
+}
+
+/// Replace each hyphen or Unicode dash in a page range, along with any adjacent hyphens, dashes,
+/// and whitespace, by `--`.
+/// ```
+/// use serde_bibtex::transform::normalize_pages;
+///
+/// assert_eq!(normalize_pages("10-12"), "10--12");
+/// assert_eq!(normalize_pages("10 – 12, S1—S4"), "10--12, S1--S4");
+/// assert_eq!(normalize_pages("10--12"), "10--12");
+/// ```
+pub fn normalize_pages(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len() + 1);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_dash(c) {
+            normalized.truncate(normalized.trim_end().len());
+            while let Some(next) = chars.peek() {
+                if !(is_dash(*next) || next.is_whitespace()) {
+                    break;
+                }
+                chars.next();
+            }
+            normalized.push_str("--");
+        } else {
+            normalized.push(c);
+        }
+    }
+    normalized
+}
+
+/// Whether a `pages` value is suspicious, since one of its comma-separated parts contains more
+/// than one range, such as `10-12-14`, or a range with a missing endpoint, such as `10-`.
+/// ```
+/// use serde_bibtex::transform::suspicious_pages;
+///
+/// assert!(suspicious_pages("10-12-14"));
+/// assert!(suspicious_pages("1--3, 10--"));
+/// assert!(!suspicious_pages("1--3, 10--12, 15"));
+/// ```
+pub fn suspicious_pages(text: &str) -> bool {
+    normalize_pages(text).split(',').any(|part| {
+        let mut endpoints = part.split("--");
+        let first = endpoints.next().unwrap_or_default();
+        match (endpoints.next(), endpoints.next()) {
+            (None, _) => false,
+            (Some(last), None) => first.trim().is_empty() || last.trim().is_empty(),
+            (Some(_), Some(_)) => true,
+        }
+    })
+}
+
+/// Normalize page ranges in the `pages` field using [`normalize_pages`], so that hyphens and
+/// Unicode dashes are replaced by `--`.
+///
+/// Only values which consist entirely of text are normalized. Values which are
+/// [suspicious](suspicious_pages) are left unchanged, and are reported to the callback set with
+/// [`NormalizePages::on_suspicious`].
+/// ```
+/// use serde_bibtex::transform::{NormalizePages, Transform};
+/// use serde_bibtex::value::Bibliography;
+///
+/// let input = r#"
+///     @article{a, pages = {10 - 12}}
+///     @article{b, pages = {10-12-14}}
+/// "#;
+///
+/// let mut suspicious = Vec::new();
+/// let mut bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+/// NormalizePages::new()
+///     .on_suspicious(|key, pages| suspicious.push(format!("{key}: {pages}")))
+///     .run(&mut bibliography);
+///
+/// assert_eq!(
+///     serde_bibtex::to_string(&bibliography).unwrap(),
+///     r#"@article{a,
+///   pages = {10--12},
+/// }
+///
+/// @article{b,
+///   pages = {10-12-14},
+/// }
+/// "#
+/// );
+/// assert_eq!(suspicious, vec!["b: 10-12-14"]);
+/// ```
+pub struct NormalizePages<'a> {
+    keys: HashSet<UniCase<String>>,
+    on_suspicious: Option<Box<Report<'a>>>,
+}
+
+impl Default for NormalizePages<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> NormalizePages<'a> {
+    /// Construct a pass which normalizes the `pages` field.
+    pub fn new() -> Self {
+        Self {
+            keys: HashSet::from([UniCase::new("pages".to_owned())]),
+            on_suspicious: None,
+        }
+    }
+
+    /// Set the fields which contain page ranges, replacing the default `pages` field. Field keys
+    /// are compared case-insensitively.
+    pub fn with_fields<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keys = keys
+            .into_iter()
+            .map(|key| UniCase::new(key.into()))
+            .collect();
+        self
+    }
+
+    /// Call `report` with the entry key and the value of each suspicious page range.
+    pub fn on_suspicious<F: FnMut(&str, &str) + 'a>(mut self, report: F) -> Self {
+        self.on_suspicious = Some(Box::new(report));
+        self
+    }
+}
+
+impl Transform for NormalizePages<'_> {
+    fn apply(&mut self, entry: &mut Entry) {
+        if let Entry::Regular(regular) = entry {
+            for (key, value) in regular.fields.iter_mut() {
+                if !self
+                    .keys
+                    .contains(&UniCase::new(key.as_str()) as &dyn CaseKey)
+                {
+                    continue;
+                }
+                let Some(text) = value.to_text() else {
+                    continue;
+                };
+                if suspicious_pages(&text) {
+                    if let Some(report) = self.on_suspicious.as_mut() {
+                        report(&regular.entry_key, &text);
+                    }
+                } else {
+                    let normalized = normalize_pages(&text);
+                    if normalized != text {
+                        *value = Value::text(normalized);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::RegularEntry;
+
+    #[test]
+    fn test_normalize_pages() {
+        assert_eq!(normalize_pages("1-2"), "1--2");
+        assert_eq!(normalize_pages("1---2"), "1--2");
+        assert_eq!(normalize_pages("1\u{2010}2,3 \u{2212} 4"), "1--2,3--4");
+        assert_eq!(normalize_pages("12"), "12");
+        assert_eq!(normalize_pages("e101"), "e101");
+        assert_eq!(normalize_pages(" 1 - "), " 1--");
+
+        assert!(suspicious_pages("1-2-3"));
+        assert!(suspicious_pages("-5"));
+        assert!(suspicious_pages("1--2, \u{2013}"));
+        assert!(!suspicious_pages(""));
+        assert!(!suspicious_pages("iv, 1--2"));
+    }
+
+    #[test]
+    fn test_normalize_pages_pass() {
+        let mut entry = RegularEntry::new("article", "key");
+        entry.set_field("pages", "1\u{2013}2");
+        entry.set_field("numpages", "1-2");
+        entry.fields.push(("Pages".into(), Value::variable("p")));
+        let mut entry = Entry::Regular(entry);
+
+        let mut reports = 0;
+        NormalizePages::new()
+            .on_suspicious(|_, _| reports += 1)
+            .apply(&mut entry);
+        assert_eq!(reports, 0);
+        let Entry::Regular(regular) = &entry else {
+            unreachable!()
+        };
+        assert_eq!(
+            regular.fields,
+            vec![
+                ("pages".into(), Value::text("1--2")),
+                ("numpages".into(), Value::text("1-2")),
+                ("Pages".into(), Value::variable("p")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+use unicase::UniCase;
+
+use super::{math_spans, Transform};
+use crate::entry::CaseKey;
+use crate::value::{Entry, Token};
+
+/// A word which is not enclosed in brackets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Word {
+    range: Range<usize>,
+    /// Whether BibTeX preserves the case of the first character, which is the case at the start
+    /// of the text and after a colon.
+    initial: bool,
+}
+
+/// The maximal alphanumeric runs at bracket depth zero, excluding control sequences such as
+/// `\emph` and math. The flag `start` indicates whether the text is at the start of the title.
+fn words(text: &str, start: bool) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut initial = start;
+    let mut chars = text.char_indices().peekable();
+    let mut math = math_spans(text).into_iter().peekable();
+
+    while let Some((idx, ch)) = chars.next() {
+        while math.next_if(|span| span.start < idx).is_some() {}
+        if let Some(span) = math.next_if(|span| span.start == idx) {
+            while chars.next_if(|(next, _)| *next < span.end).is_some() {}
+            initial = false;
+            continue;
+        }
+        match ch {
+            '{' => {
+                depth += 1;
+                initial = false;
+            }
+            '}' => depth = depth.saturating_sub(1),
+            _ if depth > 0 => {}
+            ':' => initial = true,
+            '\\' => {
+                initial = false;
+                while chars.next_if(|(_, c)| c.is_alphabetic()).is_some() {}
+            }
+            c if c.is_alphanumeric() => {
+                let mut end = idx + c.len_utf8();
+                while let Some((next, c)) = chars.next_if(|(_, c)| c.is_alphanumeric()) {
+                    end = next + c.len_utf8();
+                }
+                words.push(Word {
+                    range: idx..end,
+                    initial,
+                });
+                initial = false;
+            }
+            c if c.is_whitespace() => {}
+            _ => initial = false,
+        }
+    }
+    words
+}
+
+/// Whether BibTeX would convert some character of the word to lowercase.
+fn is_downcased(word: &str, initial: bool) -> bool {
+    let skip = if initial { 1 } else { 0 };
+    word.chars().skip(skip).any(char::is_uppercase)
+}
+
+/// Whether the word looks like an acronym or a name with internal capitals, such as `DNA` or
+/// `LaTeX`.
+fn is_acronym(word: &str) -> bool {
+    word.chars().skip(1).any(char::is_uppercase)
+}
+
+/// The words in a title which a BibTeX style would convert to lowercase.
+///
+/// BibTeX styles which use sentence case convert every character which is not enclosed in
+/// brackets to lowercase, except for the first character of the title and the first character
+/// after a colon. This function returns the words containing an uppercase character which would
+/// be converted.
+/// ```
+/// use serde_bibtex::transform::downcased_words;
+///
+/// assert_eq!(
+///     downcased_words("Sequencing DNA: Methods for {RNA} in Paris"),
+///     vec!["DNA", "Paris"]
+/// );
+/// ```
+pub fn downcased_words(text: &str) -> Vec<&str> {
+    words(text, true)
+        .into_iter()
+        .map(|word| (&text[word.range], word.initial))
+        .filter(|(word, initial)| is_downcased(word, *initial))
+        .map(|(word, _)| word)
+        .collect()
+}
+
+fn field_set<I, S>(keys: I) -> HashSet<UniCase<String>>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    keys.into_iter()
+        .map(|key| UniCase::new(key.into()))
+        .collect()
+}
+
+/// Apply `f` to every text token in the given fields of a regular entry. The flag passed to `f`
+/// indicates whether the token is the first token of the value.
+fn for_each_text<F>(entry: &mut Entry, keys: &HashSet<UniCase<String>>, mut f: F)
+where
+    F: FnMut(&mut String, bool),
+{
+    if let Entry::Regular(regular) = entry {
+        for (key, value) in regular.fields.iter_mut() {
+            if !keys.contains(&UniCase::new(key.as_str()) as &dyn CaseKey) {
+                continue;
+            }
+            for (idx, token) in value.0.iter_mut().enumerate() {
+                if let Token::Text(text) = token {
+                    f(text, idx == 0);
+                }
+            }
+        }
+    }
+}
+
+/// Protect individual words in titles by enclosing them in brackets, so that BibTeX styles do not
+/// convert them to lowercase.
+///
+/// A word is protected if BibTeX would change its capitalization and either it appears in the
+/// configured word list, or it looks like an acronym: that is, it contains an uppercase character
+/// other than its first character. Words in the word list are compared case-sensitively.
+/// ```
+/// use serde_bibtex::transform::{ProtectWords, Transform};
+/// use serde_bibtex::value::{Entry, RegularEntry, Value};
+///
+/// let mut entry = RegularEntry::new("article", "key");
+/// entry.set_field("title", "The DNA of Bacteria in Paris");
+/// let mut entry = Entry::Regular(entry);
+///
+/// ProtectWords::new().words(["Paris"]).apply(&mut entry);
+///
+/// match entry {
+///     Entry::Regular(regular) => assert_eq!(
+///         regular.field("title"),
+///         Some(&Value::text("The {DNA} of Bacteria in {Paris}"))
+///     ),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProtectWords {
+    keys: HashSet<UniCase<String>>,
+    words: HashSet<String>,
+    acronyms: bool,
+}
+
+impl Default for ProtectWords {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProtectWords {
+    /// Construct a pass which protects acronyms in the `title` field.
+    pub fn new() -> Self {
+        Self::with_fields(["title"])
+    }
+
+    /// Construct a pass which protects acronyms in the given fields.
+    pub fn with_fields<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            keys: field_set(keys),
+            words: HashSet::new(),
+            acronyms: true,
+        }
+    }
+
+    /// Also protect the given words, such as proper nouns.
+    pub fn words<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.words.extend(words.into_iter().map(Into::into));
+        self
+    }
+
+    /// Whether to protect words which look like acronyms. The default is `true`.
+    pub fn acronyms(mut self, acronyms: bool) -> Self {
+        self.acronyms = acronyms;
+        self
+    }
+
+    fn protect(&self, text: &mut String, first: bool) {
+        let mut protected = String::with_capacity(text.len());
+        let mut last = 0;
+        for Word { range, initial } in words(text, first) {
+            let word = &text[range.clone()];
+            if is_downcased(word, initial)
+                && ((self.acronyms && is_acronym(word)) || self.words.contains(word))
+            {
+                protected.push_str(&text[last..range.start]);
+                protected.push('{');
+                protected.push_str(word);
+                protected.push('}');
+                last = range.end;
+            }
+        }
+        if last > 0 {
+            protected.push_str(&text[last..]);
+            *text = protected;
+        }
+    }
+}
+
+impl Transform for ProtectWords {
+    fn apply(&mut self, entry: &mut Entry) {
+        for_each_text(entry, &self.keys, |text, first| self.protect(text, first));
+    }
+}
+
+/// Remove brackets which do not protect any uppercase characters, such as the brackets in
+/// `{the} {DNA}`.
+///
+/// Brackets are only removed at the top level, and only if they do not contain other brackets or
+/// math, or begin with a control sequence, since `{\"o}` and similar denote special characters.
+/// Empty brackets and brackets which follow a control word, such as `\TeX{}` or
+/// `\emph{important}`, are also kept, since they delimit the control word or its argument.
+/// Brackets within [math](super::math_spans) are left unchanged.
+#[derive(Debug, Clone)]
+pub struct StripRedundantBraces {
+    keys: HashSet<UniCase<String>>,
+}
+
+impl Default for StripRedundantBraces {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StripRedundantBraces {
+    /// Construct a pass which removes redundant brackets in the `title` field.
+    pub fn new() -> Self {
+        Self::with_fields(["title"])
+    }
+
+    /// Construct a pass which removes redundant brackets in the given fields.
+    pub fn with_fields<I, S>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            keys: field_set(keys),
+        }
+    }
+}
+
+/// Remove top-level groups of `text` which do not protect anything.
+fn strip_redundant(text: &mut String) {
+    let mut stripped = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    let mut last = 0;
+    let mut open = 0;
+    let mut nested = false;
+    let mut changed = false;
+    let mut math = math_spans(text).into_iter().peekable();
+
+    for (idx, b) in text.bytes().enumerate() {
+        while math.next_if(|span| span.end <= idx).is_some() {}
+        if math.peek().is_some_and(|span| span.contains(&idx)) {
+            nested |= depth > 0;
+            continue;
+        }
+        match b {
+            b'{' => {
+                if depth == 0 {
+                    open = idx;
+                    nested = false;
+                } else {
+                    nested = true;
+                }
+                depth += 1;
+            }
+            b'}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    let inner = &text[open + 1..idx];
+                    if !nested
+                        && !inner.is_empty()
+                        && !inner.starts_with('\\')
+                        && !inner.chars().any(char::is_uppercase)
+                        && !follows_control_word(&text[..open])
+                    {
+                        stripped.push_str(&text[last..open]);
+                        stripped.push_str(inner);
+                        last = idx + 1;
+                        changed = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if changed {
+        stripped.push_str(&text[last..]);
+        *text = stripped;
+    }
+}
+
+/// Whether `prefix` ends with a control word such as `\emph`, so that a group which follows is
+/// an argument of the control word.
+fn follows_control_word(prefix: &str) -> bool {
+    let name = prefix.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    if name.len() == prefix.len() {
+        return false;
+    }
+    // an even number of backslashes is a sequence of line breaks `\\`
+    let backslashes = name.len() - name.trim_end_matches('\\').len();
+    backslashes % 2 == 1
+}
+
+impl Transform for StripRedundantBraces {
+    fn apply(&mut self, entry: &mut Entry) {
+        for_each_text(entry, &self.keys, |text, _| strip_redundant(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::{RegularEntry, Value};
+
+    fn title(text: &str) -> Entry {
+        let mut entry = RegularEntry::new("article", "key");
+        entry.set_field("title", text);
+        Entry::Regular(entry)
+    }
+
+    fn get_title(entry: &Entry) -> String {
+        match entry {
+            Entry::Regular(regular) => regular.field("title").unwrap().to_text().unwrap(),
+            _ => panic!("expected a regular entry"),
+        }
+    }
+
+    #[test]
+    fn test_words() {
+        let text = "A {B c} d\\emph{E}: f√g";
+        assert_eq!(
+            words(text, true),
+            vec![
+                Word {
+                    range: 0..1,
+                    initial: true
+                },
+                Word {
+                    range: 8..9,
+                    initial: false
+                },
+                Word {
+                    range: 19..20,
+                    initial: true
+                },
+                Word {
+                    range: 23..24,
+                    initial: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_downcased_words() {
+        assert_eq!(downcased_words("Title: Subtitle"), Vec::<&str>::new());
+        assert_eq!(downcased_words("The LaTeX {Companion}"), vec!["LaTeX"]);
+        assert_eq!(downcased_words("über Ärzte"), vec!["Ärzte"]);
+        assert_eq!(downcased_words("\\TeX{} and X"), vec!["X"]);
+    }
+
+    #[test]
+    fn test_protect_words() {
+        let mut pass = ProtectWords::new().words(["Einstein", "Bose"]);
+        for (input, expected) in [
+            ("DNA and RNA", "{DNA} and {RNA}"),
+            ("On Einstein", "On {Einstein}"),
+            ("Einstein: Bose", "Einstein: Bose"),
+            ("The {DNA} of iPhones.", "The {DNA} of {iPhones}."),
+            ("the \\LaTeX\\ Companion", "the \\LaTeX\\ Companion"),
+            ("On $GL_n$ and SL", "On $GL_n$ and {SL}"),
+            ("Bounds \\(O(nN)\\) for DNA", "Bounds \\(O(nN)\\) for {DNA}"),
+            ("no change", "no change"),
+        ] {
+            let mut entry = title(input);
+            pass.apply(&mut entry);
+            assert_eq!(get_title(&entry), expected);
+        }
+
+        let mut entry = title("the DNA");
+        ProtectWords::new().acronyms(false).apply(&mut entry);
+        assert_eq!(get_title(&entry), "the DNA");
+    }
+
+    #[test]
+    fn test_protect_words_tokens() {
+        let mut entry = RegularEntry::new("article", "key");
+        entry.set_field(
+            "booktitle",
+            Value(vec![
+                Token::Text("DNA ".into()),
+                Token::Variable("m".into()),
+                Token::Text(": DNA".into()),
+            ]),
+        );
+        let mut entry = Entry::Regular(entry);
+        ProtectWords::with_fields(["booktitle"]).apply(&mut entry);
+        match entry {
+            Entry::Regular(regular) => assert_eq!(
+                regular.field("booktitle"),
+                Some(&Value(vec![
+                    Token::Text("{DNA} ".into()),
+                    Token::Variable("m".into()),
+                    Token::Text(": {DNA}".into()),
+                ]))
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_strip_redundant_braces() {
+        let mut pass = StripRedundantBraces::new();
+        for (input, expected) in [
+            ("{the} {DNA}", "the {DNA}"),
+            ("{{nested}} {\\\"o}", "{{nested}} {\\\"o}"),
+            ("{a}{b}c", "abc"),
+            ("unbalanced}", "unbalanced}"),
+            ("{Ärzte} {über}", "{Ärzte} über"),
+            ("$\\frac{a}{b}$ {c}", "$\\frac{a}{b}$ c"),
+            ("{$x$} {y}", "{$x$} y"),
+            ("\\emph{important} results", "\\emph{important} results"),
+            ("\\TeX{} {and} more", "\\TeX{} and more"),
+            ("{} {x}", "{} x"),
+            ("a\\\\b{x}", "a\\\\bx"),
+        ] {
+            let mut entry = title(input);
+            pass.apply(&mut entry);
+            assert_eq!(get_title(&entry), expected);
+        }
+    }
+}
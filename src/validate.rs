@@ -0,0 +1,414 @@
+//! # Bibliography analysis
+//! This module provides checks on an entire bibliography which are useful for linting, such as
+//! finding macros which are never used or references to entries which do not exist, along with
+//! a fast syntax check.
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use unicase::UniCase;
+
+use crate::{
+    error::Result,
+    parse::BibtexParse,
+    token::{EntryType, FieldKey, Token, Variable},
+    SliceReader, StrReader,
+};
+
+/// The name and location of a macro in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroSpan<'r> {
+    /// The name of the macro.
+    pub name: Variable<&'r str>,
+    /// The byte range of the name in the input.
+    pub span: Range<usize>,
+}
+
+/// The result of [`macros`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MacroAnalysis<'r> {
+    /// The macro definitions which are never used, in the order in which they appear.
+    pub unused: Vec<MacroSpan<'r>>,
+    /// The uses of macros which are not defined, in the order in which they appear.
+    pub undefined: Vec<MacroSpan<'r>>,
+}
+
+/// The fields which refer to other entries by key. These fields are checked by
+/// [`dangling_references`], and rewritten by default by
+/// [`RewriteKeys`](crate::transform::RewriteKeys).
+pub const REFERENCE_FIELDS: [&str; 4] = ["crossref", "xref", "xdata", "related"];
+
+/// A reference to another entry, and its location in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceSpan<'r> {
+    /// The key of the field which contains the reference, such as `crossref`.
+    pub field: FieldKey<&'r str>,
+    /// The entry key to which the field refers.
+    pub key: &'r str,
+    /// The byte range of the entry key in the input.
+    pub span: Range<usize>,
+}
+
+/// Find the references in a `&str` to entries which do not exist.
+///
+/// The [reference fields](REFERENCE_FIELDS), such as `crossref`, `xdata`, and
+/// `related`, contain comma-separated lists of entry keys. A reference is dangling if there is no
+/// regular entry with that key anywhere in the input, where entry keys are compared
+/// case-insensitively as in BibTeX. Macros in reference fields are ignored.
+/// ```
+/// use serde_bibtex::validate::dangling_references;
+///
+/// let input = r#"
+///     @proceedings{conf, title = {Proceedings}}
+///     @inproceedings{paper, crossref = {Conf}, related = {conf, missing}}
+/// "#;
+///
+/// let dangling = dangling_references(input).unwrap();
+///
+/// assert_eq!(dangling.len(), 1);
+/// assert_eq!(dangling[0].field.as_ref(), "related");
+/// assert_eq!(dangling[0].key, "missing");
+/// assert_eq!(&input[dangling[0].span.clone()], "missing");
+/// ```
+pub fn dangling_references(input: &str) -> Result<Vec<ReferenceSpan<'_>>> {
+    find_dangling(StrReader::new(input))
+}
+
+/// Find the references in a `&[u8]` to entries which do not exist. References which are not
+/// valid UTF-8 are ignored.
+///
+/// See [`dangling_references`] for more detail.
+pub fn dangling_references_slice(input: &[u8]) -> Result<Vec<ReferenceSpan<'_>>> {
+    find_dangling(SliceReader::new(input))
+}
+
+/// Find the macros in a `&str` which are defined but never used, and the macros which are used
+/// but never defined.
+///
+/// As in BibTeX, macros are expanded in the order in which they appear: a macro is only defined
+/// after its `@string` entry, and a later definition replaces an earlier one. In particular, a
+/// definition which is replaced before it is used is reported as unused. Macros are used in field
+/// values, `@preamble` entries, and the values of other macros.
+///
+/// Note that BibTeX styles usually define the month macros, such as `jan`, so uses of these macros
+/// are reported as undefined unless they are defined in the input.
+/// ```
+/// use serde_bibtex::validate::macros;
+///
+/// let input = r#"
+///     @string{ams = {AMS}}
+///     @string{pr = {Providence}}
+///     @book{key, publisher = ams, month = jan}
+/// "#;
+///
+/// let analysis = macros(input).unwrap();
+///
+/// assert_eq!(analysis.unused.len(), 1);
+/// assert_eq!(analysis.unused[0].name.as_ref(), "pr");
+/// assert_eq!(&input[analysis.unused[0].span.clone()], "pr");
+///
+/// assert_eq!(analysis.undefined.len(), 1);
+/// assert_eq!(analysis.undefined[0].name.as_ref(), "jan");
+/// ```
+pub fn macros(input: &str) -> Result<MacroAnalysis<'_>> {
+    analyze(StrReader::new(input))
+}
+
+/// Find the macros in a `&[u8]` which are defined but never used, and the macros which are used
+/// but never defined.
+///
+/// See [`macros`] for more detail.
+pub fn macros_slice(input: &[u8]) -> Result<MacroAnalysis<'_>> {
+    analyze(SliceReader::new(input))
+}
+
+/// Check that a `&str` is syntactically valid BibTeX, as quickly as possible.
+///
+/// The input is scanned without constructing any tokens or resolving any macros, so undefined
+/// macros are not an error. This is the same check which is performed when deserializing
+/// [`IgnoredAny`](serde::de::IgnoredAny), and the error records the position at which the input
+/// became invalid.
+/// ```
+/// use serde_bibtex::validate::fast_check;
+///
+/// assert!(fast_check("@article{key, title = {Title} # undefined}").is_ok());
+///
+/// let err = fast_check("@article{key, title = {Title} {Subtitle}}").unwrap_err();
+/// assert_eq!(err.position(), Some(30));
+/// ```
+pub fn fast_check(input: &str) -> Result<()> {
+    check(StrReader::new(input))
+}
+
+/// Check that a `&[u8]` is syntactically valid BibTeX, as quickly as possible. Entry types,
+/// entry keys, field keys, and macro names must be valid UTF-8.
+///
+/// See [`fast_check`] for more detail.
+pub fn fast_check_slice(input: &[u8]) -> Result<()> {
+    check(SliceReader::new(input))
+}
+
+fn check<'r, R: BibtexParse<'r>>(mut parser: R) -> Result<()> {
+    let result = parser.ignore_bibliography();
+    result.map_err(|err| parser.locate(err))
+}
+
+fn analyze<'r, R: BibtexParse<'r>>(mut parser: R) -> Result<MacroAnalysis<'r>> {
+    let mut analyzer = Analyzer::default();
+    let result = analyzer.entries(&mut parser);
+    result.map_err(|err| parser.locate(err))?;
+    Ok(analyzer.finish())
+}
+
+fn find_dangling<'r, R: BibtexParse<'r>>(mut parser: R) -> Result<Vec<ReferenceSpan<'r>>> {
+    let mut keys = HashSet::new();
+    let mut references = Vec::new();
+    let result = collect_references(&mut parser, &mut keys, &mut references);
+    result.map_err(|err| parser.locate(err))?;
+    references.retain(|reference| !keys.contains(&UniCase::new(reference.key)));
+    Ok(references)
+}
+
+/// Collect the entry keys of the regular entries, and the references in their reference fields.
+fn collect_references<'r, R: BibtexParse<'r>>(
+    parser: &mut R,
+    keys: &mut HashSet<UniCase<&'r str>>,
+    references: &mut Vec<ReferenceSpan<'r>>,
+) -> Result<()> {
+    while let Some(entry) = parser.entry_type()? {
+        let EntryType::Regular(_) = entry else {
+            parser.ignore_entry(entry)?;
+            continue;
+        };
+        let closing_bracket = parser.initial()?;
+        keys.insert(UniCase::new(parser.entry_key()?.into_inner()));
+        while let Some(field) = parser.field_or_terminal()? {
+            parser.field_sep()?;
+            if !REFERENCE_FIELDS
+                .iter()
+                .any(|key| field.as_ref().eq_ignore_ascii_case(key))
+            {
+                parser.ignore_value()?;
+                continue;
+            }
+
+            let mut is_first_token = true;
+            while let Some(token) = parser.token(&mut is_first_token)? {
+                let Token::Text(text) = token else {
+                    continue;
+                };
+                let Ok(text) = text.into_str() else {
+                    continue;
+                };
+                // exclude the closing delimiter, unless the token is a number
+                let mut end = parser.position();
+                if matches!(parser.source(end - 1..end), b"}" | b"\"") {
+                    end -= 1;
+                }
+                let mut offset = end - text.len();
+                for part in text.split(',') {
+                    let key = part.trim();
+                    if !key.is_empty() {
+                        let start = offset + (part.len() - part.trim_start().len());
+                        references.push(ReferenceSpan {
+                            field: field.clone(),
+                            key,
+                            span: start..start + key.len(),
+                        });
+                    }
+                    offset += part.len() + 1;
+                }
+            }
+        }
+        parser.comma_opt();
+        parser.terminal(closing_bracket)?;
+    }
+    Ok(())
+}
+
+/// The state of the macro analysis.
+#[derive(Default)]
+struct Analyzer<'r> {
+    /// The current definition of each macro, and whether it has been used.
+    defined: HashMap<Variable<&'r str>, (Range<usize>, bool)>,
+    analysis: MacroAnalysis<'r>,
+}
+
+impl<'r> Analyzer<'r> {
+    fn entries<R: BibtexParse<'r>>(&mut self, parser: &mut R) -> Result<()> {
+        while let Some(entry) = parser.entry_type()? {
+            match entry {
+                EntryType::Macro => {
+                    let closing_bracket = parser.initial()?;
+                    if let Some(name) = parser.macro_variable_opt()? {
+                        let end = parser.position();
+                        let span = end - name.as_ref().len()..end;
+                        parser.field_sep()?;
+                        // the value is read before the definition, since it can only refer to
+                        // earlier definitions
+                        self.value(parser)?;
+                        self.define(name, span);
+                        parser.comma_opt();
+                    }
+                    parser.terminal(closing_bracket)?;
+                }
+                EntryType::Preamble => {
+                    let closing_bracket = parser.initial()?;
+                    self.value(parser)?;
+                    parser.terminal(closing_bracket)?;
+                }
+                EntryType::Regular(_) => {
+                    let closing_bracket = parser.initial()?;
+                    parser.entry_key()?;
+                    while parser.field_or_terminal()?.is_some() {
+                        parser.field_sep()?;
+                        self.value(parser)?;
+                    }
+                    parser.comma_opt();
+                    parser.terminal(closing_bracket)?;
+                }
+                EntryType::Comment => parser.ignore_comment()?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a value, recording the macros which it uses.
+    fn value<R: BibtexParse<'r>>(&mut self, parser: &mut R) -> Result<()> {
+        let mut is_first_token = true;
+        while let Some(token) = parser.token(&mut is_first_token)? {
+            if let Token::Variable(name) = token {
+                match self.defined.get_mut(&name) {
+                    Some((_, used)) => *used = true,
+                    None => {
+                        let end = parser.position();
+                        let span = end - name.as_ref().len()..end;
+                        self.analysis.undefined.push(MacroSpan { name, span });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a definition, replacing any previous definition.
+    fn define(&mut self, name: Variable<&'r str>, span: Range<usize>) {
+        if let Some((name, (span, false))) = self.defined.remove_entry(&name) {
+            self.analysis.unused.push(MacroSpan { name, span });
+        }
+        self.defined.insert(name, (span, false));
+    }
+
+    fn finish(mut self) -> MacroAnalysis<'r> {
+        self.analysis.unused.extend(
+            self.defined
+                .into_iter()
+                .filter(|(_, (_, used))| !used)
+                .map(|(name, (span, _))| MacroSpan { name, span }),
+        );
+        self.analysis.unused.sort_by_key(|m| m.span.start);
+        self.analysis
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names<'r>(spans: &[MacroSpan<'r>], input: &str) -> Vec<&'r str> {
+        for MacroSpan { name, span } in spans {
+            assert_eq!(&input[span.clone()], name.as_ref());
+        }
+        spans.iter().map(|m| m.name.clone().into_inner()).collect()
+    }
+
+    #[test]
+    fn test_macros() {
+        let input = r#"
+            @string{a = {A}}
+            @string{b = a # {B}}
+            @string{c = c}
+            @string{C = {C}}
+            @preamble{b # d}
+            @comment{e # f}
+            @article{key, title = {T} # C, author = "x" # E,}
+            @string(a = 1)
+            @string{}
+        "#;
+
+        let analysis = macros(input).unwrap();
+        assert_eq!(names(&analysis.unused, input), vec!["c", "a"]);
+        assert_eq!(names(&analysis.undefined, input), vec!["c", "d", "E"]);
+        assert_eq!(macros_slice(input.as_bytes()).unwrap(), analysis);
+    }
+
+    #[test]
+    fn test_fast_check() {
+        use serde::{de::IgnoredAny, Deserialize};
+
+        use crate::de::Deserializer;
+
+        for input in [
+            "",
+            "junk @comment{x} @preamble{a # {b}} @string{c = 1}",
+            "@article{key, title = {T} # c # \"x{}\",} @misc(k)",
+        ] {
+            assert!(fast_check(input).is_ok(), "{input}");
+            assert!(fast_check_slice(input.as_bytes()).is_ok(), "{input}");
+        }
+
+        for input in [
+            "@article{key, title = {T}",
+            "@article{key, title = {T} x}",
+            "@article{key, title = \"{\"}",
+            "@article{key title = {T}}",
+            "@string{a = }",
+        ] {
+            let err = fast_check(input).unwrap_err();
+            // the same error as when deserializing
+            let expected = IgnoredAny::deserialize(&mut Deserializer::from_str(input)).unwrap_err();
+            assert_eq!(err.to_string(), expected.to_string(), "{input}");
+            assert_eq!(
+                fast_check_slice(input.as_bytes()).unwrap_err().to_string(),
+                expected.to_string()
+            );
+        }
+
+        assert!(fast_check_slice(b"@article{k\xff, title = {T}}").is_err());
+        assert!(fast_check_slice(b"@article{k, title = {\xff}}").is_ok());
+    }
+
+    #[test]
+    fn test_dangling_references() {
+        let input = r#"
+            @string{m = {missing}}
+            @comment{crossref = {x}}
+            @article{a, CrossRef = "b", xref = 12, note = {c}}
+            @article{B, related = { a ,, c,d } # {e} # m, xdata = {}}
+            @xdata{d,}
+        "#;
+
+        let dangling = dangling_references(input).unwrap();
+        for reference in dangling.iter() {
+            assert_eq!(&input[reference.span.clone()], reference.key);
+        }
+        assert_eq!(
+            dangling
+                .iter()
+                .map(|reference| (reference.field.as_ref(), reference.key))
+                .collect::<Vec<_>>(),
+            vec![("xref", "12"), ("related", "c"), ("related", "e")]
+        );
+        assert_eq!(
+            dangling_references_slice(input.as_bytes()).unwrap(),
+            dangling
+        );
+
+        assert!(dangling_references("@article{a, crossref = {b}").is_err());
+    }
+
+    #[test]
+    fn test_macros_error() {
+        assert!(macros("@string{a = {A}} @article{key, title = a #}").is_err());
+        assert_eq!(macros("").unwrap(), MacroAnalysis::default());
+    }
+}
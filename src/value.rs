@@ -0,0 +1,984 @@
+//! # Owned document model
+//!
+//! The types in this module represent a bibliography without loss of information, other than
+//! formatting: unlike the types in the [entry module](crate::entry), macros are preserved rather
+//! than expanded, and every entry is retained. They are intended for programs which read a
+//! bibliography, modify it, and write it back.
+//! ```
+//! use serde_bibtex::value::{Bibliography, Value};
+//!
+//! let input = r#"
+//!     @string{jan = {January}}
+//!     @article{key,
+//!       title = {Title},
+//!       month = jan,
+//!       note = {Remove me},
+//!     }
+//! "#;
+//!
+//! let mut bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+//!
+//! assert!(bibliography.rename_key("key", "Author2024"));
+//! let entry = bibliography.get_mut("Author2024").unwrap();
+//! entry.set_field("year", Value::text("2024"));
+//! entry.remove_field("note");
+//!
+//! assert_eq!(
+//!     serde_bibtex::to_string(&bibliography).unwrap(),
+//!     r#"@string{jan = {January}}
+//!
+//! @article{Author2024,
+//!   title = {Title},
+//!   month = jan,
+//!   year = {2024},
+//! }
+//! "#
+//! );
+//! ```
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use unicase::UniCase;
+
+use crate::transform::REFERENCE_FIELDS;
+
+mod sort;
+
+pub use sort::{Collator, SortKey, SortScheme};
+
+/// A token in a [`Value`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Token {
+    /// A macro, such as `jan`.
+    Variable(String),
+    /// Text, such as `{January}` or `"January"`.
+    Text(String),
+}
+
+/// The value of a field, macro, or preamble, as a sequence of tokens joined by `#`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Value(pub Vec<Token>);
+
+impl Value {
+    /// A value consisting of a single text token.
+    pub fn text<S: Into<String>>(text: S) -> Self {
+        Self(vec![Token::Text(text.into())])
+    }
+
+    /// A value consisting of a single macro.
+    pub fn variable<S: Into<String>>(name: S) -> Self {
+        Self(vec![Token::Variable(name.into())])
+    }
+
+    /// The concatenated text of the value, or `None` if the value contains a macro.
+    pub fn to_text(&self) -> Option<String> {
+        self.0
+            .iter()
+            .map(|token| match token {
+                Token::Text(text) => Some(text.as_str()),
+                Token::Variable(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+/// A regular entry, such as `@article{key, title = {Title}}`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RegularEntry {
+    /// The entry type, such as `article`.
+    pub entry_type: String,
+    /// The entry key.
+    pub entry_key: String,
+    /// The fields, in the order in which they appear.
+    pub fields: Vec<(String, Value)>,
+}
+
+impl RegularEntry {
+    /// Construct a regular entry without any fields.
+    pub fn new<T: Into<String>, K: Into<String>>(entry_type: T, entry_key: K) -> Self {
+        Self {
+            entry_type: entry_type.into(),
+            entry_key: entry_key.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        let key = UniCase::new(key);
+        self.fields
+            .iter()
+            .position(|(field_key, _)| UniCase::new(field_key.as_str()) == key)
+    }
+
+    /// The value of the first field with the given key. Field keys are compared
+    /// case-insensitively.
+    pub fn field(&self, key: &str) -> Option<&Value> {
+        self.position(key).map(|idx| &self.fields[idx].1)
+    }
+
+    /// A mutable reference to the value of the first field with the given key.
+    pub fn field_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.position(key).map(|idx| &mut self.fields[idx].1)
+    }
+
+    /// Set the value of the first field with the given key, returning the previous value. If
+    /// there is no such field, the field is appended.
+    pub fn set_field<K: Into<String>, V: Into<Value>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Option<Value> {
+        let key = key.into();
+        let value = value.into();
+        match self.position(&key) {
+            Some(idx) => Some(std::mem::replace(&mut self.fields[idx].1, value)),
+            None => {
+                self.fields.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Remove every field with the given key, returning the value of the first such field.
+    pub fn remove_field(&mut self, key: &str) -> Option<Value> {
+        let idx = self.position(key)?;
+        let (_, value) = self.fields.remove(idx);
+        let key = UniCase::new(key);
+        self.fields
+            .retain(|(field_key, _)| UniCase::new(field_key.as_str()) != key);
+        Some(value)
+    }
+
+    /// Rename the key of every field with the key `from`, returning whether any field was
+    /// renamed.
+    pub fn rename_field<K: Into<String>>(&mut self, from: &str, to: K) -> bool {
+        let from = UniCase::new(from);
+        let to = to.into();
+        let mut renamed = false;
+        for (field_key, _) in self.fields.iter_mut() {
+            if UniCase::new(field_key.as_str()) == from {
+                field_key.clone_from(&to);
+                renamed = true;
+            }
+        }
+        renamed
+    }
+}
+
+/// An entry in a [`Bibliography`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Entry {
+    /// A regular entry.
+    Regular(RegularEntry),
+    /// A macro entry, such as `@string{jan = {January}}`, or `None` if the entry is empty.
+    Macro(Option<(String, Value)>),
+    /// A comment entry, such as `@comment{text}`.
+    Comment(String),
+    /// A preamble entry, such as `@preamble{{text}}`.
+    Preamble(Value),
+}
+
+/// A bibliography, as the sequence of all of its entries.
+///
+/// The entries keep their unexpanded tokens, so that a bibliography can be edited and written back
+/// without losing information. To query a bibliography by entry key, use
+/// [`entry::Bibliography`](crate::entry::Bibliography) instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Bibliography(pub Vec<Entry>);
+
+impl Bibliography {
+    /// An iterator over the regular entries.
+    pub fn regular_entries(&self) -> impl Iterator<Item = &RegularEntry> {
+        self.0.iter().filter_map(|entry| match entry {
+            Entry::Regular(regular) => Some(regular),
+            _ => None,
+        })
+    }
+
+    /// A mutable iterator over the regular entries.
+    pub fn regular_entries_mut(&mut self) -> impl Iterator<Item = &mut RegularEntry> {
+        self.0.iter_mut().filter_map(|entry| match entry {
+            Entry::Regular(regular) => Some(regular),
+            _ => None,
+        })
+    }
+
+    /// The first regular entry with the given entry key. Entry keys are compared
+    /// case-insensitively.
+    pub fn get(&self, entry_key: &str) -> Option<&RegularEntry> {
+        let entry_key = UniCase::new(entry_key);
+        self.regular_entries()
+            .find(|entry| UniCase::new(entry.entry_key.as_str()) == entry_key)
+    }
+
+    /// A mutable reference to the first regular entry with the given entry key.
+    pub fn get_mut(&mut self, entry_key: &str) -> Option<&mut RegularEntry> {
+        let entry_key = UniCase::new(entry_key);
+        self.regular_entries_mut()
+            .find(|entry| UniCase::new(entry.entry_key.as_str()) == entry_key)
+    }
+
+    /// Append an entry.
+    pub fn push<E: Into<Entry>>(&mut self, entry: E) {
+        self.0.push(entry.into());
+    }
+
+    /// Remove the first regular entry with the given entry key.
+    pub fn remove(&mut self, entry_key: &str) -> Option<RegularEntry> {
+        let entry_key = UniCase::new(entry_key);
+        let idx = self.0.iter().position(|entry| {
+            matches!(entry, Entry::Regular(regular) if UniCase::new(regular.entry_key.as_str()) == entry_key)
+        })?;
+        match self.0.remove(idx) {
+            Entry::Regular(regular) => Some(regular),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Change the entry key of the first regular entry with the key `from`, returning whether
+    /// such an entry exists.
+    pub fn rename_key<K: Into<String>>(&mut self, from: &str, to: K) -> bool {
+        match self.get_mut(from) {
+            Some(entry) => {
+                entry.entry_key = to.into();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Divide the bibliography into [sections](Section), each of which starts at a comment entry.
+    ///
+    /// If the bibliography does not start with a comment entry, the first section has no header.
+    /// The sections can be reorganized and then combined with [`Bibliography::from_sections`],
+    /// which is the inverse of this method.
+    /// ```
+    /// use serde_bibtex::value::{Bibliography, Section};
+    ///
+    /// let input = r#"
+    ///     @string{acm = {ACM}}
+    ///     @comment{Books}
+    ///     @book{b, publisher = acm}
+    ///     @comment{Articles}
+    ///     @article{a1, title = {First}}
+    ///     @article{a2, title = {Second}}
+    /// "#;
+    ///
+    /// let bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+    /// let mut sections = bibliography.into_sections();
+    /// assert_eq!(sections[0].header, None);
+    /// assert_eq!(sections[1].header.as_deref(), Some("Books"));
+    /// assert_eq!(sections[2].entries.len(), 2);
+    ///
+    /// // move the articles before the books, and the book into a new section
+    /// sections.swap(1, 2);
+    /// let mut misc = Section::new("Miscellaneous");
+    /// misc.push(sections[2].entries.remove(0));
+    /// sections.push(misc);
+    ///
+    /// assert_eq!(
+    ///     serde_bibtex::to_string(&Bibliography::from_sections(sections)).unwrap(),
+    ///     r#"@string{acm = {ACM}}
+    ///
+    /// @comment{Articles}
+    ///
+    /// @article{a1,
+    ///   title = {First},
+    /// }
+    ///
+    /// @article{a2,
+    ///   title = {Second},
+    /// }
+    ///
+    /// @comment{Books}
+    ///
+    /// @comment{Miscellaneous}
+    ///
+    /// @book{b,
+    ///   publisher = acm,
+    /// }
+    /// "#
+    /// );
+    /// ```
+    pub fn into_sections(self) -> Vec<Section> {
+        let mut sections = vec![Section::default()];
+        for entry in self.0 {
+            match entry {
+                Entry::Comment(text) => sections.push(Section::new(text)),
+                entry => sections.last_mut().unwrap().entries.push(entry),
+            }
+        }
+        if sections[0].entries.is_empty() && sections.len() > 1 {
+            sections.remove(0);
+        }
+        sections
+    }
+
+    /// Combine [sections](Section) into a bibliography, writing the header of each section as a
+    /// comment entry before its entries.
+    pub fn from_sections<I: IntoIterator<Item = Section>>(sections: I) -> Self {
+        let mut entries = Vec::new();
+        for section in sections {
+            entries.extend(section.header.map(Entry::Comment));
+            entries.extend(section.entries);
+        }
+        Self(entries)
+    }
+
+    /// The bibliography reduced to the regular entries with the given entry keys, such as the
+    /// keys cited by a document, along with the entries which they require.
+    ///
+    /// As in BibTeX, entry keys are compared case-insensitively, and the key `*` selects every
+    /// regular entry. The entries referred to by the [reference
+    /// fields](crate::transform::REFERENCE_FIELDS) of a selected entry, such as its `crossref`
+    /// parent, are selected recursively. The result also contains every preamble, and the macro
+    /// entries which define the macros used by the selected entries and preambles, including the
+    /// macros used by those definitions. Comments are dropped, keys without an entry are
+    /// ignored, and the entries are kept in their original order. Only the first regular entry
+    /// with each key is selected.
+    /// ```
+    /// use serde_bibtex::value::Bibliography;
+    ///
+    /// let input = r#"
+    ///     @string{acm = {ACM}}
+    ///     @string{pub = acm # { Press}}
+    ///     @string{unused = {Unused}}
+    ///     @proceedings{conf, title = {Proceedings}, publisher = pub}
+    ///     @inproceedings{paper, title = {Paper}, crossref = {conf}}
+    ///     @article{other, title = {Other}}
+    /// "#;
+    ///
+    /// let bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+    /// let subset = bibliography.subset(["Paper", "missing"]);
+    ///
+    /// assert_eq!(
+    ///     serde_bibtex::to_string(&subset).unwrap(),
+    ///     r#"@string{acm = {ACM}}
+    ///
+    /// @string{pub = acm # { Press}}
+    ///
+    /// @proceedings{conf,
+    ///   title = {Proceedings},
+    ///   publisher = pub,
+    /// }
+    ///
+    /// @inproceedings{paper,
+    ///   title = {Paper},
+    ///   crossref = {conf},
+    /// }
+    /// "#
+    /// );
+    /// ```
+    pub fn subset<I, K>(&self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<str>,
+    {
+        let mut index: HashMap<UniCase<&str>, usize> = HashMap::new();
+        for (idx, entry) in self.0.iter().enumerate() {
+            if let Entry::Regular(regular) = entry {
+                index
+                    .entry(UniCase::new(regular.entry_key.as_str()))
+                    .or_insert(idx);
+            }
+        }
+
+        // select the cited entries, and the entries to which they refer
+        let mut selected = BTreeSet::new();
+        let mut pending: Vec<String> = Vec::new();
+        for key in keys {
+            match key.as_ref() {
+                "*" => selected.extend(index.values().copied()),
+                key => pending.push(key.to_owned()),
+            }
+        }
+        pending.extend(
+            selected
+                .iter()
+                .flat_map(|idx| references(&self.0[*idx]))
+                .map(str::to_owned),
+        );
+        while let Some(key) = pending.pop() {
+            if let Some(idx) = index.get(&UniCase::new(key.as_str())) {
+                if selected.insert(*idx) {
+                    pending.extend(references(&self.0[*idx]).map(str::to_owned));
+                }
+            }
+        }
+
+        // select the macro definitions used by the selected entries and the preambles
+        let mut definitions: HashMap<UniCase<&str>, Vec<usize>> = HashMap::new();
+        for (idx, entry) in self.0.iter().enumerate() {
+            match entry {
+                Entry::Macro(Some((name, _))) => {
+                    definitions
+                        .entry(UniCase::new(name.as_str()))
+                        .or_default()
+                        .push(idx);
+                }
+                Entry::Preamble(_) => {
+                    selected.insert(idx);
+                }
+                _ => {}
+            }
+        }
+        let mut used: HashSet<UniCase<&str>> = HashSet::new();
+        let mut pending: Vec<&str> = selected
+            .iter()
+            .flat_map(|idx| variables(&self.0[*idx]))
+            .collect();
+        while let Some(name) = pending.pop() {
+            if used.insert(UniCase::new(name)) {
+                for idx in definitions.get(&UniCase::new(name)).into_iter().flatten() {
+                    selected.insert(*idx);
+                    pending.extend(variables(&self.0[*idx]));
+                }
+            }
+        }
+
+        Self(
+            selected
+                .into_iter()
+                .map(|idx| self.0[idx].clone())
+                .collect(),
+        )
+    }
+
+    /// Move fields which are shared by several entries into a biblatex `@xdata` entry.
+    ///
+    /// The fields of `container` are removed from every regular entry which has all of them with
+    /// the same values, other than the `@xdata` entries, and the key of the container is appended
+    /// to the `xdata` field of each such entry. The container, with the entry type `xdata`, is
+    /// then inserted before the first such entry. The keys of the modified entries are returned;
+    /// if there are none, the bibliography is unchanged. This is the inverse of
+    /// [`Bibliography::resolve_xdata`].
+    ///
+    /// Nothing is changed if `container` has no fields, or if its key is already the key of a
+    /// regular entry, compared case-insensitively.
+    /// ```
+    /// use serde_bibtex::value::{Bibliography, RegularEntry};
+    ///
+    /// let input = r#"
+    ///     @inproceedings{a, title = {A}, booktitle = {Proceedings}, publisher = {ACM}}
+    ///     @inproceedings{b, title = {B}, booktitle = {Proceedings}, publisher = {ACM}}
+    ///     @inproceedings{c, title = {C}, booktitle = {Other}, publisher = {ACM}}
+    /// "#;
+    ///
+    /// let mut bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+    /// let mut container = RegularEntry::new("xdata", "proc");
+    /// container.set_field("booktitle", "Proceedings");
+    /// container.set_field("publisher", "ACM");
+    ///
+    /// assert_eq!(bibliography.extract_xdata(container), vec!["a", "b"]);
+    /// assert_eq!(
+    ///     serde_bibtex::to_string(&bibliography.subset(["a"])).unwrap(),
+    ///     r#"@xdata{proc,
+    ///   booktitle = {Proceedings},
+    ///   publisher = {ACM},
+    /// }
+    ///
+    /// @inproceedings{a,
+    ///   title = {A},
+    ///   xdata = {proc},
+    /// }
+    /// "#
+    /// );
+    ///
+    /// let original: Bibliography = serde_bibtex::from_str(input).unwrap();
+    /// assert!(bibliography.resolve_xdata().is_empty());
+    /// bibliography.remove("proc");
+    /// assert_eq!(bibliography, original);
+    /// ```
+    pub fn extract_xdata(&mut self, mut container: RegularEntry) -> Vec<String> {
+        if container.fields.is_empty() || self.get(&container.entry_key).is_some() {
+            return Vec::new();
+        }
+        container.entry_type = "xdata".to_owned();
+        let mut first = None;
+        let mut children = Vec::new();
+        for (idx, entry) in self.0.iter_mut().enumerate() {
+            let Entry::Regular(regular) = entry else {
+                continue;
+            };
+            if regular.entry_type.eq_ignore_ascii_case("xdata")
+                || !container
+                    .fields
+                    .iter()
+                    .all(|(key, value)| regular.field(key) == Some(value))
+            {
+                continue;
+            }
+            for (key, _) in container.fields.iter() {
+                regular.remove_field(key);
+            }
+            // append to the existing tokens, so that macros in the `xdata` field are kept
+            let reference = format!(",{}", container.entry_key);
+            match regular.field_mut("xdata") {
+                Some(xdata) if xdata.to_text().is_none_or(|text| !text.trim().is_empty()) => {
+                    match xdata.0.last_mut() {
+                        Some(Token::Text(text)) => text.push_str(&reference),
+                        _ => xdata.0.push(Token::Text(reference)),
+                    }
+                }
+                _ => {
+                    regular.set_field("xdata", container.entry_key.clone());
+                }
+            }
+            first.get_or_insert(idx);
+            children.push(regular.entry_key.clone());
+        }
+        if let Some(idx) = first {
+            self.0.insert(idx, Entry::Regular(container));
+        }
+        children
+    }
+
+    /// Copy the fields of the biblatex `@xdata` entries referred to by the `xdata` field of each
+    /// regular entry into the entry itself, returning the keys which do not refer to an `@xdata`
+    /// entry.
+    ///
+    /// Fields which are already present in an entry are not replaced, and `@xdata` entries which
+    /// refer to other `@xdata` entries are resolved recursively. If a reference is listed earlier
+    /// in the `xdata` field, its fields take precedence. As elsewhere, entry keys are compared
+    /// case-insensitively. The `xdata` field is removed from every entry whose references are
+    /// all resolved. The `@xdata` entries themselves are retained unchanged, including their own
+    /// `xdata` fields. See
+    /// [`Bibliography::extract_xdata`] for an example.
+    pub fn resolve_xdata(&mut self) -> Vec<String> {
+        let is_xdata = |entry: &RegularEntry| entry.entry_type.eq_ignore_ascii_case("xdata");
+        let owned: Vec<RegularEntry> = self
+            .regular_entries()
+            .filter(|entry| is_xdata(entry))
+            .cloned()
+            .collect();
+        let containers: HashMap<UniCase<&str>, &RegularEntry> = owned
+            .iter()
+            .map(|entry| (UniCase::new(entry.entry_key.as_str()), entry))
+            .collect();
+
+        let mut all_dangling = Vec::new();
+        for entry in self.regular_entries_mut() {
+            if is_xdata(entry) {
+                continue;
+            }
+            let Some(xdata) = entry.field("xdata").cloned() else {
+                continue;
+            };
+            let mut visited = HashSet::new();
+            let mut dangling = Vec::new();
+            let mut inherited = RegularEntry::new("", "");
+            for key in keys(&xdata) {
+                inherit_xdata(
+                    &containers,
+                    key,
+                    &mut visited,
+                    &mut inherited,
+                    &mut dangling,
+                );
+            }
+            for (key, value) in inherited.fields {
+                if entry.field(&key).is_none() {
+                    entry.fields.push((key, value));
+                }
+            }
+            if dangling.is_empty() {
+                entry.remove_field("xdata");
+            }
+            for key in dangling {
+                if !all_dangling.contains(&key) {
+                    all_dangling.push(key);
+                }
+            }
+        }
+        all_dangling
+    }
+}
+
+/// The keys in the reference fields of an entry, which may be comma-separated lists. Macros in
+/// reference fields are ignored.
+fn references(entry: &Entry) -> impl Iterator<Item = &str> {
+    let fields = match entry {
+        Entry::Regular(regular) => regular.fields.as_slice(),
+        _ => &[],
+    };
+    fields
+        .iter()
+        .filter(|(key, _)| {
+            REFERENCE_FIELDS
+                .iter()
+                .any(|field| key.eq_ignore_ascii_case(field))
+        })
+        .flat_map(|(_, value)| keys(value))
+}
+
+/// The keys in a comma-separated list of entry keys. Macros are ignored.
+fn keys(value: &Value) -> impl Iterator<Item = &str> {
+    value
+        .0
+        .iter()
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text.as_str()),
+            Token::Variable(_) => None,
+        })
+        .flat_map(|text| text.split(','))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Append the fields of the `@xdata` entry with the given key, and of the `@xdata` entries to
+/// which it refers, to `inherited`, unless a field with the same key is already present.
+fn inherit_xdata(
+    containers: &HashMap<UniCase<&str>, &RegularEntry>,
+    key: &str,
+    visited: &mut HashSet<String>,
+    inherited: &mut RegularEntry,
+    dangling: &mut Vec<String>,
+) {
+    if !visited.insert(key.to_lowercase()) {
+        return;
+    }
+    let Some(container) = containers.get(&UniCase::new(key)) else {
+        dangling.push(key.to_owned());
+        return;
+    };
+    for (field_key, value) in container.fields.iter() {
+        if !field_key.eq_ignore_ascii_case("xdata") && inherited.field(field_key).is_none() {
+            inherited.fields.push((field_key.clone(), value.clone()));
+        }
+    }
+    for nested in container.field("xdata").into_iter().flat_map(keys) {
+        inherit_xdata(containers, nested, visited, inherited, dangling);
+    }
+}
+
+/// The names of the macros used by an entry.
+fn variables(entry: &Entry) -> impl Iterator<Item = &str> {
+    let values: Vec<&Value> = match entry {
+        Entry::Regular(regular) => regular.fields.iter().map(|(_, value)| value).collect(),
+        Entry::Macro(Some((_, value))) | Entry::Preamble(value) => vec![value],
+        Entry::Macro(None) | Entry::Comment(_) => Vec::new(),
+    };
+    values
+        .into_iter()
+        .flat_map(|value| value.0.iter())
+        .filter_map(|token| match token {
+            Token::Variable(name) => Some(name.as_str()),
+            Token::Text(_) => None,
+        })
+}
+
+/// A section of a [`Bibliography`], consisting of a comment entry which acts as a header and the
+/// entries which follow it, up to the next comment entry.
+///
+/// Only `@comment` entries are preserved by the parser, so a bibliography which is divided into
+/// sections by TeX-style `%` comments must use `@comment` entries instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Section {
+    /// The text of the comment entry, or `None` for the entries before the first comment entry.
+    pub header: Option<String>,
+    /// The entries in the section, none of which are comment entries.
+    pub entries: Vec<Entry>,
+}
+
+impl Section {
+    /// An empty section with the given header.
+    pub fn new<S: Into<String>>(header: S) -> Self {
+        Self {
+            header: Some(header.into()),
+            entries: Vec::new(),
+        }
+    }
+
+    /// An iterator over the regular entries.
+    pub fn regular_entries(&self) -> impl Iterator<Item = &RegularEntry> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::Regular(regular) => Some(regular),
+            _ => None,
+        })
+    }
+
+    /// Append an entry.
+    pub fn push<E: Into<Entry>>(&mut self, entry: E) {
+        self.entries.push(entry.into());
+    }
+}
+
+impl From<RegularEntry> for Entry {
+    fn from(entry: RegularEntry) -> Self {
+        Self::Regular(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value() {
+        let value = Value(vec![Token::Text("a".into()), Token::Text("b".into())]);
+        assert_eq!(value.to_text().as_deref(), Some("ab"));
+        assert_eq!(Value::variable("jan").to_text(), None);
+        assert_eq!(Value::from("a"), Value::text("a"));
+    }
+
+    #[test]
+    fn test_regular_entry() {
+        let mut entry = RegularEntry::new("article", "key");
+        assert_eq!(entry.set_field("title", "A"), None);
+        assert_eq!(entry.set_field("TITLE", "B"), Some(Value::text("A")));
+        assert_eq!(entry.fields, vec![("title".into(), Value::text("B"))]);
+
+        entry.fields.push(("Title".into(), Value::text("C")));
+        entry.set_field("year", Value::variable("y"));
+        assert!(entry.rename_field("title", "booktitle"));
+        assert_eq!(entry.field("BookTitle"), Some(&Value::text("B")));
+
+        assert_eq!(entry.remove_field("booktitle"), Some(Value::text("B")));
+        assert_eq!(entry.fields, vec![("year".into(), Value::variable("y"))]);
+        assert_eq!(entry.remove_field("booktitle"), None);
+        assert!(!entry.rename_field("booktitle", "title"));
+    }
+
+    #[test]
+    fn test_bibliography_round_trip() {
+        let input = r#"@string{a = {A}}
+
+@preamble{{text} # a}
+
+@comment{comment}
+
+@article{key,
+  author = a # { and } # {B},
+  title = {Title},
+}
+
+@book{key2,
+}
+"#;
+        let mut bibliography: Bibliography = crate::from_str(input).unwrap();
+        assert_eq!(bibliography.0.len(), 5);
+        assert_eq!(
+            bibliography.get("key").unwrap().field("author"),
+            Some(&Value(vec![
+                Token::Variable("a".into()),
+                Token::Text(" and ".into()),
+                Token::Text("B".into()),
+            ]))
+        );
+        assert_eq!(crate::to_string(&bibliography).unwrap(), input);
+
+        // entry keys are compared case-insensitively
+        assert_eq!(bibliography.get("KEY").unwrap().entry_key, "key");
+        assert!(!bibliography.rename_key("missing", "other"));
+        assert!(bibliography.rename_key("KEY2", "key3"));
+        assert_eq!(bibliography.remove("Key3").unwrap().entry_type, "book");
+        bibliography.push(RegularEntry::new("misc", "key4"));
+        assert_eq!(
+            bibliography
+                .regular_entries()
+                .map(|entry| entry.entry_key.as_str())
+                .collect::<Vec<_>>(),
+            vec!["key", "key4"]
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = r#"
+            @comment{A}
+            @comment{B}
+            @article{b1, title = {B1}}
+            @preamble{{p}}
+            @comment{C}
+            @book{c1, title = {C1}}
+        "#;
+        let bibliography: Bibliography = crate::from_str(input).unwrap();
+        let sections = bibliography.clone().into_sections();
+        assert_eq!(
+            sections
+                .iter()
+                .map(|section| section.header.as_deref())
+                .collect::<Vec<_>>(),
+            [Some("A"), Some("B"), Some("C")]
+        );
+        assert!(sections[0].entries.is_empty());
+        assert_eq!(sections[1].entries.len(), 2);
+        assert_eq!(
+            sections[2]
+                .regular_entries()
+                .map(|entry| entry.entry_key.as_str())
+                .collect::<Vec<_>>(),
+            ["c1"]
+        );
+        assert_eq!(Bibliography::from_sections(sections), bibliography);
+
+        let bibliography: Bibliography = crate::from_str("@misc{m,}").unwrap();
+        let sections = bibliography.clone().into_sections();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].header, None);
+        assert_eq!(Bibliography::from_sections(sections), bibliography);
+
+        assert_eq!(
+            Bibliography::default().into_sections(),
+            vec![Section::default()]
+        );
+    }
+
+    #[test]
+    fn test_xdata() {
+        let input = r#"
+            @xdata{pub, publisher = {ACM}, location = {New York}}
+            @XData{conf, booktitle = {Conference}, xdata = {pub, missing}}
+            @xdata{loop, xdata = {LOOP}, note = {Loop}}
+            @inproceedings{a, xdata = {CONF, loop}, location = {Online}}
+            @inproceedings{b, xdata = {pub}}
+            @article{c, title = {C}}
+        "#;
+        let mut bibliography: Bibliography = crate::from_str(input).unwrap();
+        assert_eq!(bibliography.resolve_xdata(), vec!["missing"]);
+
+        // the reference to `missing` is not resolved, so the `xdata` field is retained
+        let a = bibliography.get("a").unwrap();
+        assert_eq!(
+            a.fields,
+            vec![
+                ("xdata".into(), Value::text("CONF, loop")),
+                ("location".into(), Value::text("Online")),
+                ("booktitle".into(), Value::text("Conference")),
+                ("publisher".into(), Value::text("ACM")),
+                ("note".into(), Value::text("Loop")),
+            ]
+        );
+        assert_eq!(bibliography.get("b").unwrap().fields.len(), 2);
+        // nested references of `@xdata` entries are not flattened
+        assert!(bibliography.get("conf").unwrap().field("xdata").is_some());
+        assert_eq!(
+            bibliography.get("loop").unwrap().fields,
+            vec![
+                ("xdata".into(), Value::text("LOOP")),
+                ("note".into(), Value::text("Loop")),
+            ]
+        );
+
+        // only entries with every field are modified, and references are appended
+        let mut bibliography: Bibliography = crate::from_str(
+            "@misc{x, note = {N}, xdata = {other}} @misc{y, note = {n}} @xdata{other, note = {N}}",
+        )
+        .unwrap();
+        let mut container = RegularEntry::new("misc", "shared");
+        container.set_field("note", "N");
+        assert_eq!(bibliography.extract_xdata(container.clone()), vec!["x"]);
+        assert_eq!(bibliography.0.len(), 4);
+        assert_eq!(bibliography.get("shared").unwrap().entry_type, "xdata");
+        assert_eq!(
+            bibliography.get("x").unwrap().field("xdata"),
+            Some(&Value::text("other,shared"))
+        );
+        // the `@xdata` entry is unchanged
+        assert!(bibliography.get("other").unwrap().field("note").is_some());
+
+        // a container with an existing key or without fields changes nothing
+        let unchanged = bibliography.clone();
+        let mut existing = container.clone();
+        existing.entry_key = "SHARED".into();
+        assert!(bibliography.extract_xdata(existing).is_empty());
+        assert!(bibliography
+            .extract_xdata(RegularEntry::new("misc", "empty"))
+            .is_empty());
+        container.entry_key = "new".into();
+        container.set_field("year", "2024");
+        assert!(bibliography.extract_xdata(container).is_empty());
+        assert_eq!(bibliography, unchanged);
+
+        // a macro in an existing `xdata` field is kept
+        let mut bibliography: Bibliography = crate::from_str(
+            "@string{shared = {other}} @xdata{other, note = {Z}} @misc{x, note = {N}, xdata = shared}",
+        )
+        .unwrap();
+        let mut container = RegularEntry::new("xdata", "new");
+        container.set_field("note", "N");
+        assert_eq!(bibliography.extract_xdata(container), vec!["x"]);
+        assert_eq!(
+            bibliography.get("x").unwrap().field("xdata"),
+            Some(&Value(vec![
+                Token::Variable("shared".into()),
+                Token::Text(",new".into())
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_subset() {
+        let input = r#"
+            @string{a = {A}}
+            @string{b = a # {B}}
+            @string{c = {C}}
+            @string{A = {Redefined}}
+            @string{d = {D}}
+            @preamble{d}
+            @comment{comment}
+            @book{parent, title = b}
+            @inproceedings{child, crossref = {Parent}, related = {x, other ,}}
+            @article{other, title = c, xref = {child}}
+            @article{unrelated, title = {U}}
+            @misc{child, title = {Duplicate}}
+        "#;
+        let bibliography: Bibliography = crate::from_str(input).unwrap();
+        let keys = |bibliography: &Bibliography| -> Vec<String> {
+            bibliography
+                .0
+                .iter()
+                .map(|entry| match entry {
+                    Entry::Regular(regular) => regular.entry_key.clone(),
+                    Entry::Macro(Some((name, _))) => format!("@{name}"),
+                    Entry::Preamble(_) => "@preamble".into(),
+                    _ => panic!("unexpected entry"),
+                })
+                .collect()
+        };
+
+        // references are followed recursively, and cycles terminate
+        let subset = bibliography.subset(["CHILD"]);
+        assert_eq!(
+            keys(&subset),
+            [
+                "@a",
+                "@b",
+                "@c",
+                "@A",
+                "@d",
+                "@preamble",
+                "parent",
+                "child",
+                "other"
+            ]
+        );
+        assert_eq!(
+            subset.get("child").unwrap().field("crossref"),
+            Some(&Value::text("Parent"))
+        );
+
+        let subset = bibliography.subset(["unrelated", "unrelated", "missing"]);
+        assert_eq!(keys(&subset), ["@d", "@preamble", "unrelated"]);
+
+        assert_eq!(bibliography.subset(["*"]).0.len(), 10);
+        assert_eq!(
+            keys(&bibliography.subset(Vec::<&str>::new())),
+            ["@d", "@preamble"]
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use std::cmp::Ordering;
+
+use super::{Bibliography, Entry, RegularEntry};
+
+/// A sorting scheme, named after the corresponding biblatex `sorting` option.
+///
+/// The components of the sort key are, in order:
+///
+/// - `n`: the names, from the first of the `sortname`, `author`, `editor`, and `translator`
+///   fields, or the title if there are no names;
+/// - `y`: the year, from the `sortyear` or `year` field, or the year of the `date` field;
+/// - `t`: the title, from the `sorttitle` or `title` field;
+/// - `a`: the alphabetic label, from the `label` field, or otherwise computed from the last names
+///   of the authors and the year, as in `Knu84`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SortScheme {
+    /// Sort by name, title, and year.
+    #[default]
+    Nty,
+    /// Sort by name, year, and title.
+    Nyt,
+    /// Sort by year, name, and title.
+    Ynt,
+    /// Sort by alphabetic label, name, year, and title.
+    Anyt,
+}
+
+/// The key by which a regular entry is sorted, according to a [`SortScheme`].
+///
+/// Each component of the key is normalized: brackets, accents, and TeX commands which take an
+/// argument or change the font are removed, the letters of other commands such as `\TeX` are
+/// kept, and each run of whitespace is replaced by a single space. Entries without a year are sorted after
+/// entries with a year.
+/// ```
+/// use serde_bibtex::value::{RegularEntry, SortKey, SortScheme};
+///
+/// let mut entry = RegularEntry::new("book", "knuth");
+/// entry.set_field("author", "Knuth, Donald E.");
+/// entry.set_field("title", "The {\\TeX}book");
+/// entry.set_field("year", "1984");
+///
+/// assert_eq!(
+///     SortKey::new(&entry, SortScheme::Anyt).components(),
+///     ["Knu84", "Knuth Donald E.", "1984", "The TeXbook"]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortKey(Vec<String>);
+
+/// The year used for entries without a year.
+const MISSING_YEAR: &str = "9999";
+
+impl SortKey {
+    /// Compute the sort key of an entry.
+    pub fn new(entry: &RegularEntry, scheme: SortScheme) -> Self {
+        let text = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| entry.field(key).and_then(|value| value.to_text()))
+        };
+
+        let title = normalize(&text(&["sorttitle", "title"]).unwrap_or_default());
+        let names = text(&["sortname", "author", "editor", "translator"])
+            .map(|names| split_names(&names))
+            .unwrap_or_default();
+        let year = text(&["sortyear", "year"])
+            .or_else(|| text(&["date"]).map(|date| date.chars().take(4).collect()))
+            .map(|year| normalize(&year))
+            .filter(|year| !year.is_empty());
+
+        let sort_names = if names.is_empty() {
+            title.clone()
+        } else {
+            names
+                .iter()
+                .map(|(last, first)| format!("{last} {first}").trim_end().to_owned())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let year_key = year.clone().unwrap_or_else(|| MISSING_YEAR.to_owned());
+
+        Self(match scheme {
+            SortScheme::Nty => vec![sort_names, title, year_key],
+            SortScheme::Nyt => vec![sort_names, year_key, title],
+            SortScheme::Ynt => vec![year_key, sort_names, title],
+            SortScheme::Anyt => {
+                let label = match text(&["label"]) {
+                    Some(label) => normalize(&label),
+                    None => alphabetic_label(&names, &title, year.as_deref()),
+                };
+                vec![label, sort_names, year_key, title]
+            }
+        })
+    }
+
+    /// The components of the key, in the order in which they are compared.
+    pub fn components(&self) -> &[String] {
+        &self.0
+    }
+}
+
+/// Compares [sort keys](SortKey).
+///
+/// With the `unicode-collation` feature, strings are compared using the Unicode Collation
+/// Algorithm with the CLDR root collation order, so that for instance `Émile` sorts before
+/// `Ernie`. Otherwise, strings are compared case-insensitively by code point.
+#[derive(Debug, Clone, Default)]
+pub struct Collator {
+    #[cfg(feature = "unicode-collation")]
+    collator: feruca::Collator,
+}
+
+impl Collator {
+    /// Construct a collator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare two strings.
+    #[cfg(feature = "unicode-collation")]
+    pub fn compare_str(&mut self, a: &str, b: &str) -> Ordering {
+        self.collator.collate(a, b)
+    }
+
+    /// Compare two strings.
+    #[cfg(not(feature = "unicode-collation"))]
+    pub fn compare_str(&mut self, a: &str, b: &str) -> Ordering {
+        a.chars()
+            .flat_map(char::to_lowercase)
+            .cmp(b.chars().flat_map(char::to_lowercase))
+            .then_with(|| a.cmp(b))
+    }
+
+    /// Compare two sort keys component by component.
+    pub fn compare(&mut self, a: &SortKey, b: &SortKey) -> Ordering {
+        for (a, b) in a.0.iter().zip(b.0.iter()) {
+            match self.compare_str(a, b) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+        }
+        a.0.len().cmp(&b.0.len())
+    }
+}
+
+impl Bibliography {
+    /// Sort the regular entries according to a [`SortScheme`], comparing the [sort
+    /// keys](SortKey) with a [`Collator`].
+    ///
+    /// The other entries, such as macros and preambles, are moved before the regular entries and
+    /// otherwise retain their order, so that macros are defined before they are used. Entries with
+    /// equal sort keys retain their order.
+    /// ```
+    /// use serde_bibtex::value::{Bibliography, SortScheme};
+    ///
+    /// let input = r#"
+    ///     @article{b, author = {Zhang, Wei}, year = {2001}, title = {B}}
+    ///     @string{j = {Journal}}
+    ///     @article{a, author = {Adams, Ann and Brown, Bob}, year = {2010}, title = {A}}
+    ///     @book{c, editor = {Adams, Ann and Brown, Bob}, year = {1999}, title = {C}}
+    /// "#;
+    ///
+    /// let mut bibliography: Bibliography = serde_bibtex::from_str(input).unwrap();
+    /// let keys = |bibliography: &Bibliography| -> Vec<String> {
+    ///     bibliography
+    ///         .regular_entries()
+    ///         .map(|entry| entry.entry_key.clone())
+    ///         .collect()
+    /// };
+    ///
+    /// bibliography.sort(SortScheme::Nyt);
+    /// assert_eq!(keys(&bibliography), ["c", "a", "b"]);
+    ///
+    /// bibliography.sort(SortScheme::Nty);
+    /// assert_eq!(keys(&bibliography), ["a", "c", "b"]);
+    ///
+    /// bibliography.sort(SortScheme::Ynt);
+    /// assert_eq!(keys(&bibliography), ["c", "b", "a"]);
+    /// ```
+    pub fn sort(&mut self, scheme: SortScheme) {
+        let mut other = Vec::new();
+        let mut regular = Vec::new();
+        for entry in std::mem::take(&mut self.0) {
+            match entry {
+                Entry::Regular(entry) => regular.push((SortKey::new(&entry, scheme), entry)),
+                entry => other.push(entry),
+            }
+        }
+
+        let mut collator = Collator::new();
+        regular.sort_by(|(a, _), (b, _)| collator.compare(a, b));
+        self.0 = other;
+        self.0
+            .extend(regular.into_iter().map(|(_, entry)| Entry::Regular(entry)));
+    }
+}
+
+/// TeX declarations which change the font of the text which follows them.
+const FONT_DECLARATIONS: &[&str] = &[
+    "bf",
+    "bfseries",
+    "em",
+    "it",
+    "itshape",
+    "mdseries",
+    "normalfont",
+    "rm",
+    "rmfamily",
+    "sc",
+    "scshape",
+    "sf",
+    "sffamily",
+    "sl",
+    "slshape",
+    "tt",
+    "ttfamily",
+    "upshape",
+];
+
+/// Remove brackets, control symbols such as the accent `\"`, and the names of commands which are
+/// followed by an argument or which change the font, and replace each run of whitespace by a
+/// single space. The letters of other commands, such as `\TeX` or `\ss`, are kept.
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {}
+            '\\' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+                    name.push(c);
+                }
+                if name.is_empty() {
+                    chars.next();
+                    continue;
+                }
+                let mut rest = chars.clone();
+                let has_argument = rest.next() == Some('{') && rest.next() != Some('}');
+                if !has_argument && !FONT_DECLARATIONS.contains(&name.as_str()) {
+                    normalized.push_str(&name);
+                }
+            }
+            c => normalized.push(c),
+        }
+    }
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split a list of names separated by `and` into `(last, first)` pairs. Names may be written as
+/// `First Last` or `Last, First`, and text in brackets is never split.
+fn split_names(text: &str) -> Vec<(String, String)> {
+    // split into words at bracket depth zero
+    let mut words: Vec<&str> = Vec::new();
+    let mut depth = 0usize;
+    let mut start = None;
+    for (idx, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(start) = start.take() {
+                    words.push(&text[start..idx]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(idx);
+    }
+    if let Some(start) = start {
+        words.push(&text[start..]);
+    }
+
+    words
+        .split(|word| word.eq_ignore_ascii_case("and"))
+        .filter(|name| !name.is_empty())
+        .map(
+            |name| match name.iter().position(|word| word.ends_with(',')) {
+                Some(idx) => (
+                    normalize(name[..=idx].join(" ").trim_end_matches(',')),
+                    normalize(&name[idx + 1..].join(" ")),
+                ),
+                None => {
+                    let (last, first) = name.split_last().unwrap();
+                    (normalize(last), normalize(&first.join(" ")))
+                }
+            },
+        )
+        .collect()
+}
+
+/// Compute an alphabetic label, such as `Knu84` for a single author or `ABC99` for several.
+fn alphabetic_label(names: &[(String, String)], title: &str, year: Option<&str>) -> String {
+    let prefix = |text: &str, len: usize| text.chars().take(len).collect::<String>();
+    let mut label = match names {
+        [] => prefix(title, 3),
+        [(last, _)] => prefix(last, 3),
+        [..] => {
+            let mut label: String = names
+                .iter()
+                .take(3)
+                .map(|(last, _)| prefix(last, 1))
+                .collect();
+            if names.len() > 3 {
+                label.push('+');
+            }
+            label
+        }
+    };
+    if let Some(year) = year {
+        let digits: Vec<char> = year.chars().filter(char::is_ascii_digit).collect();
+        label.extend(&digits[digits.len().saturating_sub(2)..]);
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_names() {
+        let names = |text| split_names(text);
+        assert_eq!(
+            names("Donald E. Knuth and Lamport, Leslie AND {Barnes and Noble}"),
+            vec![
+                ("Knuth".into(), "Donald E.".into()),
+                ("Lamport".into(), "Leslie".into()),
+                ("Barnes and Noble".into(), String::new()),
+            ]
+        );
+        assert_eq!(
+            names("{\\\"O}zt{\\\"u}rk, Ay{\\c{s}}e and and"),
+            vec![("Ozturk".into(), "Ayse".into())]
+        );
+        assert!(names("").is_empty());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("The {\\TeX}book"), "The TeXbook");
+        assert_eq!(
+            normalize("\\LaTeX{} and {\\em Emphasis}"),
+            "LaTeX and Emphasis"
+        );
+        assert_eq!(normalize("\\emph{A}  \\textbf{B}"), "A B");
+        assert_eq!(
+            normalize("Stra{\\ss}e {\\'e}t{\\'e} \\c{c}a"),
+            "Strasse ete ca"
+        );
+    }
+
+    #[test]
+    fn test_sort_key() {
+        let mut entry = RegularEntry::new("article", "key");
+        entry.set_field("editor", "A. Bee and C. Dee and E. Eff and G. Gee");
+        entry.set_field("title", "{T}itle");
+        entry.set_field("date", "2024-01-02");
+        assert_eq!(
+            SortKey::new(&entry, SortScheme::Anyt).components(),
+            ["BDE+24", "Bee A. Dee C. Eff E. Gee G.", "2024", "Title"]
+        );
+
+        let mut entry = RegularEntry::new("misc", "key");
+        entry.set_field("title", "Anonymous");
+        assert_eq!(
+            SortKey::new(&entry, SortScheme::Ynt).components(),
+            ["9999", "Anonymous", "Anonymous"]
+        );
+        entry.set_field("label", "Anon");
+        assert_eq!(
+            SortKey::new(&entry, SortScheme::Anyt).components()[0],
+            "Anon"
+        );
+    }
+
+    #[test]
+    fn test_collator() {
+        let mut collator = Collator::new();
+        assert_eq!(collator.compare_str("apple", "Banana"), Ordering::Less);
+        assert_ne!(collator.compare_str("b", "B"), Ordering::Equal);
+
+        #[cfg(feature = "unicode-collation")]
+        assert_eq!(collator.compare_str("Émile", "Ernie"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_sort() {
+        let input = r#"
+            @preamble{{p}}
+            @misc{b, title = {Same}}
+            @string{s = {S}}
+            @misc{a, title = {Same}}
+            @misc{c, title = {Earlier}, year = 2000}
+        "#;
+        let mut bibliography: Bibliography = crate::from_str(input).unwrap();
+        bibliography.sort(SortScheme::Ynt);
+        assert!(matches!(bibliography.0[0], Entry::Preamble(_)));
+        assert!(matches!(bibliography.0[1], Entry::Macro(_)));
+        assert_eq!(
+            bibliography
+                .regular_entries()
+                .map(|entry| entry.entry_key.as_str())
+                .collect::<Vec<_>>(),
+            ["c", "b", "a"]
+        );
+    }
+}
@@ -3,7 +3,7 @@ use serde::de::IgnoredAny;
 use serde::Deserialize;
 use serde_bibtex::{
     entry::{OwnedBibliography, RawBibliography},
-    syntax::{BibtexParser, Rule},
+    syntax::{crosscheck, BibtexParser, Rule},
     Result,
     {de::Deserializer, MacroDictionary},
 };
@@ -85,6 +85,9 @@ macro_rules! test_file_str {
 
         let parsed = BibtexParser::parse(Rule::bib, input_str);
         assert!(parsed.is_ok());
+
+        let crosschecked = crosscheck(input_str);
+        assert!(crosschecked.is_ok(), "{:?}", crosschecked);
     };
 }
 